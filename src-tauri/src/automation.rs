@@ -0,0 +1,620 @@
+// Column automation module
+// Rules stored in `columns.automation_rules` that run when a card enters the
+// column, each an action guarded by an optional condition. A failing rule is
+// reported but never undoes the move.
+
+use crate::database::{self, CardError, Database};
+use crate::git::GhostMode;
+use crate::process::ProcessManager;
+use rusqlite::Transaction;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AutomationRule {
+    #[serde(default)]
+    pub trigger: RuleTrigger,
+    /// The action only runs when this holds for the card
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<RuleCondition>,
+    pub action: RuleAction,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleTrigger {
+    /// A card moved into the column from another one
+    #[default]
+    OnEnter,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum RuleCondition {
+    /// The card's status is one of `statuses`
+    StatusIn { statuses: Vec<String> },
+    /// The card's priority is one of `priorities`
+    PriorityIn { priorities: Vec<String> },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum RuleAction {
+    SetStatus {
+        status: String,
+    },
+    CreateGhostBranch,
+    AttachLabel {
+        label_id: String,
+    },
+    /// Only runs when the project's `allow_automation_commands` setting is on
+    RunCommand {
+        command: String,
+    },
+}
+
+/// What happened when a rule's action ran. Rules whose condition didn't hold
+/// have no outcome.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleOutcome {
+    /// `None` when the stored rules couldn't be parsed at all
+    pub rule: Option<AutomationRule>,
+    pub ok: bool,
+    pub message: String,
+}
+
+/// Most rules a single column may hold
+pub const MAX_RULES_PER_COLUMN: usize = 10;
+
+/// Longest `run_command` command string, in bytes
+pub const MAX_COMMAND_BYTES: usize = 1024;
+
+/// Every value of an action's `type` tag
+const ACTION_TYPES: &[&str] = &[
+    "set_status",
+    "create_ghost_branch",
+    "attach_label",
+    "run_command",
+];
+
+/// Result of checking a rules document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RulesValidation {
+    pub valid: bool,
+    /// The rules re-serialized in canonical form, when valid
+    pub normalized: Option<Vec<AutomationRule>>,
+    pub errors: Vec<String>,
+}
+
+/// Check a rules document (a JSON array) and report every problem found, each
+/// prefixed with its 1-based rule number
+pub fn validate_rules(value: &serde_json::Value) -> RulesValidation {
+    let invalid = |errors| RulesValidation {
+        valid: false,
+        normalized: None,
+        errors,
+    };
+
+    let Some(items) = value.as_array() else {
+        return invalid(vec!["rules must be a JSON array".to_string()]);
+    };
+
+    let mut errors = Vec::new();
+    if items.len() > MAX_RULES_PER_COLUMN {
+        errors.push(format!(
+            "a column can have at most {} rules, got {}",
+            MAX_RULES_PER_COLUMN,
+            items.len()
+        ));
+    }
+
+    let mut rules = Vec::with_capacity(items.len());
+    for (index, item) in items.iter().enumerate() {
+        match parse_rule(item) {
+            Ok(rule) => rules.push(rule),
+            Err(error) => errors.push(format!("rule {}: {}", index + 1, error)),
+        }
+    }
+
+    if errors.is_empty() {
+        RulesValidation {
+            valid: true,
+            normalized: Some(rules),
+            errors,
+        }
+    } else {
+        invalid(errors)
+    }
+}
+
+/// Parse one rule. A bare action, the format before rules had a trigger and
+/// condition, is read as an unconditional `on_enter` rule.
+fn parse_rule(item: &serde_json::Value) -> Result<AutomationRule, String> {
+    let Some(action) = item.get("action") else {
+        return parse_action(item).map(|action| AutomationRule {
+            trigger: RuleTrigger::OnEnter,
+            condition: None,
+            action,
+        });
+    };
+
+    // The action first, for its more specific errors
+    parse_action(action)?;
+    let rule: AutomationRule = serde_json::from_value(item.clone()).map_err(|e| e.to_string())?;
+
+    match &rule.condition {
+        Some(RuleCondition::StatusIn { statuses }) => {
+            if let Some(status) = statuses.iter().find(|s| !crate::status::is_valid_status(s)) {
+                return Err(format!("condition: unknown status '{}'", status));
+            }
+        }
+        Some(RuleCondition::PriorityIn { priorities }) => {
+            if let Some(priority) = priorities
+                .iter()
+                .find(|p| !database::CARD_PRIORITIES.contains(&p.as_str()))
+            {
+                return Err(format!("condition: unknown priority '{}'", priority));
+            }
+        }
+        None => {}
+    }
+    Ok(rule)
+}
+
+fn parse_action(item: &serde_json::Value) -> Result<RuleAction, String> {
+    let action_type = item
+        .get("type")
+        .ok_or("missing 'type'")?
+        .as_str()
+        .ok_or("'type' must be a string")?;
+    if !ACTION_TYPES.contains(&action_type) {
+        return Err(format!("unknown action '{}'", action_type));
+    }
+
+    let action: RuleAction = serde_json::from_value(item.clone()).map_err(|e| e.to_string())?;
+
+    match &action {
+        RuleAction::SetStatus { status } if !crate::status::is_valid_status(status) => {
+            Err(format!("unknown status '{}'", status))
+        }
+        RuleAction::AttachLabel { label_id } if label_id.trim().is_empty() => {
+            Err("label_id must not be empty".to_string())
+        }
+        RuleAction::RunCommand { command } if command.trim().is_empty() => {
+            Err("command must not be empty".to_string())
+        }
+        RuleAction::RunCommand { command } if command.len() > MAX_COMMAND_BYTES => {
+            Err(format!("command must be under {} bytes", MAX_COMMAND_BYTES))
+        }
+        _ => Ok(action),
+    }
+}
+
+/// Move a card and run the rules of `column_id` against it if it entered the
+/// column, in order. Conditions, status and label actions are evaluated in the
+/// move's transaction, so each rule sees what the ones before it did; ghost
+/// branches and commands can't be rolled back, so they run once it has
+/// committed. Each action's result is also recorded in the card's activity.
+/// Returns the column the card came from along with the outcomes.
+pub fn move_card(
+    db: &Arc<Database>,
+    processes: &Arc<ProcessManager>,
+    card_id: &str,
+    column_id: &str,
+    position: i32,
+    override_limit: bool,
+) -> Result<(String, Vec<RuleOutcome>), CardError> {
+    // Read up front: inside the move's transaction another pool connection
+    // would wait on its write lock
+    let rules = db
+        .get_column_automation(column_id)
+        .map_err(|e| e.to_string())
+        .and_then(|raw| {
+            parse_stored_rules(&raw)
+                .map_err(|e| format!("Invalid automation rules on column {}: {}", column_id, e))
+        });
+
+    let (from_column_id, applied) =
+        db.move_card_and(card_id, column_id, position, override_limit, |tx| {
+            let Ok(rules) = &rules else {
+                return Vec::new();
+            };
+            rules
+                .iter()
+                .map(
+                    |rule| match condition_holds_in(tx, card_id, rule.condition.as_ref()) {
+                        Ok(true) => Step::Run(apply_action_in(tx, card_id, &rule.action)),
+                        Ok(false) => Step::Skipped,
+                        Err(e) => Step::Run(Some(Err(format!("Condition failed: {}", e)))),
+                    },
+                )
+                .collect::<Vec<_>>()
+        })?;
+
+    // Rules only fire on entering a column, not on reordering within one
+    let Some(applied) = applied else {
+        return Ok((from_column_id, Vec::new()));
+    };
+
+    let rules = match rules {
+        Ok(rules) => rules,
+        Err(message) => {
+            let _ = db.add_card_activity(card_id, "automation", &message);
+            let outcome = RuleOutcome {
+                rule: None,
+                ok: false,
+                message,
+            };
+            return Ok((from_column_id, vec![outcome]));
+        }
+    };
+
+    let outcomes = rules
+        .into_iter()
+        .zip(applied)
+        .filter_map(|(rule, step)| {
+            let Step::Run(result) = step else {
+                return None;
+            };
+            let result =
+                result.unwrap_or_else(|| apply_action(db, processes, card_id, &rule.action));
+            let (ok, message) = match result {
+                Ok(message) => (true, message),
+                Err(message) => (false, message),
+            };
+            let _ = db.add_card_activity(card_id, "automation", &message);
+
+            Some(RuleOutcome {
+                rule: Some(rule),
+                ok,
+                message,
+            })
+        })
+        .collect();
+    Ok((from_column_id, outcomes))
+}
+
+/// A rule's fate inside the move's transaction
+enum Step {
+    /// Its condition didn't hold
+    Skipped,
+    /// The action's result, or `None` when it runs after the commit
+    Run(Option<Result<String, String>>),
+}
+
+/// Stored rules, in the current format or as bare actions
+fn parse_stored_rules(raw: &str) -> Result<Vec<AutomationRule>, String> {
+    let value = serde_json::from_str(raw).map_err(|e| e.to_string())?;
+    let validation = validate_rules(&value);
+    validation
+        .normalized
+        .ok_or_else(|| validation.errors.join("; "))
+}
+
+fn condition_holds_in(
+    tx: &Transaction,
+    card_id: &str,
+    condition: Option<&RuleCondition>,
+) -> rusqlite::Result<bool> {
+    let Some(condition) = condition else {
+        return Ok(true);
+    };
+
+    let (status, priority): (String, String) = tx.query_row(
+        "SELECT status, priority FROM cards WHERE id = ?1",
+        [card_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    Ok(match condition {
+        RuleCondition::StatusIn { statuses } => statuses.contains(&status),
+        RuleCondition::PriorityIn { priorities } => priorities.contains(&priority),
+    })
+}
+
+/// Apply an action that only touches the database inside the move's
+/// transaction. `None` for actions that have to wait until the move has
+/// committed.
+fn apply_action_in(
+    tx: &Transaction,
+    card_id: &str,
+    action: &RuleAction,
+) -> Option<Result<String, String>> {
+    match action {
+        RuleAction::SetStatus { status } => Some(
+            database::set_card_status_in(tx, card_id, status, false)
+                .map(|_| format!("Set status to {}", status))
+                .map_err(|e| format!("Set status failed: {}", e)),
+        ),
+        RuleAction::AttachLabel { label_id } => Some(
+            database::attach_label_in(tx, card_id, label_id)
+                .map(|attached| {
+                    if attached {
+                        format!("Attached label {}", label_id)
+                    } else {
+                        format!("Label {} was already attached", label_id)
+                    }
+                })
+                .map_err(|e| e.to_string()),
+        ),
+        RuleAction::CreateGhostBranch | RuleAction::RunCommand { .. } => None,
+    }
+}
+
+fn apply_action(
+    db: &Arc<Database>,
+    processes: &Arc<ProcessManager>,
+    card_id: &str,
+    action: &RuleAction,
+) -> Result<String, String> {
+    match action {
+        RuleAction::CreateGhostBranch => {
+            let root = db
+                .get_card_project_root(card_id)
+                .map_err(|e| e.to_string())?
+                .ok_or("Card has no project")?;
+            let branch = GhostMode::new(&root)?
+                .create_ghost_branch(card_id)
+                .map_err(|e| format!("Create ghost branch failed: {}", e))?;
+            db.update_card_metadata(card_id, &serde_json::json!({ "ghostBranch": branch }), None)
+                .map_err(|e| e.to_string())?;
+            Ok(format!("Created ghost branch {}", branch))
+        }
+        RuleAction::RunCommand { command } => run_command(db, processes, card_id, command),
+        RuleAction::SetStatus { .. } | RuleAction::AttachLabel { .. } => {
+            unreachable!("applied in the move's transaction")
+        }
+    }
+}
+
+fn run_command(
+    db: &Arc<Database>,
+    processes: &Arc<ProcessManager>,
+    card_id: &str,
+    command: &str,
+) -> Result<String, String> {
+    let project_id = db
+        .get_card_project_id(card_id)
+        .map_err(|e| e.to_string())?
+        .ok_or("Card has no project")?;
+    let settings = db
+        .get_project_settings(&project_id)
+        .map_err(|e| e.to_string())?;
+    if !settings.allow_automation_commands {
+        return Err(format!(
+            "Skipped `{}`: automation commands are disabled for this project",
+            command
+        ));
+    }
+
+    let cwd = match db.get_card_folder(card_id).map_err(|e| e.to_string())? {
+        Some(folder) => folder,
+        None => db
+            .get_card_project_root(card_id)
+            .map_err(|e| e.to_string())?
+            .ok_or("Card has no project")?,
+    };
+
+    #[cfg(windows)]
+    let (shell, flag) = ("cmd", "/C");
+    #[cfg(not(windows))]
+    let (shell, flag) = ("sh", "-c");
+
+    let process = processes.spawn(
+        &cwd,
+        shell,
+        &[flag.to_string(), command.to_string()],
+        &HashMap::new(),
+        |_, _| {},
+    )?;
+
+    // Report how it ended once it's done, without holding up the move
+    let (db, card_id, finished) = (Arc::clone(db), card_id.to_string(), command.to_string());
+    tokio::spawn(async move {
+        let message = match process.handle.await.ok().flatten() {
+            Some(code) => format!("`{}` exited with code {}", finished, code),
+            None => format!("`{}` was terminated", finished),
+        };
+        let _ = db.add_card_activity(&card_id, "automation", &message);
+    });
+
+    Ok(format!("Started `{}` (pid {})", command, process.pid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::testing::*;
+
+    /// A card in a "Todo" column and an empty "Doing" column holding `rules`
+    fn board_with_rules(db: &Database, rules: serde_json::Value) -> (String, String) {
+        board_with_rules_in(db, "/automation", rules)
+    }
+
+    /// `board_with_rules` for a project rooted at `root`
+    fn board_with_rules_in(
+        db: &Database,
+        root: &str,
+        rules: serde_json::Value,
+    ) -> (String, String) {
+        let fixture = project_with_column(db, root);
+        let card_id = add_card(db, &fixture.column_id, "Card");
+        let doing = uuid::Uuid::new_v4().to_string();
+        db.create_column(&doing, &fixture.board_id, "Doing", 1)
+            .unwrap();
+
+        let rules = validate_rules(&rules).normalized.expect("valid rules");
+        db.set_column_automation(&doing, &serde_json::to_string(&rules).unwrap())
+            .unwrap();
+        (card_id, doing)
+    }
+
+    fn status_of(db: &Database, card_id: &str) -> String {
+        db.get_card(card_id).unwrap().unwrap().status
+    }
+
+    fn metadata_of(db: &Database, card_id: &str) -> serde_json::Value {
+        serde_json::from_str(&db.get_card(card_id).unwrap().unwrap().metadata).unwrap()
+    }
+
+    #[test]
+    fn entering_a_column_sets_the_status() {
+        let db = Arc::new(memory_db());
+        let processes = Arc::new(ProcessManager::new());
+        let rules = serde_json::json!([{
+            "trigger": "on_enter",
+            "condition": { "type": "status_in", "statuses": ["idle"] },
+            "action": { "type": "set_status", "status": "queued" },
+        }]);
+        let (card_id, doing) = board_with_rules(&db, rules);
+
+        let (_, outcomes) = move_card(&db, &processes, &card_id, &doing, 0, false).unwrap();
+        assert_eq!(outcomes.len(), 1);
+        assert!(outcomes[0].ok, "{}", outcomes[0].message);
+        assert_eq!(status_of(&db, &card_id), "queued");
+
+        // Reordering within the column doesn't enter it again
+        let (_, outcomes) = move_card(&db, &processes, &card_id, &doing, 0, false).unwrap();
+        assert!(outcomes.is_empty());
+    }
+
+    #[test]
+    fn rule_whose_condition_fails_does_not_fire() {
+        let db = Arc::new(memory_db());
+        let processes = Arc::new(ProcessManager::new());
+        let rules = serde_json::json!([{
+            "condition": { "type": "priority_in", "priorities": ["urgent"] },
+            "action": { "type": "set_status", "status": "queued" },
+        }]);
+        let (card_id, doing) = board_with_rules(&db, rules);
+
+        let (_, outcomes) = move_card(&db, &processes, &card_id, &doing, 0, false).unwrap();
+        assert!(outcomes.is_empty());
+        assert_eq!(status_of(&db, &card_id), "idle");
+    }
+
+    #[test]
+    fn bare_actions_are_read_as_on_enter_rules() {
+        let validation = validate_rules(&serde_json::json!([
+            { "type": "set_status", "status": "queued" }
+        ]));
+        assert_eq!(
+            validation.normalized.unwrap(),
+            [AutomationRule {
+                trigger: RuleTrigger::OnEnter,
+                condition: None,
+                action: RuleAction::SetStatus {
+                    status: "queued".to_string()
+                },
+            }]
+        );
+    }
+
+    #[test]
+    fn invalid_rules_are_reported_by_number() {
+        let validation = validate_rules(&serde_json::json!([
+            { "action": { "type": "set_status", "status": "queued" } },
+            { "action": { "type": "explode" } },
+            { "condition": { "type": "status_in", "statuses": ["lost"] },
+              "action": { "type": "set_status", "status": "queued" } },
+            { "trigger": "on_exit", "action": { "type": "create_ghost_branch" } },
+        ]));
+        assert!(!validation.valid);
+        assert_eq!(validation.errors.len(), 3, "{:?}", validation.errors);
+        assert!(validation.errors[0].starts_with("rule 2: unknown action"));
+        assert!(validation.errors[1].starts_with("rule 3: condition: unknown status"));
+        assert!(validation.errors[2].starts_with("rule 4:"));
+    }
+
+    #[test]
+    fn rules_run_in_order_and_see_what_earlier_ones_did() {
+        let db = Arc::new(memory_db());
+        let processes = Arc::new(ProcessManager::new());
+        let rules = serde_json::json!([
+            { "action": { "type": "set_status", "status": "queued" } },
+            {
+                "condition": { "type": "status_in", "statuses": ["queued"] },
+                "action": { "type": "attach_label", "label_id": "ready" },
+            },
+            { "action": { "type": "attach_label", "label_id": "ready" } },
+            {
+                "condition": { "type": "status_in", "statuses": ["idle"] },
+                "action": { "type": "attach_label", "label_id": "never" },
+            },
+        ]);
+        let (card_id, doing) = board_with_rules(&db, rules);
+
+        let (_, outcomes) = move_card(&db, &processes, &card_id, &doing, 0, false).unwrap();
+        let messages: Vec<&str> = outcomes.iter().map(|o| o.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "Set status to queued",
+                "Attached label ready",
+                "Label ready was already attached",
+            ]
+        );
+        assert!(outcomes.iter().all(|o| o.ok));
+        assert_eq!(status_of(&db, &card_id), "queued");
+        assert_eq!(
+            metadata_of(&db, &card_id)["labels"],
+            serde_json::json!(["ready"])
+        );
+        assert_eq!(db.get_card_activity(&card_id).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn create_ghost_branch_rule_branches_the_project_repo() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init(dir.path()).unwrap();
+        let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "Initial", &tree, &[])
+            .unwrap();
+
+        let db = Arc::new(memory_db());
+        let processes = Arc::new(ProcessManager::new());
+        let rules = serde_json::json!([{ "type": "create_ghost_branch" }]);
+        let (card_id, doing) = board_with_rules_in(&db, &dir.path().to_string_lossy(), rules);
+
+        let (_, outcomes) = move_card(&db, &processes, &card_id, &doing, 0, false).unwrap();
+        assert!(outcomes[0].ok, "{}", outcomes[0].message);
+        let branch = metadata_of(&db, &card_id)["ghostBranch"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert!(repo.find_branch(&branch, git2::BranchType::Local).is_ok());
+    }
+
+    #[tokio::test]
+    async fn run_command_rule_only_runs_when_the_project_allows_it() {
+        let db = Arc::new(memory_db());
+        let processes = Arc::new(ProcessManager::new());
+        let dir = tempfile::tempdir().unwrap();
+        let rules = serde_json::json!([{ "type": "run_command", "command": "true" }]);
+        let (card_id, doing) = board_with_rules_in(&db, &dir.path().to_string_lossy(), rules);
+
+        let (_, outcomes) = move_card(&db, &processes, &card_id, &doing, 0, false).unwrap();
+        assert!(!outcomes[0].ok);
+        assert!(
+            outcomes[0].message.contains("disabled"),
+            "{}",
+            outcomes[0].message
+        );
+
+        let project_id = db.get_card_project_id(&card_id).unwrap().unwrap();
+        let mut settings = db.get_project_settings(&project_id).unwrap();
+        settings.allow_automation_commands = true;
+        db.update_project_settings(&project_id, &settings).unwrap();
+        let todo = db.get_card_location(&card_id).unwrap().unwrap();
+        let columns = db.get_columns(&todo.board_id).unwrap();
+        move_card(&db, &processes, &card_id, &columns[0].id, 0, false).unwrap();
+
+        let (_, outcomes) = move_card(&db, &processes, &card_id, &doing, 0, false).unwrap();
+        assert!(outcomes[0].ok, "{}", outcomes[0].message);
+        assert!(outcomes[0].message.starts_with("Started `true`"));
+    }
+}
@@ -0,0 +1,246 @@
+// Database backup module
+// Snapshots of the database in `app_data_dir/backups`, taken on a timer and on
+// request, and restoring from one of them. Only the newest few automatic
+// snapshots are kept.
+
+use crate::database::Database;
+use crate::error::AppError;
+use crate::preferences;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+const AUTO_PREFIX: &str = "auto-";
+const MANUAL_PREFIX: &str = "manual-";
+
+/// How often the background task checks whether a snapshot is due
+const CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// A snapshot file, also the payload of the `backup-completed` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupInfo {
+    pub path: String,
+    pub name: String,
+    pub size: u64,
+    pub created_at: i64,
+    /// Taken by the timer rather than on request
+    pub automatic: bool,
+}
+
+pub struct Backups {
+    dir: PathBuf,
+    /// Held while a snapshot is taken or restored, so they never overlap
+    busy: Mutex<()>,
+}
+
+impl Backups {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            busy: Mutex::new(()),
+        }
+    }
+
+    /// Snapshot the database now. `None` when another backup or a restore is
+    /// already running.
+    pub fn create(&self, db: &Database, automatic: bool) -> Result<Option<BackupInfo>, AppError> {
+        let Some(_busy) = self.busy.try_lock() else {
+            return Ok(None);
+        };
+
+        std::fs::create_dir_all(&self.dir)
+            .map_err(|e| AppError::io("Failed to create the backup folder", e))?;
+
+        let prefix = if automatic {
+            AUTO_PREFIX
+        } else {
+            MANUAL_PREFIX
+        };
+        let stem = format!("{}{}", prefix, chrono::Utc::now().format("%Y%m%d-%H%M%S"));
+        let path = unused_path(&self.dir, &stem);
+        if let Err(e) = db.backup_to(&path) {
+            let _ = std::fs::remove_file(&path);
+            return Err(e.into());
+        }
+
+        backup_info(&path).map(Some)
+    }
+
+    /// Every snapshot, newest first
+    pub fn list(&self) -> Result<Vec<BackupInfo>, AppError> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(AppError::io("Failed to read the backup folder", e)),
+        };
+
+        let mut backups: Vec<BackupInfo> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| is_backup_file(path))
+            .filter_map(|path| backup_info(&path).ok())
+            .collect();
+        // Within a second, a longer name has a higher counter and is newer
+        backups.sort_by(|a, b| {
+            b.created_at
+                .cmp(&a.created_at)
+                .then_with(|| (b.name.len(), &b.name).cmp(&(a.name.len(), &a.name)))
+        });
+        Ok(backups)
+    }
+
+    /// Delete all but the newest `keep` automatic snapshots. Ones taken on
+    /// request are left alone.
+    pub fn prune(&self, keep: usize) -> Result<Vec<BackupInfo>, AppError> {
+        let stale: Vec<BackupInfo> = self
+            .list()?
+            .into_iter()
+            .filter(|backup| backup.automatic)
+            .skip(keep)
+            .collect();
+
+        for backup in &stale {
+            std::fs::remove_file(&backup.path)
+                .map_err(|e| AppError::io("Failed to delete an old backup", e))?;
+        }
+        Ok(stale)
+    }
+
+    /// Put the snapshot at `path`, which has to be one in the backup folder,
+    /// back in place of the current data
+    pub fn restore(&self, db: &Database, path: &Path) -> Result<(), AppError> {
+        let path = self.resolve(path)?;
+        let Some(_busy) = self.busy.try_lock() else {
+            return Err(AppError::Conflict(
+                "A backup or restore is already running".to_string(),
+            ));
+        };
+
+        db.restore_from(&path)?;
+        Ok(())
+    }
+
+    fn resolve(&self, path: &Path) -> Result<PathBuf, AppError> {
+        let path = path
+            .canonicalize()
+            .map_err(|e| AppError::io("Backup not found", e))?;
+        let dir = self
+            .dir
+            .canonicalize()
+            .map_err(|e| AppError::io("Backup not found", e))?;
+
+        if path.parent() != Some(dir.as_path()) || !is_backup_file(&path) {
+            return Err(AppError::validation(
+                "path",
+                "Only snapshots in the backup folder can be restored",
+            ));
+        }
+        Ok(path)
+    }
+}
+
+/// `dir/stem.db`, or with a counter added when a snapshot taken in the same
+/// second already has that name
+fn unused_path(dir: &Path, stem: &str) -> PathBuf {
+    let mut path = dir.join(format!("{}.db", stem));
+    let mut n = 2;
+    while path.exists() {
+        path = dir.join(format!("{}-{}.db", stem, n));
+        n += 1;
+    }
+    path
+}
+
+fn is_backup_file(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    path.is_file()
+        && name.ends_with(".db")
+        && (name.starts_with(AUTO_PREFIX) || name.starts_with(MANUAL_PREFIX))
+}
+
+fn backup_info(path: &Path) -> Result<BackupInfo, AppError> {
+    let metadata =
+        std::fs::metadata(path).map_err(|e| AppError::io("Failed to read the backup", e))?;
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    Ok(BackupInfo {
+        path: path.to_string_lossy().into_owned(),
+        automatic: name.starts_with(AUTO_PREFIX),
+        name,
+        size: metadata.len(),
+        created_at: metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs() as i64),
+    })
+}
+
+/// Take an automatic snapshot whenever the newest one is older than the
+/// `backup.interval_hours` preference, checking at startup and then every
+/// few minutes. A check that finds a backup or restore running skips to the
+/// next one.
+pub fn spawn_auto_backups(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let handle = app.clone();
+            let _ = tokio::task::spawn_blocking(move || auto_backup_if_due(&handle)).await;
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+fn auto_backup_if_due(app: &AppHandle) {
+    let db = app.state::<Arc<Database>>();
+    let backups = app.state::<Arc<Backups>>();
+
+    let interval = preferences::backup_interval_hours(&db) as i64 * 60 * 60;
+    let newest = backups
+        .list()
+        .ok()
+        .and_then(|list| list.into_iter().find(|backup| backup.automatic));
+    if newest.is_some_and(|backup| chrono::Utc::now().timestamp() - backup.created_at < interval) {
+        return;
+    }
+
+    if let Ok(Some(backup)) = backups.create(&db, true) {
+        let _ = backups.prune(preferences::backup_keep(&db));
+        let _ = app.emit("backup-completed", &backup);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::testing::*;
+
+    #[test]
+    fn snapshots_taken_in_the_same_second_are_all_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let backups = Backups::new(dir.path().to_path_buf());
+        let db = memory_db();
+
+        let taken: Vec<BackupInfo> = (0..3)
+            .map(|_| backups.create(&db, false).unwrap().unwrap())
+            .collect();
+
+        let mut names: Vec<&str> = taken.iter().map(|backup| backup.name.as_str()).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), 3);
+
+        let listed = backups.list().unwrap();
+        assert_eq!(listed.len(), 3);
+        if taken[0].created_at == taken[2].created_at {
+            assert_eq!(listed[0].name, taken[2].name);
+        }
+    }
+}
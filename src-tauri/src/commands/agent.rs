@@ -0,0 +1,89 @@
+use crate::database::{AgentLog, Database};
+use crate::error::AppError;
+use crate::logs::LogBuffer;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Runtime, State};
+
+#[tauri::command]
+pub async fn append_agent_log(
+    execution_id: String,
+    level: String,
+    message: String,
+    metadata: Option<String>,
+    app: AppHandle,
+    db: State<'_, Arc<Database>>,
+    buffer: State<'_, Arc<LogBuffer>>,
+) -> Result<AgentLog, AppError> {
+    let buffer = buffer.inner().clone();
+    db.blocking(move |db| {
+        record_log(
+            &app,
+            db,
+            &buffer,
+            &execution_id,
+            &level,
+            &message,
+            metadata.as_deref(),
+        )
+    })
+    .await
+}
+
+/// Persist a log entry, add it to the tail buffer and notify live viewers
+pub(crate) fn record_log<R: Runtime>(
+    app: &AppHandle<R>,
+    db: &Database,
+    buffer: &LogBuffer,
+    execution_id: &str,
+    level: &str,
+    message: &str,
+    metadata: Option<&str>,
+) -> Result<AgentLog, AppError> {
+    let log = db.append_log(execution_id, level, message, metadata)?;
+    publish_log(app, buffer, &log)?;
+    Ok(log)
+}
+
+/// Add a stored log entry to the tail buffer and notify live viewers
+pub(crate) fn publish_log<R: Runtime>(
+    app: &AppHandle<R>,
+    buffer: &LogBuffer,
+    log: &AgentLog,
+) -> Result<(), AppError> {
+    buffer.push(log);
+
+    // Live subscribers listen on agent-log://<execution_id>
+    app.emit(&format!("agent-log://{}", log.execution_id), log)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_agent_logs(
+    execution_id: String,
+    after_seq: Option<i64>,
+    level: Option<String>,
+    limit: Option<i64>,
+    db: State<'_, Arc<Database>>,
+    buffer: State<'_, Arc<LogBuffer>>,
+) -> Result<Vec<AgentLog>, AppError> {
+    // Tail requests are usually served from the in-memory buffer
+    if let Some(after) = after_seq {
+        if let Some(logs) = buffer.tail(&execution_id, after, level.as_deref(), limit) {
+            return Ok(logs);
+        }
+    }
+
+    db.blocking(move |db| db.get_logs(&execution_id, after_seq, level.as_deref(), limit))
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn prune_agent_logs(
+    older_than_days: i64,
+    db: State<'_, Arc<Database>>,
+) -> Result<usize, AppError> {
+    db.blocking(move |db| db.prune_logs(older_than_days))
+        .await
+        .map_err(AppError::from)
+}
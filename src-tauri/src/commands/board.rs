@@ -1,4 +1,5 @@
-use crate::database::{Board, Database};
+use crate::database::{Board, BoardCard, BoardData, Database, DuplicatedBoard};
+use crate::error::AppError;
 use std::sync::Arc;
 use tauri::State;
 
@@ -6,8 +7,10 @@ use tauri::State;
 pub async fn get_boards(
     project_id: String,
     db: State<'_, Arc<Database>>,
-) -> Result<Vec<Board>, String> {
-    db.get_boards(&project_id).map_err(|e| e.to_string())
+) -> Result<Vec<Board>, AppError> {
+    db.blocking(move |db| db.get_boards(&project_id))
+        .await
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -16,18 +19,55 @@ pub async fn create_board(
     name: String,
     position: i32,
     db: State<'_, Arc<Database>>,
-) -> Result<Board, String> {
+) -> Result<Board, AppError> {
     let id = uuid::Uuid::new_v4().to_string();
     let now = chrono::Utc::now().timestamp();
 
-    db.create_board(&id, &project_id, &name, position)
-        .map_err(|e| e.to_string())?;
+    db.blocking(move |db| {
+        db.create_board(&id, &project_id, &name, position)?;
 
-    Ok(Board {
-        id,
-        project_id,
-        name,
-        position,
-        created_at: now,
+        Ok(Board {
+            id,
+            project_id,
+            name,
+            position,
+            created_at: now,
+        })
     })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_board_data(
+    board_id: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<BoardData, AppError> {
+    db.blocking(move |db| db.get_board_data(&board_id))
+        .await
+        .map_err(AppError::from)
+}
+
+/// All live cards on a board, ordered by column then position, each with its
+/// column's name
+#[tauri::command]
+pub async fn get_cards_for_board(
+    board_id: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<Vec<BoardCard>, AppError> {
+    db.blocking(move |db| db.get_cards_for_board(&board_id))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Deep-copy a board. Live cards come along unless `include_cards` is false.
+#[tauri::command]
+pub async fn duplicate_board(
+    board_id: String,
+    new_name: String,
+    include_cards: Option<bool>,
+    db: State<'_, Arc<Database>>,
+) -> Result<DuplicatedBoard, AppError> {
+    db.blocking(move |db| db.duplicate_board(&board_id, &new_name, include_cards.unwrap_or(true)))
+        .await
+        .map_err(AppError::from)
 }
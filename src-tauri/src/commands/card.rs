@@ -1,66 +1,416 @@
-use crate::database::{Card, Database};
+use crate::automation::{self, RuleOutcome};
+use crate::database::{
+    AgentConfig, AppliedOperation, BulkCardOp, BulkCardResult, Card, CardActivity, CardAuditEntry,
+    CardError, CardExecution, CardFilter, CardLocation, CardSort, ChecklistItem, ChecklistProgress,
+    Database, RecentCard, CARD_PRIORITIES,
+};
+use crate::error::AppError;
+use crate::process::ProcessManager;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tauri::State;
+use tauri::{AppHandle, Emitter, State};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CardFile {
+    pub path: String,
+    pub exists: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CardChangeKind {
+    Created,
+    Updated,
+    StatusChanged,
+    Moved,
+    Deleted,
+}
+
+/// Payload of the `card-changed` event, so other windows can refresh a single
+/// card instead of reloading the board
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CardChanged {
+    pub card_id: String,
+    pub project_id: String,
+    pub board_id: String,
+    /// The card's column now; for a delete, the one it was in
+    pub column_id: String,
+    /// Where a moved card came from. Only set for moves.
+    pub from_column_id: Option<String>,
+    pub kind: CardChangeKind,
+}
+
+impl CardChanged {
+    pub fn new(
+        kind: CardChangeKind,
+        location: CardLocation,
+        from_column_id: Option<String>,
+    ) -> Self {
+        Self {
+            card_id: location.card_id,
+            project_id: location.project_id,
+            board_id: location.board_id,
+            column_id: location.column_id,
+            from_column_id,
+            kind,
+        }
+    }
+}
+
+/// Where a card is, for events only. A failed lookup just means no event.
+fn locate_card(db: &Database, id: &str) -> Option<CardLocation> {
+    db.get_card_location(id).ok().flatten()
+}
+
+fn emit_card_changed(app: &AppHandle, changed: Option<CardChanged>) {
+    if let Some(changed) = changed {
+        let _ = app.emit("card-changed", &changed);
+    }
+}
+
+/// Where each card is before a bulk operation; repeated ids count once
+fn locate_cards(db: &Database, ids: &[String]) -> Vec<CardLocation> {
+    let mut seen = HashSet::new();
+    ids.iter()
+        .filter(|id| seen.insert(id.as_str()))
+        .filter_map(|id| locate_card(db, id))
+        .collect()
+}
+
+/// One event per card a bulk operation changed, given where each one was
+/// before it
+fn bulk_changes(db: &Database, before: Vec<CardLocation>, op: &BulkCardOp) -> Vec<CardChanged> {
+    before
+        .into_iter()
+        .filter_map(|before| {
+            let (kind, from_column_id) = match op {
+                BulkCardOp::Delete => {
+                    return Some(CardChanged::new(CardChangeKind::Deleted, before, None))
+                }
+                BulkCardOp::MoveToColumn { .. } => (CardChangeKind::Moved, Some(before.column_id)),
+                BulkCardOp::SetStatus { .. } => (CardChangeKind::StatusChanged, None),
+                BulkCardOp::Archive | BulkCardOp::AssignLabel { .. } => {
+                    (CardChangeKind::Updated, None)
+                }
+            };
+            locate_card(db, &before.card_id)
+                .map(|location| CardChanged::new(kind, location, from_column_id))
+        })
+        .collect()
+}
+
+/// The event for an undone or redone change: a card deleted or brought back
+/// by it is reported as such
+fn replay_change(applied: &AppliedOperation) -> Option<CardChanged> {
+    match (applied.before.clone(), applied.after.clone()) {
+        (Some(before), None) => Some(CardChanged::new(CardChangeKind::Deleted, before, None)),
+        (None, Some(after)) => Some(CardChanged::new(CardChangeKind::Created, after, None)),
+        (Some(before), Some(after)) if applied.kind == "move" => Some(CardChanged::new(
+            CardChangeKind::Moved,
+            after,
+            Some(before.column_id),
+        )),
+        (Some(_), Some(after)) => Some(CardChanged::new(CardChangeKind::Updated, after, None)),
+        (None, None) => None,
+    }
+}
+
+fn validate_priority(priority: &str) -> Result<(), AppError> {
+    if CARD_PRIORITIES.contains(&priority) {
+        Ok(())
+    } else {
+        Err(AppError::validation(
+            "priority",
+            format!("Unknown priority: {}", priority),
+        ))
+    }
+}
 
 #[tauri::command]
 pub async fn get_cards(
     column_id: String,
+    sort_by: Option<CardSort>,
+    db: State<'_, Arc<Database>>,
+) -> Result<Vec<Card>, AppError> {
+    db.blocking(move |db| db.get_cards(&column_id, sort_by.unwrap_or_default()))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Live cards of one priority across the whole project, e.g. every "urgent"
+/// card for a fires view
+#[tauri::command]
+pub async fn get_cards_by_priority(
+    project_id: String,
+    priority: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<Vec<Card>, AppError> {
+    validate_priority(&priority)?;
+
+    db.blocking(move |db| db.get_cards_by_priority(&project_id, &priority))
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_card(id: String, db: State<'_, Arc<Database>>) -> Result<Card, AppError> {
+    db.blocking(move |db| {
+        db.get_card(&id)?
+            .ok_or_else(|| CardError::CardNotFound(id).into())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn query_cards(
+    board_id: String,
+    filter: CardFilter,
     db: State<'_, Arc<Database>>,
-) -> Result<Vec<Card>, String> {
-    db.get_cards(&column_id).map_err(|e| e.to_string())
+) -> Result<HashMap<String, Vec<Card>>, AppError> {
+    db.blocking(move |db| db.query_cards(&board_id, &filter))
+        .await
+        .map_err(AppError::from)
 }
 
+/// Add a card. A column at its WIP limit refuses it with `WIP_LIMIT_EXCEEDED`
+/// unless `override_limit` is set. `priority` defaults to "normal".
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn create_card(
     column_id: String,
     title: String,
     description: Option<String>,
     position: i32,
+    priority: Option<String>,
+    override_limit: Option<bool>,
+    app: AppHandle,
     db: State<'_, Arc<Database>>,
-) -> Result<Card, String> {
+) -> Result<Card, AppError> {
+    if let Some(priority) = &priority {
+        validate_priority(priority)?;
+    }
+
     let id = uuid::Uuid::new_v4().to_string();
     let now = chrono::Utc::now().timestamp();
+    let priority = priority.unwrap_or_else(|| "normal".to_string());
 
-    db.create_card(&id, &column_id, &title, description.as_deref(), position)
-        .map_err(|e| e.to_string())?;
-
-    Ok(Card {
-        id,
-        column_id,
-        title,
-        description,
-        folder_path: None,
-        file_paths: "[]".to_string(),
-        agent_config: "{}".to_string(),
-        position,
-        status: "idle".to_string(),
-        created_at: now,
-        updated_at: now,
-        metadata: "{}".to_string(),
-    })
+    let (card, changed) = db
+        .blocking(move |db| {
+            db.create_card(
+                &id,
+                &column_id,
+                &title,
+                description.as_deref(),
+                position,
+                Some(&priority),
+                override_limit.unwrap_or(false),
+            )?;
+            let changed = locate_card(db, &id)
+                .map(|location| CardChanged::new(CardChangeKind::Created, location, None));
+
+            let card = Card {
+                id,
+                column_id,
+                title,
+                description,
+                folder_path: None,
+                file_paths: "[]".to_string(),
+                agent_config: "{}".to_string(),
+                position,
+                status: "idle".to_string(),
+                created_at: now,
+                updated_at: now,
+                metadata: "{}".to_string(),
+                due_at: None,
+                priority,
+                archived_at: None,
+                version: 0,
+                checklist_progress: ChecklistProgress::default(),
+            };
+            Ok::<_, AppError>((card, changed))
+        })
+        .await?;
+
+    emit_card_changed(&app, changed);
+    Ok(card)
 }
 
+/// Save a card's fields. Pass the `version` the edit was based on as
+/// `expected_version` to get a `CONFLICT` carrying the current card instead of
+/// overwriting someone else's change.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn update_card(
     id: String,
     title: String,
     description: Option<String>,
     status: String,
+    priority: Option<String>,
+    force: Option<bool>,
+    expected_version: Option<i64>,
+    app: AppHandle,
+    db: State<'_, Arc<Database>>,
+) -> Result<(), AppError> {
+    if let Some(priority) = &priority {
+        validate_priority(priority)?;
+    }
+
+    let changed = db
+        .blocking(move |db| {
+            db.update_card(
+                &id,
+                &title,
+                description.as_deref(),
+                &status,
+                priority.as_deref(),
+                force.unwrap_or(false),
+                expected_version,
+            )?;
+            Ok::<_, CardError>(
+                locate_card(db, &id)
+                    .map(|location| CardChanged::new(CardChangeKind::Updated, location, None)),
+            )
+        })
+        .await?;
+
+    emit_card_changed(&app, changed);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn update_card_status(
+    id: String,
+    status: String,
+    force: Option<bool>,
+    app: AppHandle,
     db: State<'_, Arc<Database>>,
-) -> Result<(), String> {
-    db.update_card(&id, &title, description.as_deref(), &status)
-        .map_err(|e| e.to_string())
+) -> Result<(), AppError> {
+    let changed = db
+        .blocking(move |db| {
+            db.update_card_status(&id, &status, force.unwrap_or(false))?;
+            Ok::<_, CardError>(
+                locate_card(db, &id).map(|location| {
+                    CardChanged::new(CardChangeKind::StatusChanged, location, None)
+                }),
+            )
+        })
+        .await?;
+
+    emit_card_changed(&app, changed);
+    Ok(())
 }
 
+/// Change a card's status, following the transition rules in `status`
+/// without exception. A disallowed change fails with `INVALID_TRANSITION`
+/// naming both states; `update_card_status` with `force` is the way around it.
+#[tauri::command]
+pub async fn transition_card_status(
+    id: String,
+    new_status: String,
+    app: AppHandle,
+    db: State<'_, Arc<Database>>,
+) -> Result<(), AppError> {
+    update_card_status(id, new_status, Some(false), app, db).await
+}
+
+/// Move a card and run the destination column's automation. Like
+/// `create_card`, a full column needs `override_limit`.
 #[tauri::command]
 pub async fn move_card(
     id: String,
     column_id: String,
     position: i32,
+    override_limit: Option<bool>,
+    app: AppHandle,
+    db: State<'_, Arc<Database>>,
+    processes: State<'_, Arc<ProcessManager>>,
+) -> Result<Vec<RuleOutcome>, AppError> {
+    let processes = processes.inner().clone();
+    let (outcomes, changed) = db
+        .blocking(move |db| {
+            move_card_in(
+                db,
+                &processes,
+                &id,
+                &column_id,
+                position,
+                override_limit.unwrap_or(false),
+            )
+        })
+        .await?;
+
+    emit_card_changed(&app, changed);
+    Ok(outcomes)
+}
+
+/// Move a card, returning the rule outcomes and the event describing the move
+fn move_card_in(
+    db: &Arc<Database>,
+    processes: &Arc<ProcessManager>,
+    id: &str,
+    column_id: &str,
+    position: i32,
+    override_limit: bool,
+) -> Result<(Vec<RuleOutcome>, Option<CardChanged>), CardError> {
+    let (from_column_id, outcomes) =
+        automation::move_card(db, processes, id, column_id, position, override_limit)?;
+    let changed = locate_card(db, id)
+        .map(|location| CardChanged::new(CardChangeKind::Moved, location, Some(from_column_id)));
+    Ok((outcomes, changed))
+}
+
+#[tauri::command]
+pub async fn get_card_activity(
+    card_id: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<Vec<CardActivity>, AppError> {
+    db.blocking(move |db| db.get_card_activity(&card_id))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Field-level change history of a card, newest first
+#[tauri::command]
+pub async fn get_card_history(
+    card_id: String,
     db: State<'_, Arc<Database>>,
-) -> Result<(), String> {
-    db.move_card(&id, &column_id, position)
-        .map_err(|e| e.to_string())
+) -> Result<Vec<CardAuditEntry>, AppError> {
+    db.blocking(move |db| db.get_card_history(&card_id))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Move a card onto another board in the same project, at the end of
+/// `target_column_id` or of the board's first column. `keep_labels` defaults
+/// to true.
+#[tauri::command]
+pub async fn move_card_to_board(
+    id: String,
+    board_id: String,
+    target_column_id: Option<String>,
+    keep_labels: Option<bool>,
+    app: AppHandle,
+    db: State<'_, Arc<Database>>,
+) -> Result<Card, AppError> {
+    let (card, changed) = db
+        .blocking(move |db| {
+            let (card, from_column_id) = db.move_card_to_board(
+                &id,
+                &board_id,
+                target_column_id.as_deref(),
+                keep_labels.unwrap_or(true),
+            )?;
+            let changed = locate_card(db, &card.id).map(|location| {
+                CardChanged::new(CardChangeKind::Moved, location, Some(from_column_id))
+            });
+            Ok::<_, CardError>((card, changed))
+        })
+        .await?;
+
+    emit_card_changed(&app, changed);
+    Ok(card)
 }
 
 #[tauri::command]
@@ -68,33 +418,617 @@ pub async fn attach_folder(
     id: String,
     folder_path: String,
     db: State<'_, Arc<Database>>,
-) -> Result<(), String> {
-    db.attach_folder(&id, &folder_path)
-        .map_err(|e| e.to_string())
+) -> Result<(), AppError> {
+    db.blocking(move |db| db.attach_folder(&id, &folder_path))
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn update_card_metadata(
+    id: String,
+    patch: serde_json::Value,
+    expected_version: Option<i64>,
+    db: State<'_, Arc<Database>>,
+) -> Result<serde_json::Value, AppError> {
+    db.blocking(move |db| db.update_card_metadata(&id, &patch, expected_version))
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_agent_config(
+    card_id: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<AgentConfig, AppError> {
+    db.blocking(move |db| db.get_agent_config(&card_id))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Validate and store a card's agent config, returning the canonical form
+#[tauri::command]
+pub async fn set_agent_config(
+    card_id: String,
+    config: serde_json::Value,
+    expected_version: Option<i64>,
+    db: State<'_, Arc<Database>>,
+) -> Result<AgentConfig, AppError> {
+    let config: AgentConfig = serde_json::from_value(config)
+        .map_err(|e| AppError::validation("config", format!("Invalid agent config: {}", e)))?;
+    config
+        .validate()
+        .map_err(|e| AppError::validation("config", e))?;
+
+    db.blocking(move |db| {
+        db.set_agent_config(&card_id, &config, expected_version)
+            .map(|_| config)
+    })
+    .await
+    .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn attach_files(
+    card_id: String,
+    paths: Vec<String>,
+    db: State<'_, Arc<Database>>,
+) -> Result<Vec<String>, AppError> {
+    db.blocking(move |db| attach_files_in(db, &card_id, &paths))
+        .await
+}
+
+/// Attach `paths` to a card by their canonical form, so one file is only
+/// attached once however it was spelled
+fn attach_files_in(
+    db: &Database,
+    card_id: &str,
+    paths: &[String],
+) -> Result<Vec<String>, AppError> {
+    let root = db
+        .get_card_project_root(card_id)?
+        .and_then(|root| Path::new(&root).canonicalize().ok());
+
+    let mut canonical_paths = Vec::with_capacity(paths.len());
+    for path in paths {
+        let canonical = Path::new(path)
+            .canonicalize()
+            .map_err(|_| AppError::NotFound(format!("Path does not exist: {}", path)))?;
+
+        if let Some(root) = &root {
+            if !canonical.starts_with(root) {
+                return Err(AppError::validation(
+                    "paths",
+                    format!("Path is outside the project: {}", path),
+                ));
+            }
+        }
+        canonical_paths.push(canonical.to_string_lossy().into_owned());
+    }
+
+    db.attach_files(card_id, &canonical_paths)
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-pub async fn delete_card(id: String, db: State<'_, Arc<Database>>) -> Result<(), String> {
-    db.delete_card(&id).map_err(|e| e.to_string())
+pub async fn detach_file(
+    card_id: String,
+    path: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<Vec<String>, AppError> {
+    db.blocking(move |db| db.detach_file(&card_id, &path))
+        .await
+        .map_err(AppError::from)
 }
 
-/// Phase 1 placeholder for card execution
-/// In Phase 2, this will invoke the AI agent
+/// Attached files with an `exists` flag so the UI can mark broken links
 #[tauri::command]
-pub async fn execute_card_placeholder(
+pub async fn get_card_files(
     card_id: String,
     db: State<'_, Arc<Database>>,
-) -> Result<String, String> {
-    // Update status to executing
-    db.update_card_status(&card_id, "executing")
-        .map_err(|e| e.to_string())?;
+) -> Result<Vec<CardFile>, AppError> {
+    db.blocking(move |db| {
+        let paths = db.get_card_file_paths(&card_id)?;
+
+        Ok(paths
+            .into_iter()
+            .map(|path| CardFile {
+                exists: Path::new(&path).exists(),
+                path,
+            })
+            .collect())
+    })
+    .await
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CardMarkdown {
+    pub markdown: String,
+    /// Where it was written, when a destination was given
+    pub path: Option<String>,
+}
+
+/// Render a card as a markdown document for sharing outside the app. With
+/// `dest_path` the document is also written there; a directory gets a file
+/// named after the card's title.
+#[tauri::command]
+pub async fn export_card_markdown(
+    card_id: String,
+    dest_path: Option<String>,
+    db: State<'_, Arc<Database>>,
+) -> Result<CardMarkdown, AppError> {
+    let (card, checklist, execution) = db
+        .blocking(move |db| {
+            let card = db
+                .get_card(&card_id)?
+                .ok_or_else(|| CardError::CardNotFound(card_id.clone()))?;
+            let checklist = db.get_checklist(&card_id)?;
+            let execution = db.get_latest_execution(&card_id)?;
+            Ok::<_, AppError>((card, checklist, execution))
+        })
+        .await?;
+
+    let markdown = render_card_markdown(&card, &checklist, execution.as_ref());
+
+    let Some(dest_path) = dest_path else {
+        return Ok(CardMarkdown {
+            markdown,
+            path: None,
+        });
+    };
+
+    let mut path = PathBuf::from(dest_path);
+    if path.is_dir() {
+        path.push(format!("{}.md", markdown_file_stem(&card)));
+    }
+
+    tokio::fs::write(&path, &markdown)
+        .await
+        .map_err(|e| AppError::io("Failed to write card export", e))?;
+
+    Ok(CardMarkdown {
+        markdown,
+        path: Some(path.to_string_lossy().into_owned()),
+    })
+}
+
+fn render_card_markdown(
+    card: &Card,
+    checklist: &[ChecklistItem],
+    execution: Option<&CardExecution>,
+) -> String {
+    let timestamp = |secs: i64| {
+        chrono::DateTime::from_timestamp(secs, 0)
+            .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+            .unwrap_or_else(|| secs.to_string())
+    };
+
+    let mut out = format!("# {}\n\n", card.title);
+
+    if let Some(description) = card.description.as_deref().filter(|d| !d.trim().is_empty()) {
+        out.push_str(&format!("{}\n\n", description.trim_end()));
+    }
+
+    out.push_str("| Field | Value |\n| --- | --- |\n");
+    out.push_str(&format!("| Status | {} |\n", card.status));
+    out.push_str(&format!("| Priority | {} |\n", card.priority));
+    out.push_str(&format!("| Created | {} |\n", timestamp(card.created_at)));
+    out.push_str(&format!("| Updated | {} |\n", timestamp(card.updated_at)));
+    if let Some(due_at) = card.due_at {
+        out.push_str(&format!("| Due | {} |\n", timestamp(due_at)));
+    }
+    out.push_str(&format!(
+        "| Folder | {} |\n",
+        card.folder_path.as_deref().unwrap_or("-")
+    ));
+
+    if !checklist.is_empty() {
+        out.push_str("\n## Checklist\n\n");
+        for item in checklist {
+            let mark = if item.done { "x" } else { " " };
+            out.push_str(&format!("- [{}] {}\n", mark, item.text));
+        }
+    }
+
+    let files: Vec<String> = serde_json::from_str(&card.file_paths).unwrap_or_default();
+    if !files.is_empty() {
+        out.push_str("\n## Files\n\n");
+        for file in &files {
+            out.push_str(&format!("- {}\n", file));
+        }
+    }
+
+    if let Some(execution) = execution {
+        out.push_str("\n## Latest execution\n\n");
+        out.push_str(&format!(
+            "{} by the {} agent, started {}",
+            execution.status,
+            execution.agent_type,
+            timestamp(execution.started_at)
+        ));
+        if let Some(completed_at) = execution.completed_at {
+            out.push_str(&format!(", finished {}", timestamp(completed_at)));
+        }
+        out.push('\n');
+
+        if let Some(output) = execution.output_result.as_deref() {
+            out.push_str(&format!("\n{}\n", output.trim_end()));
+        }
+        if let Some(error) = execution.error_message.as_deref() {
+            out.push_str(&format!("\nError: {}\n", error.trim_end()));
+        }
+    }
+
+    out
+}
+
+/// The card's title made safe to use as a file name on any platform, falling
+/// back to its id when nothing usable is left
+fn markdown_file_stem(card: &Card) -> String {
+    let cleaned: String = card
+        .title
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '-',
+            c if c.is_control() => '-',
+            c => c,
+        })
+        .take(100)
+        .collect();
+
+    let stem = cleaned.trim().trim_matches('.').trim();
+    if stem.is_empty() {
+        card.id.clone()
+    } else {
+        stem.to_string()
+    }
+}
+
+#[tauri::command]
+pub async fn set_card_due(
+    id: String,
+    due_at: Option<i64>,
+    db: State<'_, Arc<Database>>,
+) -> Result<(), AppError> {
+    db.blocking(move |db| db.set_card_due(&id, due_at))
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_overdue_cards(
+    project_id: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<Vec<Card>, AppError> {
+    db.blocking(move |db| db.get_overdue_cards(&project_id))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Project-wide "recently changed" feed; `status_filter` narrows it to e.g.
+/// cards in error
+#[tauri::command]
+pub async fn get_recent_cards(
+    project_id: String,
+    limit: Option<u32>,
+    since: Option<i64>,
+    status_filter: Option<Vec<String>>,
+    db: State<'_, Arc<Database>>,
+) -> Result<Vec<RecentCard>, AppError> {
+    db.blocking(move |db| {
+        db.get_recent_cards(
+            &project_id,
+            limit.unwrap_or(50),
+            since,
+            &status_filter.unwrap_or_default(),
+        )
+    })
+    .await
+    .map_err(AppError::from)
+}
+
+/// Copy a card, optionally into another column (appended at the end)
+#[tauri::command]
+pub async fn duplicate_card(
+    id: String,
+    target_column_id: Option<String>,
+    app: AppHandle,
+    db: State<'_, Arc<Database>>,
+) -> Result<Card, AppError> {
+    let (card, changed) = db
+        .blocking(move |db| {
+            let card = db.duplicate_card(&id, target_column_id.as_deref())?;
+            let changed = locate_card(db, &card.id)
+                .map(|location| CardChanged::new(CardChangeKind::Created, location, None));
+            Ok::<_, CardError>((card, changed))
+        })
+        .await?;
+
+    emit_card_changed(&app, changed);
+    Ok(card)
+}
+
+#[tauri::command]
+pub async fn bulk_update_cards(
+    ids: Vec<String>,
+    op: BulkCardOp,
+    atomic: bool,
+    app: AppHandle,
+    db: State<'_, Arc<Database>>,
+) -> Result<Vec<BulkCardResult>, AppError> {
+    let (results, changes) = db
+        .blocking(move |db| {
+            let before = locate_cards(db, &ids);
+            let results = db.bulk_update_cards(&ids, &op, atomic)?;
+            let changed: HashSet<&str> = results
+                .iter()
+                .filter(|result| result.ok)
+                .map(|result| result.id.as_str())
+                .collect();
+            let before = before
+                .into_iter()
+                .filter(|location| changed.contains(location.card_id.as_str()))
+                .collect();
+            let changes = bulk_changes(db, before, &op);
+            Ok::<_, rusqlite::Error>((results, changes))
+        })
+        .await?;
+
+    for changed in changes {
+        emit_card_changed(&app, Some(changed));
+    }
+    Ok(results)
+}
+
+/// Run `apply`, an all-or-nothing bulk `op`, and emit `card-changed` for
+/// each card
+async fn bulk_apply(
+    ids: Vec<String>,
+    op: BulkCardOp,
+    app: AppHandle,
+    db: State<'_, Arc<Database>>,
+    apply: impl FnOnce(&Database, &[String]) -> Result<usize, CardError> + Send + 'static,
+) -> Result<usize, AppError> {
+    let (count, changes) = db
+        .blocking(move |db| {
+            let before = locate_cards(db, &ids);
+            let count = apply(db, &ids)?;
+            Ok::<_, CardError>((count, bulk_changes(db, before, &op)))
+        })
+        .await?;
+
+    for changed in changes {
+        emit_card_changed(&app, Some(changed));
+    }
+    Ok(count)
+}
+
+/// Move the cards to the end of `target_column_id`, all or nothing. Returns
+/// how many moved.
+#[tauri::command]
+pub async fn bulk_move_cards(
+    ids: Vec<String>,
+    target_column_id: String,
+    app: AppHandle,
+    db: State<'_, Arc<Database>>,
+) -> Result<usize, AppError> {
+    let op = BulkCardOp::MoveToColumn {
+        column_id: target_column_id.clone(),
+    };
+    bulk_apply(ids, op, app, db, move |db, ids| {
+        db.bulk_move_cards(ids, &target_column_id)
+    })
+    .await
+}
+
+/// Delete the cards, all or nothing. Returns how many were deleted.
+#[tauri::command]
+pub async fn bulk_delete_cards(
+    ids: Vec<String>,
+    app: AppHandle,
+    db: State<'_, Arc<Database>>,
+) -> Result<usize, AppError> {
+    bulk_apply(ids, BulkCardOp::Delete, app, db, |db, ids| {
+        db.bulk_delete_cards(ids)
+    })
+    .await
+}
+
+/// Set the status of every card, all or nothing. Returns how many changed.
+#[tauri::command]
+pub async fn bulk_update_status(
+    ids: Vec<String>,
+    status: String,
+    app: AppHandle,
+    db: State<'_, Arc<Database>>,
+) -> Result<usize, AppError> {
+    let op = BulkCardOp::SetStatus {
+        status: status.clone(),
+    };
+    bulk_apply(ids, op, app, db, move |db, ids| {
+        db.bulk_update_status(ids, &status)
+    })
+    .await
+}
+
+/// Attach a label to every card, all or nothing. Returns how many cards
+/// were touched, including ones that already had it.
+#[tauri::command]
+pub async fn bulk_assign_label(
+    ids: Vec<String>,
+    label_id: String,
+    app: AppHandle,
+    db: State<'_, Arc<Database>>,
+) -> Result<usize, AppError> {
+    let op = BulkCardOp::AssignLabel {
+        label_id: label_id.clone(),
+    };
+    bulk_apply(ids, op, app, db, move |db, ids| {
+        db.bulk_assign_label(ids, &label_id)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn delete_card(
+    id: String,
+    app: AppHandle,
+    db: State<'_, Arc<Database>>,
+) -> Result<(), AppError> {
+    let changed = db
+        .blocking(move |db| {
+            let location = locate_card(db, &id);
+            db.delete_card(&id)?;
+            Ok::<_, rusqlite::Error>(
+                location.map(|location| CardChanged::new(CardChangeKind::Deleted, location, None)),
+            )
+        })
+        .await?;
+
+    emit_card_changed(&app, changed);
+    Ok(())
+}
+
+/// Reverse the project's most recent card update, move or delete. `None` when
+/// there's nothing to undo.
+#[tauri::command]
+pub async fn undo_last(
+    project_id: String,
+    app: AppHandle,
+    db: State<'_, Arc<Database>>,
+) -> Result<Option<AppliedOperation>, AppError> {
+    let applied = db.blocking(move |db| db.undo_last(&project_id)).await?;
+
+    emit_card_changed(&app, applied.as_ref().and_then(replay_change));
+    Ok(applied)
+}
+
+/// Re-apply the change most recently undone in the project
+#[tauri::command]
+pub async fn redo_last(
+    project_id: String,
+    app: AppHandle,
+    db: State<'_, Arc<Database>>,
+) -> Result<Option<AppliedOperation>, AppError> {
+    let applied = db.blocking(move |db| db.redo_last(&project_id)).await?;
+
+    emit_card_changed(&app, applied.as_ref().and_then(replay_change));
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::testing::*;
+
+    #[test]
+    fn a_move_event_names_both_columns() {
+        let db = Arc::new(memory_db());
+        let fixture = project_with_column(&db, "/tmp/card-changed");
+        let done = uuid::Uuid::new_v4().to_string();
+        db.create_column(&done, &fixture.board_id, "Done", 1)
+            .unwrap();
+        let card_id = add_card(&db, &fixture.column_id, "Move me");
+
+        let processes = Arc::new(ProcessManager::new());
+        let (_, changed) = move_card_in(&db, &processes, &card_id, &done, 0, false).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(changed.unwrap()).unwrap(),
+            serde_json::json!({
+                "card_id": card_id,
+                "project_id": fixture.project_id,
+                "board_id": fixture.board_id,
+                "column_id": done,
+                "from_column_id": fixture.column_id,
+                "kind": "moved",
+            })
+        );
+    }
+
+    #[test]
+    fn undoing_a_delete_reports_the_card_as_created_again() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/tmp/undo-changed");
+        let card_id = add_card(&db, &fixture.column_id, "Gone and back");
+        db.delete_card(&card_id).unwrap();
+
+        let undone = db.undo_last(&fixture.project_id).unwrap().unwrap();
+        let changed = replay_change(&undone).unwrap();
+        assert_eq!(changed.kind, CardChangeKind::Created);
+        assert_eq!(changed.column_id, fixture.column_id);
+
+        let redone = db.redo_last(&fixture.project_id).unwrap().unwrap();
+        let changed = replay_change(&redone).unwrap();
+        assert_eq!(changed.kind, CardChangeKind::Deleted);
+        assert_eq!(changed.card_id, card_id);
+    }
+
+    #[test]
+    fn a_bulk_move_reports_each_card_once_with_its_source() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/tmp/bulk-changed");
+        let done = uuid::Uuid::new_v4().to_string();
+        db.create_column(&done, &fixture.board_id, "Done", 1)
+            .unwrap();
+        let first = add_card(&db, &fixture.column_id, "First");
+        let second = add_card(&db, &fixture.column_id, "Second");
+
+        let ids = [first.clone(), second.clone(), first.clone()];
+        let before = locate_cards(&db, &ids);
+        db.bulk_move_cards(&ids, &done).unwrap();
+        let op = BulkCardOp::MoveToColumn {
+            column_id: done.clone(),
+        };
+        let changes = bulk_changes(&db, before, &op);
+
+        let moved: Vec<_> = changes
+            .iter()
+            .map(|changed| {
+                (
+                    changed.card_id.as_str(),
+                    changed.kind,
+                    changed.column_id.as_str(),
+                    changed.from_column_id.as_deref(),
+                )
+            })
+            .collect();
+        let from = Some(fixture.column_id.as_str());
+        assert_eq!(
+            moved,
+            [
+                (first.as_str(), CardChangeKind::Moved, done.as_str(), from),
+                (second.as_str(), CardChangeKind::Moved, done.as_str(), from),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_file_is_attached_once_however_it_is_spelled() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("project");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+
+        let db = memory_db();
+        let fixture = project_with_column(&db, &root.to_string_lossy());
+        let card_id = add_card(&db, &fixture.column_id, "Attach");
 
-    // Simulate work (in real implementation, this would be async agent work)
-    tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+        let spellings = [
+            root.join("src/main.rs"),
+            root.join("src/../src/main.rs"),
+            root.join("./src/main.rs"),
+        ]
+        .map(|path| path.to_string_lossy().into_owned());
+        let attached = attach_files_in(&db, &card_id, &spellings).unwrap();
 
-    // Update status to review
-    db.update_card_status(&card_id, "review")
-        .map_err(|e| e.to_string())?;
+        let canonical = root.join("src/main.rs").canonicalize().unwrap();
+        assert_eq!(attached, [canonical.to_string_lossy().into_owned()]);
 
-    Ok("Card executed (placeholder - AI integration coming in Phase 2)".to_string())
+        let outside = dir.path().join("outside.txt");
+        std::fs::write(&outside, "").unwrap();
+        let err =
+            attach_files_in(&db, &card_id, &[outside.to_string_lossy().into_owned()]).unwrap_err();
+        assert_eq!(err.code(), "VALIDATION");
+    }
 }
@@ -0,0 +1,56 @@
+use crate::database::{ChecklistItem, Database};
+use crate::error::AppError;
+use std::sync::Arc;
+use tauri::State;
+
+#[tauri::command]
+pub async fn get_checklist(
+    card_id: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<Vec<ChecklistItem>, AppError> {
+    db.blocking(move |db| db.get_checklist(&card_id))
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn add_checklist_item(
+    card_id: String,
+    text: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<ChecklistItem, AppError> {
+    db.blocking(move |db| db.add_checklist_item(&card_id, &text))
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn toggle_checklist_item(
+    id: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<ChecklistItem, AppError> {
+    db.blocking(move |db| db.toggle_checklist_item(&id))
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn reorder_checklist_items(
+    card_id: String,
+    ordered_ids: Vec<String>,
+    db: State<'_, Arc<Database>>,
+) -> Result<(), AppError> {
+    db.blocking(move |db| db.reorder_checklist_items(&card_id, &ordered_ids))
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn delete_checklist_item(
+    id: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<(), AppError> {
+    db.blocking(move |db| db.delete_checklist_item(&id))
+        .await
+        .map_err(AppError::from)
+}
@@ -1,4 +1,6 @@
-use crate::database::{Column, Database};
+use crate::automation::{self, AutomationRule, RulesValidation};
+use crate::database::{Column, ColumnCardCount, Database};
+use crate::error::AppError;
 use std::sync::Arc;
 use tauri::State;
 
@@ -6,8 +8,20 @@ use tauri::State;
 pub async fn get_columns(
     board_id: String,
     db: State<'_, Arc<Database>>,
-) -> Result<Vec<Column>, String> {
-    db.get_columns(&board_id).map_err(|e| e.to_string())
+) -> Result<Vec<Column>, AppError> {
+    db.blocking(move |db| db.get_columns(&board_id))
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_card_counts(
+    board_id: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<Vec<ColumnCardCount>, AppError> {
+    db.blocking(move |db| db.get_card_counts(&board_id))
+        .await
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -16,21 +30,37 @@ pub async fn create_column(
     name: String,
     position: i32,
     db: State<'_, Arc<Database>>,
-) -> Result<Column, String> {
+) -> Result<Column, AppError> {
     let id = uuid::Uuid::new_v4().to_string();
     let now = chrono::Utc::now().timestamp();
 
-    db.create_column(&id, &board_id, &name, position)
-        .map_err(|e| e.to_string())?;
+    db.blocking(move |db| {
+        db.create_column(&id, &board_id, &name, position)?;
 
-    Ok(Column {
-        id,
-        board_id,
-        name,
-        position,
-        automation_rules: "[]".to_string(),
-        created_at: now,
+        Ok(Column {
+            id,
+            board_id,
+            name,
+            position,
+            automation_rules: "[]".to_string(),
+            created_at: now,
+            wip_limit: None,
+            card_count: 0,
+        })
     })
+    .await
+}
+
+/// Create several columns at once, e.g. when setting up a new board
+#[tauri::command]
+pub async fn create_columns(
+    board_id: String,
+    names: Vec<String>,
+    db: State<'_, Arc<Database>>,
+) -> Result<Vec<Column>, AppError> {
+    db.blocking(move |db| db.create_columns(&board_id, &names))
+        .await
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -39,12 +69,105 @@ pub async fn update_column(
     name: String,
     position: i32,
     db: State<'_, Arc<Database>>,
-) -> Result<(), String> {
-    db.update_column(&id, &name, position)
-        .map_err(|e| e.to_string())
+) -> Result<(), AppError> {
+    db.blocking(move |db| db.update_column(&id, &name, position))
+        .await
+        .map_err(AppError::from)
 }
 
+/// Cap how many live cards a column holds. `None` removes the limit.
 #[tauri::command]
-pub async fn delete_column(id: String, db: State<'_, Arc<Database>>) -> Result<(), String> {
-    db.delete_column(&id).map_err(|e| e.to_string())
+pub async fn set_wip_limit(
+    column_id: String,
+    limit: Option<i64>,
+    db: State<'_, Arc<Database>>,
+) -> Result<(), AppError> {
+    if limit.is_some_and(|limit| limit < 1) {
+        return Err(AppError::validation(
+            "limit",
+            "WIP limit must be at least 1",
+        ));
+    }
+
+    db.blocking(move |db| {
+        db.set_wip_limit(&column_id, limit).map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => {
+                AppError::NotFound(format!("Column not found: {}", column_id))
+            }
+            e => e.into(),
+        })
+    })
+    .await
+}
+
+/// Put a board's columns in the given order, positions 0..n. `ordered_ids`
+/// must list every column on the board exactly once.
+#[tauri::command]
+pub async fn reorder_columns(
+    board_id: String,
+    ordered_ids: Vec<String>,
+    db: State<'_, Arc<Database>>,
+) -> Result<Vec<Column>, AppError> {
+    db.blocking(move |db| db.reorder_columns(&board_id, &ordered_ids))
+        .await?
+        .ok_or_else(|| {
+            AppError::validation(
+                "ordered_ids",
+                "Must list each of the board's columns exactly once",
+            )
+        })
+}
+
+#[tauri::command]
+pub async fn delete_column(id: String, db: State<'_, Arc<Database>>) -> Result<(), AppError> {
+    db.blocking(move |db| db.delete_column(&id))
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn delete_column_and_reassign(
+    id: String,
+    target_column_id: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<(), AppError> {
+    db.blocking(move |db| db.delete_column_and_reassign(&id, &target_column_id))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Lint a rules document without storing it
+#[tauri::command]
+pub async fn validate_automation_rules(rules_json: String) -> Result<RulesValidation, AppError> {
+    let value = match serde_json::from_str(&rules_json) {
+        Ok(value) => value,
+        Err(e) => {
+            return Ok(RulesValidation {
+                valid: false,
+                normalized: None,
+                errors: vec![format!("invalid JSON: {}", e)],
+            })
+        }
+    };
+
+    Ok(automation::validate_rules(&value))
+}
+
+/// Replace a column's automation rules. Nothing is stored unless every rule
+/// passes `validate_automation_rules`.
+#[tauri::command]
+pub async fn set_column_automation(
+    column_id: String,
+    rules: serde_json::Value,
+    db: State<'_, Arc<Database>>,
+) -> Result<Vec<AutomationRule>, AppError> {
+    let validation = automation::validate_rules(&rules);
+    let Some(rules) = validation.normalized else {
+        return Err(AppError::validation("rules", validation.errors.join("; ")));
+    };
+
+    let json = serde_json::to_string(&rules)?;
+    db.blocking(move |db| db.set_column_automation(&column_id, &json).map(|_| rules))
+        .await
+        .map_err(AppError::from)
 }
@@ -0,0 +1,58 @@
+use crate::database::{Comment, Database};
+use crate::error::AppError;
+use std::sync::Arc;
+use tauri::State;
+
+#[tauri::command]
+pub async fn get_comments(
+    card_id: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<Vec<Comment>, AppError> {
+    db.blocking(move |db| db.get_comments(&card_id))
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn add_comment(
+    card_id: String,
+    author: String,
+    body: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<Comment, AppError> {
+    if body.trim().is_empty() {
+        return Err(AppError::validation(
+            "body",
+            "Comment body must not be empty",
+        ));
+    }
+
+    db.blocking(move |db| db.add_comment(&card_id, &author, &body))
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn edit_comment(
+    id: String,
+    body: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<Comment, AppError> {
+    if body.trim().is_empty() {
+        return Err(AppError::validation(
+            "body",
+            "Comment body must not be empty",
+        ));
+    }
+
+    db.blocking(move |db| db.edit_comment(&id, &body))
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn delete_comment(id: String, db: State<'_, Arc<Database>>) -> Result<(), AppError> {
+    db.blocking(move |db| db.delete_comment(&id))
+        .await
+        .map_err(AppError::from)
+}
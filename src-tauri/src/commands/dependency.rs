@@ -0,0 +1,49 @@
+use crate::database::{BlockedCard, CardDependencies, Database};
+use crate::error::AppError;
+use std::sync::Arc;
+use tauri::State;
+
+/// Make `card_id` wait on `depends_on_card_id`. Self-dependencies are a
+/// validation error; a link that would form a cycle is a conflict.
+#[tauri::command]
+pub async fn add_dependency(
+    card_id: String,
+    depends_on_card_id: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<(), AppError> {
+    db.blocking(move |db| db.add_dependency(&card_id, &depends_on_card_id))
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn remove_dependency(
+    card_id: String,
+    depends_on_card_id: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<(), AppError> {
+    db.blocking(move |db| db.remove_dependency(&card_id, &depends_on_card_id))
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_dependencies(
+    card_id: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<CardDependencies, AppError> {
+    db.blocking(move |db| db.get_dependencies(&card_id))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Cards that can't start yet because a dependency isn't done
+#[tauri::command]
+pub async fn get_blocked_cards(
+    project_id: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<Vec<BlockedCard>, AppError> {
+    db.blocking(move |db| db.get_blocked_cards(&project_id))
+        .await
+        .map_err(AppError::from)
+}
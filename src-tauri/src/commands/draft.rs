@@ -0,0 +1,66 @@
+use crate::database::{Database, Draft, DraftInfo};
+use crate::error::AppError;
+use std::sync::Arc;
+use tauri::State;
+
+/// Largest buffer kept as a draft; anything bigger would bloat the database
+const MAX_DRAFT_BYTES: usize = 5 * 1024 * 1024;
+
+/// Keep the unsaved content of an open file so it can be recovered after a
+/// crash. Called by the editor, debounced, while the buffer is dirty.
+#[tauri::command]
+pub async fn save_draft(
+    project_id: String,
+    file_path: String,
+    content: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<DraftInfo, AppError> {
+    if content.len() > MAX_DRAFT_BYTES {
+        return Err(AppError::validation(
+            "content",
+            format!(
+                "Draft is {:.1} MB; drafts are limited to {} MB",
+                content.len() as f64 / (1024.0 * 1024.0),
+                MAX_DRAFT_BYTES / (1024 * 1024)
+            ),
+        ));
+    }
+
+    db.blocking(move |db| db.save_draft(&project_id, &file_path, &content))
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_draft(
+    project_id: String,
+    file_path: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<Option<Draft>, AppError> {
+    db.blocking(move |db| db.get_draft(&project_id, &file_path))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Files in the project with unsaved drafts, most recent first
+#[tauri::command]
+pub async fn list_drafts(
+    project_id: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<Vec<DraftInfo>, AppError> {
+    db.blocking(move |db| db.list_drafts(&project_id))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Returns false if the file had no draft
+#[tauri::command]
+pub async fn discard_draft(
+    project_id: String,
+    file_path: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<bool, AppError> {
+    db.blocking(move |db| db.discard_draft(&project_id, &file_path))
+        .await
+        .map_err(AppError::from)
+}
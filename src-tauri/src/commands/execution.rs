@@ -0,0 +1,683 @@
+use super::agent::{publish_log, record_log};
+use crate::database::{AgentConfig, CardExecution, Database};
+use crate::error::AppError;
+use crate::files::resolve_in_root;
+use crate::logs::LogBuffer;
+use crate::process::{OutputStream, ProcessManager};
+use crate::queue::{ExecutionQueue, QueueSnapshot};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Runtime, State};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Most output lines stored per transaction
+const LOG_BATCH: usize = 256;
+
+/// Payload of the `execution-error` event, sent when a queued card fails to start
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionErrorEvent {
+    pub card_id: String,
+    pub message: String,
+}
+
+/// Run the command from the card's agent_config in its folder, streaming output
+/// into agent_logs. Resolves with the finished execution once the command exits.
+#[tauri::command]
+pub async fn execute_card(
+    card_id: String,
+    app: AppHandle,
+    db: State<'_, Arc<Database>>,
+    buffer: State<'_, Arc<LogBuffer>>,
+    processes: State<'_, Arc<ProcessManager>>,
+) -> Result<CardExecution, AppError> {
+    run_card(
+        app,
+        db.inner().clone(),
+        buffer.inner().clone(),
+        processes.inner().clone(),
+        &card_id,
+    )
+    .await
+}
+
+/// Queue a card to run once a slot frees up. The card shows as `queued` until
+/// it starts.
+#[tauri::command]
+pub async fn enqueue_card(
+    card_id: String,
+    app: AppHandle,
+    db: State<'_, Arc<Database>>,
+    buffer: State<'_, Arc<LogBuffer>>,
+    processes: State<'_, Arc<ProcessManager>>,
+    queue: State<'_, Arc<ExecutionQueue>>,
+) -> Result<QueueSnapshot, AppError> {
+    if !queue.push(&card_id) {
+        return Err(AppError::Conflict(format!(
+            "Card {} is already queued or running",
+            card_id
+        )));
+    }
+
+    let queued_id = card_id.clone();
+    let queued = db
+        .blocking(move |db| db.update_card_status(&queued_id, "queued", false))
+        .await;
+    if let Err(e) = queued {
+        queue.remove(&card_id);
+        return Err(e.into());
+    }
+
+    let snapshot = queue.snapshot();
+    let _ = app.emit("queue-updated", &snapshot);
+
+    let (db, buffer, processes, queue) = (
+        db.inner().clone(),
+        buffer.inner().clone(),
+        processes.inner().clone(),
+        queue.inner().clone(),
+    );
+    tokio::spawn(async move {
+        let _permit = queue.acquire().await;
+        // Whichever card is at the front now, which isn't necessarily the one
+        // this task was spawned for once the queue has been reordered
+        let Some(card_id) = queue.start_next() else {
+            return;
+        };
+        let _ = app.emit("queue-updated", queue.snapshot());
+
+        if let Err(e) = run_card(app.clone(), db.clone(), buffer, processes, &card_id).await {
+            let failed_id = card_id.clone();
+            let _ = db
+                .blocking(move |db| db.update_card_status(&failed_id, "error", false))
+                .await;
+            let _ = app.emit(
+                "execution-error",
+                ExecutionErrorEvent {
+                    card_id: card_id.clone(),
+                    message: e.to_string(),
+                },
+            );
+        }
+
+        queue.finish(&card_id);
+        let _ = app.emit("queue-updated", queue.snapshot());
+    });
+
+    Ok(snapshot)
+}
+
+/// Take a card out of the queue before it starts and put it back to `idle`.
+/// Returns false if the card wasn't waiting.
+#[tauri::command]
+pub async fn cancel_queued(
+    card_id: String,
+    app: AppHandle,
+    db: State<'_, Arc<Database>>,
+    queue: State<'_, Arc<ExecutionQueue>>,
+) -> Result<bool, AppError> {
+    if !queue.remove(&card_id) {
+        return Ok(false);
+    }
+
+    db.blocking(move |db| db.update_card_status(&card_id, "idle", false))
+        .await?;
+    let _ = app.emit("queue-updated", queue.snapshot());
+
+    Ok(true)
+}
+
+/// Stop a running execution: the row is marked `cancelled`, the card goes back to
+/// `idle` and its process is killed. Returns false, changing nothing, if the
+/// execution already finished.
+#[tauri::command]
+pub async fn cancel_execution(
+    execution_id: String,
+    app: AppHandle,
+    db: State<'_, Arc<Database>>,
+    buffer: State<'_, Arc<LogBuffer>>,
+    processes: State<'_, Arc<ProcessManager>>,
+) -> Result<bool, AppError> {
+    let (buffer, processes) = (buffer.inner().clone(), processes.inner().clone());
+    db.blocking(move |db| cancel_execution_in(&app, db, &buffer, &processes, &execution_id))
+        .await
+}
+
+fn cancel_execution_in<R: Runtime>(
+    app: &AppHandle<R>,
+    db: &Database,
+    buffer: &LogBuffer,
+    processes: &ProcessManager,
+    execution_id: &str,
+) -> Result<bool, AppError> {
+    let cancelled = db.finish_execution(
+        execution_id,
+        "cancelled",
+        "idle",
+        None,
+        Some("Cancelled by user"),
+    )?;
+    if cancelled.is_none() {
+        return Ok(false);
+    }
+
+    processes.kill_tagged(execution_id);
+    let _ = record_log(
+        app,
+        db,
+        buffer,
+        execution_id,
+        "warn",
+        "Execution cancelled",
+        None,
+    );
+    buffer.evict(execution_id);
+
+    Ok(true)
+}
+
+/// Move a queued card to the front so it starts when the next slot frees up.
+/// Returns false if the card wasn't waiting.
+#[tauri::command]
+pub async fn prioritize_execution(
+    card_id: String,
+    app: AppHandle,
+    queue: State<'_, Arc<ExecutionQueue>>,
+) -> Result<bool, AppError> {
+    if !queue.prioritize(&card_id) {
+        return Ok(false);
+    }
+
+    let _ = app.emit("queue-updated", queue.snapshot());
+    Ok(true)
+}
+
+/// Order the waiting cards as `ordered_ids`; any left out keep their relative
+/// order behind them
+#[tauri::command]
+pub async fn reorder_queue(
+    ordered_ids: Vec<String>,
+    app: AppHandle,
+    queue: State<'_, Arc<ExecutionQueue>>,
+) -> Result<QueueSnapshot, AppError> {
+    queue.reorder(&ordered_ids);
+
+    let snapshot = queue.snapshot();
+    let _ = app.emit("queue-updated", &snapshot);
+    Ok(snapshot)
+}
+
+#[tauri::command]
+pub async fn get_queue(queue: State<'_, Arc<ExecutionQueue>>) -> Result<QueueSnapshot, AppError> {
+    Ok(queue.snapshot())
+}
+
+#[tauri::command]
+pub async fn set_max_concurrent(
+    max_concurrent: usize,
+    app: AppHandle,
+    queue: State<'_, Arc<ExecutionQueue>>,
+) -> Result<QueueSnapshot, AppError> {
+    if max_concurrent == 0 {
+        return Err(AppError::validation(
+            "max_concurrent",
+            "max_concurrent must be at least 1",
+        ));
+    }
+
+    queue.set_max_concurrent(max_concurrent);
+
+    let snapshot = queue.snapshot();
+    let _ = app.emit("queue-updated", &snapshot);
+    Ok(snapshot)
+}
+
+pub(crate) async fn run_card<R: Runtime>(
+    app: AppHandle<R>,
+    db: Arc<Database>,
+    buffer: Arc<LogBuffer>,
+    processes: Arc<ProcessManager>,
+    card_id: &str,
+) -> Result<CardExecution, AppError> {
+    let setup_id = card_id.to_string();
+    let (config, command, cwd, execution) = db
+        .blocking(move |db| {
+            let (config, command, cwd) = prepare_run(db, &setup_id)?;
+            let input_context = serde_json::to_string(&config)?;
+            let execution = db.start_execution(&setup_id, &config.agent_type, &input_context)?;
+            Ok::<_, AppError>((config, command, cwd, execution))
+        })
+        .await?;
+
+    let (line_tx, line_rx) = mpsc::unbounded_channel();
+    let writer = spawn_log_writer(
+        app.clone(),
+        db.clone(),
+        buffer.clone(),
+        execution.id.clone(),
+        line_rx,
+    );
+    let spawned = processes.spawn(
+        &cwd.to_string_lossy(),
+        &command,
+        &config.args,
+        &config.env,
+        move |stream, line| {
+            let _ = line_tx.send((stream, line));
+        },
+    );
+
+    let process = match spawned {
+        Ok(process) => process,
+        Err(e) => {
+            let (app, buffer, execution_id) = (app.clone(), buffer.clone(), execution.id.clone());
+            return db
+                .blocking(move |db| {
+                    let _ = record_log(&app, db, &buffer, &execution_id, "error", &e, None);
+                    buffer.evict(&execution_id);
+                    db.finish_execution(&execution_id, "failed", "error", None, Some(&e))?;
+                    Err(AppError::Io(e))
+                })
+                .await;
+        }
+    };
+
+    processes.tag(process.pid, &execution.id);
+
+    let started = format!("Started {} (pid {})", command, process.pid);
+    let (log_app, log_buffer, execution_id) = (app.clone(), buffer.clone(), execution.id.clone());
+    let current = db
+        .blocking(move |db| {
+            let _ = record_log(
+                &log_app,
+                db,
+                &log_buffer,
+                &execution_id,
+                "info",
+                &started,
+                None,
+            );
+            db.get_execution(&execution_id)
+        })
+        .await?;
+    // A cancel that landed before the tag had no process to kill
+    if current.status != "running" {
+        processes.kill(process.pid);
+    }
+
+    let exit_code = process.handle.await.ok().flatten();
+    // The handle only resolves once every line was handed over, which drops
+    // the sender; wait for the writer to store them before closing out
+    let _ = writer.await;
+    let (status, card_status, level, message) = exit_outcome(exit_code);
+
+    let output_result = serde_json::json!({ "exit_code": exit_code }).to_string();
+    db.blocking(move |db| {
+        let error_message = (status == "failed").then_some(message.as_str());
+        let finished = db.finish_execution(
+            &execution.id,
+            status,
+            card_status,
+            Some(&output_result),
+            error_message,
+        )?;
+
+        let finished = match finished {
+            Some(finished) => {
+                let _ = record_log(&app, db, &buffer, &execution.id, level, &message, None);
+                finished
+            }
+            // Cancelled; cancel_execution already closed it out
+            None => db.get_execution(&execution.id)?,
+        };
+        // Also catches lines a cancelled process wrote after the cancel evicted
+        buffer.evict(&execution.id);
+        Ok(finished)
+    })
+    .await
+}
+
+/// Store a process's output lines on a blocking thread until the sender is
+/// dropped. Each batch of waiting lines is written in one transaction, so a
+/// chatty process neither ties up async workers nor takes the writer per line.
+fn spawn_log_writer<R: Runtime>(
+    app: AppHandle<R>,
+    db: Arc<Database>,
+    buffer: Arc<LogBuffer>,
+    execution_id: String,
+    mut lines: mpsc::UnboundedReceiver<(OutputStream, String)>,
+) -> JoinHandle<()> {
+    tokio::task::spawn_blocking(move || {
+        while let Some(first) = lines.blocking_recv() {
+            let mut batch = vec![first];
+            while batch.len() < LOG_BATCH {
+                match lines.try_recv() {
+                    Ok(line) => batch.push(line),
+                    Err(_) => break,
+                }
+            }
+
+            let entries: Vec<_> = batch
+                .iter()
+                .map(|(stream, line)| {
+                    let (level, metadata) = match stream {
+                        OutputStream::Stdout => ("info", r#"{"stream":"stdout"}"#),
+                        OutputStream::Stderr => ("warn", r#"{"stream":"stderr"}"#),
+                    };
+                    (level, line.as_str(), Some(metadata))
+                })
+                .collect();
+            if let Ok(logs) = db.append_logs(&execution_id, &entries) {
+                for log in &logs {
+                    let _ = publish_log(&app, &buffer, log);
+                }
+            }
+        }
+    })
+}
+
+/// The validated agent_config of a card, the program it runs and the folder to
+/// run it in. A `cwd` may not leave the card's folder, or the project folder
+/// for a card without one.
+fn prepare_run(db: &Database, card_id: &str) -> Result<(AgentConfig, String, PathBuf), AppError> {
+    let folder = db
+        .get_card(card_id)?
+        .ok_or_else(|| AppError::NotFound(format!("Card not found: {}", card_id)))?
+        .folder_path;
+
+    let invalid = |e: String| AppError::validation("agent_config", e);
+    let config = db
+        .get_agent_config(card_id)
+        .map_err(|e| invalid(format!("Invalid agent_config: {}", e)))?;
+    config
+        .validate()
+        .map_err(|e| invalid(format!("Invalid agent_config: {}", e)))?;
+    let command = config
+        .command
+        .clone()
+        .ok_or_else(|| invalid("agent_config has no command to run".to_string()))?;
+
+    let cwd = match (folder, config.cwd.as_deref()) {
+        (Some(folder), Some(cwd)) => resolve_in_root(Path::new(&folder), Path::new(cwd))?,
+        (Some(folder), None) => folder.into(),
+        (None, Some(cwd)) => {
+            let root = db
+                .get_card_project_root(card_id)?
+                .ok_or_else(|| AppError::NotFound(format!("Card not found: {}", card_id)))?;
+            resolve_in_root(Path::new(&root), Path::new(cwd))?
+        }
+        (None, None) => {
+            return Err(AppError::validation(
+                "folder_path",
+                "Card has no folder to run in",
+            ))
+        }
+    };
+
+    Ok((config, command, cwd))
+}
+
+/// Execution status, card status, log level and log message for an exit code
+fn exit_outcome(exit_code: Option<i32>) -> (&'static str, &'static str, &'static str, String) {
+    match exit_code {
+        Some(0) => ("success", "done", "info", "Exited with code 0".to_string()),
+        Some(code) => (
+            "failed",
+            "error",
+            "error",
+            format!("Exited with code {}", code),
+        ),
+        None => (
+            "failed",
+            "error",
+            "error",
+            "Terminated by signal".to_string(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::testing::*;
+    use std::time::Duration;
+
+    fn card_running(db: &Database, folder: &Path, config: AgentConfig) -> String {
+        let fixture = project_with_column(db, &folder.to_string_lossy());
+        let card_id = add_card(db, &fixture.column_id, "Run");
+        db.attach_folder(&card_id, &folder.to_string_lossy())
+            .unwrap();
+        db.set_agent_config(&card_id, &config, None).unwrap();
+        card_id
+    }
+
+    fn shell(script: &str) -> AgentConfig {
+        AgentConfig {
+            command: Some("sh".to_string()),
+            args: vec!["-c".to_string(), script.to_string()],
+            ..AgentConfig::default()
+        }
+    }
+
+    async fn exit_code_of(db: &Database, card_id: &str) -> Option<i32> {
+        let (config, command, cwd) = prepare_run(db, card_id).unwrap();
+        let processes = Arc::new(ProcessManager::new());
+        let process = processes
+            .spawn(
+                &cwd.to_string_lossy(),
+                &command,
+                &config.args,
+                &config.env,
+                |_, _| {},
+            )
+            .unwrap();
+        process.handle.await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn succeeding_command_finishes_the_card_as_done() {
+        let db = memory_db();
+        let folder = tempfile::tempdir().unwrap();
+        let card_id = card_running(&db, folder.path(), shell("test -d ."));
+
+        let (status, card_status, _, _) = exit_outcome(exit_code_of(&db, &card_id).await);
+        assert_eq!((status, card_status), ("success", "done"));
+    }
+
+    #[tokio::test]
+    async fn failing_command_finishes_the_card_as_error() {
+        let db = memory_db();
+        let folder = tempfile::tempdir().unwrap();
+        let card_id = card_running(&db, folder.path(), shell("exit 3"));
+
+        let (status, card_status, _, message) = exit_outcome(exit_code_of(&db, &card_id).await);
+        assert_eq!((status, card_status), ("failed", "error"));
+        assert_eq!(message, "Exited with code 3");
+    }
+
+    #[test]
+    fn missing_card_is_not_found() {
+        let db = memory_db();
+        let err = prepare_run(&db, "missing").unwrap_err();
+        assert_eq!(err.code(), "NOT_FOUND");
+    }
+
+    #[test]
+    fn config_without_a_command_is_rejected() {
+        let db = memory_db();
+        let folder = tempfile::tempdir().unwrap();
+        let card_id = card_running(&db, folder.path(), AgentConfig::default());
+
+        let err = prepare_run(&db, &card_id).unwrap_err();
+        assert_eq!(err.code(), "VALIDATION");
+    }
+
+    #[test]
+    fn without_a_card_folder_cwd_stays_in_the_project() {
+        let db = memory_db();
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir(root.path().join("tools")).unwrap();
+        let fixture = project_with_column(&db, &root.path().to_string_lossy());
+        let card_id = add_card(&db, &fixture.column_id, "No folder");
+
+        let run_in = |cwd: &str| {
+            let config = AgentConfig {
+                cwd: Some(cwd.to_string()),
+                ..shell("true")
+            };
+            db.set_agent_config(&card_id, &config, None).unwrap();
+            prepare_run(&db, &card_id).map(|(_, _, cwd)| cwd)
+        };
+
+        let tools = root.path().canonicalize().unwrap().join("tools");
+        assert_eq!(run_in("tools").unwrap(), tools);
+        assert_eq!(run_in(&tools.to_string_lossy()).unwrap(), tools);
+        for escape in ["/etc", "../.."] {
+            let err = run_in(escape).unwrap_err();
+            assert_eq!(err.code(), "PATH_ESCAPES_PROJECT", "{}", escape);
+        }
+    }
+
+    #[test]
+    fn cwd_may_not_leave_the_card_folder() {
+        let db = memory_db();
+        let folder = tempfile::tempdir().unwrap();
+        std::fs::create_dir(folder.path().join("sub")).unwrap();
+        let inside = AgentConfig {
+            cwd: Some("sub".to_string()),
+            ..shell("true")
+        };
+        let card_id = card_running(&db, folder.path(), inside);
+        let (_, _, cwd) = prepare_run(&db, &card_id).unwrap();
+        assert_eq!(cwd, folder.path().canonicalize().unwrap().join("sub"));
+
+        for escape in ["../..", "/etc"] {
+            let config = AgentConfig {
+                cwd: Some(escape.to_string()),
+                ..shell("true")
+            };
+            db.set_agent_config(&card_id, &config, None).unwrap();
+            let err = prepare_run(&db, &card_id).unwrap_err();
+            assert_eq!(err.code(), "PATH_ESCAPES_PROJECT", "{}", escape);
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn run_card_records_the_outcome_on_the_card_and_execution() {
+        let db = Arc::new(memory_db());
+        let app = tauri::test::mock_app();
+        let buffer = Arc::new(LogBuffer::new());
+        let processes = Arc::new(ProcessManager::new());
+
+        for (script, status, card_status, exit_code) in [
+            ("echo hello", "success", "done", 0),
+            ("echo oops >&2; exit 4", "failed", "error", 4),
+        ] {
+            let folder = tempfile::tempdir().unwrap();
+            let card_id = card_running(&db, folder.path(), shell(script));
+
+            let execution = run_card(
+                app.handle().clone(),
+                db.clone(),
+                buffer.clone(),
+                processes.clone(),
+                &card_id,
+            )
+            .await
+            .unwrap();
+
+            let stored = db.get_execution(&execution.id).unwrap();
+            assert_eq!(stored.status, status, "{}", script);
+            assert!(stored.completed_at.is_some());
+            let output: serde_json::Value =
+                serde_json::from_str(stored.output_result.as_deref().unwrap()).unwrap();
+            assert_eq!(output["exit_code"], exit_code);
+            assert_eq!(db.get_card(&card_id).unwrap().unwrap().status, card_status);
+
+            let logs: Vec<(String, String)> = db
+                .get_logs(&execution.id, None, None, None)
+                .unwrap()
+                .into_iter()
+                .map(|log| (log.level, log.message))
+                .collect();
+            let finished = format!("Exited with code {}", exit_code);
+            let (line, level) = if exit_code == 0 {
+                ("hello", "info")
+            } else {
+                ("oops", "warn")
+            };
+            assert!(logs.contains(&(level.to_string(), line.to_string())));
+            assert_eq!(logs.last().unwrap().1, finished);
+            if exit_code != 0 {
+                assert_eq!(stored.error_message.as_deref(), Some(finished.as_str()));
+            }
+        }
+    }
+
+    /// Whether `pid` is alive; a zombie waiting to be reaped counts as gone
+    #[cfg(unix)]
+    fn is_running(pid: &str) -> bool {
+        let output = std::process::Command::new("ps")
+            .args(["-o", "stat=", "-p", pid])
+            .output()
+            .unwrap();
+        let stat = String::from_utf8_lossy(&output.stdout);
+        !stat.trim().is_empty() && !stat.trim().starts_with('Z')
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn cancelling_stops_the_command_and_everything_it_started() {
+        let db = Arc::new(memory_db());
+        let folder = tempfile::tempdir().unwrap();
+        let script = "sleep 30 & echo $! > sleeper.pid; wait";
+        let card_id = card_running(&db, folder.path(), shell(script));
+        let app = tauri::test::mock_app();
+        let buffer = Arc::new(LogBuffer::new());
+        let processes = Arc::new(ProcessManager::new());
+
+        let run = tokio::spawn({
+            let (app, db, buffer, processes) = (
+                app.handle().clone(),
+                db.clone(),
+                buffer.clone(),
+                processes.clone(),
+            );
+            let card_id = card_id.clone();
+            async move { run_card(app, db, buffer, processes, &card_id).await }
+        });
+
+        let pid_file = folder.path().join("sleeper.pid");
+        let sleeper = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                match std::fs::read_to_string(&pid_file) {
+                    Ok(pid) if pid.ends_with('\n') => return pid.trim().to_string(),
+                    _ => tokio::time::sleep(Duration::from_millis(20)).await,
+                }
+            }
+        })
+        .await
+        .expect("the command should start");
+        assert!(is_running(&sleeper));
+
+        let execution = db.get_latest_execution(&card_id).unwrap().unwrap();
+        assert!(
+            cancel_execution_in(app.handle(), &db, &buffer, &processes, &execution.id).unwrap()
+        );
+
+        // Only ends once nothing holds the output pipes open any more
+        let finished = tokio::time::timeout(Duration::from_secs(10), run)
+            .await
+            .expect("cancelled run should end")
+            .unwrap()
+            .unwrap();
+        assert_eq!(finished.status, "cancelled");
+        assert_eq!(db.get_card(&card_id).unwrap().unwrap().status, "idle");
+        assert!(!is_running(&sleeper));
+        assert!(
+            !cancel_execution_in(app.handle(), &db, &buffer, &processes, &execution.id).unwrap()
+        );
+    }
+}
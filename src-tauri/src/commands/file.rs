@@ -1,98 +1,332 @@
+use crate::database::{Database, RecentKind};
+use crate::error::AppError;
+use crate::files::{self, DirStats, DirStatsCache, FileListCache, FileNode, FileTreeCache};
+use crate::preferences;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, State};
 use tauri_plugin_dialog::DialogExt;
 
+/// The project's file tree, served from the watched in-memory copy when warm.
+/// Later changes arrive as `tree-updated` events rather than needing a
+/// re-fetch.
+#[tauri::command]
+pub async fn get_file_tree(
+    project_path: String,
+    app: AppHandle,
+    cache: State<'_, Arc<FileTreeCache>>,
+    db: State<'_, Arc<Database>>,
+) -> Result<FileNode, AppError> {
+    if !Path::new(&project_path).exists() {
+        return Err(AppError::NotFound(format!(
+            "Path does not exist: {}",
+            project_path
+        )));
+    }
+
+    let cache = Arc::clone(&cache);
+    let db = Arc::clone(&db);
+    tokio::task::spawn_blocking(move || {
+        let ignored = preferences::file_tree_ignore(&db, &project_path);
+        cache.get(Path::new(&project_path), ignored, move |event| {
+            let _ = app.emit("tree-updated", &event);
+        })
+    })
+    .await?
+    .ok_or_else(|| AppError::Internal("Failed to build file tree".to_string()))
+}
+
+/// Total size and file / folder counts under a directory, respecting ignores.
+/// Cached until the file watcher sees something change inside it.
+#[tauri::command]
+pub async fn get_directory_stats(
+    path: String,
+    cache: State<'_, Arc<DirStatsCache>>,
+) -> Result<DirStats, AppError> {
+    if !Path::new(&path).is_dir() {
+        return Err(AppError::validation(
+            "path",
+            format!("Not a directory: {}", path),
+        ));
+    }
+
+    let cache = Arc::clone(&cache);
+    tokio::task::spawn_blocking(move || {
+        cache.get_or_compute(Path::new(&path), files::directory_stats)
+    })
+    .await?
+    .map_err(|e| AppError::io("Failed to read directory", e))
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct FileNode {
-    pub name: String,
+pub struct FileMatch {
+    /// Relative to the project root, `/`-separated
     pub path: String,
-    pub is_directory: bool,
-    pub children: Option<Vec<FileNode>>,
-    pub size: Option<u64>,
-    pub modified: Option<i64>,
+    pub score: i64,
+    /// Character positions in `path` that matched, for highlighting
+    pub indices: Vec<usize>,
 }
 
+/// Quick open: project files whose relative path fuzzily matches `query`, best
+/// first. The file list is walked once and cached until something under the
+/// project changes, so this is cheap enough to call on every keystroke.
 #[tauri::command]
-pub async fn get_file_tree(project_path: String) -> Result<FileNode, String> {
-    let path = Path::new(&project_path);
-
-    if !path.exists() {
-        return Err(format!("Path does not exist: {}", project_path));
+pub async fn fuzzy_find_files(
+    project_path: String,
+    query: String,
+    limit: Option<usize>,
+    cache: State<'_, Arc<FileListCache>>,
+) -> Result<Vec<FileMatch>, AppError> {
+    if !Path::new(&project_path).is_dir() {
+        return Err(AppError::validation(
+            "project_path",
+            format!("Not a directory: {}", project_path),
+        ));
     }
 
-    fn build_tree(path: &Path, max_depth: usize, current_depth: usize) -> Option<FileNode> {
-        if current_depth > max_depth {
-            return None;
-        }
+    let query = query.trim().to_string();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
 
-        let name = path
-            .file_name()
-            .map(|n| n.to_string_lossy().to_string())
-            .unwrap_or_else(|| path.to_string_lossy().to_string());
+    let cache = Arc::clone(&cache);
+    let files = tokio::task::spawn_blocking(move || {
+        cache.get_or_compute(Path::new(&project_path), |root| {
+            Arc::new(files::list_files(root))
+        })
+    })
+    .await?
+    .map_err(|e| AppError::io("Failed to list project files", e))?;
 
-        // Skip hidden files and common ignored directories
-        if name.starts_with('.') || name == "node_modules" || name == "target" || name == "dist" {
-            return None;
-        }
+    Ok(rank_files(&files, &query, limit.unwrap_or(50)))
+}
 
-        let metadata = std::fs::metadata(path).ok();
-        let is_directory = path.is_dir();
-
-        let (size, modified) = metadata
-            .map(|m| {
-                (
-                    if is_directory { None } else { Some(m.len()) },
-                    m.modified()
-                        .ok()
-                        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                        .map(|d| d.as_secs() as i64),
-                )
+/// The best `limit` fuzzy matches for `query` among `files`
+fn rank_files(files: &[String], query: &str, limit: usize) -> Vec<FileMatch> {
+    let matcher = SkimMatcherV2::default();
+    let mut matches: Vec<FileMatch> = files
+        .iter()
+        .filter_map(|path| {
+            let (score, indices) = matcher.fuzzy_indices(path, query)?;
+            Some(FileMatch {
+                path: path.clone(),
+                score,
+                indices,
             })
-            .unwrap_or((None, None));
-
-        let children = if is_directory {
-            let mut entries: Vec<FileNode> = std::fs::read_dir(path)
-                .ok()?
-                .filter_map(|entry| entry.ok())
-                .filter_map(|entry| build_tree(&entry.path(), max_depth, current_depth + 1))
-                .collect();
-
-            // Sort: directories first, then files, alphabetically
-            entries.sort_by(|a, b| {
-                match (a.is_directory, b.is_directory) {
-                    (true, false) => std::cmp::Ordering::Less,
-                    (false, true) => std::cmp::Ordering::Greater,
-                    _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
-                }
-            });
+        })
+        .collect();
 
-            Some(entries)
-        } else {
-            None
-        };
+    // Equal scores favour the shorter, usually shallower, path
+    matches.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then(a.path.len().cmp(&b.path.len()))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    matches.truncate(limit);
+    matches
+}
 
-        Some(FileNode {
-            name,
-            path: path.to_string_lossy().to_string(),
-            is_directory,
-            children,
-            size,
-            modified,
-        })
-    }
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextEncoding {
+    #[default]
+    #[serde(rename = "utf-8")]
+    Utf8,
+    #[serde(rename = "utf-16le")]
+    Utf16Le,
+    #[serde(rename = "utf-16be")]
+    Utf16Be,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+    /// Both kinds; the content is written back with its line endings as they are
+    Mixed,
+}
 
-    build_tree(path, 10, 0).ok_or_else(|| "Failed to build file tree".to_string())
+/// How a text file is stored on disk. New files default to UTF-8, no BOM, LF.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileFormat {
+    pub encoding: TextEncoding,
+    pub bom: bool,
+    pub line_ending: LineEnding,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileReadResult {
+    pub content: String,
+    #[serde(flatten)]
+    pub format: FileFormat,
+}
+
+/// Read a text file, detecting its encoding from the BOM and its line endings.
+/// Passing the returned format back to `write_file` round-trips it byte for
+/// byte. A file that isn't valid text in its encoding fails with `UNSUPPORTED`
+/// rather than being decoded lossily.
+/// When `opened` is set the user opened the file, so it goes to the top of the
+/// recent files list; reads done for the agent or chat leave the list alone.
+/// With a `project_id` the path has to be inside that project's folder.
 #[tauri::command]
-pub async fn read_file(path: String) -> Result<String, String> {
-    tokio::fs::read_to_string(&path)
+pub async fn read_file(
+    path: String,
+    project_id: Option<String>,
+    opened: Option<bool>,
+    db: State<'_, Arc<Database>>,
+) -> Result<FileReadResult, AppError> {
+    let target = scoped_path(&db, project_id, &path).await?;
+    let bytes = tokio::fs::read(&target)
         .await
-        .map_err(|e| format!("Failed to read file: {}", e))
+        .map_err(|e| AppError::io("Failed to read file", e))?;
+
+    if opened.unwrap_or(false) {
+        // One entry per file however its path was spelled
+        let target = tokio::fs::canonicalize(&target).await.unwrap_or(target);
+        // Best effort: a failed write to the recent list shouldn't fail the read
+        let _ = db
+            .blocking(move |db| db.record_recent(RecentKind::File, &target.to_string_lossy()))
+            .await;
+    }
+
+    decode_text(&bytes)
+}
+
+fn decode_text(bytes: &[u8]) -> Result<FileReadResult, AppError> {
+    let (encoding, bom_len) =
+        encoding_rs::Encoding::for_bom(bytes).unwrap_or((encoding_rs::UTF_8, 0));
+    let (content, malformed) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+    if malformed {
+        return Err(AppError::Unsupported(format!(
+            "Not a {} text file",
+            encoding.name()
+        )));
+    }
+
+    let text_encoding = if encoding == encoding_rs::UTF_16LE {
+        TextEncoding::Utf16Le
+    } else if encoding == encoding_rs::UTF_16BE {
+        TextEncoding::Utf16Be
+    } else {
+        TextEncoding::Utf8
+    };
+
+    let crlf = content.matches("\r\n").count();
+    let lf = content.matches('\n').count() - crlf;
+
+    Ok(FileReadResult {
+        format: FileFormat {
+            encoding: text_encoding,
+            bom: bom_len > 0,
+            line_ending: match (crlf, lf) {
+                (0, _) => LineEnding::Lf,
+                (_, 0) => LineEnding::Crlf,
+                _ => LineEnding::Mixed,
+            },
+        },
+        content: content.into_owned(),
+    })
+}
+
+fn encode_text(content: &str, format: FileFormat) -> Vec<u8> {
+    let content = match format.line_ending {
+        LineEnding::Lf => content.replace("\r\n", "\n"),
+        LineEnding::Crlf => content.replace("\r\n", "\n").replace('\n', "\r\n"),
+        LineEnding::Mixed => content.to_string(),
+    };
+
+    let mut bytes = Vec::with_capacity(content.len() + 3);
+    match format.encoding {
+        TextEncoding::Utf8 => {
+            if format.bom {
+                bytes.extend_from_slice(&[0xEF, 0xBB, 0xBF]);
+            }
+            bytes.extend_from_slice(content.as_bytes());
+        }
+        TextEncoding::Utf16Le => {
+            if format.bom {
+                bytes.extend_from_slice(&[0xFF, 0xFE]);
+            }
+            bytes.extend(content.encode_utf16().flat_map(u16::to_le_bytes));
+        }
+        TextEncoding::Utf16Be => {
+            if format.bom {
+                bytes.extend_from_slice(&[0xFE, 0xFF]);
+            }
+            bytes.extend(content.encode_utf16().flat_map(u16::to_be_bytes));
+        }
+    }
+
+    bytes
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileRange {
+    /// Lines `start_line..=end_line` without their line endings
+    pub lines: Vec<String>,
+    /// First line returned, 1-based
+    pub start_line: usize,
+    /// Last line returned; less than `start_line` when the range is past EOF
+    pub end_line: usize,
+    pub total_lines: usize,
+}
+
+/// Read a 1-based inclusive line range, streaming the file so only the requested
+/// lines are kept in memory. Ranges running past EOF are clamped.
 #[tauri::command]
-pub async fn open_folder_dialog(app: tauri::AppHandle) -> Result<Option<String>, String> {
+pub async fn read_file_range(
+    path: String,
+    start_line: usize,
+    end_line: usize,
+) -> Result<FileRange, AppError> {
+    tokio::task::spawn_blocking(move || read_range(Path::new(&path), start_line, end_line))
+        .await?
+        .map_err(|e| AppError::io("Failed to read file", e))
+}
+
+fn read_range(path: &Path, start_line: usize, end_line: usize) -> std::io::Result<FileRange> {
+    use std::io::BufRead;
+
+    let start_line = start_line.max(1);
+    let mut reader = std::io::BufReader::new(std::fs::File::open(path)?);
+    let mut buf = Vec::new();
+    let mut lines = Vec::new();
+    let mut total_lines = 0;
+
+    loop {
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf)? == 0 {
+            break;
+        }
+        total_lines += 1;
+
+        if (start_line..=end_line).contains(&total_lines) {
+            if buf.ends_with(b"\n") {
+                buf.pop();
+                if buf.ends_with(b"\r") {
+                    buf.pop();
+                }
+            }
+            lines.push(String::from_utf8_lossy(&buf).into_owned());
+        }
+    }
+
+    Ok(FileRange {
+        start_line,
+        end_line: start_line + lines.len() - 1,
+        lines,
+        total_lines,
+    })
+}
+
+#[tauri::command]
+pub async fn open_folder_dialog(app: tauri::AppHandle) -> Result<Option<String>, AppError> {
     use std::sync::mpsc;
 
     let (tx, rx) = mpsc::channel();
@@ -106,60 +340,374 @@ pub async fn open_folder_dialog(app: tauri::AppHandle) -> Result<Option<String>,
 
     // Wait for dialog result
     rx.recv()
-        .map_err(|e| format!("Dialog error: {}", e))
+        .map_err(|e| AppError::Internal(format!("Dialog error: {}", e)))
 }
 
+/// Save a file atomically: the content goes to a temp file next to the target
+/// which is then renamed over it, so a crash never leaves a half-written file.
+/// With a `format` (as returned by `read_file`) the content is re-encoded and its
+/// line endings normalized to match; without one it is written as-is in UTF-8.
+/// With a `project_id` the path has to be inside that project's folder, and
+/// the file's draft in that project is dropped once the write succeeds.
 #[tauri::command]
-pub async fn write_file(path: String, content: String) -> Result<(), String> {
-    // Create parent directories if they don't exist
-    if let Some(parent) = Path::new(&path).parent() {
-        tokio::fs::create_dir_all(parent)
-            .await
-            .map_err(|e| format!("Failed to create parent directories: {}", e))?;
+pub async fn write_file(
+    path: String,
+    content: String,
+    format: Option<FileFormat>,
+    project_id: Option<String>,
+    db: State<'_, Arc<Database>>,
+) -> Result<(), AppError> {
+    let written_at = chrono::Utc::now().timestamp();
+    let target = scoped_path(&db, project_id.clone(), &path).await?;
+    let draft = project_id.map(|project_id| (project_id, path, content.clone()));
+
+    let bytes = match format {
+        Some(format) => encode_text(&content, format),
+        None => content.into_bytes(),
+    };
+
+    tokio::task::spawn_blocking(move || write_atomic(&target, &bytes))
+        .await?
+        .map_err(|e| AppError::io("Failed to write file", e))?;
+
+    if let Some((project_id, path, content)) = draft {
+        db.blocking(move |db| db.clear_saved_draft(&project_id, &path, &content, written_at))
+            .await?;
+    }
+    Ok(())
+}
+
+fn write_atomic(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    if !parent.exists() {
+        std::fs::create_dir_all(parent)?;
     }
 
-    tokio::fs::write(&path, content)
-        .await
-        .map_err(|e| format!("Failed to write file: {}", e))
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "not a file path"))?;
+    let temp_path = parent.join(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        uuid::Uuid::new_v4()
+    ));
+
+    let result = (|| {
+        let mut file = std::fs::File::create(&temp_path)?;
+        file.write_all(content)?;
+
+        // Keep the mode of the file being replaced (e.g. executable scripts)
+        if let Ok(metadata) = std::fs::metadata(path) {
+            file.set_permissions(metadata.permissions())?;
+        }
+
+        file.sync_all()?;
+        std::fs::rename(&temp_path, path)
+    })();
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+
+    result
 }
 
+/// With a `project_id` the path has to be inside that project's folder
 #[tauri::command]
-pub async fn create_file(path: String, content: String) -> Result<(), String> {
+pub async fn create_file(
+    path: String,
+    content: String,
+    project_id: Option<String>,
+    db: State<'_, Arc<Database>>,
+) -> Result<(), AppError> {
+    let path = scoped_path(&db, project_id, &path).await?;
+
     // Create parent directories if they don't exist
-    if let Some(parent) = Path::new(&path).parent() {
+    if let Some(parent) = path.parent() {
         tokio::fs::create_dir_all(parent)
             .await
-            .map_err(|e| format!("Failed to create parent directories: {}", e))?;
+            .map_err(|e| AppError::io("Failed to create parent directories", e))?;
     }
 
     tokio::fs::write(&path, content)
         .await
-        .map_err(|e| format!("Failed to create file: {}", e))
+        .map_err(|e| AppError::io("Failed to create file", e))
 }
 
+/// With a `project_id` the path has to be inside that project's folder
 #[tauri::command]
-pub async fn create_directory(path: String) -> Result<(), String> {
+pub async fn create_directory(
+    path: String,
+    project_id: Option<String>,
+    db: State<'_, Arc<Database>>,
+) -> Result<(), AppError> {
+    let path = scoped_path(&db, project_id, &path).await?;
     tokio::fs::create_dir_all(&path)
         .await
-        .map_err(|e| format!("Failed to create directory: {}", e))
+        .map_err(|e| AppError::io("Failed to create directory", e))
 }
 
+/// With a `project_id` the path has to be inside that project's folder, and
+/// the folder itself can't be deleted. A symlink is removed, not its target.
 #[tauri::command]
-pub async fn delete_file(path: String) -> Result<(), String> {
-    let path_ref = Path::new(&path);
+pub async fn delete_file(
+    path: String,
+    project_id: Option<String>,
+    db: State<'_, Arc<Database>>,
+) -> Result<(), AppError> {
+    let path = match project_id {
+        Some(project_id) => {
+            let given = Path::new(&path);
+            let (Some(parent), Some(name)) = (given.parent(), given.file_name()) else {
+                return Err(AppError::PathEscapesProject(format!(
+                    "{} is outside the project folder",
+                    path
+                )));
+            };
+            let parent = parent.to_string_lossy().into_owned();
+            scoped_path(&db, Some(project_id), &parent)
+                .await?
+                .join(name)
+        }
+        None => PathBuf::from(path),
+    };
 
-    if path_ref.is_dir() {
+    if path.is_dir() {
         tokio::fs::remove_dir_all(&path)
             .await
-            .map_err(|e| format!("Failed to delete directory: {}", e))
+            .map_err(|e| AppError::io("Failed to delete directory", e))
     } else {
         tokio::fs::remove_file(&path)
             .await
-            .map_err(|e| format!("Failed to delete file: {}", e))
+            .map_err(|e| AppError::io("Failed to delete file", e))
     }
 }
 
+/// Move a file or directory to the OS recycle bin. Fails with `Unsupported`
+/// when the OS has no trash for the path, so the UI can offer a permanent
+/// delete; any other failure is an `Io` error.
 #[tauri::command]
-pub async fn file_exists(path: String) -> Result<bool, String> {
+pub async fn trash_path(path: String) -> Result<(), AppError> {
+    if !Path::new(&path).exists() {
+        return Err(AppError::NotFound(format!("Path does not exist: {}", path)));
+    }
+
+    tokio::task::spawn_blocking(move || trash::delete(&path))
+        .await?
+        .map_err(trash_error)
+}
+
+fn trash_error(e: trash::Error) -> AppError {
+    match e {
+        // What the trash crate reports when no trash can be found or used,
+        // e.g. no home trash and none on the path's mount
+        trash::Error::Unknown { description } => {
+            AppError::Unsupported(format!("Can't move to trash: {}", description))
+        }
+        #[cfg(all(
+            unix,
+            not(target_os = "macos"),
+            not(target_os = "ios"),
+            not(target_os = "android")
+        ))]
+        trash::Error::FileSystem { source, .. } => AppError::io("Failed to move to trash", source),
+        e => AppError::Io(format!("Failed to move to trash: {}", e)),
+    }
+}
+
+/// Where a file command should act: `path` as given, or with a `project_id`,
+/// resolved and confined to that project's folder
+async fn scoped_path(
+    db: &Arc<Database>,
+    project_id: Option<String>,
+    path: &str,
+) -> Result<PathBuf, AppError> {
+    let Some(project_id) = project_id else {
+        return Ok(PathBuf::from(path));
+    };
+
+    let project = db.blocking(move |db| db.get_project(&project_id)).await?;
+    files::resolve_in_root(Path::new(&project.root_path), Path::new(path))
+}
+
+#[tauri::command]
+pub async fn file_exists(path: String) -> Result<bool, AppError> {
     Ok(Path::new(&path).exists())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(bytes: &[u8]) -> FileReadResult {
+        let read = decode_text(bytes).unwrap();
+        assert_eq!(encode_text(&read.content, read.format), bytes);
+        read
+    }
+
+    #[test]
+    fn crlf_file_round_trips() {
+        let read = round_trip(b"one\r\ntwo\r\n");
+        assert_eq!(read.format.line_ending, LineEnding::Crlf);
+        assert_eq!(read.content, "one\r\ntwo\r\n");
+    }
+
+    #[test]
+    fn mixed_line_endings_round_trip() {
+        let read = round_trip(b"one\r\ntwo\nthree\r\n");
+        assert_eq!(read.format.line_ending, LineEnding::Mixed);
+    }
+
+    #[test]
+    fn utf16_with_bom_round_trips() {
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend("h\u{e9}\r\n".encode_utf16().flat_map(u16::to_le_bytes));
+        let read = round_trip(&bytes);
+        assert_eq!(read.format.encoding, TextEncoding::Utf16Le);
+        assert!(read.format.bom);
+        assert_eq!(read.content, "h\u{e9}\r\n");
+    }
+
+    #[test]
+    fn non_utf8_without_bom_is_rejected() {
+        let latin1 = b"caf\xe9\n";
+        let err = decode_text(latin1).unwrap_err();
+        assert_eq!(err.code(), "UNSUPPORTED");
+    }
+
+    #[test]
+    fn only_a_missing_trash_is_unsupported() {
+        let missing = trash::Error::Unknown {
+            description: "no trash".to_string(),
+        };
+        assert_eq!(trash_error(missing).code(), "UNSUPPORTED");
+
+        let denied = trash::Error::CouldNotAccess {
+            target: "/root/secret".to_string(),
+        };
+        assert_eq!(trash_error(denied).code(), "IO");
+
+        #[cfg(target_os = "linux")]
+        {
+            let denied = trash::Error::FileSystem {
+                path: PathBuf::from("/root/secret"),
+                source: std::io::Error::from(std::io::ErrorKind::PermissionDenied),
+            };
+            assert_eq!(trash_error(denied).code(), "IO");
+        }
+    }
+
+    #[tokio::test]
+    async fn trashed_file_is_gone_from_where_it_was() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("unwanted.txt");
+        std::fs::write(&path, "bye").unwrap();
+
+        match trash_path(path.to_string_lossy().into_owned()).await {
+            Ok(()) => assert!(!path.exists()),
+            // No trash on this machine, e.g. a bare CI container
+            Err(e) if e.code() == "UNSUPPORTED" => eprintln!("skipped: {}", e),
+            Err(e) => panic!("trash failed: {}", e),
+        }
+    }
+
+    fn dir_entries(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn atomic_write_replaces_the_file_and_leaves_no_temp_behind() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested/script.sh");
+
+        write_atomic(&path, b"echo one\n").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"echo one\n");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        write_atomic(&path, b"echo two\n").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"echo two\n");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o755);
+        }
+        assert_eq!(dir_entries(&dir.path().join("nested")), ["script.sh"]);
+    }
+
+    #[test]
+    fn failed_atomic_write_cleans_up_its_temp_file() {
+        let dir = tempfile::tempdir().unwrap();
+        // A directory can't be replaced by a file, so the rename fails
+        std::fs::create_dir_all(dir.path().join("taken/inner")).unwrap();
+
+        assert!(write_atomic(&dir.path().join("taken"), b"content").is_err());
+        assert_eq!(dir_entries(dir.path()), ["taken"]);
+    }
+
+    #[test]
+    fn line_ranges_are_sliced_and_clamped_to_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lines.txt");
+        std::fs::write(&path, "one\ntwo\r\nthree\nfour\nfive").unwrap();
+
+        let range = read_range(&path, 2, 3).unwrap();
+        assert_eq!(range.lines, ["two", "three"]);
+        assert_eq!((range.start_line, range.end_line), (2, 3));
+        assert_eq!(range.total_lines, 5);
+
+        let range = read_range(&path, 4, 100).unwrap();
+        assert_eq!(range.lines, ["four", "five"]);
+        assert_eq!((range.start_line, range.end_line), (4, 5));
+
+        let range = read_range(&path, 0, 1).unwrap();
+        assert_eq!(range.lines, ["one"]);
+        assert_eq!(range.start_line, 1);
+
+        let range = read_range(&path, 8, 9).unwrap();
+        assert!(range.lines.is_empty());
+        assert!(range.end_line < range.start_line);
+        assert_eq!(range.total_lines, 5);
+    }
+
+    #[test]
+    fn quick_open_ranks_subsequence_matches_first() {
+        let files = [
+            "README.md",
+            "src/main.rs",
+            "src/database/mod.rs",
+            "docs/modules/readers.md",
+            "src/commands/markdown.rs",
+        ]
+        .map(String::from);
+
+        let matches = rank_files(&files, "mdrs", 10);
+        assert_eq!(matches[0].path, "src/database/mod.rs");
+        let matched: String = matches[0]
+            .indices
+            .iter()
+            .map(|&i| matches[0].path.as_bytes()[i] as char)
+            .collect();
+        assert_eq!(matched, "mdrs");
+        assert!(matches
+            .windows(2)
+            .all(|pair| pair[0].score >= pair[1].score));
+
+        assert_eq!(rank_files(&files, "mdrs", 1).len(), 1);
+        assert!(rank_files(&files, "xyzzy", 10).is_empty());
+    }
+}
@@ -1,179 +1,1669 @@
-use git2::{BranchType, Repository, Signature};
+use crate::database::Database;
+use crate::error::AppError;
+use crate::git::{default_branch_name, head_commit, is_default_branch, GhostMode};
+use crate::preferences;
+use git2::{
+    build::CheckoutBuilder, BranchType, CheckoutNotificationType, Commit, Delta, Diff, DiffDelta,
+    DiffFindOptions, DiffOptions, ErrorCode, IndexAddOption, Oid, Patch, Repository, Revwalk,
+    Signature, Sort, StashApplyOptions, StashFlags, StatusOptions,
+};
 use serde::{Deserialize, Serialize};
+use std::path::{Component, Path};
+use std::sync::Arc;
+use tauri::State;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GitBranch {
     pub name: String,
     pub is_head: bool,
     pub is_ghost: bool,
-    pub last_commit: Option<String>,
+    pub last_commit: Option<GitCommit>,
+    /// Against the default branch; `None` for the default branch itself or
+    /// when the repository has neither main nor master
+    pub ahead_behind: Option<AheadBehind>,
 }
 
+/// Commits a branch has that the default branch doesn't, and the reverse
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct AheadBehind {
+    pub ahead: usize,
+    pub behind: usize,
+}
+
+/// A commit in the history; `message` is only its first line
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GitCommit {
     pub sha: String,
     pub message: String,
     pub timestamp: i64,
     pub author: String,
+    /// More than one for a merge
+    pub parent_count: usize,
+}
+
+/// The commit `commit_changes` made
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitSummary {
+    pub sha: String,
+    pub files_changed: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GitFileState {
+    Modified,
+    Added,
+    Deleted,
+    Renamed,
+    Untracked,
+    Conflicted,
+}
+
+/// One change to a file. A file changed both in the index and in the working
+/// tree is listed twice, once with `staged` set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitFileStatus {
+    pub path: String,
+    pub status: GitFileState,
+    pub staged: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GitStatusCounts {
+    pub modified: usize,
+    pub added: usize,
+    pub deleted: usize,
+    pub renamed: usize,
+    pub untracked: usize,
+    pub conflicted: usize,
+    pub staged: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitStatus {
+    pub branch: String,
+    pub files: Vec<GitFileStatus>,
+    pub counts: GitStatusCounts,
+}
+
+/// A saved stash; `index` 0 is the newest and shifts as stashes come and go
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StashEntry {
+    pub index: usize,
+    pub message: String,
+    pub timestamp: i64,
+}
+
+/// What to do about uncommitted changes when switching branches
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DirtyTreeStrategy {
+    /// Fail with `DIRTY_WORKING_TREE`
+    #[default]
+    Refuse,
+    /// Stash the changes under the card's id; `apply_card_stash` brings
+    /// them back
+    Stash,
+    /// Switch anyway, like `git checkout --force`: uncommitted changes to
+    /// tracked files are thrown away. Untracked files are left alone. A
+    /// merge never throws changes away; it carries them over instead and
+    /// fails when they're in the way.
+    Force,
+}
+
+/// How `merge_ghost_branch` lands a ghost branch on the default branch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeMode {
+    /// A two-parent merge commit that keeps the branch's history
+    #[default]
+    Merge,
+    /// One commit with the branch's final tree and a single parent
+    Squash,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileDiffStatus {
+    Added,
+    Deleted,
+    Modified,
+    Renamed,
+    Copied,
+    TypeChange,
+}
+
+/// A file changed between two revisions. Binary files come without hunks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileDiff {
+    pub old_path: Option<String>,
+    pub new_path: Option<String>,
+    pub status: FileDiffStatus,
+    pub is_binary: bool,
+    pub hunks: Vec<DiffHunk>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffHunk {
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<DiffLine>,
+}
+
+/// Line counts for one file. Binary files count no lines.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileDiffStat {
+    pub old_path: Option<String>,
+    pub new_path: Option<String>,
+    pub status: FileDiffStatus,
+    pub is_binary: bool,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffStats {
+    pub files: Vec<FileDiffStat>,
+    pub files_changed: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+/// `origin` is `+`, `-` or a space for context; `=`, `>` and `<` mark a
+/// missing newline at the end of the file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffLine {
+    pub origin: char,
+    pub content: String,
 }
 
 #[tauri::command]
-pub async fn get_branches(repo_path: String) -> Result<Vec<GitBranch>, String> {
-    let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
+pub async fn get_branches(repo_path: String) -> Result<Vec<GitBranch>, AppError> {
+    let repo = Repository::open(&repo_path)?;
+    let base = default_branch_commit(&repo).map(|c| c.id());
 
-    let branches = repo
-        .branches(Some(BranchType::Local))
-        .map_err(|e| e.to_string())?;
+    let branches = repo.branches(Some(BranchType::Local))?;
 
     let mut result = Vec::new();
 
     for branch in branches {
-        let (branch, _) = branch.map_err(|e| e.to_string())?;
-        let name = branch.name().map_err(|e| e.to_string())?.unwrap_or("").to_string();
+        let (branch, _) = branch?;
+        let name = branch.name()?.unwrap_or("").to_string();
         let is_head = branch.is_head();
         let is_ghost = name.starts_with("ghost/");
 
-        let last_commit = branch
-            .get()
-            .peel_to_commit()
-            .ok()
-            .map(|c| c.message().unwrap_or("").to_string());
+        let tip = branch.get().peel_to_commit().ok();
+        let ahead_behind = match (&tip, base) {
+            (Some(tip), Some(base)) if !is_default_branch(&name) => {
+                let (ahead, behind) = repo.graph_ahead_behind(tip.id(), base)?;
+                Some(AheadBehind { ahead, behind })
+            }
+            _ => None,
+        };
 
         result.push(GitBranch {
             name,
             is_head,
             is_ghost,
-            last_commit,
+            last_commit: tip.as_ref().map(git_commit),
+            ahead_behind,
         });
     }
 
     Ok(result)
 }
 
+/// Check out the local branch `branch_name` and point HEAD at it.
+/// Uncommitted changes the switch would clobber are handled according to
+/// `strategy`; when they block it, `DIRTY_WORKING_TREE` lists the files.
 #[tauri::command]
-pub async fn create_ghost_branch(repo_path: String, card_id: String) -> Result<String, String> {
-    let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
+pub async fn checkout_branch(
+    repo_path: String,
+    branch_name: String,
+    strategy: Option<DirtyTreeStrategy>,
+) -> Result<(), AppError> {
+    let mut repo = Repository::open(&repo_path)?;
+    let message = format!("viraith: before switching to {}", branch_name);
+    switch_branch(
+        &mut repo,
+        &branch_name,
+        strategy.unwrap_or_default(),
+        &message,
+    )
+}
+
+/// Delete `ghost_branch` without merging it and return the SHA it pointed
+/// at; the commits stay in the object database until git prunes them, so
+/// the branch can be recreated from it. If it is checked out, HEAD first
+/// moves to the default branch, with uncommitted changes handled according
+/// to `strategy` and stashed under the card. Only `ghost/` branches can be
+/// discarded.
+#[tauri::command]
+pub async fn discard_ghost_branch(
+    repo_path: String,
+    ghost_branch: String,
+    strategy: Option<DirtyTreeStrategy>,
+) -> Result<String, AppError> {
+    if !ghost_branch.starts_with("ghost/") {
+        return Err(AppError::validation(
+            "ghost_branch",
+            format!("Not a ghost branch: {}", ghost_branch),
+        ));
+    }
+
+    let mut repo = Repository::open(&repo_path)?;
+    let sha = branch_commit(&repo, &ghost_branch)?.id().to_string();
+
+    if repo
+        .find_branch(&ghost_branch, BranchType::Local)?
+        .is_head()
+    {
+        let default = default_branch_name(&repo).ok_or_else(|| {
+            AppError::NotFound("The repository has no main or master branch".into())
+        })?;
+        // Branch names look like ghost/<card_id>/<timestamp>
+        let card_id = ghost_branch.split('/').nth(1).unwrap_or(&ghost_branch);
+        switch_branch(
+            &mut repo,
+            default,
+            strategy.unwrap_or_default(),
+            &card_stash_message(card_id),
+        )?;
+    }
+
+    repo.find_branch(&ghost_branch, BranchType::Local)?
+        .delete()?;
+    Ok(sha)
+}
 
-    let head = repo.head().map_err(|e| e.to_string())?;
-    let commit = head.peel_to_commit().map_err(|e| e.to_string())?;
+/// Check out `branch_name` and point HEAD at it, after applying `strategy`
+/// to the uncommitted changes in the way
+fn switch_branch(
+    repo: &mut Repository,
+    branch_name: &str,
+    strategy: DirtyTreeStrategy,
+    stash_message: &str,
+) -> Result<(), AppError> {
+    if repo.is_bare() {
+        return Err(AppError::Unsupported(
+            "A bare repository has no working tree to check out into".to_string(),
+        ));
+    }
+
+    let dirty = {
+        let target = branch_commit(repo, branch_name)?.tree()?;
+        blocking_changes(repo, Some(&target))?
+    };
+    guard_dirty_tree(repo, dirty, strategy, stash_message)?;
+
+    let commit = branch_commit(repo, branch_name)?;
+    checkout_with(repo, commit.as_object(), strategy)?;
+    repo.set_head(&format!("refs/heads/{}", branch_name))?;
+    Ok(())
+}
+
+/// Branch off HEAD and check the branch out. A repository without commits
+/// fails with `REPOSITORY_HAS_NO_COMMITS`, unless `create_initial_commit` is
+/// set to first commit an empty tree to branch from. Uncommitted changes to
+/// tracked files are handled according to `strategy`.
+#[tauri::command]
+pub async fn create_ghost_branch(
+    repo_path: String,
+    card_id: String,
+    create_initial_commit: Option<bool>,
+    strategy: Option<DirtyTreeStrategy>,
+) -> Result<String, AppError> {
+    let mut repo = Repository::open(&repo_path)?;
+
+    // The new branch starts at HEAD, so untracked files can't be in the way
+    let dirty = blocking_changes(&repo, None)?;
+    let message = card_stash_message(&card_id);
+    guard_dirty_tree(&mut repo, dirty, strategy.unwrap_or_default(), &message)?;
+
+    let commit = match head_commit(&repo) {
+        Err(AppError::RepositoryHasNoCommits(_)) if create_initial_commit.unwrap_or(false) => {
+            initial_commit(&repo)?
+        }
+        result => result?,
+    };
 
     let timestamp = chrono::Utc::now().timestamp();
     let branch_name = format!("ghost/{}/{}", card_id, timestamp);
 
-    repo.branch(&branch_name, &commit, false)
-        .map_err(|e| e.to_string())?;
+    repo.branch(&branch_name, &commit, false)?;
 
     // Checkout the ghost branch
-    let obj = repo
-        .revparse_single(&format!("refs/heads/{}", branch_name))
-        .map_err(|e| e.to_string())?;
+    let obj = repo.revparse_single(&format!("refs/heads/{}", branch_name))?;
 
-    repo.checkout_tree(&obj, None).map_err(|e| e.to_string())?;
+    repo.checkout_tree(&obj, None)?;
 
-    repo.set_head(&format!("refs/heads/{}", branch_name))
-        .map_err(|e| e.to_string())?;
+    repo.set_head(&format!("refs/heads/{}", branch_name))?;
 
     Ok(branch_name)
 }
 
+/// Stage `paths` (relative to the repo root, or absolute inside it), or every
+/// change when `None`, and commit on HEAD as the user git is configured for.
+/// Only commits on a ghost branch unless `allow_non_ghost` is set, and fails
+/// with `NOTHING_TO_COMMIT` when the result would match HEAD.
 #[tauri::command]
-pub async fn get_branch_diff(
+pub async fn commit_changes(
     repo_path: String,
-    branch1: String,
-    branch2: String,
-) -> Result<String, String> {
-    let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
-
-    let tree1 = repo
-        .revparse_single(&branch1)
-        .map_err(|e| e.to_string())?
-        .peel_to_tree()
-        .map_err(|e| e.to_string())?;
-
-    let tree2 = repo
-        .revparse_single(&branch2)
-        .map_err(|e| e.to_string())?
-        .peel_to_tree()
-        .map_err(|e| e.to_string())?;
-
-    let diff = repo
-        .diff_tree_to_tree(Some(&tree1), Some(&tree2), None)
-        .map_err(|e| e.to_string())?;
-
-    let mut diff_text = String::new();
-    diff.print(git2::DiffFormat::Patch, |_delta, _hunk, line| {
-        diff_text.push_str(&String::from_utf8_lossy(line.content()));
-        true
-    })
-    .map_err(|e| e.to_string())?;
+    message: String,
+    paths: Option<Vec<String>>,
+    allow_non_ghost: Option<bool>,
+) -> Result<CommitSummary, AppError> {
+    if message.trim().is_empty() {
+        return Err(AppError::validation("message", "Commit message is empty"));
+    }
+
+    let repo = Repository::open(&repo_path)?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| AppError::Unsupported("Can't commit in a bare repository".to_string()))?
+        .to_path_buf();
 
-    Ok(diff_text)
+    let head = repo.head().ok();
+    let branch = head
+        .as_ref()
+        .and_then(|head| head.shorthand())
+        .unwrap_or("HEAD")
+        .to_string();
+    if !branch.starts_with("ghost/") && !allow_non_ghost.unwrap_or(false) {
+        return Err(AppError::Conflict(format!(
+            "{} is not a ghost branch; pass allow_non_ghost to commit on it anyway",
+            branch
+        )));
+    }
+    let parent = head.and_then(|head| head.peel_to_commit().ok());
+
+    let pathspecs = match paths {
+        Some(paths) => paths
+            .iter()
+            .map(|path| repo_relative(&workdir, path))
+            .collect::<Result<Vec<_>, _>>()?,
+        None => vec!["*".to_string()],
+    };
+
+    // add_all picks up new and modified files, update_all deleted ones
+    let mut index = repo.index()?;
+    index.add_all(pathspecs.iter(), IndexAddOption::DEFAULT, None)?;
+    index.update_all(pathspecs.iter(), None)?;
+
+    let parent_tree = parent.as_ref().map(|commit| commit.tree()).transpose()?;
+    let files_changed = repo
+        .diff_tree_to_index(parent_tree.as_ref(), Some(&index), None)?
+        .deltas()
+        .len();
+    if files_changed == 0 {
+        return Err(AppError::NothingToCommit(format!(
+            "Nothing to commit on {}",
+            branch
+        )));
+    }
+
+    index.write()?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let signature = signature(&repo)?;
+    let parents: Vec<_> = parent.iter().collect();
+    let oid = repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        &message,
+        &tree,
+        &parents,
+    )?;
+
+    Ok(CommitSummary {
+        sha: oid.to_string(),
+        files_changed,
+    })
 }
 
+/// What is dirty in the working tree and index, untracked files included and
+/// ignored ones left out
 #[tauri::command]
-pub async fn merge_ghost_branch(repo_path: String, ghost_branch: String) -> Result<(), String> {
-    let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
+pub async fn get_git_status(repo_path: String) -> Result<GitStatus, AppError> {
+    let repo = Repository::open(&repo_path)?;
+    worktree_status(&repo)
+}
 
-    // Checkout main
-    let main = repo
-        .revparse_single("main")
-        .or_else(|_| repo.revparse_single("master"))
-        .map_err(|e| e.to_string())?;
+/// Every uncommitted change on the checked-out branch. Bare repositories and
+/// a detached HEAD are refused, since neither has a branch to report on.
+pub(crate) fn worktree_status(repo: &Repository) -> Result<GitStatus, AppError> {
+    if repo.is_bare() {
+        return Err(AppError::Unsupported(
+            "A bare repository has no working tree".to_string(),
+        ));
+    }
+    if repo.head_detached()? {
+        return Err(AppError::DetachedHead(
+            "HEAD is detached; check out a branch first".to_string(),
+        ));
+    }
 
-    repo.checkout_tree(&main, None).map_err(|e| e.to_string())?;
+    // An unborn HEAD still names the branch the first commit will go on
+    let branch = match repo.head() {
+        Ok(head) => head.shorthand().unwrap_or("HEAD").to_string(),
+        Err(_) => repo
+            .find_reference("HEAD")?
+            .symbolic_target()
+            .and_then(|target| target.strip_prefix("refs/heads/"))
+            .unwrap_or("HEAD")
+            .to_string(),
+    };
 
-    let main_branch_name = if repo.revparse_single("main").is_ok() {
-        "main"
-    } else {
-        "master"
+    let files = status_files(repo)?;
+    let mut counts = GitStatusCounts::default();
+    for file in &files {
+        let count = match file.status {
+            GitFileState::Modified => &mut counts.modified,
+            GitFileState::Added => &mut counts.added,
+            GitFileState::Deleted => &mut counts.deleted,
+            GitFileState::Renamed => &mut counts.renamed,
+            GitFileState::Untracked => &mut counts.untracked,
+            GitFileState::Conflicted => &mut counts.conflicted,
+        };
+        *count += 1;
+        if file.staged {
+            counts.staged += 1;
+        }
+    }
+
+    Ok(GitStatus {
+        branch,
+        files,
+        counts,
+    })
+}
+
+/// Every changed path in the index and working tree, ignored files left out
+fn status_files(repo: &Repository) -> Result<Vec<GitFileStatus>, AppError> {
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .include_ignored(false)
+        .renames_head_to_index(true);
+
+    let mut files = Vec::new();
+    for entry in repo.statuses(Some(&mut options))?.iter() {
+        let Some(path) = entry.path() else {
+            continue;
+        };
+        let status = entry.status();
+
+        if status.is_conflicted() {
+            files.push(file_status(path, GitFileState::Conflicted, false));
+            continue;
+        }
+
+        let staged = if status.is_index_new() {
+            Some(GitFileState::Added)
+        } else if status.is_index_deleted() {
+            Some(GitFileState::Deleted)
+        } else if status.is_index_renamed() {
+            Some(GitFileState::Renamed)
+        } else if status.is_index_modified() || status.is_index_typechange() {
+            Some(GitFileState::Modified)
+        } else {
+            None
+        };
+        let unstaged = if status.is_wt_new() {
+            Some(GitFileState::Untracked)
+        } else if status.is_wt_deleted() {
+            Some(GitFileState::Deleted)
+        } else if status.is_wt_renamed() {
+            Some(GitFileState::Renamed)
+        } else if status.is_wt_modified() || status.is_wt_typechange() {
+            Some(GitFileState::Modified)
+        } else {
+            None
+        };
+
+        if let Some(state) = staged {
+            files.push(file_status(path, state, true));
+        }
+        if let Some(state) = unstaged {
+            files.push(file_status(path, state, false));
+        }
+    }
+
+    Ok(files)
+}
+
+fn file_status(path: &str, status: GitFileState, staged: bool) -> GitFileStatus {
+    GitFileStatus {
+        path: path.to_string(),
+        status,
+        staged,
+    }
+}
+
+const DEFAULT_HISTORY_LIMIT: usize = 50;
+
+/// Commits reachable from `branch`, or HEAD when `None`, newest first. Paging
+/// with `skip` and `limit` is stable as long as no commits are added, since
+/// the walk is topological with ties broken by time. A repository without
+/// commits has an empty history.
+#[tauri::command]
+pub async fn get_commit_history(
+    repo_path: String,
+    branch: Option<String>,
+    limit: Option<usize>,
+    skip: Option<usize>,
+) -> Result<Vec<GitCommit>, AppError> {
+    let repo = Repository::open(&repo_path)?;
+    let start = match branch {
+        Some(branch) => branch_commit(&repo, &branch)?,
+        None => match head_commit(&repo) {
+            Err(AppError::RepositoryHasNoCommits(_)) => return Ok(Vec::new()),
+            result => result?,
+        },
     };
 
-    repo.set_head(&format!("refs/heads/{}", main_branch_name))
-        .map_err(|e| e.to_string())?;
+    let mut walk = repo.revwalk()?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+    walk.push(start.id())?;
+    collect_commits(
+        &repo,
+        walk,
+        skip.unwrap_or(0),
+        limit.unwrap_or(DEFAULT_HISTORY_LIMIT),
+    )
+}
 
-    // Get commits
-    let ghost_commit = repo
-        .revparse_single(&ghost_branch)
-        .map_err(|e| e.to_string())?
-        .peel_to_commit()
-        .map_err(|e| e.to_string())?;
+/// Commits on `ghost_branch` that the default branch (main, or else master)
+/// doesn't have yet, newest first
+#[tauri::command]
+pub async fn get_ghost_branch_commits(
+    repo_path: String,
+    ghost_branch: String,
+) -> Result<Vec<GitCommit>, AppError> {
+    let repo = Repository::open(&repo_path)?;
+    let tip = branch_commit(&repo, &ghost_branch)?;
+    let base = default_branch_commit(&repo)
+        .ok_or_else(|| AppError::NotFound("The repository has no main or master branch".into()))?;
+
+    let mut walk = repo.revwalk()?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+    walk.push(tip.id())?;
+    walk.hide(base.id())?;
+    collect_commits(&repo, walk, 0, usize::MAX)
+}
 
-    let head_commit = repo
-        .head()
-        .map_err(|e| e.to_string())?
+/// The tip of the local branch `name`
+fn branch_commit<'r>(repo: &'r Repository, name: &str) -> Result<Commit<'r>, AppError> {
+    match repo.find_branch(name, BranchType::Local) {
+        Ok(branch) => Ok(branch.get().peel_to_commit()?),
+        Err(e) if matches!(e.code(), ErrorCode::NotFound | ErrorCode::InvalidSpec) => {
+            Err(AppError::NotFound(format!("Branch not found: {}", name)))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// The tip of main, or else master
+fn default_branch_commit(repo: &Repository) -> Option<Commit<'_>> {
+    let name = default_branch_name(repo)?;
+    repo.find_branch(name, BranchType::Local)
+        .ok()?
+        .get()
         .peel_to_commit()
-        .map_err(|e| e.to_string())?;
+        .ok()
+}
 
-    // Merge
-    let mut index = repo
-        .merge_commits(&head_commit, &ghost_commit, None)
-        .map_err(|e| e.to_string())?;
+fn git_commit(commit: &Commit) -> GitCommit {
+    let author = commit.author().name().unwrap_or("").to_string();
+    GitCommit {
+        sha: commit.id().to_string(),
+        message: commit.summary().unwrap_or("").to_string(),
+        timestamp: commit.time().seconds(),
+        author,
+        parent_count: commit.parent_count(),
+    }
+}
 
-    if index.has_conflicts() {
-        return Err("Merge conflicts detected - resolve manually".to_string());
+fn collect_commits(
+    repo: &Repository,
+    walk: Revwalk,
+    skip: usize,
+    limit: usize,
+) -> Result<Vec<GitCommit>, AppError> {
+    walk.skip(skip)
+        .take(limit)
+        .map(|oid| Ok(git_commit(&repo.find_commit(oid?)?)))
+        .collect()
+}
+
+/// Pop the newest stash `create_ghost_branch` or `merge_ghost_branch` made for
+/// `card_id` back onto the working tree, as `apply_stash` with `drop` does
+#[tauri::command]
+pub async fn apply_card_stash(repo_path: String, card_id: String) -> Result<(), AppError> {
+    let mut repo = Repository::open(&repo_path)?;
+
+    let marker = stash_marker(&card_id);
+    let Some((index, ..)) = stash_list(&mut repo)?
+        .into_iter()
+        .find(|(_, message, _)| message.contains(&marker))
+    else {
+        return Err(AppError::NotFound(format!(
+            "No stashed changes for card {}",
+            card_id
+        )));
+    };
+
+    apply_stash_at(&mut repo, index, true)
+}
+
+/// Stash uncommitted changes, untracked files too if `include_untracked` is
+/// set. Fails with `NOTHING_TO_COMMIT` when there is nothing to stash.
+#[tauri::command]
+pub async fn stash_changes(
+    repo_path: String,
+    message: Option<String>,
+    include_untracked: Option<bool>,
+) -> Result<StashEntry, AppError> {
+    let mut repo = Repository::open(&repo_path)?;
+    let oid = save_stash(
+        &mut repo,
+        message.as_deref(),
+        include_untracked.unwrap_or(false),
+    )?;
+
+    let commit = repo.find_commit(oid)?;
+    Ok(StashEntry {
+        index: 0,
+        message: commit.message().unwrap_or("").to_string(),
+        timestamp: commit.time().seconds(),
+    })
+}
+
+/// Saved stashes, newest first
+#[tauri::command]
+pub async fn list_stashes(repo_path: String) -> Result<Vec<StashEntry>, AppError> {
+    let mut repo = Repository::open(&repo_path)?;
+
+    stash_list(&mut repo)?
+        .into_iter()
+        .map(|(index, message, oid)| {
+            Ok(StashEntry {
+                index,
+                message,
+                timestamp: repo.find_commit(oid)?.time().seconds(),
+            })
+        })
+        .collect()
+}
+
+/// Apply the stash at `index` onto the working tree, dropping it afterwards
+/// if `drop` is set. Uncommitted changes in the way fail with
+/// `DIRTY_WORKING_TREE` before anything is touched; if it applies with
+/// conflicts they are left to resolve, the stash is kept and the error is
+/// `MERGE_CONFLICT`. Either way `files` lists the paths.
+#[tauri::command]
+pub async fn apply_stash(repo_path: String, index: usize, drop: bool) -> Result<(), AppError> {
+    let mut repo = Repository::open(&repo_path)?;
+    apply_stash_at(&mut repo, index, drop)
+}
+
+#[tauri::command]
+pub async fn drop_stash(repo_path: String, index: usize) -> Result<(), AppError> {
+    let mut repo = Repository::open(&repo_path)?;
+    stash_oid(&mut repo, index)?;
+    repo.stash_drop(index)?;
+    Ok(())
+}
+
+/// Every stash as (index, message, commit), newest first
+fn stash_list(repo: &mut Repository) -> Result<Vec<(usize, String, Oid)>, AppError> {
+    let mut stashes = Vec::new();
+    repo.stash_foreach(|index, message, oid| {
+        stashes.push((index, message.to_string(), *oid));
+        true
+    })?;
+    Ok(stashes)
+}
+
+fn stash_oid(repo: &mut Repository, index: usize) -> Result<Oid, AppError> {
+    stash_list(repo)?
+        .into_iter()
+        .find(|(i, ..)| *i == index)
+        .map(|(.., oid)| oid)
+        .ok_or_else(|| AppError::NotFound(format!("No stash at index {}", index)))
+}
+
+fn save_stash(
+    repo: &mut Repository,
+    message: Option<&str>,
+    include_untracked: bool,
+) -> Result<Oid, AppError> {
+    let mut flags = StashFlags::DEFAULT;
+    if include_untracked {
+        flags |= StashFlags::INCLUDE_UNTRACKED;
+    }
+
+    let signature = signature(repo)?;
+    match repo.stash_save2(&signature, message, Some(flags)) {
+        Ok(oid) => Ok(oid),
+        Err(e) if e.code() == ErrorCode::NotFound => Err(AppError::NothingToCommit(
+            "There are no changes to stash".to_string(),
+        )),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn apply_stash_at(repo: &mut Repository, index: usize, drop: bool) -> Result<(), AppError> {
+    let oid = stash_oid(repo, index)?;
+
+    // Restoring an untracked file over one that exists now would merge the
+    // two in place, so refuse up front and leave the stash as it is
+    let workdir = repo.workdir().map(Path::to_path_buf).unwrap_or_default();
+    let taken: Vec<String> = untracked_in_stash(repo, oid)?
+        .into_iter()
+        .filter(|path| workdir.join(path).exists())
+        .collect();
+    if !taken.is_empty() {
+        return Err(AppError::DirtyWorkingTree {
+            message: format!(
+                "Stashed files already exist in the working tree: {}",
+                taken.join(", ")
+            ),
+            files: taken,
+        });
+    }
+
+    let mut blocked = Vec::new();
+    let applied = {
+        let mut options = StashApplyOptions::new();
+        options.checkout_options(conflict_notifier(&mut blocked));
+        repo.stash_apply(index, Some(&mut options))
+    };
+    match applied {
+        Err(_) if !blocked.is_empty() => return Err(blocked_by_changes(blocked)),
+        result => result?,
+    }
+
+    let conflicts: Vec<String> = repo
+        .index()?
+        .conflicts()?
+        .filter_map(|conflict| {
+            let conflict = conflict.ok()?;
+            let entry = conflict.our.or(conflict.their).or(conflict.ancestor)?;
+            Some(String::from_utf8_lossy(&entry.path).into_owned())
+        })
+        .collect();
+    if !conflicts.is_empty() {
+        return Err(AppError::MergeConflict {
+            message: format!(
+                "The stash applied with conflicts in {} file(s) and was kept",
+                conflicts.len()
+            ),
+            files: conflicts,
+        });
+    }
+
+    if drop {
+        repo.stash_drop(index)?;
+    }
+    Ok(())
+}
+
+/// Uncommitted changes a checkout of `target` would clobber or carry along.
+/// Untracked files only count when `target` has something at that path.
+fn blocking_changes(
+    repo: &Repository,
+    target: Option<&git2::Tree>,
+) -> Result<Vec<GitFileStatus>, AppError> {
+    if repo.is_bare() {
+        return Ok(Vec::new());
+    }
+
+    Ok(status_files(repo)?
+        .into_iter()
+        .filter(|file| {
+            file.status != GitFileState::Untracked
+                || target.is_some_and(|tree| tree.get_path(Path::new(&file.path)).is_ok())
+        })
+        .collect())
+}
+
+/// Apply `strategy` to the `dirty` files before a checkout, stashing them
+/// under `stash_message` for `DirtyTreeStrategy::Stash`
+fn guard_dirty_tree(
+    repo: &mut Repository,
+    dirty: Vec<GitFileStatus>,
+    strategy: DirtyTreeStrategy,
+    stash_message: &str,
+) -> Result<(), AppError> {
+    if dirty.is_empty() {
+        return Ok(());
+    }
+
+    match strategy {
+        DirtyTreeStrategy::Force => Ok(()),
+        DirtyTreeStrategy::Refuse => {
+            let mut files: Vec<String> = dirty.into_iter().map(|file| file.path).collect();
+            files.dedup();
+            Err(AppError::DirtyWorkingTree {
+                message: format!(
+                    "{} uncommitted file(s) would be affected; commit, stash or force",
+                    files.len()
+                ),
+                files,
+            })
+        }
+        DirtyTreeStrategy::Stash => {
+            let untracked = dirty
+                .iter()
+                .any(|file| file.status == GitFileState::Untracked);
+            save_stash(repo, Some(stash_message), untracked)?;
+            Ok(())
+        }
+    }
+}
+
+/// Paths of the untracked files a stash saved, which live in its third parent
+fn untracked_in_stash(repo: &Repository, stash: Oid) -> Result<Vec<String>, AppError> {
+    let commit = repo.find_commit(stash)?;
+    let Ok(untracked) = commit.parent(2) else {
+        return Ok(Vec::new());
+    };
+
+    let mut paths = Vec::new();
+    untracked
+        .tree()?
+        .walk(git2::TreeWalkMode::PreOrder, |root, entry| {
+            if entry.kind() == Some(git2::ObjectType::Blob) {
+                if let Some(name) = entry.name() {
+                    paths.push(format!("{}{}", root, name));
+                }
+            }
+            git2::TreeWalkResult::Ok
+        })?;
+    Ok(paths)
+}
+
+fn stash_marker(card_id: &str) -> String {
+    format!("viraith card {}:", card_id)
+}
+
+fn card_stash_message(card_id: &str) -> String {
+    format!("{} before switching branches", stash_marker(card_id))
+}
+
+/// Check `target` out the way `strategy` asks: `Force` overwrites whatever
+/// is in the way, the others leave it and fail as `checkout_or_report` does
+fn checkout_with(
+    repo: &Repository,
+    target: &git2::Object,
+    strategy: DirtyTreeStrategy,
+) -> Result<(), AppError> {
+    if strategy == DirtyTreeStrategy::Force {
+        let mut checkout = CheckoutBuilder::new();
+        checkout.force();
+        Ok(repo.checkout_tree(target, Some(&mut checkout))?)
+    } else {
+        checkout_or_report(repo, target)
+    }
+}
+
+/// Check `target` out. When uncommitted changes are in the way nothing is
+/// touched and the error is `DIRTY_WORKING_TREE` listing them.
+fn checkout_or_report(repo: &Repository, target: &git2::Object) -> Result<(), AppError> {
+    let mut blocked = Vec::new();
+    let result = {
+        let mut checkout = conflict_notifier(&mut blocked);
+        repo.checkout_tree(target, Some(&mut checkout))
+    };
+    match result {
+        Err(_) if !blocked.is_empty() => Err(blocked_by_changes(blocked)),
+        result => Ok(result?),
     }
+}
 
-    // Create merge commit
-    let tree_id = index.write_tree_to(&repo).map_err(|e| e.to_string())?;
-    let tree = repo.find_tree(tree_id).map_err(|e| e.to_string())?;
+/// A safe checkout that records the paths it refuses to overwrite
+fn conflict_notifier(blocked: &mut Vec<String>) -> CheckoutBuilder<'_> {
+    let mut checkout = CheckoutBuilder::new();
+    checkout
+        .notify_on(CheckoutNotificationType::CONFLICT)
+        .notify(|_, path, _, _, _| {
+            if let Some(path) = path {
+                blocked.push(path.to_string_lossy().replace('\\', "/"));
+            }
+            true
+        });
+    checkout
+}
 
-    let signature = Signature::now("VIRAITH User", "user@viraith.dev").map_err(|e| e.to_string())?;
+fn blocked_by_changes(files: Vec<String>) -> AppError {
+    AppError::DirtyWorkingTree {
+        message: format!(
+            "Uncommitted changes to {} file(s) are in the way",
+            files.len()
+        ),
+        files,
+    }
+}
 
-    repo.commit(
+/// Commit an empty tree on the unborn HEAD
+fn initial_commit(repo: &Repository) -> Result<Commit<'_>, AppError> {
+    let tree = repo.find_tree(repo.treebuilder(None)?.write()?)?;
+    let signature = signature(repo)?;
+    let oid = repo.commit(
         Some("HEAD"),
         &signature,
         &signature,
-        &format!("[VIRAITH] Merge ghost branch {}", ghost_branch),
+        "Initial commit",
         &tree,
-        &[&head_commit, &ghost_commit],
+        &[],
+    )?;
+    Ok(repo.find_commit(oid)?)
+}
+
+/// The user git is configured for, or a VIRAITH placeholder
+fn signature(repo: &Repository) -> Result<Signature<'static>, AppError> {
+    Ok(repo
+        .signature()
+        .or_else(|_| Signature::now("VIRAITH User", "user@viraith.dev"))?)
+}
+
+/// `path` as a pathspec relative to `workdir`
+fn repo_relative(workdir: &Path, path: &str) -> Result<String, AppError> {
+    let path = Path::new(path);
+    let relative = if path.is_absolute() {
+        path.strip_prefix(workdir).ok()
+    } else {
+        Some(path)
+    };
+
+    match relative {
+        Some(relative)
+            if !relative
+                .components()
+                .any(|component| matches!(component, Component::ParentDir)) =>
+        {
+            Ok(relative.to_string_lossy().replace('\\', "/"))
+        }
+        _ => Err(AppError::validation(
+            "paths",
+            format!("{} is outside the repository", path.display()),
+        )),
+    }
+}
+
+/// The diff between two revisions as one unified patch
+#[tauri::command]
+pub async fn get_branch_diff(
+    repo_path: String,
+    branch1: String,
+    branch2: String,
+) -> Result<String, AppError> {
+    let repo = Repository::open(&repo_path)?;
+    let diff = tree_diff(&repo, &branch1, &branch2)?;
+    let files = diff_files(&diff)?;
+    Ok(render_patch(&files))
+}
+
+/// The diff between two revisions, file by file, with renames detected
+#[tauri::command]
+pub async fn get_branch_diff_structured(
+    repo_path: String,
+    branch1: String,
+    branch2: String,
+) -> Result<Vec<FileDiff>, AppError> {
+    let repo = Repository::open(&repo_path)?;
+    let diff = tree_diff(&repo, &branch1, &branch2)?;
+    diff_files(&diff)
+}
+
+/// Uncommitted changes, staged or not, against `base` (HEAD when `None`), in
+/// the same shape as `get_branch_diff_structured`. Untracked files show up as
+/// added; ignored ones are left out. A repository without commits is diffed
+/// against an empty tree.
+#[tauri::command]
+pub async fn get_workdir_diff(
+    repo_path: String,
+    base: Option<String>,
+) -> Result<Vec<FileDiff>, AppError> {
+    let repo = Repository::open(&repo_path)?;
+    if repo.is_bare() {
+        return Err(AppError::Unsupported(
+            "A bare repository has no working tree".to_string(),
+        ));
+    }
+
+    let tree = match base {
+        Some(base) => Some(repo.revparse_single(&base)?.peel_to_tree()?),
+        None => match head_commit(&repo) {
+            Ok(commit) => Some(commit.tree()?),
+            Err(AppError::RepositoryHasNoCommits(_)) => None,
+            Err(e) => return Err(e),
+        },
+    };
+
+    let mut options = DiffOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .show_untracked_content(true)
+        .include_ignored(false);
+
+    let mut diff = repo.diff_tree_to_workdir_with_index(tree.as_ref(), Some(&mut options))?;
+    diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+    diff_files(&diff)
+}
+
+/// Files changed and lines added and removed between two revisions, counted
+/// without rendering any patch text. Takes any revspec, such as a branch, a
+/// SHA or `HEAD`.
+#[tauri::command]
+pub async fn get_diff_stats(
+    repo_path: String,
+    branch1: String,
+    branch2: String,
+) -> Result<DiffStats, AppError> {
+    let repo = Repository::open(&repo_path)?;
+    let diff = tree_diff(&repo, &branch1, &branch2)?;
+    let totals = diff.stats()?;
+
+    let mut files = Vec::new();
+    for index in 0..diff.deltas().len() {
+        let patch = Patch::from_diff(&diff, index)?;
+        let Some(delta) = diff.get_delta(index) else {
+            continue;
+        };
+        let is_binary = delta.flags().is_binary();
+        let (_, insertions, deletions) = match patch.filter(|_| !is_binary) {
+            Some(patch) => patch.line_stats()?,
+            None => (0, 0, 0),
+        };
+        let (old_path, new_path, status) = delta_paths(&delta);
+
+        files.push(FileDiffStat {
+            old_path,
+            new_path,
+            status,
+            is_binary,
+            insertions,
+            deletions,
+        });
+    }
+
+    Ok(DiffStats {
+        files,
+        files_changed: totals.files_changed(),
+        insertions: totals.insertions(),
+        deletions: totals.deletions(),
+    })
+}
+
+/// The diff from one revision's tree to another's, with renames detected
+fn tree_diff<'r>(repo: &'r Repository, from: &str, to: &str) -> Result<Diff<'r>, AppError> {
+    let tree1 = repo.revparse_single(from)?.peel_to_tree()?;
+    let tree2 = repo.revparse_single(to)?.peel_to_tree()?;
+
+    let mut diff = repo.diff_tree_to_tree(Some(&tree1), Some(&tree2), None)?;
+    diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+    Ok(diff)
+}
+
+/// Old path, new path and kind of change; a side that doesn't exist is `None`
+fn delta_paths(delta: &DiffDelta) -> (Option<String>, Option<String>, FileDiffStatus) {
+    let status = match delta.status() {
+        Delta::Added | Delta::Untracked => FileDiffStatus::Added,
+        Delta::Deleted => FileDiffStatus::Deleted,
+        Delta::Renamed => FileDiffStatus::Renamed,
+        Delta::Copied => FileDiffStatus::Copied,
+        Delta::Typechange => FileDiffStatus::TypeChange,
+        _ => FileDiffStatus::Modified,
+    };
+    let path = |file: git2::DiffFile| {
+        file.path()
+            .map(|path| path.to_string_lossy().replace('\\', "/"))
+    };
+
+    let old_path = (status != FileDiffStatus::Added)
+        .then(|| path(delta.old_file()))
+        .flatten();
+    let new_path = (status != FileDiffStatus::Deleted)
+        .then(|| path(delta.new_file()))
+        .flatten();
+    (old_path, new_path, status)
+}
+
+/// Each file in `diff` with its hunks and lines
+fn diff_files(diff: &Diff) -> Result<Vec<FileDiff>, AppError> {
+    let mut files = Vec::new();
+    for index in 0..diff.deltas().len() {
+        // Generating the patch is what detects binary content
+        let patch = Patch::from_diff(diff, index)?;
+        let Some(delta) = diff.get_delta(index) else {
+            continue;
+        };
+
+        let (old_path, new_path, status) = delta_paths(&delta);
+        let is_binary = delta.flags().is_binary();
+
+        let mut hunks = Vec::new();
+        if let Some(patch) = patch.filter(|_| !is_binary) {
+            for hunk_index in 0..patch.num_hunks() {
+                let (hunk, line_count) = patch.hunk(hunk_index)?;
+                let mut lines = Vec::with_capacity(line_count);
+                for line_index in 0..line_count {
+                    let line = patch.line_in_hunk(hunk_index, line_index)?;
+                    lines.push(DiffLine {
+                        origin: line.origin(),
+                        content: String::from_utf8_lossy(line.content()).into_owned(),
+                    });
+                }
+                hunks.push(DiffHunk {
+                    old_start: hunk.old_start(),
+                    old_lines: hunk.old_lines(),
+                    new_start: hunk.new_start(),
+                    new_lines: hunk.new_lines(),
+                    lines,
+                });
+            }
+        }
+
+        files.push(FileDiff {
+            old_path,
+            new_path,
+            status,
+            is_binary,
+            hunks,
+        });
+    }
+
+    Ok(files)
+}
+
+fn render_patch(files: &[FileDiff]) -> String {
+    let mut text = String::new();
+    for file in files {
+        let old = file.old_path.as_deref().or(file.new_path.as_deref());
+        let new = file.new_path.as_deref().or(file.old_path.as_deref());
+        let (old, new) = (old.unwrap_or_default(), new.unwrap_or_default());
+
+        text.push_str(&format!("diff --git a/{} b/{}\n", old, new));
+        if file.status == FileDiffStatus::Renamed {
+            text.push_str(&format!("rename from {}\nrename to {}\n", old, new));
+        }
+        if file.is_binary {
+            text.push_str(&format!("Binary files a/{} and b/{} differ\n", old, new));
+            continue;
+        }
+        if file.hunks.is_empty() {
+            continue;
+        }
+
+        let side = |prefix: &str, path: Option<&String>| {
+            path.map_or("/dev/null".to_string(), |path| {
+                format!("{}{}", prefix, path)
+            })
+        };
+        text.push_str(&format!("--- {}\n", side("a/", file.old_path.as_ref())));
+        text.push_str(&format!("+++ {}\n", side("b/", file.new_path.as_ref())));
+
+        for hunk in &file.hunks {
+            text.push_str(&format!(
+                "@@ -{},{} +{},{} @@\n",
+                hunk.old_start, hunk.old_lines, hunk.new_start, hunk.new_lines
+            ));
+            for line in &hunk.lines {
+                if matches!(line.origin, '+' | '-' | ' ') {
+                    text.push(line.origin);
+                }
+                text.push_str(&line.content);
+            }
+        }
+    }
+    text
+}
+
+/// Merge `ghost_branch` into the default branch and delete it. `message`
+/// overrides the generated commit message; for a squash that one is the card
+/// title (`card_title`, or else looked up from the branch name) followed by
+/// the subjects of the squashed commits.
+#[tauri::command]
+pub async fn merge_ghost_branch(
+    repo_path: String,
+    ghost_branch: String,
+    mode: Option<MergeMode>,
+    message: Option<String>,
+    card_title: Option<String>,
+    strategy: Option<DirtyTreeStrategy>,
+    db: State<'_, Arc<Database>>,
+) -> Result<(), AppError> {
+    let mut repo = Repository::open(&repo_path)?;
+    merge_into_default(
+        &mut repo,
+        &ghost_branch,
+        mode.unwrap_or_default(),
+        message,
+        strategy.unwrap_or_default(),
+        |card_id| card_title.or_else(|| db.get_card_title(card_id).ok().flatten()),
     )
-    .map_err(|e| e.to_string())?;
+}
+
+/// `merge_ghost_branch` on an open repository. `card_title` is only asked
+/// for a squash without a `message`, with the card id from the branch name.
+fn merge_into_default(
+    repo: &mut Repository,
+    ghost_branch: &str,
+    mode: MergeMode,
+    message: Option<String>,
+    strategy: DirtyTreeStrategy,
+    card_title: impl FnOnce(&str) -> Option<String>,
+) -> Result<(), AppError> {
+    head_commit(repo)?;
+    let main_branch_name = default_branch_name(repo)
+        .ok_or_else(|| AppError::NotFound("The repository has no main or master branch".into()))?;
+    let main_commit = branch_commit(repo, main_branch_name)?;
+    let ghost_commit = branch_commit(repo, ghost_branch)?;
+
+    // Merge
+    let mut index = repo.merge_commits(&main_commit, &ghost_commit, None)?;
+
+    if index.has_conflicts() {
+        return Err(AppError::Conflict(
+            "Merge conflicts detected - resolve manually".to_string(),
+        ));
+    }
+
+    let tree_id = index.write_tree_to(repo)?;
+    let main_id = main_commit.id();
+    let ghost_id = ghost_commit.id();
+    drop((main_commit, ghost_commit));
+
+    let dirty = {
+        let tree = repo.find_tree(tree_id)?;
+        blocking_changes(repo, Some(&tree))?
+    };
+    // Branch names look like ghost/<card_id>/<timestamp>
+    let card_id = ghost_branch.split('/').nth(1).unwrap_or(ghost_branch);
+    guard_dirty_tree(repo, dirty, strategy, &card_stash_message(card_id))?;
+
+    // Straight to the merged result; changes carried over that are in the
+    // way fail here, before HEAD has moved
+    let tree = repo.find_tree(tree_id)?;
+    checkout_or_report(repo, tree.as_object())?;
+    repo.set_head(&format!("refs/heads/{}", main_branch_name))?;
+
+    let head_commit = repo.find_commit(main_id)?;
+    let ghost_commit = repo.find_commit(ghost_id)?;
+    let signature = signature(repo)?;
+
+    if mode == MergeMode::Squash {
+        // Squash: apply the ghost tree as a single commit on top of main
+        let message = match message {
+            Some(message) => message,
+            None => {
+                let summary = card_title(card_id).unwrap_or_else(|| ghost_branch.to_string());
+
+                let mut walk = repo.revwalk()?;
+                walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME | Sort::REVERSE)?;
+                walk.push(ghost_commit.id())?;
+                walk.hide(head_commit.id())?;
+                let squashed = collect_commits(repo, walk, 0, usize::MAX)?;
+
+                let mut message = format!("[VIRAITH] {}", summary);
+                if !squashed.is_empty() {
+                    let subjects: Vec<String> = squashed
+                        .iter()
+                        .map(|commit| format!("- {}", commit.message))
+                        .collect();
+                    message.push_str("\n\n");
+                    message.push_str(&subjects.join("\n"));
+                }
+                message
+            }
+        };
+
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            &message,
+            &tree,
+            &[&head_commit],
+        )?;
+    } else {
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            &message.unwrap_or_else(|| format!("[VIRAITH] Merge ghost branch {}", ghost_branch)),
+            &tree,
+            &[&head_commit, &ghost_commit],
+        )?;
+    }
 
     // Delete ghost branch
-    let mut branch = repo
-        .find_branch(&ghost_branch, BranchType::Local)
-        .map_err(|e| e.to_string())?;
-    branch.delete().map_err(|e| e.to_string())?;
+    let mut branch = repo.find_branch(ghost_branch, BranchType::Local)?;
+    branch.delete()?;
 
     Ok(())
 }
+
+/// Delete stale ghost branches that are merged into the default branch.
+/// `max_age_secs` defaults to the `git.ghost_branch_max_age_secs`
+/// preference. Unmerged ones are left for `discard_ghost_branch` unless
+/// `force` is set; that loses their work, so only pass it once the user has
+/// confirmed.
+#[tauri::command]
+pub async fn prune_ghost_branches(
+    repo_path: String,
+    max_age_secs: Option<i64>,
+    force: Option<bool>,
+    db: State<'_, Arc<Database>>,
+) -> Result<Vec<String>, AppError> {
+    let max_age_secs = match max_age_secs {
+        Some(secs) => secs,
+        None => {
+            db.blocking(|db| Ok::<_, AppError>(preferences::ghost_branch_max_age_secs(db)))
+                .await?
+        }
+    };
+    let ghost = GhostMode::new(&repo_path).map_err(AppError::Git)?;
+    ghost
+        .prune_ghost_branches(max_age_secs, force.unwrap_or(false))
+        .map_err(AppError::Git)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A repository in a temp dir with an identity to commit as
+    fn temp_repo() -> (tempfile::TempDir, Repository) {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        (dir, repo)
+    }
+
+    /// Write `files` and commit them on HEAD
+    fn commit_files(repo: &Repository, files: &[(&str, &str)], message: &str) -> Oid {
+        let workdir = repo.workdir().unwrap();
+        let mut index = repo.index().unwrap();
+        for (name, content) in files {
+            std::fs::write(workdir.join(name), content).unwrap();
+            index.add_path(Path::new(name)).unwrap();
+        }
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = repo.signature().unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<_> = parent.iter().collect();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .unwrap()
+    }
+
+    /// `main` with a.txt, and `feature` branched off it changing a.txt,
+    /// checked out on main
+    fn two_branches() -> (tempfile::TempDir, Repository) {
+        let (dir, repo) = temp_repo();
+        commit_files(&repo, &[("a.txt", "main\n")], "Initial");
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("main", &head, true).unwrap();
+        repo.set_head("refs/heads/main").unwrap();
+
+        repo.branch("feature", &head, false).unwrap();
+        repo.set_head("refs/heads/feature").unwrap();
+        commit_files(
+            &repo,
+            &[("a.txt", "feature\n"), ("b.txt", "new\n")],
+            "Feature",
+        );
+        repo.set_head("refs/heads/main").unwrap();
+        let mut checkout = CheckoutBuilder::new();
+        repo.checkout_head(Some(checkout.force())).unwrap();
+        drop(head);
+        (dir, repo)
+    }
+
+    fn read(repo: &Repository, name: &str) -> Option<String> {
+        std::fs::read_to_string(repo.workdir().unwrap().join(name)).ok()
+    }
+
+    fn head_name(repo: &Repository) -> String {
+        repo.head().unwrap().shorthand().unwrap().to_string()
+    }
+
+    #[test]
+    fn switching_branches_moves_head_and_the_working_tree() {
+        let (_dir, mut repo) = two_branches();
+
+        switch_branch(&mut repo, "feature", DirtyTreeStrategy::Refuse, "unused").unwrap();
+        assert_eq!(head_name(&repo), "feature");
+        assert_eq!(read(&repo, "a.txt").as_deref(), Some("feature\n"));
+        assert_eq!(read(&repo, "b.txt").as_deref(), Some("new\n"));
+
+        switch_branch(&mut repo, "main", DirtyTreeStrategy::Refuse, "unused").unwrap();
+        assert_eq!(head_name(&repo), "main");
+        assert_eq!(read(&repo, "a.txt").as_deref(), Some("main\n"));
+        assert_eq!(read(&repo, "b.txt"), None);
+        assert!(worktree_status(&repo).unwrap().files.is_empty());
+    }
+
+    #[test]
+    fn force_overwrites_changes_the_switch_would_clobber() {
+        let (_dir, mut repo) = two_branches();
+        std::fs::write(repo.workdir().unwrap().join("a.txt"), "edited\n").unwrap();
+
+        let refused = switch_branch(&mut repo, "feature", DirtyTreeStrategy::Refuse, "unused");
+        assert_eq!(refused.unwrap_err().code(), "DIRTY_WORKING_TREE");
+        assert_eq!(head_name(&repo), "main");
+        assert_eq!(read(&repo, "a.txt").as_deref(), Some("edited\n"));
+
+        switch_branch(&mut repo, "feature", DirtyTreeStrategy::Force, "unused").unwrap();
+        assert_eq!(head_name(&repo), "feature");
+        assert_eq!(read(&repo, "a.txt").as_deref(), Some("feature\n"));
+        assert!(worktree_status(&repo).unwrap().files.is_empty());
+    }
+
+    /// `two_branches` with `feature` renamed to a ghost branch, a.txt edited
+    /// on main and an untracked file next to it
+    fn dirty_before_merge() -> (tempfile::TempDir, Repository) {
+        let (dir, repo) = two_branches();
+        repo.find_branch("feature", BranchType::Local)
+            .unwrap()
+            .rename(GHOST, false)
+            .unwrap();
+        let workdir = repo.workdir().unwrap();
+        std::fs::write(workdir.join("a.txt"), "edited\n").unwrap();
+        std::fs::write(workdir.join("notes.txt"), "mine\n").unwrap();
+        (dir, repo)
+    }
+
+    const GHOST: &str = "ghost/card-1/1";
+
+    fn merge(repo: &mut Repository, strategy: DirtyTreeStrategy) -> Result<(), AppError> {
+        merge_into_default(repo, GHOST, MergeMode::Merge, None, strategy, |_| None)
+    }
+
+    /// Nothing happened: the edit is still there and the branch unmerged
+    fn assert_untouched(repo: &Repository) {
+        assert_eq!(head_name(repo), "main");
+        assert_eq!(read(repo, "a.txt").as_deref(), Some("edited\n"));
+        assert_eq!(read(repo, "notes.txt").as_deref(), Some("mine\n"));
+        assert!(repo.find_branch(GHOST, BranchType::Local).is_ok());
+    }
+
+    #[test]
+    fn merge_refuses_a_dirty_tree_without_touching_it() {
+        let (_dir, mut repo) = dirty_before_merge();
+        let err = merge(&mut repo, DirtyTreeStrategy::Refuse).unwrap_err();
+        assert_eq!(err.code(), "DIRTY_WORKING_TREE");
+        assert_untouched(&repo);
+    }
+
+    #[test]
+    fn forced_merge_still_keeps_changes_that_are_in_the_way() {
+        let (_dir, mut repo) = dirty_before_merge();
+        let err = merge(&mut repo, DirtyTreeStrategy::Force).unwrap_err();
+        assert_eq!(err.code(), "DIRTY_WORKING_TREE");
+        assert_untouched(&repo);
+    }
+
+    #[test]
+    fn merge_with_stash_keeps_the_changes_in_the_card_stash() {
+        let (_dir, mut repo) = dirty_before_merge();
+        merge(&mut repo, DirtyTreeStrategy::Stash).unwrap();
+
+        assert_eq!(head_name(&repo), "main");
+        assert_eq!(read(&repo, "a.txt").as_deref(), Some("feature\n"));
+        assert_eq!(read(&repo, "b.txt").as_deref(), Some("new\n"));
+        assert_eq!(read(&repo, "notes.txt").as_deref(), Some("mine\n"));
+        assert!(repo.find_branch(GHOST, BranchType::Local).is_err());
+        let parents = repo
+            .head()
+            .unwrap()
+            .peel_to_commit()
+            .unwrap()
+            .parent_count();
+        assert_eq!(parents, 2);
+
+        let stashes = stash_list(&mut repo).unwrap();
+        assert_eq!(stashes.len(), 1);
+        assert!(stashes[0].1.contains(&stash_marker("card-1")));
+        let stashed = repo.find_commit(stashes[0].2).unwrap().tree().unwrap();
+        let blob = stashed
+            .get_path(Path::new("a.txt"))
+            .unwrap()
+            .to_object(&repo);
+        assert_eq!(blob.unwrap().as_blob().unwrap().content(), b"edited\n");
+    }
+
+    #[test]
+    fn merge_carries_over_changes_that_are_not_in_the_way() {
+        for strategy in [
+            DirtyTreeStrategy::Refuse,
+            DirtyTreeStrategy::Stash,
+            DirtyTreeStrategy::Force,
+        ] {
+            let (_dir, mut repo) = dirty_before_merge();
+            // a.txt back as committed; only the untracked file is left
+            std::fs::write(repo.workdir().unwrap().join("a.txt"), "main\n").unwrap();
+
+            merge(&mut repo, strategy).unwrap();
+            assert_eq!(read(&repo, "a.txt").as_deref(), Some("feature\n"));
+            assert_eq!(read(&repo, "notes.txt").as_deref(), Some("mine\n"));
+            assert!(stash_list(&mut repo).unwrap().is_empty(), "{:?}", strategy);
+        }
+    }
+
+    #[test]
+    fn squash_commits_as_the_configured_user() {
+        let (_dir, mut repo) = two_branches();
+        repo.find_branch("feature", BranchType::Local)
+            .unwrap()
+            .rename(GHOST, false)
+            .unwrap();
+
+        merge_into_default(
+            &mut repo,
+            GHOST,
+            MergeMode::Squash,
+            None,
+            DirtyTreeStrategy::Refuse,
+            |card_id| Some(format!("Title of {}", card_id)),
+        )
+        .unwrap();
+
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.parent_count(), 1);
+        assert_eq!(head.author().name(), Some("Test"));
+        assert_eq!(head.committer().email(), Some("test@example.com"));
+        assert_eq!(
+            head.message(),
+            Some("[VIRAITH] Title of card-1\n\n- Feature")
+        );
+    }
+
+    #[test]
+    fn squashing_three_commits_adds_one_single_parent_commit() {
+        let (_dir, mut repo) = two_branches();
+        let main_before = repo.head().unwrap().peel_to_commit().unwrap().id();
+        let mut checkout = CheckoutBuilder::new();
+        repo.set_head("refs/heads/feature").unwrap();
+        repo.checkout_head(Some(checkout.force())).unwrap();
+        commit_files(&repo, &[("c.txt", "two\n")], "Second");
+        commit_files(&repo, &[("d.txt", "three\n")], "Third");
+        repo.find_branch("feature", BranchType::Local)
+            .unwrap()
+            .rename(GHOST, false)
+            .unwrap();
+        repo.set_head("refs/heads/main").unwrap();
+        repo.checkout_head(Some(checkout.force())).unwrap();
+
+        merge_into_default(
+            &mut repo,
+            GHOST,
+            MergeMode::Squash,
+            Some("Ship it".to_string()),
+            DirtyTreeStrategy::Refuse,
+            |_| None,
+        )
+        .unwrap();
+
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(head.parent_ids().collect::<Vec<_>>(), [main_before]);
+        assert_eq!(head.message(), Some("Ship it"));
+        assert_eq!(head_name(&repo), "main");
+        for (name, content) in [
+            ("a.txt", "feature\n"),
+            ("b.txt", "new\n"),
+            ("c.txt", "two\n"),
+            ("d.txt", "three\n"),
+        ] {
+            assert_eq!(read(&repo, name).as_deref(), Some(content));
+        }
+        assert!(repo.find_branch(GHOST, BranchType::Local).is_err());
+    }
+
+    #[tokio::test]
+    async fn branches_report_how_far_they_have_drifted_from_main() {
+        let (dir, repo) = two_branches();
+        let feature = repo.find_branch("feature", BranchType::Local).unwrap();
+        let fork = feature.get().peel_to_commit().unwrap().parent(0).unwrap();
+        repo.branch("ghost/idle", &fork, false).unwrap();
+        commit_files(&repo, &[("c.txt", "one\n")], "Main one");
+        commit_files(&repo, &[("c.txt", "two\n")], "Main two");
+
+        let branches = get_branches(dir.path().to_string_lossy().into_owned())
+            .await
+            .unwrap();
+        let drift = |name: &str| {
+            let branch = branches.iter().find(|b| b.name == name).unwrap();
+            (
+                branch.is_ghost,
+                branch.ahead_behind.map(|ab| (ab.ahead, ab.behind)),
+            )
+        };
+
+        assert_eq!(drift("main"), (false, None));
+        assert_eq!(drift("feature"), (false, Some((1, 2))));
+        assert_eq!(drift("ghost/idle"), (true, Some((0, 2))));
+
+        let main = branches.iter().find(|b| b.name == "main").unwrap();
+        let last = main.last_commit.as_ref().unwrap();
+        assert_eq!(last.message, "Main two");
+        assert_eq!(last.author, "Test");
+        assert_eq!(last.sha, repo.head().unwrap().target().unwrap().to_string());
+    }
+}
@@ -0,0 +1,171 @@
+use crate::database::{Database, FileIndexEntry, IndexStatus};
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+use tauri::State;
+
+/// Number of rows written per transaction while indexing
+const INDEX_BATCH_SIZE: usize = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexSummary {
+    pub indexed: usize,
+    pub unchanged: usize,
+    pub removed: usize,
+}
+
+/// Hash every file in the project and persist the results. Files whose size and
+/// mtime match the stored entry are skipped, so re-running is incremental.
+#[tauri::command]
+pub async fn index_project(
+    project_id: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<IndexSummary, AppError> {
+    db.blocking(move |db| {
+        let project = db.get_project(&project_id)?;
+
+        let known: HashMap<String, (i64, i64)> = db
+            .get_file_index(&project_id)?
+            .into_iter()
+            .map(|entry| (entry.path, (entry.size, entry.mtime)))
+            .collect();
+
+        let (changed, seen) = scan_project(&project_id, &project.root_path, &known, hash_file);
+
+        for batch in changed.chunks(INDEX_BATCH_SIZE) {
+            db.upsert_file_index(batch)?;
+        }
+
+        let removed: Vec<String> = known
+            .into_keys()
+            .filter(|path| !seen.contains(path))
+            .collect();
+
+        for batch in removed.chunks(INDEX_BATCH_SIZE) {
+            db.remove_file_index_paths(&project_id, batch)?;
+        }
+
+        Ok(IndexSummary {
+            indexed: changed.len(),
+            unchanged: seen.len() - changed.len(),
+            removed: removed.len(),
+        })
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_index_status(
+    project_id: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<IndexStatus, AppError> {
+    db.blocking(move |db| db.get_index_status(&project_id))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Walk the project (respecting .gitignore) and `hash` files that are new or
+/// modified. Returns the changed entries plus every relative path that was
+/// seen, including files that couldn't be read this time so their old entry
+/// is kept.
+fn scan_project(
+    project_id: &str,
+    root_path: &str,
+    known: &HashMap<String, (i64, i64)>,
+    hash: impl Fn(&Path) -> std::io::Result<String>,
+) -> (Vec<FileIndexEntry>, HashSet<String>) {
+    let root = Path::new(root_path);
+    let now = chrono::Utc::now().timestamp();
+
+    let mut changed = Vec::new();
+    let mut seen = HashSet::new();
+
+    let walker = ignore::WalkBuilder::new(root).build();
+
+    for entry in walker.filter_map(|e| e.ok()) {
+        if !entry.file_type().map(|t| t.is_file()).unwrap_or(false) {
+            continue;
+        }
+
+        let path = entry
+            .path()
+            .strip_prefix(root)
+            .unwrap_or(entry.path())
+            .to_string_lossy()
+            .to_string();
+        seen.insert(path.clone());
+
+        let metadata = match entry.metadata() {
+            Ok(m) => m,
+            Err(_) => continue,
+        };
+
+        let size = metadata.len() as i64;
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+
+        if known.get(&path) == Some(&(size, mtime)) {
+            continue;
+        }
+
+        let hash = match hash(entry.path()) {
+            Ok(h) => h,
+            Err(_) => continue,
+        };
+
+        changed.push(FileIndexEntry {
+            project_id: project_id.to_string(),
+            path,
+            hash,
+            size,
+            mtime,
+            indexed_at: now,
+        });
+    }
+
+    (changed, seen)
+}
+
+fn hash_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_file_that_cannot_be_hashed_keeps_its_entry() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("locked.txt"), "changed").unwrap();
+        std::fs::write(root.path().join("open.txt"), "new").unwrap();
+        let known = HashMap::from([
+            ("locked.txt".to_string(), (1, 1)),
+            ("deleted.txt".to_string(), (1, 1)),
+        ]);
+
+        let (changed, seen) =
+            scan_project("project", &root.path().to_string_lossy(), &known, |path| {
+                if path.ends_with("locked.txt") {
+                    Err(std::io::Error::from(std::io::ErrorKind::PermissionDenied))
+                } else {
+                    hash_file(path)
+                }
+            });
+
+        let changed: Vec<&str> = changed.iter().map(|entry| entry.path.as_str()).collect();
+        assert_eq!(changed, ["open.txt"]);
+        assert!(seen.contains("locked.txt"));
+        assert!(!seen.contains("deleted.txt"));
+    }
+}
@@ -0,0 +1,50 @@
+use crate::database::Database;
+use crate::deep_link;
+use crate::error::AppError;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tauri::{AppHandle, State};
+use tauri_plugin_deep_link::DeepLinkExt;
+
+/// Set once the links the app was started with have been followed
+static LAUNCH_LINKS_OPENED: AtomicBool = AtomicBool::new(false);
+
+/// The `viraith://` link to a card, for sharing
+#[tauri::command]
+pub async fn get_card_link(
+    card_id: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<String, AppError> {
+    let location = db
+        .blocking({
+            let card_id = card_id.clone();
+            move |db| db.get_card_location(&card_id)
+        })
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Card not found: {}", card_id)))?;
+
+    Ok(deep_link::card_link(
+        &location.project_id,
+        &location.card_id,
+    ))
+}
+
+/// Follow the links the app was started with, if any. Links are reported
+/// through events, which are lost until the page listens for them, so the
+/// page calls this once its `navigate` listeners are in place. Only the
+/// first call does anything.
+#[tauri::command]
+pub async fn open_launch_links(app: AppHandle) -> Result<(), AppError> {
+    if LAUNCH_LINKS_OPENED.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+
+    let urls = app
+        .deep_link()
+        .get_current()
+        .map_err(|e| AppError::Internal(format!("Failed to read the launch link: {}", e)))?;
+    for url in urls.into_iter().flatten() {
+        deep_link::open(&app, url.to_string());
+    }
+    Ok(())
+}
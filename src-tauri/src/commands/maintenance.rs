@@ -0,0 +1,107 @@
+use crate::backup::{BackupInfo, Backups};
+use crate::database::{Database, MaintenanceReport, StartupHealth};
+use crate::error::AppError;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, State};
+
+/// Integrity check, orphaned-row report, optional `VACUUM` and a size report
+#[tauri::command]
+pub async fn run_db_maintenance(
+    vacuum: Option<bool>,
+    db: State<'_, Arc<Database>>,
+) -> Result<MaintenanceReport, AppError> {
+    db.blocking(move |db| db.run_maintenance(vacuum.unwrap_or(false)))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Whether the integrity check at startup passed, so the UI can warn early
+#[tauri::command]
+pub async fn get_startup_health(db: State<'_, Arc<Database>>) -> Result<StartupHealth, AppError> {
+    Ok(db.startup_health())
+}
+
+/// Snapshot the database into the backup folder now
+#[tauri::command]
+pub async fn create_backup(
+    app: AppHandle,
+    db: State<'_, Arc<Database>>,
+    backups: State<'_, Arc<Backups>>,
+) -> Result<BackupInfo, AppError> {
+    let db = Arc::clone(&db);
+    let backups = Arc::clone(&backups);
+    let backup = tokio::task::spawn_blocking(move || backups.create(&db, false))
+        .await??
+        .ok_or_else(|| AppError::Conflict("A backup or restore is already running".to_string()))?;
+
+    let _ = app.emit("backup-completed", &backup);
+    Ok(backup)
+}
+
+/// Snapshots in the backup folder, newest first
+#[tauri::command]
+pub async fn list_backups(backups: State<'_, Arc<Backups>>) -> Result<Vec<BackupInfo>, AppError> {
+    let backups = Arc::clone(&backups);
+    tokio::task::spawn_blocking(move || backups.list()).await?
+}
+
+/// Replace the current data with a snapshot from the backup folder
+#[tauri::command]
+pub async fn restore_backup(
+    path: String,
+    db: State<'_, Arc<Database>>,
+    backups: State<'_, Arc<Backups>>,
+) -> Result<(), AppError> {
+    let db = Arc::clone(&db);
+    let backups = Arc::clone(&backups);
+    tokio::task::spawn_blocking(move || backups.restore(&db, &PathBuf::from(path))).await?
+}
+
+#[tauri::command]
+pub async fn is_database_encrypted(db: State<'_, Arc<Database>>) -> Result<bool, AppError> {
+    Ok(db.is_encrypted())
+}
+
+/// Encrypt the database with `new_password`, or switch it to a new one. The
+/// data is copied into a freshly encrypted file that then replaces the old
+/// one, and the password is kept in the OS keychain for the next start.
+#[tauri::command]
+pub async fn set_database_password(
+    new_password: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<(), AppError> {
+    if new_password.is_empty() {
+        return Err(AppError::validation(
+            "new_password",
+            "Password cannot be empty",
+        ));
+    }
+
+    #[cfg(feature = "encrypted-db")]
+    {
+        use crate::keychain;
+
+        let previous = keychain::database_key().map_err(AppError::Internal)?;
+        let key = new_password.clone();
+        db.blocking(move |db| db.reencrypt(Some(&key))).await?;
+
+        // Only now does the file need the new key. If the keychain won't take
+        // it, put the file back under the key the keychain still holds, or it
+        // couldn't be opened on the next start.
+        if let Err(e) = keychain::set_database_key(Some(&new_password)) {
+            db.blocking(move |db| db.reencrypt(previous.as_deref()))
+                .await?;
+            return Err(AppError::Internal(e));
+        }
+        Ok(())
+    }
+
+    #[cfg(not(feature = "encrypted-db"))]
+    {
+        let _ = db;
+        Err(AppError::Internal(
+            "This build doesn't support database encryption".to_string(),
+        ))
+    }
+}
@@ -1,8 +1,22 @@
+pub mod agent;
 pub mod board;
 pub mod card;
+pub mod checklist;
 pub mod column;
+pub mod comment;
+pub mod dependency;
+pub mod draft;
+pub mod execution;
 pub mod file;
 pub mod git;
+pub mod index;
+pub mod link;
+pub mod maintenance;
+pub mod preference;
 pub mod project;
+pub mod recent;
 pub mod shell;
+pub mod template;
+pub mod terminal;
+pub mod todo;
 pub mod window;
@@ -0,0 +1,52 @@
+use crate::database::Database;
+use crate::error::AppError;
+use crate::preferences;
+use std::sync::Arc;
+use tauri::State;
+
+#[tauri::command]
+pub async fn get_preference(
+    key: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<Option<serde_json::Value>, AppError> {
+    db.blocking(move |db| db.get_preference(&key))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Store a preference. Any key is accepted; the ones the backend reads must
+/// hold a value of the shape it expects.
+#[tauri::command]
+pub async fn set_preference(
+    key: String,
+    value: serde_json::Value,
+    db: State<'_, Arc<Database>>,
+) -> Result<(), AppError> {
+    if key.trim().is_empty() {
+        return Err(AppError::validation(
+            "key",
+            "Preference key cannot be empty",
+        ));
+    }
+    preferences::validate(&key, &value)?;
+
+    db.blocking(move |db| db.set_preference(&key, &value))
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_all_preferences(
+    db: State<'_, Arc<Database>>,
+) -> Result<serde_json::Map<String, serde_json::Value>, AppError> {
+    db.blocking(|db| db.get_all_preferences())
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn reset_preferences(db: State<'_, Arc<Database>>) -> Result<(), AppError> {
+    db.blocking(|db| db.reset_preferences())
+        .await
+        .map_err(AppError::from)
+}
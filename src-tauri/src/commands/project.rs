@@ -1,35 +1,520 @@
-use crate::database::{Database, Project};
+use crate::database::{
+    Database, Project, ProjectBundle, ProjectReport, ProjectSettings, ProjectStats, RecentCard,
+    RecentProject, ScaffoldedBoard, BUNDLE_VERSION,
+};
+use crate::error::AppError;
+use crate::files;
+use crate::git::GhostMode;
+use crate::status::CARD_STATUSES;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
 use std::sync::Arc;
 use tauri::State;
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreatedProject {
+    #[serde(flatten)]
+    pub project: Project,
+    /// The starter board, when the project was scaffolded
+    pub board: Option<ScaffoldedBoard>,
+}
+
+/// Turn a failed project insert into `ProjectPathExists` when the folder is
+/// already registered, so the UI can offer to open that project instead
+fn project_insert_error(db: &Database, root_path: &str, error: rusqlite::Error) -> AppError {
+    let is_constraint = matches!(
+        &error,
+        rusqlite::Error::SqliteFailure(e, _) if e.code == rusqlite::ErrorCode::ConstraintViolation
+    );
+
+    if is_constraint {
+        if let Ok(Some(existing)) = db.get_project_by_root(root_path) {
+            return AppError::ProjectPathExists {
+                existing_project_id: existing.id,
+                existing_project_name: existing.name,
+            };
+        }
+    }
+
+    error.into()
+}
+
+/// Create a project. Unless `scaffold` is false it comes with a "Main" board
+/// and Backlog / In Progress / Review / Done columns.
 #[tauri::command]
 pub async fn create_project(
     name: String,
     root_path: String,
+    scaffold: Option<bool>,
     db: State<'_, Arc<Database>>,
-) -> Result<Project, String> {
+) -> Result<CreatedProject, AppError> {
+    db.blocking(move |db| create_project_in(db, name, &root_path, scaffold.unwrap_or(true)))
+        .await
+}
+
+fn create_project_in(
+    db: &Database,
+    name: String,
+    root_path: &str,
+    scaffold: bool,
+) -> Result<CreatedProject, AppError> {
     let id = uuid::Uuid::new_v4().to_string();
     let now = chrono::Utc::now().timestamp();
+    let root_path = files::canonical_root(root_path);
 
-    db.create_project(&id, &name, &root_path)
-        .map_err(|e| e.to_string())?;
+    let created = if scaffold {
+        db.create_project_scaffolded(&id, &name, &root_path)
+            .map(Some)
+    } else {
+        db.create_project(&id, &name, &root_path).map(|_| None)
+    };
+    let board = created.map_err(|e| project_insert_error(db, &root_path, e))?;
+    let _ = db.touch_project(&id);
 
-    Ok(Project {
-        id,
-        name,
-        root_path,
-        created_at: now,
-        updated_at: now,
-        settings: "{}".to_string(),
+    Ok(CreatedProject {
+        project: Project {
+            id,
+            name,
+            root_path,
+            created_at: now,
+            updated_at: now,
+            settings: "{}".to_string(),
+            locked: false,
+        },
+        board,
     })
 }
 
 #[tauri::command]
-pub async fn get_projects(db: State<'_, Arc<Database>>) -> Result<Vec<Project>, String> {
-    db.get_projects().map_err(|e| e.to_string())
+pub async fn get_projects(db: State<'_, Arc<Database>>) -> Result<Vec<Project>, AppError> {
+    db.blocking(move |db| db.get_projects())
+        .await
+        .map_err(AppError::from)
+}
+
+/// Mark a project as opened so it moves to the top of `get_recent_projects`
+/// and the recent projects list
+#[tauri::command]
+pub async fn touch_project(id: String, db: State<'_, Arc<Database>>) -> Result<(), AppError> {
+    db.blocking(move |db| {
+        db.touch_project(&id).map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => {
+                AppError::NotFound(format!("Project not found: {}", id))
+            }
+            e => e.into(),
+        })
+    })
+    .await
+}
+
+/// Projects for the picker, most recently opened first
+#[tauri::command]
+pub async fn get_recent_projects(
+    limit: Option<u32>,
+    db: State<'_, Arc<Database>>,
+) -> Result<Vec<RecentProject>, AppError> {
+    db.blocking(move |db| db.get_recent_projects(limit.unwrap_or(20)))
+        .await
+        .map_err(AppError::from)
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectDeletion {
+    pub branches_deleted: Vec<String>,
+    pub branches_failed: Vec<String>,
+}
+
+/// Delete a project and, unless `keep_branches` is set, the ghost branches its
+/// cards left in the repository. Branch cleanup is best effort: the project is
+/// deleted even if its folder is gone, isn't a git repo or a branch won't delete.
+#[tauri::command]
+pub async fn delete_project(
+    id: String,
+    keep_branches: Option<bool>,
+    db: State<'_, Arc<Database>>,
+) -> Result<ProjectDeletion, AppError> {
+    db.blocking(move |db| {
+        let project = db.get_project(&id)?;
+        let card_ids: HashSet<String> = db.get_project_card_ids(&id)?.into_iter().collect();
+
+        db.delete_project(&id)?;
+
+        if keep_branches.unwrap_or(false) || card_ids.is_empty() {
+            return Ok(ProjectDeletion::default());
+        }
+
+        let cleanup = GhostMode::new(&project.root_path)
+            .and_then(|ghost| ghost.delete_card_branches(&card_ids));
+
+        Ok(match cleanup {
+            Ok((branches_deleted, branches_failed)) => ProjectDeletion {
+                branches_deleted,
+                branches_failed,
+            },
+            Err(_) => ProjectDeletion::default(),
+        })
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_project_settings(
+    id: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<ProjectSettings, AppError> {
+    db.blocking(move |db| db.get_project_settings(&id))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Make a project read-only, or writable again. Changes to a locked
+/// project's boards, columns and cards fail with `PROJECT_LOCKED`.
+#[tauri::command]
+pub async fn set_project_locked(
+    id: String,
+    locked: bool,
+    db: State<'_, Arc<Database>>,
+) -> Result<(), AppError> {
+    db.blocking(move |db| {
+        db.set_project_locked(&id, locked).map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => {
+                AppError::NotFound(format!("Project not found: {}", id))
+            }
+            e => e.into(),
+        })
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn update_project_settings(
+    id: String,
+    settings: ProjectSettings,
+    db: State<'_, Arc<Database>>,
+) -> Result<(), AppError> {
+    if let Some(pattern) = settings
+        .ignore_patterns
+        .iter()
+        .find(|pattern| !files::is_valid_ignore_pattern(pattern))
+    {
+        return Err(AppError::validation(
+            "ignore_patterns",
+            format!("Not a valid glob pattern: {}", pattern),
+        ));
+    }
+
+    db.blocking(move |db| db.update_project_settings(&id, &settings))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Serialize a project with its boards, columns, cards and executions to JSON
+#[tauri::command]
+pub async fn export_project(
+    project_id: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<String, AppError> {
+    let bundle = db
+        .blocking(move |db| db.export_project(&project_id))
+        .await?;
+    serde_json::to_string_pretty(&bundle).map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn import_project(
+    json: String,
+    new_root_path: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<Project, AppError> {
+    let bundle: ProjectBundle = serde_json::from_str(&json)
+        .map_err(|e| AppError::validation("json", format!("Invalid project bundle: {}", e)))?;
+
+    if bundle.schema_version > BUNDLE_VERSION {
+        return Err(AppError::validation(
+            "json",
+            format!(
+                "Unsupported bundle version {} (expected {} or older)",
+                bundle.schema_version, BUNDLE_VERSION
+            ),
+        ));
+    }
+
+    db.blocking(move |db| db.import_project(&bundle, &new_root_path))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Totals, weekly throughput and execution outcomes for a project dashboard
+#[tauri::command]
+pub async fn get_project_stats(
+    project_id: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<ProjectStats, AppError> {
+    let now = chrono::Utc::now().timestamp();
+    db.blocking(move |db| db.get_project_stats(&project_id, now))
+        .await
+        .map_err(AppError::from)
+}
+
+/// How far back "recently updated" reaches in a status report
+const REPORT_RECENT_DAYS: i64 = 7;
+
+/// Totals from a status report, for the UI to summarize
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReportTotals {
+    pub boards: usize,
+    pub columns: usize,
+    pub cards: usize,
+    /// Live cards per status; statuses with no cards are left out
+    pub by_status: BTreeMap<String, usize>,
+    pub overdue: usize,
+    pub recently_updated: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectReportExport {
+    pub path: String,
+    pub totals: ReportTotals,
 }
 
+/// Write a markdown status report of a project to `dest_path`: each board's
+/// columns with their cards grouped by status, then overdue cards and cards
+/// updated in the last week. A directory gets a dated file name. Ordering is
+/// stable, so successive reports can be diffed.
 #[tauri::command]
-pub async fn delete_project(id: String, db: State<'_, Arc<Database>>) -> Result<(), String> {
-    db.delete_project(&id).map_err(|e| e.to_string())
+pub async fn export_project_report(
+    project_id: String,
+    dest_path: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<ProjectReportExport, AppError> {
+    let now = chrono::Utc::now().timestamp();
+    let recent_since = now - REPORT_RECENT_DAYS * 24 * 60 * 60;
+
+    let report = db
+        .blocking(move |db| db.get_project_report(&project_id, now, recent_since))
+        .await?;
+
+    let totals = report_totals(&report);
+    let markdown = render_project_report(&report, &totals, now);
+
+    let mut path = PathBuf::from(dest_path);
+    if path.is_dir() {
+        let date = chrono::DateTime::from_timestamp(now, 0)
+            .map(|t| t.format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|| now.to_string());
+        path.push(format!("status-report-{}.md", date));
+    }
+
+    tokio::fs::write(&path, &markdown)
+        .await
+        .map_err(|e| AppError::io("Failed to write project report", e))?;
+
+    Ok(ProjectReportExport {
+        path: path.to_string_lossy().into_owned(),
+        totals,
+    })
+}
+
+fn report_totals(report: &ProjectReport) -> ReportTotals {
+    let mut totals = ReportTotals {
+        boards: report.boards.len(),
+        overdue: report.overdue.len(),
+        recently_updated: report.recently_updated.len(),
+        ..Default::default()
+    };
+
+    for column in report.boards.iter().flat_map(|board| &board.columns) {
+        totals.columns += 1;
+        totals.cards += column.cards.len();
+        for card in &column.cards {
+            *totals.by_status.entry(card.status.clone()).or_default() += 1;
+        }
+    }
+
+    totals
+}
+
+fn render_project_report(report: &ProjectReport, totals: &ReportTotals, now: i64) -> String {
+    let date = |secs: i64| {
+        chrono::DateTime::from_timestamp(secs, 0)
+            .map(|t| t.format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|| secs.to_string())
+    };
+    let location = |card: &RecentCard| format!("{} / {}", card.board_name, card.column_name);
+
+    let mut out = format!("# {} status report\n\n", report.project.name);
+    out.push_str(&format!("Generated {}\n\n", date(now)));
+
+    let by_status: Vec<String> = CARD_STATUSES
+        .iter()
+        .filter_map(|status| {
+            let count = totals.by_status.get(*status)?;
+            Some(format!("{} {}", count, status))
+        })
+        .collect();
+    out.push_str(&format!(
+        "{} cards across {} boards",
+        totals.cards, totals.boards
+    ));
+    if !by_status.is_empty() {
+        out.push_str(&format!(" ({})", by_status.join(", ")));
+    }
+    out.push_str(&format!(
+        ". {} overdue, {} updated in the last {} days.\n",
+        totals.overdue, totals.recently_updated, REPORT_RECENT_DAYS
+    ));
+
+    for board in &report.boards {
+        out.push_str(&format!("\n## {}\n", board.board.name));
+
+        for column in &board.columns {
+            out.push_str(&format!(
+                "\n### {} ({})\n",
+                column.column.name,
+                column.cards.len()
+            ));
+            if column.cards.is_empty() {
+                out.push_str("\nNo cards\n");
+                continue;
+            }
+
+            // Cards keep their column order within each status group
+            for status in CARD_STATUSES {
+                let cards: Vec<_> = column
+                    .cards
+                    .iter()
+                    .filter(|card| card.status == *status)
+                    .collect();
+                if cards.is_empty() {
+                    continue;
+                }
+
+                out.push_str(&format!("\n**{}** ({})\n\n", status, cards.len()));
+                for card in cards {
+                    out.push_str(&format!("- {}\n", card.title));
+                }
+            }
+        }
+    }
+
+    let has_due_dates = report
+        .boards
+        .iter()
+        .flat_map(|board| &board.columns)
+        .flat_map(|column| &column.cards)
+        .any(|card| card.due_at.is_some());
+    if has_due_dates {
+        out.push_str("\n## Overdue\n\n");
+        if report.overdue.is_empty() {
+            out.push_str("Nothing overdue\n");
+        }
+        for card in &report.overdue {
+            out.push_str(&format!(
+                "- {} ({}), due {}\n",
+                card.card.title,
+                location(card),
+                card.card.due_at.map(date).unwrap_or_default()
+            ));
+        }
+    }
+
+    out.push_str(&format!(
+        "\n## Updated in the last {} days\n\n",
+        REPORT_RECENT_DAYS
+    ));
+    if report.recently_updated.is_empty() {
+        out.push_str("No recent updates\n");
+    }
+    for card in &report.recently_updated {
+        out.push_str(&format!(
+            "- {} ({}), {} on {}\n",
+            card.card.title,
+            location(card),
+            card.card.status,
+            date(card.card.updated_at)
+        ));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::testing::*;
+
+    #[test]
+    fn a_registered_root_is_reported_with_the_existing_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_string_lossy().into_owned();
+        let db = memory_db();
+
+        let first = create_project_in(&db, "First".into(), &root, false).unwrap();
+        let second = create_project_in(&db, "Second".into(), &root, false).unwrap_err();
+
+        match second {
+            AppError::ProjectPathExists {
+                existing_project_id,
+                existing_project_name,
+            } => {
+                assert_eq!(existing_project_id, first.project.id);
+                assert_eq!(existing_project_name, "First");
+            }
+            other => panic!("expected ProjectPathExists, got {:?}", other),
+        }
+        assert_eq!(db.get_projects().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn root_spellings_resolve_to_one_canonical_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("project");
+        std::fs::create_dir(&root).unwrap();
+        let canonical = root.canonicalize().unwrap().to_string_lossy().into_owned();
+
+        let db = memory_db();
+        let with_slash = format!("{}/", root.to_string_lossy());
+        let created = create_project_in(&db, "Slash".into(), &with_slash, false).unwrap();
+        assert_eq!(created.project.root_path, canonical);
+
+        let dotted = root.join("../project").to_string_lossy().into_owned();
+        assert!(matches!(
+            create_project_in(&db, "Dotted".into(), &dotted, false),
+            Err(AppError::ProjectPathExists { .. })
+        ));
+
+        #[cfg(unix)]
+        {
+            let link = dir.path().join("link");
+            std::os::unix::fs::symlink(&root, &link).unwrap();
+            let linked = link.to_string_lossy().into_owned();
+            assert!(matches!(
+                create_project_in(&db, "Linked".into(), &linked, false),
+                Err(AppError::ProjectPathExists { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn creating_a_scaffolded_project_twice_points_at_the_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("project");
+        std::fs::create_dir(&root).unwrap();
+        let root = root.to_string_lossy().into_owned();
+        let db = memory_db();
+
+        let first = create_project_in(&db, "App".into(), &root, true).unwrap();
+        let again =
+            create_project_in(&db, "App again".into(), &format!("{}/", root), true).unwrap_err();
+
+        let error = serde_json::to_value(&again).unwrap();
+        assert_eq!(error["code"], "PROJECT_PATH_EXISTS");
+        assert_eq!(error["existing_project_id"], first.project.id.as_str());
+        assert_eq!(error["existing_project_name"], "App");
+
+        // The failed attempt leaves no half-made starter board behind
+        assert_eq!(db.get_projects().unwrap().len(), 1);
+        assert_eq!(db.get_boards(&first.project.id).unwrap().len(), 1);
+    }
 }
@@ -0,0 +1,30 @@
+use crate::database::{Database, RecentItem, RecentKind};
+use crate::error::AppError;
+use std::sync::Arc;
+use tauri::State;
+
+/// Put a project id or file path at the top of its recent list. Opening a
+/// project or reading a file already records it; this covers anything else the
+/// UI considers "opened".
+#[tauri::command]
+pub async fn record_recent(
+    kind: RecentKind,
+    path_or_id: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<(), AppError> {
+    db.blocking(move |db| db.record_recent(kind, &path_or_id))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Recently opened projects or files, most recent first
+#[tauri::command]
+pub async fn get_recent(
+    kind: RecentKind,
+    limit: Option<u32>,
+    db: State<'_, Arc<Database>>,
+) -> Result<Vec<RecentItem>, AppError> {
+    db.blocking(move |db| db.get_recent(kind, limit.unwrap_or(20)))
+        .await
+        .map_err(AppError::from)
+}
@@ -1,5 +1,40 @@
+use crate::error::AppError;
+use crate::process::{OutputStream, ProcessManager};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, State};
+
+/// Default limit for `run_command` when the caller doesn't pass one
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandOutput {
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: Option<i32>,
+}
+
+/// Payload of the `command-output` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandOutputEvent {
+    pub execution_id: String,
+    pub stream: OutputStream,
+    pub line: String,
+}
+
+/// Payload of the `command-exit` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandExitEvent {
+    pub execution_id: String,
+    pub pid: u32,
+    pub exit_code: Option<i32>,
+}
+
 #[tauri::command]
-pub async fn reveal_in_finder(path: String) -> Result<(), String> {
+pub async fn reveal_in_finder(path: String) -> Result<(), AppError> {
     #[cfg(target_os = "macos")]
     {
         use std::process::Command;
@@ -7,7 +42,7 @@ pub async fn reveal_in_finder(path: String) -> Result<(), String> {
             .arg("-R")
             .arg(&path)
             .spawn()
-            .map_err(|e| format!("Failed to reveal in Finder: {}", e))?;
+            .map_err(|e| AppError::io("Failed to reveal in Finder", e))?;
     }
 
     #[cfg(target_os = "windows")]
@@ -17,7 +52,7 @@ pub async fn reveal_in_finder(path: String) -> Result<(), String> {
             .arg("/select,")
             .arg(&path)
             .spawn()
-            .map_err(|e| format!("Failed to reveal in Explorer: {}", e))?;
+            .map_err(|e| AppError::io("Failed to reveal in Explorer", e))?;
     }
 
     #[cfg(target_os = "linux")]
@@ -33,21 +68,92 @@ pub async fn reveal_in_finder(path: String) -> Result<(), String> {
                 format!("array:string:file://{}", path).as_str(),
             ])
             .spawn()
-            .map_err(|e| format!("Failed to reveal in file manager: {}", e))?;
+            .map_err(|e| AppError::io("Failed to reveal in file manager", e))?;
     }
 
     Ok(())
 }
 
+/// Schemes `open_external_url` will hand to the OS
+const EXTERNAL_URL_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+/// Open a web or mail link in the user's default handler. Anything that isn't a
+/// well-formed http(s) or mailto URL is rejected.
 #[tauri::command]
-pub async fn open_path(path: String) -> Result<(), String> {
+pub async fn open_external_url(url: String) -> Result<(), AppError> {
+    let url = parse_external_url(&url)?;
+
+    #[cfg(target_os = "macos")]
+    {
+        use std::process::Command;
+        Command::new("open")
+            .arg(url.as_str())
+            .spawn()
+            .map_err(|e| AppError::io("Failed to open URL", e))?;
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use std::process::Command;
+        // Avoids cmd's `start`, which would re-parse the URL as shell syntax
+        Command::new("rundll32")
+            .arg("url.dll,FileProtocolHandler")
+            .arg(url.as_str())
+            .spawn()
+            .map_err(|e| AppError::io("Failed to open URL", e))?;
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        use std::process::Command;
+        Command::new("xdg-open")
+            .arg(url.as_str())
+            .spawn()
+            .map_err(|e| AppError::io("Failed to open URL", e))?;
+    }
+
+    Ok(())
+}
+
+fn parse_external_url(raw: &str) -> Result<url::Url, AppError> {
+    let url = url::Url::parse(raw.trim())
+        .map_err(|e| AppError::validation("url", format!("Invalid URL: {}", e)))?;
+
+    if !EXTERNAL_URL_SCHEMES.contains(&url.scheme()) {
+        return Err(AppError::validation(
+            "url",
+            format!("Refusing to open '{}' URL", url.scheme()),
+        ));
+    }
+
+    if url.scheme() != "mailto" && url.host_str().is_none() {
+        return Err(AppError::validation("url", "URL has no host"));
+    }
+
+    Ok(url)
+}
+
+#[tauri::command]
+pub async fn open_path(path: String) -> Result<(), AppError> {
+    // A leading '-' would be read as a flag by the opener
+    if path.starts_with('-') {
+        return Err(AppError::validation(
+            "path",
+            format!("Refusing to open '{}'", path),
+        ));
+    }
+
+    if !Path::new(&path).exists() {
+        return Err(AppError::NotFound(format!("Path does not exist: {}", path)));
+    }
+
     #[cfg(target_os = "macos")]
     {
         use std::process::Command;
         Command::new("open")
             .arg(&path)
             .spawn()
-            .map_err(|e| format!("Failed to open: {}", e))?;
+            .map_err(|e| AppError::io("Failed to open", e))?;
     }
 
     #[cfg(target_os = "windows")]
@@ -57,7 +163,7 @@ pub async fn open_path(path: String) -> Result<(), String> {
             .arg("")
             .arg(&path)
             .spawn()
-            .map_err(|e| format!("Failed to open: {}", e))?;
+            .map_err(|e| AppError::io("Failed to open", e))?;
     }
 
     #[cfg(target_os = "linux")]
@@ -66,8 +172,184 @@ pub async fn open_path(path: String) -> Result<(), String> {
         Command::new("xdg-open")
             .arg(&path)
             .spawn()
-            .map_err(|e| format!("Failed to open: {}", e))?;
+            .map_err(|e| AppError::io("Failed to open", e))?;
     }
 
     Ok(())
 }
+
+/// Run a program to completion and capture its output. The child is killed if it
+/// outlives `timeout_secs`.
+#[tauri::command]
+pub async fn run_command(
+    cwd: String,
+    program: String,
+    args: Vec<String>,
+    env: Option<HashMap<String, String>>,
+    timeout_secs: Option<u64>,
+) -> Result<CommandOutput, AppError> {
+    let timeout = timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS);
+
+    let child = tokio::process::Command::new(&program)
+        .args(&args)
+        .current_dir(&cwd)
+        .envs(env.unwrap_or_default())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| AppError::io(&format!("Failed to start {}", program), e))?;
+
+    // Dropping the wait future on timeout drops the child, which kills it
+    let output = tokio::time::timeout(
+        tokio::time::Duration::from_secs(timeout),
+        child.wait_with_output(),
+    )
+    .await
+    .map_err(|_| AppError::Timeout {
+        message: format!("Command timed out after {} seconds", timeout),
+        seconds: timeout,
+    })?
+    .map_err(|e| AppError::io(&format!("Failed to run {}", program), e))?;
+
+    Ok(CommandOutput {
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        exit_code: output.status.code(),
+    })
+}
+
+/// Start a program and stream its output as `command-output` events, followed by
+/// a `command-exit` event. Returns the pid, which can be passed to `kill_command`.
+#[tauri::command]
+pub async fn run_command_streaming(
+    cwd: String,
+    program: String,
+    args: Vec<String>,
+    execution_id: Option<String>,
+    app: AppHandle,
+    processes: State<'_, Arc<ProcessManager>>,
+) -> Result<u32, AppError> {
+    let execution_id = execution_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let line_app = app.clone();
+    let line_execution_id = execution_id.clone();
+    let process = processes
+        .spawn(
+            &cwd,
+            &program,
+            &args,
+            &HashMap::new(),
+            move |stream, line| {
+                let _ = line_app.emit(
+                    "command-output",
+                    CommandOutputEvent {
+                        execution_id: line_execution_id.clone(),
+                        stream,
+                        line,
+                    },
+                );
+            },
+        )
+        .map_err(AppError::Io)?;
+
+    let pid = process.pid;
+    tokio::spawn(async move {
+        let exit_code = process.handle.await.ok().flatten();
+        let _ = app.emit(
+            "command-exit",
+            CommandExitEvent {
+                execution_id,
+                pid,
+                exit_code,
+            },
+        );
+    });
+
+    Ok(pid)
+}
+
+#[tauri::command]
+pub async fn kill_command(
+    pid: u32,
+    processes: State<'_, Arc<ProcessManager>>,
+) -> Result<bool, AppError> {
+    Ok(processes.kill(pid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_web_and_mail_urls_are_opened() {
+        for url in [
+            "https://example.com/docs?page=1",
+            "  http://localhost:3000  ",
+            "mailto:dev@example.com",
+        ] {
+            assert!(parse_external_url(url).is_ok(), "{}", url);
+        }
+        for url in [
+            "file:///etc/passwd",
+            "javascript:alert(1)",
+            "--flag",
+            "https://",
+            "example.com",
+        ] {
+            let err = parse_external_url(url).unwrap_err();
+            assert_eq!(err.code(), "VALIDATION", "{}", url);
+        }
+    }
+
+    #[tokio::test]
+    async fn open_path_refuses_flags_and_missing_paths() {
+        let err = open_path("--help".to_string()).await.unwrap_err();
+        assert_eq!(err.code(), "VALIDATION");
+
+        let missing = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let err = open_path(missing.to_string_lossy().into_owned())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "NOT_FOUND");
+    }
+
+    #[cfg(unix)]
+    async fn sh(script: &str, timeout_secs: Option<u64>) -> Result<CommandOutput, AppError> {
+        run_command(
+            ".".to_string(),
+            "sh".to_string(),
+            vec!["-c".to_string(), script.to_string()],
+            Some(HashMap::from([("GREETING".to_string(), "hi".to_string())])),
+            timeout_secs,
+        )
+        .await
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn run_command_captures_stdout_and_the_exit_code() {
+        let output = sh("echo $GREETING", None).await.unwrap();
+        assert_eq!(output.stdout, "hi\n");
+        assert_eq!(output.stderr, "");
+        assert_eq!(output.exit_code, Some(0));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn run_command_reports_a_failing_exit_and_stderr() {
+        let output = sh("echo oops >&2; exit 3", None).await.unwrap();
+        assert_eq!(output.stdout, "");
+        assert_eq!(output.stderr, "oops\n");
+        assert_eq!(output.exit_code, Some(3));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn run_command_gives_up_after_the_timeout() {
+        let err = sh("sleep 5", Some(1)).await.unwrap_err();
+        assert_eq!(err.code(), "TIMEOUT");
+        assert_eq!(err.detail(), Some(serde_json::json!({ "seconds": 1 })));
+    }
+}
@@ -0,0 +1,47 @@
+use crate::database::{Board, BoardTemplate, Database};
+use crate::error::AppError;
+use std::sync::Arc;
+use tauri::State;
+
+#[tauri::command]
+pub async fn list_templates(db: State<'_, Arc<Database>>) -> Result<Vec<BoardTemplate>, AppError> {
+    db.blocking(move |db| db.list_templates())
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn save_board_as_template(
+    board_id: String,
+    name: String,
+    include_cards: Option<bool>,
+    db: State<'_, Arc<Database>>,
+) -> Result<BoardTemplate, AppError> {
+    db.blocking(move |db| {
+        db.save_board_as_template(&board_id, &name, include_cards.unwrap_or(false))
+    })
+    .await
+    .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn create_board_from_template(
+    project_id: String,
+    template_id: String,
+    board_name: String,
+    db: State<'_, Arc<Database>>,
+) -> Result<Board, AppError> {
+    db.blocking(move |db| db.create_board_from_template(&project_id, &template_id, &board_name))
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn delete_template(id: String, db: State<'_, Arc<Database>>) -> Result<(), AppError> {
+    if !db.blocking(move |db| db.delete_template(&id)).await? {
+        return Err(AppError::Conflict(
+            "Built-in templates can't be deleted".to_string(),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use crate::error::AppError;
+use crate::terminal::TerminalManager;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, State};
+
+/// Payload of the `terminal-output` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminalOutputEvent {
+    pub id: String,
+    pub data: String,
+}
+
+/// Payload of the `terminal-exit` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminalExitEvent {
+    pub id: String,
+}
+
+/// Open a shell session. Its output arrives as `terminal-output` events and a
+/// `terminal-exit` event follows when the shell ends.
+#[tauri::command]
+pub async fn terminal_create(
+    cwd: String,
+    rows: u16,
+    cols: u16,
+    app: AppHandle,
+    terminals: State<'_, Arc<TerminalManager>>,
+) -> Result<String, AppError> {
+    let output_app = app.clone();
+
+    terminals
+        .create(
+            &cwd,
+            rows,
+            cols,
+            move |id, data| {
+                let _ = output_app.emit(
+                    "terminal-output",
+                    TerminalOutputEvent {
+                        id: id.to_string(),
+                        data: data.to_string(),
+                    },
+                );
+            },
+            move |id| {
+                let _ = app.emit("terminal-exit", TerminalExitEvent { id: id.to_string() });
+            },
+        )
+        .map_err(AppError::Io)
+}
+
+#[tauri::command]
+pub async fn terminal_write(
+    id: String,
+    data: String,
+    terminals: State<'_, Arc<TerminalManager>>,
+) -> Result<(), AppError> {
+    terminals.write(&id, &data)
+}
+
+#[tauri::command]
+pub async fn terminal_resize(
+    id: String,
+    rows: u16,
+    cols: u16,
+    terminals: State<'_, Arc<TerminalManager>>,
+) -> Result<(), AppError> {
+    terminals.resize(&id, rows, cols)
+}
+
+#[tauri::command]
+pub async fn terminal_close(
+    id: String,
+    terminals: State<'_, Arc<TerminalManager>>,
+) -> Result<bool, AppError> {
+    Ok(terminals.close(&id))
+}
@@ -0,0 +1,117 @@
+use crate::database::{Database, TodoCard};
+use crate::error::AppError;
+use crate::files::{self, TodoHit};
+use crate::preferences;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::State;
+
+/// Files larger than this are skipped unless the caller says otherwise
+const DEFAULT_MAX_FILE_SIZE: u64 = 1024 * 1024;
+const DEFAULT_MAX_FILES: usize = 10_000;
+
+/// Longest card title made from a comment, in characters
+const MAX_TODO_TITLE: usize = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TodoScanResult {
+    pub todos: Vec<TodoHit>,
+    pub files_scanned: usize,
+    /// The file limit was reached before the whole project was scanned
+    pub truncated: bool,
+    /// Cards made for comments that didn't have one yet
+    pub created_card_ids: Vec<String>,
+}
+
+/// List the TODO, FIXME and HACK comments in a project's files. With
+/// `create_cards` each one also becomes a card in `target_column_id`, unless
+/// an earlier scan already made a card for the same file and line.
+#[tauri::command]
+pub async fn scan_todos(
+    project_id: String,
+    create_cards: bool,
+    target_column_id: Option<String>,
+    max_file_size: Option<u64>,
+    max_files: Option<usize>,
+    db: State<'_, Arc<Database>>,
+) -> Result<TodoScanResult, AppError> {
+    let column_id = match target_column_id {
+        Some(column_id) if create_cards => Some(column_id),
+        None if create_cards => {
+            return Err(AppError::validation(
+                "target_column_id",
+                "A target column is needed to create cards",
+            ))
+        }
+        _ => None,
+    };
+
+    let lookup_id = project_id.clone();
+    let project = db.blocking(move |db| db.get_project(&lookup_id)).await?;
+
+    let root = PathBuf::from(&project.root_path);
+    if !root.is_dir() {
+        return Err(AppError::NotFound(format!(
+            "Project folder does not exist: {}",
+            project.root_path
+        )));
+    }
+
+    let prefs_db = Arc::clone(&db);
+    let scan_root = root.clone();
+    let scan = tokio::task::spawn_blocking(move || {
+        let ignored = preferences::file_tree_ignore(&prefs_db, &project.root_path);
+        files::scan_todos(
+            &scan_root,
+            &ignored,
+            max_file_size.unwrap_or(DEFAULT_MAX_FILE_SIZE),
+            max_files.unwrap_or(DEFAULT_MAX_FILES),
+        )
+    })
+    .await?;
+
+    let created_card_ids = match column_id {
+        Some(column_id) => {
+            let todos: Vec<TodoCard> = scan.hits.iter().map(|hit| todo_card(&root, hit)).collect();
+            db.blocking(move |db| db.create_todo_cards(&project_id, &column_id, &todos))
+                .await?
+        }
+        None => Vec::new(),
+    };
+
+    Ok(TodoScanResult {
+        todos: scan.hits,
+        files_scanned: scan.files_scanned,
+        truncated: scan.truncated,
+        created_card_ids,
+    })
+}
+
+fn todo_card(root: &Path, hit: &TodoHit) -> TodoCard {
+    let location = format!("{}:{}", hit.path, hit.line);
+
+    let title = if hit.text.is_empty() {
+        format!("{} in {}", hit.tag, location)
+    } else {
+        format!("{}: {}", hit.tag, hit.text)
+    };
+    let title = match title.char_indices().nth(MAX_TODO_TITLE) {
+        Some((end, _)) => format!("{}...", title[..end].trim_end()),
+        None => title,
+    };
+
+    let description = if hit.text.is_empty() {
+        format!("Found at `{}`", location)
+    } else {
+        format!("{}\n\nFound at `{}`", hit.text, location)
+    };
+
+    TodoCard {
+        path: hit.path.clone(),
+        line: hit.line,
+        title,
+        description,
+        file_path: root.join(&hit.path).to_string_lossy().into_owned(),
+    }
+}
@@ -1,29 +1,410 @@
-use tauri::{AppHandle, Manager};
+use crate::database::{Database, WindowState};
+use crate::error::AppError;
+use crate::preferences;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::{
+    AppHandle, Emitter, Manager, PhysicalPosition, PhysicalSize, State, Theme, WebviewUrl,
+    WebviewWindow, WebviewWindowBuilder,
+};
 
+const MAIN_WINDOW: &str = "main";
+
+/// Board windows are labelled with this prefix and the board id, so each
+/// board has at most one window and it can be found again by label
+const BOARD_WINDOW_PREFIX: &str = "board-";
+
+/// The window a command should act on: `label` when given, else the main one
+pub fn window_label(label: Option<&str>) -> &str {
+    label.filter(|l| !l.is_empty()).unwrap_or(MAIN_WINDOW)
+}
+
+/// Label of the window showing `board_id`, or `None` when the id has
+/// characters a window label can't hold
+pub fn board_window_label(board_id: &str) -> Option<String> {
+    let valid = !board_id.is_empty()
+        && board_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'));
+    valid.then(|| format!("{}{}", BOARD_WINDOW_PREFIX, board_id))
+}
+
+/// A monitor's area in physical pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MonitorArea {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Fit a saved geometry onto the current monitors. The window goes to the
+/// monitor it overlaps most, or the first one when it overlaps none (after
+/// a monitor was unplugged, say), shrunk to fit and moved fully inside it.
+pub fn clamp_to_monitors(state: WindowState, monitors: &[MonitorArea]) -> WindowState {
+    let overlap = |m: &MonitorArea| {
+        let left = i64::from(state.x).max(i64::from(m.x));
+        let right =
+            (i64::from(state.x) + i64::from(state.width)).min(i64::from(m.x) + i64::from(m.width));
+        let top = i64::from(state.y).max(i64::from(m.y));
+        let bottom = (i64::from(state.y) + i64::from(state.height))
+            .min(i64::from(m.y) + i64::from(m.height));
+        (right - left).max(0) * (bottom - top).max(0)
+    };
+
+    let best = monitors
+        .iter()
+        .max_by_key(|m| overlap(m))
+        .filter(|m| overlap(m) > 0);
+    let Some(monitor) = best.or(monitors.first()) else {
+        return state;
+    };
+
+    let width = state.width.min(monitor.width);
+    let height = state.height.min(monitor.height);
+    let x = i64::from(state.x).clamp(
+        i64::from(monitor.x),
+        i64::from(monitor.x) + i64::from(monitor.width - width),
+    );
+    let y = i64::from(state.y).clamp(
+        i64::from(monitor.y),
+        i64::from(monitor.y) + i64::from(monitor.height - height),
+    );
+
+    WindowState {
+        x: x as i32,
+        y: y as i32,
+        width,
+        height,
+        maximized: state.maximized,
+    }
+}
+
+fn capture_window_state(window: &WebviewWindow) -> tauri::Result<WindowState> {
+    let position = window.outer_position()?;
+    let size = window.inner_size()?;
+    Ok(WindowState {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+        maximized: window.is_maximized()?,
+    })
+}
+
+/// Save `window`'s geometry under its label. Minimized windows are skipped
+/// since some platforms report them far off screen.
+pub fn persist_window_state(window: &WebviewWindow, db: &Database) -> Result<(), AppError> {
+    if window.is_minimized()? {
+        return Ok(());
+    }
+    let state = capture_window_state(window)?;
+    db.save_window_state(window.label(), &state)?;
+    Ok(())
+}
+
+/// Put `window` back where it was last saved, moved onto a connected monitor
+/// if needed. Windows never saved keep their configured defaults.
+pub fn restore_window_state(window: &WebviewWindow, db: &Database) -> Result<(), AppError> {
+    let Some(saved) = db.get_window_state(window.label())? else {
+        return Ok(());
+    };
+
+    let monitors: Vec<MonitorArea> = window
+        .available_monitors()?
+        .iter()
+        .map(|monitor| MonitorArea {
+            x: monitor.position().x,
+            y: monitor.position().y,
+            width: monitor.size().width,
+            height: monitor.size().height,
+        })
+        .collect();
+    let state = clamp_to_monitors(saved, &monitors);
+
+    window.set_size(PhysicalSize::new(state.width, state.height))?;
+    window.set_position(PhysicalPosition::new(state.x, state.y))?;
+    if state.maximized {
+        window.maximize()?;
+    }
+    Ok(())
+}
+
+/// Save a window's geometry now instead of waiting for it to close.
+/// Returns what was saved, or `None` when there is no such window.
+#[tauri::command]
+pub async fn save_window_state(
+    label: Option<String>,
+    app: AppHandle,
+    db: State<'_, Arc<Database>>,
+) -> Result<Option<WindowState>, AppError> {
+    let label = window_label(label.as_deref()).to_string();
+    let Some(window) = app.get_webview_window(&label) else {
+        return Ok(None);
+    };
+    let state = capture_window_state(&window)?;
+
+    db.blocking(move |db| db.save_window_state(&label, &state))
+        .await?;
+    Ok(Some(state))
+}
+
+#[tauri::command]
+pub async fn get_window_state(
+    label: Option<String>,
+    db: State<'_, Arc<Database>>,
+) -> Result<Option<WindowState>, AppError> {
+    let label = window_label(label.as_deref()).to_string();
+    db.blocking(move |db| db.get_window_state(&label))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Open a board in a window of its own, or focus the one already showing it.
+/// The page finds its board in the `board` query parameter. Returns the
+/// window's label for the other window commands.
 #[tauri::command]
-pub async fn minimize_window(app: AppHandle) -> Result<(), String> {
-    if let Some(window) = app.get_webview_window("main") {
-        window.minimize().map_err(|e| e.to_string())?;
+pub async fn open_board_window(
+    board_id: String,
+    app: AppHandle,
+    db: State<'_, Arc<Database>>,
+) -> Result<String, AppError> {
+    let label = board_window_label(&board_id)
+        .ok_or_else(|| AppError::validation("board_id", "Invalid board id"))?;
+
+    if let Some(window) = app.get_webview_window(&label) {
+        window.unminimize()?;
+        window.set_focus()?;
+        return Ok(label);
+    }
+
+    let db = Arc::clone(&db);
+    let lookup_id = board_id.clone();
+    let board = db
+        .blocking(move |db| db.get_board(&lookup_id))
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Board not found: {}", board_id)))?;
+
+    let url = WebviewUrl::App(format!("index.html?board={}", board_id).into());
+    let window = WebviewWindowBuilder::new(&app, &label, url)
+        .title(format!("{} - VIRAITH IDE", board.name))
+        .inner_size(1200.0, 800.0)
+        .min_inner_size(800.0, 600.0)
+        .decorations(false)
+        .build()?;
+    let _ = restore_window_state(&window, &db);
+
+    Ok(label)
+}
+
+#[tauri::command]
+pub async fn minimize_window(label: Option<String>, app: AppHandle) -> Result<(), AppError> {
+    if let Some(window) = app.get_webview_window(window_label(label.as_deref())) {
+        window.minimize()?;
     }
     Ok(())
 }
 
 #[tauri::command]
-pub async fn maximize_window(app: AppHandle) -> Result<(), String> {
-    if let Some(window) = app.get_webview_window("main") {
+pub async fn maximize_window(label: Option<String>, app: AppHandle) -> Result<(), AppError> {
+    if let Some(window) = app.get_webview_window(window_label(label.as_deref())) {
         if window.is_maximized().unwrap_or(false) {
-            window.unmaximize().map_err(|e| e.to_string())?;
+            window.unmaximize()?;
         } else {
-            window.maximize().map_err(|e| e.to_string())?;
+            window.maximize()?;
         }
     }
     Ok(())
 }
 
+/// Flip a window flag given its current value and a setter, returning the
+/// value it now has
+pub fn toggle_flag<E>(
+    current: Result<bool, E>,
+    set: impl FnOnce(bool) -> Result<(), E>,
+) -> Result<bool, E> {
+    let next = !current?;
+    set(next)?;
+    Ok(next)
+}
+
+fn find_window(app: &AppHandle, label: Option<&str>) -> Result<WebviewWindow, AppError> {
+    let label = window_label(label);
+    app.get_webview_window(label)
+        .ok_or_else(|| AppError::NotFound(format!("Window not found: {}", label)))
+}
+
+/// Enter or leave fullscreen. Returns whether the window is now fullscreen.
+#[tauri::command]
+pub async fn toggle_fullscreen(label: Option<String>, app: AppHandle) -> Result<bool, AppError> {
+    let window = find_window(&app, label.as_deref())?;
+    toggle_flag(window.is_fullscreen(), |fullscreen| {
+        window.set_fullscreen(fullscreen)
+    })
+    .map_err(AppError::from)
+}
+
+/// Keep a window above all others, or stop doing so. Returns the new setting.
 #[tauri::command]
-pub async fn close_window(app: AppHandle) -> Result<(), String> {
-    if let Some(window) = app.get_webview_window("main") {
-        window.close().map_err(|e| e.to_string())?;
+pub async fn set_always_on_top(
+    label: Option<String>,
+    enabled: bool,
+    app: AppHandle,
+) -> Result<bool, AppError> {
+    let window = find_window(&app, label.as_deref())?;
+    window.set_always_on_top(enabled)?;
+    Ok(enabled)
+}
+
+#[tauri::command]
+pub async fn close_window(label: Option<String>, app: AppHandle) -> Result<(), AppError> {
+    if let Some(window) = app.get_webview_window(window_label(label.as_deref())) {
+        window.close()?;
     }
     Ok(())
 }
+
+/// The theme the user picked and the light or dark one it comes out as
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThemeState {
+    pub preference: String,
+    pub resolved: String,
+}
+
+/// What a window needs before its first paint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StartupState {
+    pub theme: ThemeState,
+}
+
+/// `preference` as "light" or "dark". "system" follows `system_theme`, and
+/// light when the OS doesn't say.
+pub fn resolve_theme(preference: &str, system_theme: Option<Theme>) -> ThemeState {
+    let resolved = match preference {
+        "light" | "dark" => preference,
+        _ => match system_theme {
+            Some(Theme::Dark) => "dark",
+            _ => "light",
+        },
+    };
+    ThemeState {
+        preference: preference.to_string(),
+        resolved: resolved.to_string(),
+    }
+}
+
+/// The OS theme as seen by `label`'s window, or by any open window
+fn system_theme(app: &AppHandle, label: Option<&str>) -> Option<Theme> {
+    app.get_webview_window(window_label(label))
+        .or_else(|| app.webview_windows().into_values().next())
+        .and_then(|window| window.theme().ok())
+}
+
+/// Tell `window` about an OS theme change when the user follows the system
+/// theme. Called from the window event handler.
+pub fn notify_system_theme_changed(window: &WebviewWindow, db: &Database, theme: Theme) {
+    let preference = preferences::theme(db);
+    if preference == "system" {
+        let _ = window.emit_to(
+            window.label(),
+            "theme-changed",
+            resolve_theme(&preference, Some(theme)),
+        );
+    }
+}
+
+#[tauri::command]
+pub async fn get_theme(
+    label: Option<String>,
+    app: AppHandle,
+    db: State<'_, Arc<Database>>,
+) -> Result<ThemeState, AppError> {
+    let preference = db
+        .blocking(|db| Ok::<_, AppError>(preferences::theme(db)))
+        .await?;
+    Ok(resolve_theme(
+        &preference,
+        system_theme(&app, label.as_deref()),
+    ))
+}
+
+/// Store the theme preference and send the result to every window as a
+/// `theme-changed` event
+#[tauri::command]
+pub async fn set_theme(
+    theme: String,
+    app: AppHandle,
+    db: State<'_, Arc<Database>>,
+) -> Result<ThemeState, AppError> {
+    let value = serde_json::Value::String(theme.clone());
+    preferences::validate(preferences::THEME, &value)?;
+    db.blocking(move |db| db.set_preference(preferences::THEME, &value))
+        .await?;
+
+    let state = resolve_theme(&theme, system_theme(&app, None));
+    let _ = app.emit("theme-changed", &state);
+    Ok(state)
+}
+
+/// Everything the page needs to render its first frame the right way, so it
+/// can be fetched once before anything is drawn
+#[tauri::command]
+pub async fn get_startup_state(
+    label: Option<String>,
+    app: AppHandle,
+    db: State<'_, Arc<Database>>,
+) -> Result<StartupState, AppError> {
+    Ok(StartupState {
+        theme: get_theme(label, app, db).await?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_route_to_the_labelled_window_or_main() {
+        assert_eq!(window_label(None), "main");
+        assert_eq!(window_label(Some("")), "main");
+        assert_eq!(window_label(Some("board-abc")), "board-abc");
+    }
+
+    #[test]
+    fn board_windows_are_labelled_by_board_id() {
+        assert_eq!(
+            board_window_label("1b2c-3d_4").as_deref(),
+            Some("board-1b2c-3d_4")
+        );
+        assert_eq!(board_window_label(""), None);
+        assert_eq!(board_window_label("a/b"), None);
+        assert_eq!(board_window_label("a b"), None);
+    }
+
+    #[test]
+    fn toggling_flips_the_flag_and_reports_the_new_value() {
+        for current in [false, true] {
+            let mut applied = None;
+            let now = toggle_flag::<()>(Ok(current), |next| {
+                applied = Some(next);
+                Ok(())
+            });
+            assert_eq!(now, Ok(!current));
+            assert_eq!(applied, Some(!current));
+        }
+    }
+
+    #[test]
+    fn a_failed_read_or_write_is_passed_through() {
+        let mut called = false;
+        let unread = toggle_flag(Err("no window"), |_| {
+            called = true;
+            Ok(())
+        });
+        assert_eq!(unread, Err("no window"));
+        assert!(!called);
+
+        let unwritten = toggle_flag(Ok(false), |_| Err("refused"));
+        assert_eq!(unwritten, Err("refused"));
+    }
+}
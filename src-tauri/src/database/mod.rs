@@ -1,45 +1,255 @@
 mod schema;
 
-use parking_lot::Mutex;
-use rusqlite::{Connection, Result as SqliteResult};
-use std::path::Path;
+use parking_lot::{Mutex, MutexGuard};
+use rusqlite::backup::Backup;
+use rusqlite::types::{Type, Value};
+use rusqlite::{
+    Connection, OptionalExtension, Result as SqliteResult, Transaction, TransactionBehavior,
+};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 
 pub use schema::*;
 
+/// Number of read-only connections. WAL mode lets these read concurrently
+/// with each other and with the single writer.
+const READERS: usize = 4;
+
+/// How long a connection waits on a locked database before giving up
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Pages copied per step of a backup or restore
+const BACKUP_PAGES_PER_STEP: i32 = 1024;
+
+/// Card columns in the order `card_from_row` expects, for queries that select
+/// more after them
+macro_rules! card_fields {
+    () => {
+        "c.id, c.column_id, c.title, c.description, c.folder_path, c.file_paths, c.agent_config, c.position, c.status, c.created_at, c.updated_at, c.metadata, c.due_at, c.priority, c.archived_at, c.version,
+    (SELECT COUNT(*) FROM card_checklist_items i WHERE i.card_id = c.id AND i.done = 1),
+    (SELECT COUNT(*) FROM card_checklist_items i WHERE i.card_id = c.id)"
+    };
+}
+
 pub struct Database {
-    conn: Mutex<Connection>,
+    /// The only connection that writes, so writers queue on this lock rather
+    /// than failing with `SQLITE_BUSY` when their transactions overlap
+    writer: Mutex<Connection>,
+    readers: Vec<Mutex<Connection>>,
+    next: AtomicUsize,
+    /// Set when re-encrypting swapped the file but couldn't reopen it; every
+    /// call fails from then on instead of running on a stand-in connection
+    closed: AtomicBool,
+    startup_health: StartupHealth,
+    /// Only read when re-encrypting
+    #[cfg_attr(not(feature = "encrypted-db"), allow(dead_code))]
+    path: PathBuf,
+    /// SQLCipher key the connections were opened with
+    key: Mutex<Option<String>>,
 }
 
 impl Database {
-    pub fn new(path: &Path) -> SqliteResult<Self> {
-        let conn = Connection::open(path)?;
-
-        // Enable foreign keys
-        conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+    /// Open the database at `path`, decrypting it with `key` when the
+    /// `encrypted-db` feature is on. Without the feature the key is ignored.
+    pub fn new(path: &Path, key: Option<&str>) -> SqliteResult<Self> {
+        let mut conn = Self::open_connection(path, key)?;
 
         // Initialize schema
         conn.execute_batch(SCHEMA)?;
+        Self::run_migrations(&mut conn)?;
+        Self::seed_builtin_templates(&conn)?;
+
+        // A damaged file still opens; report it instead of refusing to start
+        let startup_health = match integrity_problems(&conn) {
+            Ok(problems) => StartupHealth {
+                ok: problems.is_empty(),
+                problems,
+            },
+            Err(e) => StartupHealth {
+                ok: false,
+                problems: vec![format!("Integrity check failed to run: {}", e)],
+            },
+        };
+        if !startup_health.ok {
+            eprintln!(
+                "Database integrity check failed: {}",
+                startup_health.problems.join("; ")
+            );
+        }
+
+        let readers = (0..READERS)
+            .map(|_| Self::open_reader(path, key).map(Mutex::new))
+            .collect::<SqliteResult<Vec<_>>>()?;
 
         Ok(Self {
-            conn: Mutex::new(conn),
+            writer: Mutex::new(conn),
+            readers,
+            next: AtomicUsize::new(0),
+            closed: AtomicBool::new(false),
+            startup_health,
+            path: path.to_path_buf(),
+            key: Mutex::new(key.map(str::to_string)),
         })
     }
 
+    fn open_connection(path: &Path, key: Option<&str>) -> SqliteResult<Connection> {
+        let conn = Self::open_file(path, key)?;
+
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+
+        // WAL for concurrent readers; foreign keys are per-connection
+        conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA foreign_keys = ON;")?;
+
+        Ok(conn)
+    }
+
+    /// A connection that refuses to write, so a write routed through
+    /// `with_conn` fails loudly instead of racing the writer
+    fn open_reader(path: &Path, key: Option<&str>) -> SqliteResult<Connection> {
+        let conn = Self::open_connection(path, key)?;
+        conn.pragma_update(None, "query_only", true)?;
+        Ok(conn)
+    }
+
+    /// Open `path` with `key` and make sure the key fits, without changing
+    /// any settings of the file
+    fn open_file(path: &Path, key: Option<&str>) -> SqliteResult<Connection> {
+        let conn = Connection::open(path)?;
+
+        // The key has to be in place before anything reads the file
+        #[cfg(feature = "encrypted-db")]
+        if let Some(key) = key {
+            conn.pragma_update(None, "key", key)?;
+        }
+        #[cfg(not(feature = "encrypted-db"))]
+        let _ = key;
+
+        // A wrong key only shows up on the first read, as "file is not a
+        // database"; say what actually went wrong
+        if let Err(e) = conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |_| Ok(())) {
+            if e.sqlite_error_code() != Some(rusqlite::ErrorCode::NotADatabase) {
+                return Err(e);
+            }
+            let message = if cfg!(feature = "encrypted-db") && key.is_some() {
+                "Invalid database password"
+            } else {
+                "The database is encrypted or not a database file"
+            };
+            return Err(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_NOTADB),
+                Some(message.to_string()),
+            ));
+        }
+
+        Ok(conn)
+    }
+
+    fn run_migrations(conn: &mut Connection) -> SqliteResult<()> {
+        let current: i64 =
+            conn.query_row("SELECT MAX(version) FROM schema_version", [], |row| {
+                row.get(0)
+            })?;
+
+        for (version, sql) in MIGRATIONS.iter().filter(|(v, _)| *v > current) {
+            let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+            tx.execute_batch(sql)?;
+            tx.execute(
+                "INSERT INTO schema_version (version, applied_at) VALUES (?1, strftime('%s', 'now'))",
+                [version],
+            )?;
+            tx.commit()?;
+        }
+
+        Ok(())
+    }
+
+    /// Take the first idle reader, or wait on one in round-robin order
+    fn acquire(&self) -> MutexGuard<'_, Connection> {
+        for conn in &self.readers {
+            if let Some(guard) = conn.try_lock() {
+                return guard;
+            }
+        }
+
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.readers.len();
+        self.readers[index].lock()
+    }
+
+    /// Checked after taking a connection, so a call that waited out a failed
+    /// `reencrypt` sees the result
+    fn ensure_open(&self) -> SqliteResult<()> {
+        if self.closed.load(Ordering::Acquire) {
+            return Err(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CANTOPEN),
+                Some("The database could not be reopened; restart the app".to_string()),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Every connection, writer first, so nothing else can touch the file
+    fn lock_all(&self) -> Vec<MutexGuard<'_, Connection>> {
+        std::iter::once(&self.writer)
+            .chain(&self.readers)
+            .map(|conn| conn.lock())
+            .collect()
+    }
+
+    /// Run `f` on the blocking thread pool. Commands go through this so SQLite
+    /// work, and waiting for a free connection, never stalls the async runtime.
+    pub async fn blocking<F, T, E>(self: &Arc<Self>, f: F) -> Result<T, E>
+    where
+        F: FnOnce(&Arc<Database>) -> Result<T, E> + Send + 'static,
+        T: Send + 'static,
+        E: Send + 'static,
+    {
+        let db = Arc::clone(self);
+        match tokio::task::spawn_blocking(move || f(&db)).await {
+            Ok(result) => result,
+            // Surface a panic in `f` the same way a direct call would
+            Err(e) => std::panic::resume_unwind(e.into_panic()),
+        }
+    }
+
+    /// Run `f` on one of the read-only connections
     pub fn with_conn<F, T>(&self, f: F) -> SqliteResult<T>
     where
         F: FnOnce(&Connection) -> SqliteResult<T>,
     {
-        let conn = self.conn.lock();
+        let conn = self.acquire();
+        self.ensure_open()?;
         f(&conn)
     }
 
+    /// Run `f` on the writer connection
     pub fn with_conn_mut<F, T>(&self, f: F) -> SqliteResult<T>
     where
         F: FnOnce(&mut Connection) -> SqliteResult<T>,
     {
-        let mut conn = self.conn.lock();
+        let mut conn = self.writer.lock();
+        self.ensure_open()?;
         f(&mut conn)
     }
+
+    /// Run `f` inside a transaction. Everything commits together, or nothing
+    /// does if `f` returns an error.
+    pub fn transaction<F, T, E>(&self, f: F) -> Result<T, E>
+    where
+        F: FnOnce(&Transaction) -> Result<T, E>,
+        E: From<rusqlite::Error>,
+    {
+        let mut conn = self.writer.lock();
+        self.ensure_open()?;
+        // Take the write lock up front so read-modify-write closures can't
+        // deadlock against another pooled connection upgrading its lock
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let result = f(&tx)?;
+        tx.commit()?;
+        Ok(result)
+    }
 }
 
 // Project operations
@@ -50,7 +260,7 @@ impl Database {
         name: &str,
         root_path: &str,
     ) -> SqliteResult<()> {
-        self.with_conn(|conn| {
+        self.with_conn_mut(|conn| {
             conn.execute(
                 "INSERT INTO projects (id, name, root_path) VALUES (?1, ?2, ?3)",
                 [id, name, root_path],
@@ -59,10 +269,47 @@ impl Database {
         })
     }
 
+    /// Create a project together with a "Main" board holding the starter
+    /// columns. Either everything is created or nothing is.
+    pub fn create_project_scaffolded(
+        &self,
+        id: &str,
+        name: &str,
+        root_path: &str,
+    ) -> SqliteResult<ScaffoldedBoard> {
+        self.transaction(|tx| {
+            tx.execute(
+                "INSERT INTO projects (id, name, root_path) VALUES (?1, ?2, ?3)",
+                [id, name, root_path],
+            )?;
+
+            let board_id = uuid::Uuid::new_v4().to_string();
+            tx.execute(
+                "INSERT INTO boards (id, project_id, name, position) VALUES (?1, ?2, ?3, 0)",
+                [board_id.as_str(), id, STARTER_BOARD],
+            )?;
+
+            let mut column_ids = Vec::with_capacity(STARTER_COLUMNS.len());
+            for (position, column_name) in STARTER_COLUMNS.iter().enumerate() {
+                let column_id = uuid::Uuid::new_v4().to_string();
+                tx.execute(
+                    "INSERT INTO columns (id, board_id, name, position) VALUES (?1, ?2, ?3, ?4)",
+                    rusqlite::params![column_id, board_id, column_name, position as i32],
+                )?;
+                column_ids.push(column_id);
+            }
+
+            Ok(ScaffoldedBoard {
+                board_id,
+                column_ids,
+            })
+        })
+    }
+
     pub fn get_projects(&self) -> SqliteResult<Vec<Project>> {
         self.with_conn(|conn| {
             let mut stmt = conn.prepare(
-                "SELECT id, name, root_path, created_at, updated_at, settings FROM projects ORDER BY updated_at DESC"
+                "SELECT id, name, root_path, created_at, updated_at, settings, locked FROM projects ORDER BY updated_at DESC"
             )?;
 
             let projects = stmt
@@ -74,6 +321,7 @@ impl Database {
                         created_at: row.get(3)?,
                         updated_at: row.get(4)?,
                         settings: row.get::<_, String>(5)?,
+                        locked: row.get(6)?,
                     })
                 })?
                 .collect::<SqliteResult<Vec<_>>>()?;
@@ -82,258 +330,6532 @@ impl Database {
         })
     }
 
-    pub fn delete_project(&self, id: &str) -> SqliteResult<()> {
+    pub fn get_project(&self, id: &str) -> SqliteResult<Project> {
         self.with_conn(|conn| {
-            conn.execute("DELETE FROM projects WHERE id = ?1", [id])?;
-            Ok(())
+            conn.query_row(
+                "SELECT id, name, root_path, created_at, updated_at, settings, locked FROM projects WHERE id = ?1",
+                [id],
+                |row| {
+                    Ok(Project {
+                        id: row.get(0)?,
+                        name: row.get(1)?,
+                        root_path: row.get(2)?,
+                        created_at: row.get(3)?,
+                        updated_at: row.get(4)?,
+                        settings: row.get::<_, String>(5)?,
+                        locked: row.get(6)?,
+                    })
+                },
+            )
         })
     }
-}
 
-// Board operations
-impl Database {
-    pub fn create_board(
-        &self,
-        id: &str,
-        project_id: &str,
-        name: &str,
-        position: i32,
-    ) -> SqliteResult<()> {
+    pub fn get_project_by_root(&self, root_path: &str) -> SqliteResult<Option<Project>> {
         self.with_conn(|conn| {
-            conn.execute(
-                "INSERT INTO boards (id, project_id, name, position) VALUES (?1, ?2, ?3, ?4)",
-                rusqlite::params![id, project_id, name, position],
+            conn.query_row(
+                "SELECT id, name, root_path, created_at, updated_at, settings, locked FROM projects WHERE root_path = ?1",
+                [root_path],
+                |row| {
+                    Ok(Project {
+                        id: row.get(0)?,
+                        name: row.get(1)?,
+                        root_path: row.get(2)?,
+                        created_at: row.get(3)?,
+                        updated_at: row.get(4)?,
+                        settings: row.get::<_, String>(5)?,
+                        locked: row.get(6)?,
+                    })
+                },
+            )
+            .optional()
+        })
+    }
+
+    /// Record that a project was just opened
+    pub fn touch_project(&self, id: &str) -> SqliteResult<()> {
+        self.with_conn_mut(|conn| {
+            let changed = conn.execute(
+                "UPDATE projects SET last_opened_at = strftime('%s', 'now') WHERE id = ?1",
+                [id],
             )?;
+            if changed == 0 {
+                return Err(rusqlite::Error::QueryReturnedNoRows);
+            }
             Ok(())
         })
     }
 
-    pub fn get_boards(&self, project_id: &str) -> SqliteResult<Vec<Board>> {
+    /// Most recently opened projects first; projects never opened follow, newest edit first
+    pub fn get_recent_projects(&self, limit: u32) -> SqliteResult<Vec<RecentProject>> {
         self.with_conn(|conn| {
             let mut stmt = conn.prepare(
-                "SELECT id, project_id, name, position, created_at FROM boards WHERE project_id = ?1 ORDER BY position"
+                "SELECT id, name, root_path, created_at, updated_at, settings, locked, last_opened_at FROM projects
+                 ORDER BY last_opened_at IS NULL, last_opened_at DESC, updated_at DESC
+                 LIMIT ?1",
             )?;
 
-            let boards = stmt
-                .query_map([project_id], |row| {
-                    Ok(Board {
+            let projects = stmt
+                .query_map([limit], |row| {
+                    let project = Project {
                         id: row.get(0)?,
-                        project_id: row.get(1)?,
-                        name: row.get(2)?,
-                        position: row.get(3)?,
-                        created_at: row.get(4)?,
+                        name: row.get(1)?,
+                        root_path: row.get(2)?,
+                        created_at: row.get(3)?,
+                        updated_at: row.get(4)?,
+                        settings: row.get::<_, String>(5)?,
+                        locked: row.get(6)?,
+                    };
+                    Ok(RecentProject {
+                        path_exists: Path::new(&project.root_path).is_dir(),
+                        project,
+                        last_opened_at: row.get(7)?,
                     })
                 })?
                 .collect::<SqliteResult<Vec<_>>>()?;
 
-            Ok(boards)
+            Ok(projects)
         })
     }
-}
 
-// Column operations
-impl Database {
-    pub fn create_column(
+    pub fn get_project_settings(&self, id: &str) -> SqliteResult<ProjectSettings> {
+        self.with_conn(|conn| {
+            let raw: Option<String> =
+                conn.query_row("SELECT settings FROM projects WHERE id = ?1", [id], |row| {
+                    row.get(0)
+                })?;
+
+            match raw.as_deref() {
+                None | Some("") => Ok(ProjectSettings::default()),
+                Some(raw) => serde_json::from_str(raw).map_err(|e| {
+                    rusqlite::Error::FromSqlConversionFailure(0, Type::Text, Box::new(e))
+                }),
+            }
+        })
+    }
+
+    pub fn update_project_settings(
         &self,
         id: &str,
-        board_id: &str,
-        name: &str,
-        position: i32,
+        settings: &ProjectSettings,
     ) -> SqliteResult<()> {
-        self.with_conn(|conn| {
+        let raw = serde_json::to_string(settings)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+
+        self.with_conn_mut(|conn| {
             conn.execute(
-                "INSERT INTO columns (id, board_id, name, position) VALUES (?1, ?2, ?3, ?4)",
-                rusqlite::params![id, board_id, name, position],
+                "UPDATE projects SET settings = ?2 WHERE id = ?1",
+                [id, raw.as_str()],
             )?;
             Ok(())
         })
     }
 
-    pub fn get_columns(&self, board_id: &str) -> SqliteResult<Vec<Column>> {
+    /// Lock or unlock a project. While locked, nothing on its boards can be
+    /// changed; a change fails with an error `is_project_locked` recognizes.
+    pub fn set_project_locked(&self, id: &str, locked: bool) -> SqliteResult<()> {
+        self.with_conn_mut(|conn| {
+            let changed = conn.execute(
+                "UPDATE projects SET locked = ?2 WHERE id = ?1",
+                rusqlite::params![id, locked],
+            )?;
+            if changed == 0 {
+                return Err(rusqlite::Error::QueryReturnedNoRows);
+            }
+            Ok(())
+        })
+    }
+
+    /// Ids of every card on any of the project's boards, archived ones included
+    pub fn get_project_card_ids(&self, project_id: &str) -> SqliteResult<Vec<String>> {
         self.with_conn(|conn| {
             let mut stmt = conn.prepare(
-                "SELECT id, board_id, name, position, automation_rules, created_at FROM columns WHERE board_id = ?1 ORDER BY position"
+                "SELECT c.id FROM cards c JOIN columns col ON c.column_id = col.id JOIN boards b ON col.board_id = b.id WHERE b.project_id = ?1",
             )?;
 
-            let columns = stmt
-                .query_map([board_id], |row| {
-                    Ok(Column {
-                        id: row.get(0)?,
-                        board_id: row.get(1)?,
-                        name: row.get(2)?,
-                        position: row.get(3)?,
-                        automation_rules: row.get::<_, String>(4)?,
-                        created_at: row.get(5)?,
-                    })
-                })?
+            let ids = stmt
+                .query_map([project_id], |row| row.get(0))?
                 .collect::<SqliteResult<Vec<_>>>()?;
 
-            Ok(columns)
+            Ok(ids)
         })
     }
 
-    pub fn update_column(&self, id: &str, name: &str, position: i32) -> SqliteResult<()> {
-        self.with_conn(|conn| {
-            conn.execute(
-                "UPDATE columns SET name = ?2, position = ?3 WHERE id = ?1",
-                rusqlite::params![id, name, position],
+    pub fn delete_project(&self, id: &str) -> SqliteResult<()> {
+        self.transaction(|tx| {
+            tx.execute(
+                "DELETE FROM window_state WHERE label IN
+                 (SELECT 'board-' || id FROM boards WHERE project_id = ?1)",
+                [id],
             )?;
+            tx.execute("DELETE FROM projects WHERE id = ?1", [id])?;
             Ok(())
         })
     }
+}
 
-    pub fn delete_column(&self, id: &str) -> SqliteResult<()> {
-        self.with_conn(|conn| {
-            conn.execute("DELETE FROM columns WHERE id = ?1", [id])?;
+/// What the lock triggers abort with (see migrations 14 and 15)
+const PROJECT_LOCKED: &str = "project is locked";
+
+/// Whether `e` is a change refused because the project is locked
+pub fn is_project_locked(e: &rusqlite::Error) -> bool {
+    matches!(e, rusqlite::Error::SqliteFailure(_, Some(message)) if message == PROJECT_LOCKED)
+}
+
+// Recent item operations. Recent projects are the projects'
+// `last_opened_at`; only files live in `recent_items`.
+impl Database {
+    /// Move `item` to the top of its kind's recent list, dropping the oldest
+    /// files past `MAX_RECENT_ITEMS`
+    pub fn record_recent(&self, kind: RecentKind, item: &str) -> SqliteResult<()> {
+        if kind == RecentKind::Project {
+            return self.touch_project(item);
+        }
+        self.transaction(|tx| {
+            // REPLACE re-inserts the row, so its rowid breaks ties between
+            // items opened within the same second
+            tx.execute(
+                "INSERT OR REPLACE INTO recent_items (kind, item, opened_at) VALUES (?1, ?2, strftime('%s', 'now'))",
+                [kind.as_str(), item],
+            )?;
+            tx.execute(
+                "DELETE FROM recent_items WHERE kind = ?1 AND rowid NOT IN (SELECT rowid FROM recent_items WHERE kind = ?1 ORDER BY opened_at DESC, rowid DESC LIMIT ?2)",
+                rusqlite::params![kind.as_str(), MAX_RECENT_ITEMS],
+            )?;
             Ok(())
         })
     }
+
+    /// Most recently opened first
+    pub fn get_recent(&self, kind: RecentKind, limit: u32) -> SqliteResult<Vec<RecentItem>> {
+        self.with_conn(|conn| {
+            let mut stmt = match kind {
+                RecentKind::Project => conn.prepare(
+                    "SELECT id, last_opened_at FROM projects WHERE last_opened_at IS NOT NULL
+                     ORDER BY last_opened_at DESC LIMIT ?1",
+                )?,
+                RecentKind::File => conn.prepare(
+                    "SELECT item, opened_at FROM recent_items WHERE kind = 'file' ORDER BY opened_at DESC, rowid DESC LIMIT ?1",
+                )?,
+            };
+
+            let items = stmt
+                .query_map([limit], |row| {
+                    Ok(RecentItem {
+                        kind,
+                        item: row.get(0)?,
+                        opened_at: row.get(1)?,
+                    })
+                })?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            Ok(items)
+        })
+    }
 }
 
-// Card operations
+// Draft operations
 impl Database {
-    pub fn create_card(
+    /// Store the unsaved content of a file, replacing its previous draft
+    pub fn save_draft(
         &self,
-        id: &str,
-        column_id: &str,
-        title: &str,
-        description: Option<&str>,
-        position: i32,
-    ) -> SqliteResult<()> {
+        project_id: &str,
+        file_path: &str,
+        content: &str,
+    ) -> SqliteResult<DraftInfo> {
+        self.with_conn_mut(|conn| {
+            conn.query_row(
+                "INSERT INTO drafts (id, project_id, file_path, content) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT(project_id, file_path) DO UPDATE SET content = excluded.content, saved_at = strftime('%s', 'now')
+                 RETURNING id, project_id, file_path, saved_at, length(CAST(content AS BLOB))",
+                rusqlite::params![uuid::Uuid::new_v4().to_string(), project_id, file_path, content],
+                draft_info_from_row,
+            )
+        })
+    }
+
+    pub fn get_draft(&self, project_id: &str, file_path: &str) -> SqliteResult<Option<Draft>> {
         self.with_conn(|conn| {
-            conn.execute(
-                "INSERT INTO cards (id, column_id, title, description, position) VALUES (?1, ?2, ?3, ?4, ?5)",
-                rusqlite::params![id, column_id, title, description, position],
-            )?;
-            Ok(())
+            conn.query_row(
+                "SELECT id, project_id, file_path, content, saved_at FROM drafts WHERE project_id = ?1 AND file_path = ?2",
+                [project_id, file_path],
+                |row| {
+                    Ok(Draft {
+                        id: row.get(0)?,
+                        project_id: row.get(1)?,
+                        file_path: row.get(2)?,
+                        content: row.get(3)?,
+                        saved_at: row.get(4)?,
+                    })
+                },
+            )
+            .optional()
         })
     }
 
-    pub fn get_cards(&self, column_id: &str) -> SqliteResult<Vec<Card>> {
+    /// A project's drafts without their content, most recent first
+    pub fn list_drafts(&self, project_id: &str) -> SqliteResult<Vec<DraftInfo>> {
         self.with_conn(|conn| {
             let mut stmt = conn.prepare(
-                "SELECT id, column_id, title, description, folder_path, file_paths, agent_config, position, status, created_at, updated_at, metadata FROM cards WHERE column_id = ?1 ORDER BY position"
+                "SELECT id, project_id, file_path, saved_at, length(CAST(content AS BLOB)) FROM drafts WHERE project_id = ?1 ORDER BY saved_at DESC, file_path",
             )?;
 
-            let cards = stmt
-                .query_map([column_id], |row| {
-                    Ok(Card {
-                        id: row.get(0)?,
-                        column_id: row.get(1)?,
-                        title: row.get(2)?,
-                        description: row.get(3)?,
-                        folder_path: row.get(4)?,
-                        file_paths: row.get::<_, String>(5)?,
-                        agent_config: row.get::<_, String>(6)?,
-                        position: row.get(7)?,
-                        status: row.get(8)?,
-                        created_at: row.get(9)?,
-                        updated_at: row.get(10)?,
-                        metadata: row.get::<_, String>(11)?,
-                    })
-                })?
+            let drafts = stmt
+                .query_map([project_id], draft_info_from_row)?
                 .collect::<SqliteResult<Vec<_>>>()?;
 
-            Ok(cards)
+            Ok(drafts)
         })
     }
 
-    pub fn update_card(
+    /// Returns false if there was no draft
+    pub fn discard_draft(&self, project_id: &str, file_path: &str) -> SqliteResult<bool> {
+        self.with_conn_mut(|conn| {
+            let deleted = conn.execute(
+                "DELETE FROM drafts WHERE project_id = ?1 AND file_path = ?2",
+                [project_id, file_path],
+            )?;
+            Ok(deleted > 0)
+        })
+    }
+
+    /// Drop a file's draft once `content` has been written to disk. A draft
+    /// saved at or after `written_at` with other content holds edits made
+    /// since, so it is kept.
+    pub fn clear_saved_draft(
         &self,
-        id: &str,
-        title: &str,
-        description: Option<&str>,
-        status: &str,
+        project_id: &str,
+        file_path: &str,
+        content: &str,
+        written_at: i64,
     ) -> SqliteResult<()> {
-        self.with_conn(|conn| {
+        self.with_conn_mut(|conn| {
             conn.execute(
-                "UPDATE cards SET title = ?2, description = ?3, status = ?4, updated_at = strftime('%s', 'now') WHERE id = ?1",
-                rusqlite::params![id, title, description, status],
+                "DELETE FROM drafts WHERE project_id = ?1 AND file_path = ?2 AND (content = ?3 OR saved_at < ?4)",
+                rusqlite::params![project_id, file_path, content, written_at],
             )?;
             Ok(())
         })
     }
+}
 
-    pub fn move_card(&self, id: &str, column_id: &str, position: i32) -> SqliteResult<()> {
-        self.with_conn(|conn| {
+// Window state operations
+impl Database {
+    /// Remember a window's geometry. While it is maximized only the flag is
+    /// updated, so the size it restores to on unmaximize isn't lost.
+    pub fn save_window_state(&self, label: &str, state: &WindowState) -> SqliteResult<()> {
+        self.with_conn_mut(|conn| {
             conn.execute(
-                "UPDATE cards SET column_id = ?2, position = ?3, updated_at = strftime('%s', 'now') WHERE id = ?1",
-                rusqlite::params![id, column_id, position],
+                "INSERT INTO window_state (label, x, y, width, height, maximized) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                 ON CONFLICT(label) DO UPDATE SET
+                   x = CASE WHEN excluded.maximized THEN x ELSE excluded.x END,
+                   y = CASE WHEN excluded.maximized THEN y ELSE excluded.y END,
+                   width = CASE WHEN excluded.maximized THEN width ELSE excluded.width END,
+                   height = CASE WHEN excluded.maximized THEN height ELSE excluded.height END,
+                   maximized = excluded.maximized,
+                   updated_at = strftime('%s', 'now')",
+                rusqlite::params![
+                    label,
+                    state.x,
+                    state.y,
+                    state.width,
+                    state.height,
+                    state.maximized
+                ],
             )?;
             Ok(())
         })
     }
 
-    pub fn attach_folder(&self, id: &str, folder_path: &str) -> SqliteResult<()> {
-        self.with_conn(|conn| {
+    /// Forget the geometry of board windows whose board is gone. Returns how
+    /// many were dropped.
+    pub fn prune_window_states(&self) -> SqliteResult<usize> {
+        self.with_conn_mut(|conn| {
             conn.execute(
-                "UPDATE cards SET folder_path = ?2, updated_at = strftime('%s', 'now') WHERE id = ?1",
-                [id, folder_path],
-            )?;
-            Ok(())
+                "DELETE FROM window_state
+                 WHERE substr(label, 1, 6) = 'board-'
+                   AND substr(label, 7) NOT IN (SELECT id FROM boards)",
+                [],
+            )
+        })
+    }
+
+    pub fn get_window_state(&self, label: &str) -> SqliteResult<Option<WindowState>> {
+        self.with_conn(|conn| {
+            conn.query_row(
+                "SELECT x, y, width, height, maximized FROM window_state WHERE label = ?1",
+                [label],
+                |row| {
+                    Ok(WindowState {
+                        x: row.get(0)?,
+                        y: row.get(1)?,
+                        width: row.get(2)?,
+                        height: row.get(3)?,
+                        maximized: row.get(4)?,
+                    })
+                },
+            )
+            .optional()
         })
     }
+}
 
-    pub fn update_card_status(&self, id: &str, status: &str) -> SqliteResult<()> {
+// Preference operations
+impl Database {
+    pub fn get_preference(&self, key: &str) -> SqliteResult<Option<serde_json::Value>> {
         self.with_conn(|conn| {
+            let raw: Option<String> = conn
+                .query_row(
+                    "SELECT value FROM preferences WHERE key = ?1",
+                    [key],
+                    |row| row.get(0),
+                )
+                .optional()?;
+
+            raw.map(|raw| {
+                serde_json::from_str(&raw).map_err(|e| {
+                    rusqlite::Error::FromSqlConversionFailure(0, Type::Text, Box::new(e))
+                })
+            })
+            .transpose()
+        })
+    }
+
+    pub fn set_preference(&self, key: &str, value: &serde_json::Value) -> SqliteResult<()> {
+        let raw = serde_json::to_string(value)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+
+        self.with_conn_mut(|conn| {
             conn.execute(
-                "UPDATE cards SET status = ?2, updated_at = strftime('%s', 'now') WHERE id = ?1",
-                [id, status],
+                "INSERT INTO preferences (key, value) VALUES (?1, ?2)
+                 ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = strftime('%s', 'now')",
+                [key, raw.as_str()],
             )?;
             Ok(())
         })
     }
 
-    pub fn delete_card(&self, id: &str) -> SqliteResult<()> {
+    pub fn get_all_preferences(&self) -> SqliteResult<serde_json::Map<String, serde_json::Value>> {
         self.with_conn(|conn| {
-            conn.execute("DELETE FROM cards WHERE id = ?1", [id])?;
+            let mut stmt = conn.prepare("SELECT key, value FROM preferences ORDER BY key")?;
+            let rows = stmt
+                .query_map([], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+                })?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            rows.into_iter()
+                .map(|(key, raw)| {
+                    let value = serde_json::from_str(&raw).map_err(|e| {
+                        rusqlite::Error::FromSqlConversionFailure(1, Type::Text, Box::new(e))
+                    })?;
+                    Ok((key, value))
+                })
+                .collect()
+        })
+    }
+
+    /// Forget every preference so the defaults apply again
+    pub fn reset_preferences(&self) -> SqliteResult<()> {
+        self.with_conn_mut(|conn| {
+            conn.execute("DELETE FROM preferences", [])?;
             Ok(())
         })
     }
 }
 
-// Data structures
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct Project {
-    pub id: String,
-    pub name: String,
-    pub root_path: String,
-    pub created_at: i64,
-    pub updated_at: i64,
-    pub settings: String,
-}
+// Board operations
+impl Database {
+    pub fn create_board(
+        &self,
+        id: &str,
+        project_id: &str,
+        name: &str,
+        position: i32,
+    ) -> SqliteResult<()> {
+        self.with_conn_mut(|conn| {
+            conn.execute(
+                "INSERT INTO boards (id, project_id, name, position) VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![id, project_id, name, position],
+            )?;
+            Ok(())
+        })
+    }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct Board {
-    pub id: String,
-    pub project_id: String,
+    pub fn get_board(&self, id: &str) -> SqliteResult<Option<Board>> {
+        self.with_conn(|conn| {
+            conn.query_row(
+                "SELECT id, project_id, name, position, created_at FROM boards WHERE id = ?1",
+                [id],
+                |row| {
+                    Ok(Board {
+                        id: row.get(0)?,
+                        project_id: row.get(1)?,
+                        name: row.get(2)?,
+                        position: row.get(3)?,
+                        created_at: row.get(4)?,
+                    })
+                },
+            )
+            .optional()
+        })
+    }
+
+    pub fn get_boards(&self, project_id: &str) -> SqliteResult<Vec<Board>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, project_id, name, position, created_at FROM boards WHERE project_id = ?1 ORDER BY position"
+            )?;
+
+            let boards = stmt
+                .query_map([project_id], |row| {
+                    Ok(Board {
+                        id: row.get(0)?,
+                        project_id: row.get(1)?,
+                        name: row.get(2)?,
+                        position: row.get(3)?,
+                        created_at: row.get(4)?,
+                    })
+                })?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            Ok(boards)
+        })
+    }
+    /// Clone a board and its columns (and optionally live cards with their
+    /// checklists) under a new name, all in one transaction. Column and card order
+    /// is kept; copied cards start idle with no execution history.
+    pub fn duplicate_board(
+        &self,
+        board_id: &str,
+        new_name: &str,
+        include_cards: bool,
+    ) -> SqliteResult<DuplicatedBoard> {
+        let new_board_id = uuid::Uuid::new_v4().to_string();
+
+        self.transaction(|tx| {
+            tx.execute(
+                "INSERT INTO boards (id, project_id, name, position)
+                 SELECT ?2, project_id, ?3, (SELECT COALESCE(MAX(position) + 1, 0) FROM boards b WHERE b.project_id = boards.project_id) FROM boards WHERE id = ?1",
+                [board_id, new_board_id.as_str(), new_name],
+            )?;
+
+            let column_ids = {
+                let mut stmt =
+                    tx.prepare("SELECT id FROM columns WHERE board_id = ?1 ORDER BY position")?;
+                let ids = stmt
+                    .query_map([board_id], |row| row.get::<_, String>(0))?
+                    .collect::<SqliteResult<Vec<_>>>()?;
+                ids
+            };
+
+            let mut card_count = 0;
+            for column_id in &column_ids {
+                let new_column_id = uuid::Uuid::new_v4().to_string();
+                tx.execute(
+                    "INSERT INTO columns (id, board_id, name, position, automation_rules, wip_limit)
+                     SELECT ?2, ?3, name, position, automation_rules, wip_limit FROM columns WHERE id = ?1",
+                    [column_id.as_str(), new_column_id.as_str(), new_board_id.as_str()],
+                )?;
+
+                if !include_cards {
+                    continue;
+                }
+
+                let card_ids = {
+                    let mut stmt = tx.prepare(
+                        "SELECT id FROM cards WHERE column_id = ?1 AND archived_at IS NULL ORDER BY position",
+                    )?;
+                    let ids = stmt
+                        .query_map([column_id], |row| row.get::<_, String>(0))?
+                        .collect::<SqliteResult<Vec<_>>>()?;
+                    ids
+                };
+
+                // Renumbered from 0 so gaps left by archived cards don't carry over
+                for (position, card_id) in card_ids.iter().enumerate() {
+                    let new_card_id = uuid::Uuid::new_v4().to_string();
+                    copy_card_in(tx, card_id, &new_card_id, &new_column_id, position as i32, "")?;
+                }
+                card_count += card_ids.len();
+            }
+
+            let board = tx.query_row(
+                "SELECT id, project_id, name, position, created_at FROM boards WHERE id = ?1",
+                [new_board_id.as_str()],
+                |row| {
+                    Ok(Board {
+                        id: row.get(0)?,
+                        project_id: row.get(1)?,
+                        name: row.get(2)?,
+                        position: row.get(3)?,
+                        created_at: row.get(4)?,
+                    })
+                },
+            )?;
+
+            Ok(DuplicatedBoard {
+                board,
+                column_count: column_ids.len(),
+                card_count,
+            })
+        })
+    }
+}
+
+// Board template operations
+impl Database {
+    /// Insert any built-in template that's missing, so they stay available
+    /// even after the table is emptied
+    fn seed_builtin_templates(conn: &Connection) -> SqliteResult<()> {
+        // (template, [(column, [(seed card title, description)])])
+        type Builtin<'a> = (&'a str, &'a [(&'a str, &'a [(&'a str, &'a str)])]);
+        let builtins: [Builtin; 3] = [
+            (
+                "Simple Kanban",
+                &[("To Do", &[]), ("Doing", &[]), ("Done", &[])],
+            ),
+            (
+                "Feature pipeline",
+                &[
+                    ("Backlog", &[]),
+                    ("In Progress", &[]),
+                    ("Review", &[]),
+                    ("Done", &[]),
+                ],
+            ),
+            (
+                "Bug triage",
+                &[
+                    (
+                        "New",
+                        &[(
+                            "Example bug report",
+                            "Steps to reproduce:\n1. \n\nExpected:\n\nActual:\n",
+                        )],
+                    ),
+                    ("Triaged", &[]),
+                    ("Fixing", &[]),
+                    ("Verifying", &[]),
+                    ("Closed", &[]),
+                ],
+            ),
+        ];
+
+        for (name, columns) in builtins {
+            let exists: bool = conn.query_row(
+                "SELECT EXISTS(SELECT 1 FROM board_templates WHERE builtin = 1 AND name = ?1)",
+                [name],
+                |row| row.get(0),
+            )?;
+            if exists {
+                continue;
+            }
+
+            let snapshot = TemplateSnapshot {
+                columns: columns
+                    .iter()
+                    .enumerate()
+                    .map(|(position, (name, cards))| TemplateColumn {
+                        name: name.to_string(),
+                        position: position as i32,
+                        automation_rules: "[]".to_string(),
+                        cards: cards
+                            .iter()
+                            .enumerate()
+                            .map(|(position, (title, description))| TemplateCard {
+                                title: title.to_string(),
+                                description: Some(description.to_string()),
+                                position: position as i32,
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            };
+            let raw = serde_json::to_string(&snapshot)
+                .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+
+            conn.execute(
+                "INSERT INTO board_templates (id, name, snapshot, builtin) VALUES (?1, ?2, ?3, 1)",
+                rusqlite::params![uuid::Uuid::new_v4().to_string(), name, raw],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub fn save_board_as_template(
+        &self,
+        board_id: &str,
+        name: &str,
+        include_cards: bool,
+    ) -> SqliteResult<BoardTemplate> {
+        let data = self.get_board_data(board_id)?;
+
+        let snapshot = TemplateSnapshot {
+            columns: data
+                .columns
+                .iter()
+                .map(|column| TemplateColumn {
+                    name: column.name.clone(),
+                    position: column.position,
+                    automation_rules: column.automation_rules.clone(),
+                    cards: if include_cards {
+                        data.cards_by_column
+                            .get(&column.id)
+                            .map(|cards| {
+                                cards
+                                    .iter()
+                                    .map(|card| TemplateCard {
+                                        title: card.title.clone(),
+                                        description: card.description.clone(),
+                                        position: card.position,
+                                    })
+                                    .collect()
+                            })
+                            .unwrap_or_default()
+                    } else {
+                        Vec::new()
+                    },
+                })
+                .collect(),
+        };
+        let raw = serde_json::to_string(&snapshot)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        let id = uuid::Uuid::new_v4().to_string();
+
+        self.with_conn_mut(|conn| {
+            conn.query_row(
+                "INSERT INTO board_templates (id, name, snapshot) VALUES (?1, ?2, ?3) RETURNING id, name, snapshot, builtin, created_at",
+                [id.as_str(), name, raw.as_str()],
+                template_from_row,
+            )
+        })
+    }
+
+    pub fn list_templates(&self) -> SqliteResult<Vec<BoardTemplate>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, name, snapshot, builtin, created_at FROM board_templates ORDER BY builtin DESC, name"
+            )?;
+
+            let templates = stmt
+                .query_map([], template_from_row)?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            Ok(templates)
+        })
+    }
+
+    /// Create a new board at the end of the project from a template, with fresh ids
+    pub fn create_board_from_template(
+        &self,
+        project_id: &str,
+        template_id: &str,
+        board_name: &str,
+    ) -> SqliteResult<Board> {
+        let board_id = uuid::Uuid::new_v4().to_string();
+
+        self.transaction(|tx| {
+            let template = tx.query_row(
+                "SELECT id, name, snapshot, builtin, created_at FROM board_templates WHERE id = ?1",
+                [template_id],
+                template_from_row,
+            )?;
+
+            tx.execute(
+                "INSERT INTO boards (id, project_id, name, position)
+                 VALUES (?1, ?2, ?3, (SELECT COALESCE(MAX(position) + 1, 0) FROM boards WHERE project_id = ?2))",
+                [board_id.as_str(), project_id, board_name],
+            )?;
+
+            for column in &template.snapshot.columns {
+                let column_id = uuid::Uuid::new_v4().to_string();
+                tx.execute(
+                    "INSERT INTO columns (id, board_id, name, position, automation_rules) VALUES (?1, ?2, ?3, ?4, ?5)",
+                    rusqlite::params![
+                        column_id,
+                        board_id,
+                        column.name,
+                        column.position,
+                        column.automation_rules
+                    ],
+                )?;
+
+                for card in &column.cards {
+                    tx.execute(
+                        "INSERT INTO cards (id, column_id, title, description, position) VALUES (?1, ?2, ?3, ?4, ?5)",
+                        rusqlite::params![
+                            uuid::Uuid::new_v4().to_string(),
+                            column_id,
+                            card.title,
+                            card.description,
+                            card.position
+                        ],
+                    )?;
+                }
+            }
+
+            tx.query_row(
+                "SELECT id, project_id, name, position, created_at FROM boards WHERE id = ?1",
+                [board_id.as_str()],
+                |row| {
+                    Ok(Board {
+                        id: row.get(0)?,
+                        project_id: row.get(1)?,
+                        name: row.get(2)?,
+                        position: row.get(3)?,
+                        created_at: row.get(4)?,
+                    })
+                },
+            )
+        })
+    }
+
+    /// Delete a saved template. Returns false, leaving it in place, for built-ins.
+    pub fn delete_template(&self, id: &str) -> SqliteResult<bool> {
+        self.with_conn_mut(|conn| {
+            let builtin: bool = conn.query_row(
+                "SELECT builtin FROM board_templates WHERE id = ?1",
+                [id],
+                |row| row.get(0),
+            )?;
+            if builtin {
+                return Ok(false);
+            }
+
+            conn.execute("DELETE FROM board_templates WHERE id = ?1", [id])?;
+            Ok(true)
+        })
+    }
+}
+
+fn template_from_row(row: &rusqlite::Row) -> SqliteResult<BoardTemplate> {
+    let raw: String = row.get(2)?;
+    let snapshot = serde_json::from_str(&raw)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(2, Type::Text, Box::new(e)))?;
+
+    Ok(BoardTemplate {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        snapshot,
+        builtin: row.get(3)?,
+        created_at: row.get(4)?,
+    })
+}
+
+// Column operations
+impl Database {
+    pub fn create_column(
+        &self,
+        id: &str,
+        board_id: &str,
+        name: &str,
+        position: i32,
+    ) -> SqliteResult<()> {
+        self.with_conn_mut(|conn| {
+            conn.execute(
+                "INSERT INTO columns (id, board_id, name, position) VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![id, board_id, name, position],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Insert a run of columns in one transaction, positioned 0..n in the
+    /// order given. Nothing is written if any insert fails.
+    pub fn create_columns(&self, board_id: &str, names: &[String]) -> SqliteResult<Vec<Column>> {
+        self.transaction(|tx| {
+            let mut ids = Vec::with_capacity(names.len());
+            for (position, name) in names.iter().enumerate() {
+                let id = uuid::Uuid::new_v4().to_string();
+                tx.execute(
+                    "INSERT INTO columns (id, board_id, name, position) VALUES (?1, ?2, ?3, ?4)",
+                    rusqlite::params![id, board_id, name, position as i32],
+                )?;
+                ids.push(id);
+            }
+
+            let mut stmt = tx.prepare(&format!("{} WHERE col.id = ?1", COLUMN_SELECT))?;
+            ids.iter()
+                .map(|id| stmt.query_row([id], column_from_row))
+                .collect()
+        })
+    }
+
+    pub fn get_columns(&self, board_id: &str) -> SqliteResult<Vec<Column>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "{} WHERE col.board_id = ?1 ORDER BY col.position",
+                COLUMN_SELECT
+            ))?;
+
+            let columns = stmt
+                .query_map([board_id], column_from_row)?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            Ok(columns)
+        })
+    }
+
+    /// Load a board with all of its columns and cards under a single lock,
+    /// so the UI can render it without one round trip per column.
+    pub fn get_board_data(&self, board_id: &str) -> SqliteResult<BoardData> {
+        self.with_conn(|conn| {
+            let board = conn.query_row(
+                "SELECT id, project_id, name, position, created_at FROM boards WHERE id = ?1",
+                [board_id],
+                |row| {
+                    Ok(Board {
+                        id: row.get(0)?,
+                        project_id: row.get(1)?,
+                        name: row.get(2)?,
+                        position: row.get(3)?,
+                        created_at: row.get(4)?,
+                    })
+                },
+            )?;
+
+            let mut stmt = conn.prepare(&format!(
+                "{} WHERE col.board_id = ?1 ORDER BY col.position",
+                COLUMN_SELECT
+            ))?;
+
+            let columns = stmt
+                .query_map([board_id], column_from_row)?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            let mut cards_by_column: HashMap<String, Vec<Card>> = columns
+                .iter()
+                .map(|column| (column.id.clone(), Vec::new()))
+                .collect();
+
+            let mut stmt = conn.prepare(&format!(
+                "{} JOIN columns col ON c.column_id = col.id WHERE col.board_id = ?1 AND c.archived_at IS NULL ORDER BY c.position",
+                CARD_SELECT
+            ))?;
+
+            let cards = stmt
+                .query_map([board_id], card_from_row)?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            for card in cards {
+                cards_by_column
+                    .entry(card.column_id.clone())
+                    .or_default()
+                    .push(card);
+            }
+
+            Ok(BoardData {
+                board,
+                columns,
+                cards_by_column,
+            })
+        })
+    }
+
+    /// Card totals per column, broken down by status. Columns without cards
+    /// are included with a total of zero.
+    pub fn get_card_counts(&self, board_id: &str) -> SqliteResult<Vec<ColumnCardCount>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT col.id, c.status, COUNT(c.id) FROM columns col LEFT JOIN cards c ON c.column_id = col.id AND c.archived_at IS NULL WHERE col.board_id = ?1 GROUP BY col.id, c.status ORDER BY col.position, col.id"
+            )?;
+
+            let rows = stmt
+                .query_map([board_id], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, Option<String>>(1)?,
+                        row.get::<_, i64>(2)?,
+                    ))
+                })?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            let mut counts: Vec<ColumnCardCount> = Vec::new();
+            for (column_id, status, count) in rows {
+                if counts.last().map(|c| &c.column_id) != Some(&column_id) {
+                    counts.push(ColumnCardCount {
+                        column_id,
+                        total: 0,
+                        by_status: HashMap::new(),
+                    });
+                }
+
+                if let (Some(entry), Some(status)) = (counts.last_mut(), status) {
+                    entry.total += count;
+                    entry.by_status.insert(status, count);
+                }
+            }
+
+            Ok(counts)
+        })
+    }
+
+    pub fn get_column_automation(&self, id: &str) -> SqliteResult<String> {
+        self.with_conn(|conn| {
+            conn.query_row(
+                "SELECT automation_rules FROM columns WHERE id = ?1",
+                [id],
+                |row| row.get(0),
+            )
+        })
+    }
+
+    /// Store a column's automation rules. Callers validate `rules` first.
+    pub fn set_column_automation(&self, id: &str, rules: &str) -> SqliteResult<()> {
+        self.with_conn_mut(|conn| {
+            let updated = conn.execute(
+                "UPDATE columns SET automation_rules = ?2 WHERE id = ?1",
+                [id, rules],
+            )?;
+            if updated == 0 {
+                return Err(rusqlite::Error::QueryReturnedNoRows);
+            }
+            Ok(())
+        })
+    }
+
+    /// Cap the live cards a column accepts; `None` removes the limit
+    pub fn set_wip_limit(&self, id: &str, limit: Option<i64>) -> SqliteResult<()> {
+        self.with_conn_mut(|conn| {
+            let updated = conn.execute(
+                "UPDATE columns SET wip_limit = ?2 WHERE id = ?1",
+                rusqlite::params![id, limit],
+            )?;
+            if updated == 0 {
+                return Err(rusqlite::Error::QueryReturnedNoRows);
+            }
+            Ok(())
+        })
+    }
+
+    pub fn update_column(&self, id: &str, name: &str, position: i32) -> SqliteResult<()> {
+        self.with_conn_mut(|conn| {
+            conn.execute(
+                "UPDATE columns SET name = ?2, position = ?3 WHERE id = ?1",
+                rusqlite::params![id, name, position],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Give the board's columns positions 0..n in the order of `ordered_ids`.
+    /// Returns `None`, writing nothing, unless `ordered_ids` names each of the
+    /// board's columns exactly once.
+    pub fn reorder_columns(
+        &self,
+        board_id: &str,
+        ordered_ids: &[String],
+    ) -> SqliteResult<Option<Vec<Column>>> {
+        self.transaction(|tx| {
+            let mut current: Vec<String> = tx
+                .prepare("SELECT id FROM columns WHERE board_id = ?1")?
+                .query_map([board_id], |row| row.get(0))?
+                .collect::<SqliteResult<_>>()?;
+            let mut requested = ordered_ids.to_vec();
+            current.sort();
+            requested.sort();
+            if current != requested {
+                return Ok(None);
+            }
+
+            let mut update = tx.prepare("UPDATE columns SET position = ?2 WHERE id = ?1")?;
+            for (position, id) in ordered_ids.iter().enumerate() {
+                update.execute(rusqlite::params![id, position as i32])?;
+            }
+
+            let mut stmt = tx.prepare(&format!(
+                "{} WHERE col.board_id = ?1 ORDER BY col.position",
+                COLUMN_SELECT
+            ))?;
+            let columns = stmt
+                .query_map([board_id], column_from_row)?
+                .collect::<SqliteResult<Vec<_>>>()?;
+            Ok(Some(columns))
+        })
+    }
+
+    pub fn delete_column(&self, id: &str) -> SqliteResult<()> {
+        self.with_conn_mut(|conn| {
+            conn.execute("DELETE FROM columns WHERE id = ?1", [id])?;
+            Ok(())
+        })
+    }
+
+    /// Move every card from `id` to the end of `target_column_id` on the same
+    /// board, then delete the column. Unlike `delete_column`, no cards are lost.
+    /// Live cards keep their order and count against the target's WIP limit;
+    /// archived cards move along at their old positions.
+    pub fn delete_column_and_reassign(
+        &self,
+        id: &str,
+        target_column_id: &str,
+    ) -> Result<(), CardError> {
+        if id == target_column_id {
+            return Err(CardError::ReassignToSelf(id.to_string()));
+        }
+
+        self.transaction(|tx| {
+            let board_of = |column_id: &str| -> Result<String, CardError> {
+                tx.query_row(
+                    "SELECT board_id FROM columns WHERE id = ?1",
+                    [column_id],
+                    |row| row.get(0),
+                )
+                .optional()?
+                .ok_or_else(|| CardError::ColumnNotFound(column_id.to_string()))
+            };
+            let board_id = board_of(id)?;
+            if board_of(target_column_id)? != board_id {
+                return Err(CardError::ColumnNotOnBoard {
+                    column_id: target_column_id.to_string(),
+                    board_id,
+                });
+            }
+
+            let cards = {
+                let mut stmt = tx.prepare(
+                    "SELECT id, archived_at IS NOT NULL FROM cards WHERE column_id = ?1 ORDER BY position, rowid",
+                )?;
+                let cards = stmt
+                    .query_map([id], |row| Ok((row.get::<_, String>(0)?, row.get(1)?)))?
+                    .collect::<SqliteResult<Vec<(String, bool)>>>()?;
+                cards
+            };
+
+            let mut position: i32 = tx.query_row(
+                "SELECT COUNT(*) FROM cards WHERE column_id = ?1 AND archived_at IS NULL",
+                [target_column_id],
+                |row| row.get(0),
+            )?;
+            for (card_id, archived) in cards {
+                if archived {
+                    tx.execute(
+                        "UPDATE cards SET column_id = ?2, updated_at = strftime('%s', 'now') WHERE id = ?1",
+                        [card_id.as_str(), target_column_id],
+                    )?;
+                } else {
+                    check_wip_limit_in(tx, target_column_id, Some(&card_id))?;
+                    tx.execute(
+                        "UPDATE cards SET column_id = ?2, position = ?3, updated_at = strftime('%s', 'now') WHERE id = ?1",
+                        rusqlite::params![card_id, target_column_id, position],
+                    )?;
+                    position += 1;
+                }
+                log_card_change(tx, &card_id, "column_id", Some(id), Some(target_column_id))?;
+            }
+
+            tx.execute("DELETE FROM columns WHERE id = ?1", [id])?;
+            Ok(())
+        })
+    }
+}
+
+// Card operations
+impl Database {
+    /// Insert a card, refusing a column at its WIP limit unless `override_limit`.
+    /// `priority` defaults to "normal".
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_card(
+        &self,
+        id: &str,
+        column_id: &str,
+        title: &str,
+        description: Option<&str>,
+        position: i32,
+        priority: Option<&str>,
+        override_limit: bool,
+    ) -> Result<(), CardError> {
+        self.transaction(|tx| {
+            if !override_limit {
+                check_wip_limit_in(tx, column_id, None)?;
+            }
+
+            tx.execute(
+                "INSERT INTO cards (id, column_id, title, description, position, priority) VALUES (?1, ?2, ?3, ?4, ?5, COALESCE(?6, 'normal'))",
+                rusqlite::params![id, column_id, title, description, position, priority],
+            )?;
+            Ok(())
+        })
+    }
+
+    pub fn get_cards(&self, column_id: &str, sort_by: CardSort) -> SqliteResult<Vec<Card>> {
+        let order_by = match sort_by {
+            CardSort::Position => "c.position",
+            CardSort::Priority => PRIORITY_ORDER,
+            CardSort::Updated => "c.updated_at DESC, c.position",
+        };
+
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "{} WHERE c.column_id = ?1 AND c.archived_at IS NULL ORDER BY {}",
+                CARD_SELECT, order_by
+            ))?;
+
+            let cards = stmt
+                .query_map([column_id], card_from_row)?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            Ok(cards)
+        })
+    }
+
+    /// Live cards of one priority across every board of the project, in board
+    /// order
+    pub fn get_cards_by_priority(
+        &self,
+        project_id: &str,
+        priority: &str,
+    ) -> SqliteResult<Vec<Card>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "{} JOIN columns col ON c.column_id = col.id JOIN boards b ON col.board_id = b.id WHERE b.project_id = ?1 AND c.priority = ?2 AND c.archived_at IS NULL ORDER BY b.position, col.position, c.position",
+                CARD_SELECT
+            ))?;
+
+            let cards = stmt
+                .query_map([project_id, priority], card_from_row)?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            Ok(cards)
+        })
+    }
+
+    /// Append one card per source comment to `column_id`, which must belong to
+    /// `project_id`. Comments whose `path:line` fingerprint is already on a
+    /// card in the project are skipped, archived cards included, so rescanning
+    /// doesn't bring back dismissed ones. Returns the ids of the new cards.
+    pub fn create_todo_cards(
+        &self,
+        project_id: &str,
+        column_id: &str,
+        todos: &[TodoCard],
+    ) -> Result<Vec<String>, CardError> {
+        self.transaction(|tx| {
+            let in_project: bool = tx.query_row(
+                "SELECT EXISTS (SELECT 1 FROM columns col JOIN boards b ON col.board_id = b.id WHERE col.id = ?1 AND b.project_id = ?2)",
+                [column_id, project_id],
+                |row| row.get(0),
+            )?;
+            if !in_project {
+                return Err(CardError::ColumnNotFound(column_id.to_string()));
+            }
+
+            let mut stmt = tx.prepare(
+                "SELECT json_extract(c.metadata, '$.todo.fingerprint') FROM cards c JOIN columns col ON c.column_id = col.id JOIN boards b ON col.board_id = b.id WHERE b.project_id = ?1 AND json_extract(c.metadata, '$.todo.fingerprint') IS NOT NULL",
+            )?;
+            let mut existing = stmt
+                .query_map([project_id], |row| row.get::<_, String>(0))?
+                .collect::<SqliteResult<HashSet<_>>>()?;
+
+            let mut position: i32 = tx.query_row(
+                "SELECT COUNT(*) FROM cards WHERE column_id = ?1 AND archived_at IS NULL",
+                [column_id],
+                |row| row.get(0),
+            )?;
+
+            let mut created = Vec::new();
+            for todo in todos {
+                let fingerprint = format!("{}:{}", todo.path, todo.line);
+                if !existing.insert(fingerprint.clone()) {
+                    continue;
+                }
+
+                check_wip_limit_in(tx, column_id, None)?;
+
+                let id = uuid::Uuid::new_v4().to_string();
+                let metadata = serde_json::json!({
+                    "todo": { "fingerprint": fingerprint, "path": todo.path, "line": todo.line }
+                });
+                let file_paths = serde_json::json!([todo.file_path]);
+                tx.execute(
+                    "INSERT INTO cards (id, column_id, title, description, file_paths, position, metadata) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    rusqlite::params![
+                        id,
+                        column_id,
+                        todo.title,
+                        todo.description,
+                        file_paths.to_string(),
+                        position,
+                        metadata.to_string()
+                    ],
+                )?;
+                position += 1;
+                created.push(id);
+            }
+
+            Ok(created)
+        })
+    }
+
+    /// Every live card on a board in one query, ordered by column then position
+    pub fn get_cards_for_board(&self, board_id: &str) -> SqliteResult<Vec<BoardCard>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(concat!(
+                "SELECT ",
+                card_fields!(),
+                ", col.name FROM cards c JOIN columns col ON c.column_id = col.id WHERE col.board_id = ?1 AND c.archived_at IS NULL ORDER BY col.position, c.position"
+            ))?;
+
+            let cards = stmt
+                .query_map([board_id], |row| {
+                    Ok(BoardCard {
+                        card: card_from_row(row)?,
+                        column_name: row.get(18)?,
+                    })
+                })?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            Ok(cards)
+        })
+    }
+
+    /// A single card, archived or not, with the same extras as `get_cards`
+    pub fn get_card(&self, id: &str) -> SqliteResult<Option<Card>> {
+        self.with_conn(|conn| {
+            conn.query_row(
+                &format!("{} WHERE c.id = ?1", CARD_SELECT),
+                [id],
+                card_from_row,
+            )
+            .optional()
+        })
+    }
+
+    /// Cards on a board matching every criterion set in `filter`, grouped by column.
+    /// All user input is bound as parameters; `title_contains` is matched literally.
+    pub fn query_cards(
+        &self,
+        board_id: &str,
+        filter: &CardFilter,
+    ) -> SqliteResult<HashMap<String, Vec<Card>>> {
+        let mut sql = format!(
+            "{} JOIN columns col ON c.column_id = col.id WHERE col.board_id = ? AND c.archived_at IS NULL",
+            CARD_SELECT
+        );
+        let mut params: Vec<Value> = vec![Value::from(board_id.to_string())];
+
+        if !filter.statuses.is_empty() {
+            sql.push_str(&format!(
+                " AND c.status IN ({})",
+                vec!["?"; filter.statuses.len()].join(", ")
+            ));
+            params.extend(filter.statuses.iter().cloned().map(Value::from));
+        }
+
+        if !filter.column_ids.is_empty() {
+            sql.push_str(&format!(
+                " AND c.column_id IN ({})",
+                vec!["?"; filter.column_ids.len()].join(", ")
+            ));
+            params.extend(filter.column_ids.iter().cloned().map(Value::from));
+        }
+
+        if let Some(text) = filter.title_contains.as_deref().filter(|t| !t.is_empty()) {
+            sql.push_str(" AND instr(lower(c.title), lower(?)) > 0");
+            params.push(Value::from(text.to_string()));
+        }
+
+        if let Some(updated_after) = filter.updated_after {
+            sql.push_str(" AND c.updated_at > ?");
+            params.push(Value::from(updated_after));
+        }
+
+        sql.push_str(" ORDER BY c.position");
+
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(&sql)?;
+
+            let cards = stmt
+                .query_map(rusqlite::params_from_iter(params), card_from_row)?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            let mut grouped: HashMap<String, Vec<Card>> = HashMap::new();
+            for card in cards {
+                grouped
+                    .entry(card.column_id.clone())
+                    .or_default()
+                    .push(card);
+            }
+
+            Ok(grouped)
+        })
+    }
+
+    /// Update a card's editable fields. `priority` is left unchanged when `None`.
+    /// The status change must be an allowed transition unless `force` is set.
+    /// With `expected_version`, the update is rejected if the card changed since.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_card(
+        &self,
+        id: &str,
+        title: &str,
+        description: Option<&str>,
+        status: &str,
+        priority: Option<&str>,
+        force: bool,
+        expected_version: Option<i64>,
+    ) -> Result<(), CardError> {
+        self.transaction(|tx| {
+            check_card_version_in(tx, id, expected_version)?;
+
+            let (before, after) =
+                update_card_in(tx, id, title, description, status, priority, force)?;
+            if before != after {
+                record_operation_in(tx, id, &CardOperation::Update { before, after })?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Move a card to `position` in `column_id`, closing the gap it leaves in the
+    /// source column and shifting cards down in the destination. When the card
+    /// changed columns `on_enter` runs inside the same transaction, so whatever it
+    /// writes commits together with the move. Returns the column the card came
+    /// from and `on_enter`'s result, or `None` for a move within a column. A
+    /// column at its WIP limit refuses the card unless `override_limit` is set.
+    pub fn move_card_and<T>(
+        &self,
+        id: &str,
+        column_id: &str,
+        position: i32,
+        override_limit: bool,
+        on_enter: impl FnOnce(&Transaction) -> T,
+    ) -> Result<(String, Option<T>), CardError> {
+        self.transaction(|tx| {
+            let column_exists = tx
+                .query_row("SELECT 1 FROM columns WHERE id = ?1", [column_id], |_| {
+                    Ok(())
+                })
+                .optional()?
+                .is_some();
+            if !column_exists {
+                return Err(CardError::ColumnNotFound(column_id.to_string()));
+            }
+
+            if !override_limit {
+                check_wip_limit_in(tx, column_id, Some(id))?;
+            }
+
+            let before = card_place_in(tx, id)?;
+            let source_column = move_card_in(tx, id, column_id, Some(position))?;
+            let after = card_place_in(tx, id)?;
+            if before != after {
+                record_operation_in(tx, id, &CardOperation::Move { before, after })?;
+            }
+
+            let entered = (source_column != column_id).then(|| on_enter(tx));
+            Ok((source_column, entered))
+        })
+    }
+
+    /// Move a card to the end of a column on another board of the same
+    /// project: `column_id` when given, which has to be on that board, else the
+    /// board's first column. Labels go along unless `keep_labels` is false.
+    /// Returns the moved card and the column it came from. Undoing it moves
+    /// the card back but doesn't restore dropped labels.
+    pub fn move_card_to_board(
+        &self,
+        id: &str,
+        board_id: &str,
+        column_id: Option<&str>,
+        keep_labels: bool,
+    ) -> Result<(Card, String), CardError> {
+        self.transaction(|tx| {
+            let column_id = match column_id {
+                Some(column_id) => {
+                    let column_board: Option<String> = tx
+                        .query_row(
+                            "SELECT board_id FROM columns WHERE id = ?1",
+                            [column_id],
+                            |row| row.get(0),
+                        )
+                        .optional()?;
+                    match column_board {
+                        None => return Err(CardError::ColumnNotFound(column_id.to_string())),
+                        Some(column_board) if column_board != board_id => {
+                            return Err(CardError::ColumnNotOnBoard {
+                                column_id: column_id.to_string(),
+                                board_id: board_id.to_string(),
+                            })
+                        }
+                        Some(_) => column_id.to_string(),
+                    }
+                }
+                None => {
+                    let first: Option<String> = tx
+                        .query_row(
+                            "SELECT id FROM columns WHERE board_id = ?1 ORDER BY position LIMIT 1",
+                            [board_id],
+                            |row| row.get(0),
+                        )
+                        .optional()?;
+                    first.ok_or_else(|| CardError::BoardHasNoColumns(board_id.to_string()))?
+                }
+            };
+
+            // Cards never leave their project this way
+            let card_project: Option<String> = tx
+                .query_row(
+                    "SELECT b.project_id FROM cards c JOIN columns col ON c.column_id = col.id JOIN boards b ON col.board_id = b.id WHERE c.id = ?1",
+                    [id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let Some(card_project) = card_project else {
+                return Err(CardError::CardNotFound(id.to_string()));
+            };
+            let board_project: String =
+                tx.query_row("SELECT project_id FROM boards WHERE id = ?1", [board_id], |row| {
+                    row.get(0)
+                })?;
+            if card_project != board_project {
+                return Err(CardError::OtherProject {
+                    card_id: id.to_string(),
+                    board_id: board_id.to_string(),
+                });
+            }
+
+            check_wip_limit_in(tx, &column_id, Some(id))?;
+            let before = card_place_in(tx, id)?;
+            let source_column = move_card_in(tx, id, &column_id, None)?;
+            let after = card_place_in(tx, id)?;
+            record_operation_in(tx, id, &CardOperation::Move { before, after })?;
+
+            if !keep_labels {
+                tx.execute(
+                    "UPDATE cards SET metadata = json_remove(metadata, '$.labels') WHERE id = ?1 AND json_valid(metadata)",
+                    [id],
+                )?;
+            }
+
+            let card = tx.query_row(
+                &format!("{} WHERE c.id = ?1", CARD_SELECT),
+                [id],
+                card_from_row,
+            )?;
+            Ok((card, source_column))
+        })
+    }
+
+    pub fn attach_folder(&self, id: &str, folder_path: &str) -> SqliteResult<()> {
+        self.with_conn_mut(|conn| {
+            conn.execute(
+                "UPDATE cards SET folder_path = ?2, updated_at = strftime('%s', 'now') WHERE id = ?1",
+                [id, folder_path],
+            )?;
+            Ok(())
+        })
+    }
+
+    pub fn get_card_title(&self, id: &str) -> SqliteResult<Option<String>> {
+        self.with_conn(|conn| {
+            conn.query_row("SELECT title FROM cards WHERE id = ?1", [id], |row| {
+                row.get(0)
+            })
+            .optional()
+        })
+    }
+
+    /// Apply a JSON merge patch (RFC 7396) to a card's metadata and return the result
+    pub fn update_card_metadata(
+        &self,
+        id: &str,
+        patch: &serde_json::Value,
+        expected_version: Option<i64>,
+    ) -> Result<serde_json::Value, CardError> {
+        if !patch.is_object() {
+            return Err(CardError::MetadataPatchNotObject);
+        }
+
+        self.transaction(|tx| {
+            check_card_version_in(tx, id, expected_version)?;
+
+            let raw: String =
+                tx.query_row("SELECT metadata FROM cards WHERE id = ?1", [id], |row| {
+                    row.get(0)
+                })?;
+
+            let mut metadata = serde_json::from_str::<serde_json::Value>(&raw)
+                .ok()
+                .filter(|v| v.is_object())
+                .unwrap_or_else(|| serde_json::json!({}));
+
+            merge_patch(&mut metadata, patch);
+
+            tx.execute(
+                "UPDATE cards SET metadata = ?2, updated_at = strftime('%s', 'now') WHERE id = ?1",
+                [id, metadata.to_string().as_str()],
+            )?;
+
+            Ok(metadata)
+        })
+    }
+
+    pub fn get_agent_config(&self, id: &str) -> SqliteResult<AgentConfig> {
+        self.with_conn(|conn| {
+            let raw: Option<String> = conn.query_row(
+                "SELECT agent_config FROM cards WHERE id = ?1",
+                [id],
+                |row| row.get(0),
+            )?;
+
+            match raw.as_deref() {
+                None | Some("") => Ok(AgentConfig::default()),
+                Some(raw) => serde_json::from_str(raw).map_err(|e| {
+                    rusqlite::Error::FromSqlConversionFailure(0, Type::Text, Box::new(e))
+                }),
+            }
+        })
+    }
+
+    pub fn set_agent_config(
+        &self,
+        id: &str,
+        config: &AgentConfig,
+        expected_version: Option<i64>,
+    ) -> Result<(), CardError> {
+        let raw = serde_json::to_string(config)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+
+        self.transaction(|tx| {
+            check_card_version_in(tx, id, expected_version)?;
+            tx.execute(
+                "UPDATE cards SET agent_config = ?2, updated_at = strftime('%s', 'now') WHERE id = ?1",
+                [id, raw.as_str()],
+            )?;
+            Ok(())
+        })
+    }
+
+    pub fn get_card_file_paths(&self, id: &str) -> SqliteResult<Vec<String>> {
+        self.with_conn(|conn| {
+            let raw: String =
+                conn.query_row("SELECT file_paths FROM cards WHERE id = ?1", [id], |row| {
+                    row.get(0)
+                })?;
+            Ok(parse_file_paths(&raw))
+        })
+    }
+
+    /// Add paths to a card's file list, skipping ones already attached
+    pub fn attach_files(&self, id: &str, paths: &[String]) -> SqliteResult<Vec<String>> {
+        self.transaction(|tx| {
+            let raw: String =
+                tx.query_row("SELECT file_paths FROM cards WHERE id = ?1", [id], |row| {
+                    row.get(0)
+                })?;
+
+            let mut file_paths = parse_file_paths(&raw);
+            for path in paths {
+                if !file_paths.contains(path) {
+                    file_paths.push(path.clone());
+                }
+            }
+
+            write_file_paths(tx, id, &file_paths)?;
+            Ok(file_paths)
+        })
+    }
+
+    pub fn detach_file(&self, id: &str, path: &str) -> SqliteResult<Vec<String>> {
+        self.transaction(|tx| {
+            let raw: String =
+                tx.query_row("SELECT file_paths FROM cards WHERE id = ?1", [id], |row| {
+                    row.get(0)
+                })?;
+
+            let mut file_paths = parse_file_paths(&raw);
+            file_paths.retain(|p| p != path);
+
+            write_file_paths(tx, id, &file_paths)?;
+            Ok(file_paths)
+        })
+    }
+
+    /// Root path of the project a card belongs to
+    pub fn get_card_project_root(&self, id: &str) -> SqliteResult<Option<String>> {
+        self.with_conn(|conn| {
+            conn.query_row(
+                "SELECT p.root_path FROM cards c JOIN columns col ON c.column_id = col.id JOIN boards b ON col.board_id = b.id JOIN projects p ON b.project_id = p.id WHERE c.id = ?1",
+                [id],
+                |row| row.get(0),
+            )
+            .optional()
+        })
+    }
+
+    pub fn get_card_folder(&self, id: &str) -> SqliteResult<Option<String>> {
+        self.with_conn(|conn| {
+            conn.query_row("SELECT folder_path FROM cards WHERE id = ?1", [id], |row| {
+                row.get(0)
+            })
+        })
+    }
+
+    pub fn get_card_project_id(&self, id: &str) -> SqliteResult<Option<String>> {
+        self.with_conn(|conn| {
+            conn.query_row(
+                "SELECT b.project_id FROM cards c JOIN columns col ON c.column_id = col.id JOIN boards b ON col.board_id = b.id WHERE c.id = ?1",
+                [id],
+                |row| row.get(0),
+            )
+            .optional()
+        })
+    }
+
+    /// The project, board and column a card is in
+    pub fn get_card_location(&self, id: &str) -> SqliteResult<Option<CardLocation>> {
+        self.with_conn(|conn| card_location_in(conn, id))
+    }
+
+    /// Set or clear (`None`) a card's due date
+    pub fn set_card_due(&self, id: &str, due_at: Option<i64>) -> SqliteResult<()> {
+        self.with_conn_mut(|conn| {
+            conn.execute(
+                "UPDATE cards SET due_at = ?2, updated_at = strftime('%s', 'now') WHERE id = ?1",
+                rusqlite::params![id, due_at],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Cards in a project that are past due and not yet done, most overdue first
+    pub fn get_overdue_cards(&self, project_id: &str) -> SqliteResult<Vec<Card>> {
+        let now = chrono::Utc::now().timestamp();
+
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "{} JOIN columns col ON c.column_id = col.id JOIN boards b ON col.board_id = b.id WHERE b.project_id = ?1 AND c.due_at < ?2 AND c.status != 'done' AND c.archived_at IS NULL ORDER BY c.due_at",
+                CARD_SELECT
+            ))?;
+
+            let cards = stmt
+                .query_map(rusqlite::params![project_id, now], card_from_row)?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            Ok(cards)
+        })
+    }
+
+    /// Most recently updated cards across every board in a project, newest
+    /// first, each with the board and column it sits in
+    pub fn get_recent_cards(
+        &self,
+        project_id: &str,
+        limit: u32,
+        since: Option<i64>,
+        statuses: &[String],
+    ) -> SqliteResult<Vec<RecentCard>> {
+        // The unary `+` keeps SQLite off idx_cards_archived so it walks
+        // idx_cards_updated in order and stops at the limit
+        let mut sql = format!(
+            "{} JOIN columns col ON c.column_id = col.id JOIN boards b ON col.board_id = b.id WHERE b.project_id = ? AND +c.archived_at IS NULL",
+            CARD_SELECT
+        );
+        let mut params: Vec<Value> = vec![Value::from(project_id.to_string())];
+
+        if let Some(since) = since {
+            sql.push_str(" AND c.updated_at >= ?");
+            params.push(Value::from(since));
+        }
+
+        if !statuses.is_empty() {
+            sql.push_str(&format!(
+                " AND c.status IN ({})",
+                vec!["?"; statuses.len()].join(", ")
+            ));
+            params.extend(statuses.iter().cloned().map(Value::from));
+        }
+
+        sql.push_str(" ORDER BY c.updated_at DESC LIMIT ?");
+        params.push(Value::from(limit));
+
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(&sql)?;
+            let cards = stmt
+                .query_map(rusqlite::params_from_iter(params), card_from_row)?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            // Names come from a second small query so the card row layout stays
+            // the one `card_from_row` expects
+            let mut stmt = conn.prepare(
+                "SELECT col.id, col.name, b.id, b.name FROM columns col JOIN boards b ON col.board_id = b.id WHERE b.project_id = ?1",
+            )?;
+            let locations = stmt
+                .query_map([project_id], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        (row.get(1)?, row.get(2)?, row.get(3)?),
+                    ))
+                })?
+                .collect::<SqliteResult<HashMap<String, (String, String, String)>>>()?;
+
+            Ok(cards
+                .into_iter()
+                .filter_map(|card| {
+                    let (column_name, board_id, board_name) =
+                        locations.get(&card.column_id)?.clone();
+                    Some(RecentCard {
+                        card,
+                        board_id,
+                        board_name,
+                        column_name,
+                    })
+                })
+                .collect())
+        })
+    }
+
+    /// Copy a card (and its checklist, unchecked). With no `target_column_id` the
+    /// copy goes in the slot right after the original; otherwise it is appended
+    /// to the target column. The copy starts idle, keeps the original's labels
+    /// and folder reference, and carries no execution history.
+    pub fn duplicate_card(
+        &self,
+        id: &str,
+        target_column_id: Option<&str>,
+    ) -> Result<Card, CardError> {
+        let new_id = uuid::Uuid::new_v4().to_string();
+
+        self.transaction(|tx| {
+            let source: Option<(String, i32)> = tx
+                .query_row(
+                    "SELECT column_id, position FROM cards WHERE id = ?1",
+                    [id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?;
+            let Some((column_id, position)) = source else {
+                return Err(CardError::CardNotFound(id.to_string()));
+            };
+
+            let (column_id, position) = match target_column_id {
+                Some(target) if target != column_id => {
+                    let column_exists = tx
+                        .query_row("SELECT 1 FROM columns WHERE id = ?1", [target], |_| Ok(()))
+                        .optional()?
+                        .is_some();
+                    if !column_exists {
+                        return Err(CardError::ColumnNotFound(target.to_string()));
+                    }
+
+                    let end: i32 = tx.query_row(
+                        "SELECT COUNT(*) FROM cards WHERE column_id = ?1 AND archived_at IS NULL",
+                        [target],
+                        |row| row.get(0),
+                    )?;
+                    (target.to_string(), end)
+                }
+                _ => {
+                    tx.execute(
+                        "UPDATE cards SET position = position + 1 WHERE column_id = ?1 AND position > ?2",
+                        rusqlite::params![column_id, position],
+                    )?;
+                    (column_id, position + 1)
+                }
+            };
+
+            check_wip_limit_in(tx, &column_id, None)?;
+            copy_card_in(tx, id, &new_id, &column_id, position, " (copy)")?;
+
+            Ok(tx.query_row(
+                &format!("{} WHERE c.id = ?1", CARD_SELECT),
+                [new_id.as_str()],
+                card_from_row,
+            )?)
+        })
+    }
+
+    /// Change a card's status, enforcing the allowed transitions unless `force` is set
+    pub fn update_card_status(&self, id: &str, status: &str, force: bool) -> Result<(), CardError> {
+        self.transaction(|tx| set_card_status_in(tx, id, status, force))
+    }
+
+    pub fn delete_card(&self, id: &str) -> SqliteResult<()> {
+        self.transaction(|tx| {
+            delete_card_in(tx, id)?;
+            Ok(())
+        })
+    }
+
+    /// Apply `op` to each card in selection order. With `atomic`, one failure
+    /// rolls back the whole batch; otherwise only the failing cards are skipped.
+    /// Every id gets a result either way.
+    pub fn bulk_update_cards(
+        &self,
+        ids: &[String],
+        op: &BulkCardOp,
+        atomic: bool,
+    ) -> SqliteResult<Vec<BulkCardResult>> {
+        self.transaction(|tx| {
+            if let BulkCardOp::MoveToColumn { column_id } = op {
+                let column_exists = tx
+                    .query_row("SELECT 1 FROM columns WHERE id = ?1", [column_id], |_| {
+                        Ok(())
+                    })
+                    .optional()?
+                    .is_some();
+                if !column_exists {
+                    let error = CardError::ColumnNotFound(column_id.clone()).to_string();
+                    return Ok(ids
+                        .iter()
+                        .map(|id| BulkCardResult::failed(id, error.clone()))
+                        .collect());
+                }
+            }
+
+            tx.execute_batch("SAVEPOINT bulk")?;
+
+            let mut results = Vec::with_capacity(ids.len());
+            for id in ids {
+                tx.execute_batch("SAVEPOINT bulk_card")?;
+                match apply_bulk_op(tx, id, op) {
+                    Ok(()) => {
+                        tx.execute_batch("RELEASE bulk_card")?;
+                        results.push(BulkCardResult {
+                            id: id.clone(),
+                            ok: true,
+                            error: None,
+                        });
+                    }
+                    Err(e) => {
+                        tx.execute_batch("ROLLBACK TO bulk_card; RELEASE bulk_card")?;
+                        results.push(BulkCardResult::failed(id, e.to_string()));
+                    }
+                }
+            }
+
+            if atomic && results.iter().any(|r| !r.ok) {
+                tx.execute_batch("ROLLBACK TO bulk")?;
+                for result in results.iter_mut().filter(|r| r.ok) {
+                    *result = BulkCardResult::failed(
+                        &result.id,
+                        "Rolled back because another card in the batch failed".to_string(),
+                    );
+                }
+            }
+
+            tx.execute_batch("RELEASE bulk")?;
+            Ok(results)
+        })
+    }
+
+    /// Move the cards to the end of `column_id`, in selection order
+    pub fn bulk_move_cards(&self, ids: &[String], column_id: &str) -> Result<usize, CardError> {
+        self.bulk_apply(
+            ids,
+            &BulkCardOp::MoveToColumn {
+                column_id: column_id.to_string(),
+            },
+        )
+    }
+
+    pub fn bulk_delete_cards(&self, ids: &[String]) -> Result<usize, CardError> {
+        self.bulk_apply(ids, &BulkCardOp::Delete)
+    }
+
+    /// Set every card's status, following the same transition rules as a
+    /// single update
+    pub fn bulk_update_status(&self, ids: &[String], status: &str) -> Result<usize, CardError> {
+        self.bulk_apply(
+            ids,
+            &BulkCardOp::SetStatus {
+                status: status.to_string(),
+            },
+        )
+    }
+
+    pub fn bulk_assign_label(&self, ids: &[String], label_id: &str) -> Result<usize, CardError> {
+        self.bulk_apply(
+            ids,
+            &BulkCardOp::AssignLabel {
+                label_id: label_id.to_string(),
+            },
+        )
+    }
+
+    /// Apply `op` to every card in `ids` in one transaction, all or nothing.
+    /// Every id is checked before anything changes, and the first error rolls
+    /// the whole batch back. Returns how many cards were changed; repeated ids
+    /// count once.
+    fn bulk_apply(&self, ids: &[String], op: &BulkCardOp) -> Result<usize, CardError> {
+        let mut seen = HashSet::new();
+        let ids: Vec<&str> = ids
+            .iter()
+            .map(String::as_str)
+            .filter(|id| seen.insert(*id))
+            .collect();
+
+        self.transaction(|tx| {
+            for id in &ids {
+                let exists = tx
+                    .query_row("SELECT 1 FROM cards WHERE id = ?1", [id], |_| Ok(()))
+                    .optional()?
+                    .is_some();
+                if !exists {
+                    return Err(CardError::CardNotFound(id.to_string()));
+                }
+            }
+            if let BulkCardOp::MoveToColumn { column_id } = op {
+                if !column_exists_in(tx, column_id)? {
+                    return Err(CardError::ColumnNotFound(column_id.clone()));
+                }
+            }
+
+            for id in &ids {
+                apply_bulk_op(tx, id, op)?;
+            }
+            Ok(ids.len())
+        })
+    }
+}
+
+fn apply_bulk_op(tx: &Transaction, id: &str, op: &BulkCardOp) -> Result<(), CardError> {
+    let found = match op {
+        BulkCardOp::MoveToColumn { column_id } => {
+            check_wip_limit_in(tx, column_id, Some(id))?;
+            move_card_in(tx, id, column_id, None)?;
+            true
+        }
+        BulkCardOp::SetStatus { status } => {
+            set_card_status_in(tx, id, status, false)?;
+            true
+        }
+        BulkCardOp::Delete => delete_card_in(tx, id)?,
+        BulkCardOp::Archive => archive_card_in(tx, id)?,
+        BulkCardOp::AssignLabel { label_id } => {
+            let exists = tx
+                .query_row("SELECT 1 FROM cards WHERE id = ?1", [id], |_| Ok(()))
+                .optional()?
+                .is_some();
+            if exists {
+                attach_label_in(tx, id, label_id)?;
+            }
+            exists
+        }
+    };
+
+    if found {
+        Ok(())
+    } else {
+        Err(CardError::CardNotFound(id.to_string()))
+    }
+}
+
+/// Delete a card, keeping a snapshot so it can be undone, and close the gap it
+/// leaves in its column. Returns false if the card doesn't exist.
+fn delete_card_in(tx: &Transaction, id: &str) -> SqliteResult<bool> {
+    let Some(card) = deleted_card_in(tx, id)? else {
+        return Ok(false);
+    };
+    let (column_id, position, live) = (
+        card.card.column_id.clone(),
+        card.card.position,
+        card.card.archived_at.is_none(),
+    );
+    record_operation_in(
+        tx,
+        id,
+        &CardOperation::Delete {
+            card: Box::new(card),
+        },
+    )?;
+
+    tx.execute("DELETE FROM cards WHERE id = ?1", [id])?;
+    if live {
+        close_position_gap(tx, &column_id, position)?;
+    }
+    Ok(true)
+}
+
+/// Hide a card from its column. Returns false if the card doesn't exist;
+/// archiving an already archived card is a no-op.
+fn archive_card_in(tx: &Transaction, id: &str) -> SqliteResult<bool> {
+    let card: Option<(String, i32, bool)> = tx
+        .query_row(
+            "SELECT column_id, position, archived_at IS NOT NULL FROM cards WHERE id = ?1",
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?;
+
+    match card {
+        None => Ok(false),
+        Some((_, _, true)) => Ok(true),
+        Some((column_id, position, false)) => {
+            let archived_at: i64 = tx.query_row(
+                "UPDATE cards SET archived_at = strftime('%s', 'now'), updated_at = strftime('%s', 'now') WHERE id = ?1 RETURNING archived_at",
+                [id],
+                |row| row.get(0),
+            )?;
+            close_position_gap(tx, &column_id, position)?;
+            log_card_change(tx, id, "archived_at", None, Some(&archived_at.to_string()))?;
+            Ok(true)
+        }
+    }
+}
+
+// Dependency operations
+impl Database {
+    /// Record that `card_id` can't start until `depends_on_card_id` is done.
+    /// A card can't depend on itself, and an edge that would close a cycle is
+    /// refused with the path it would create.
+    pub fn add_dependency(&self, card_id: &str, depends_on_card_id: &str) -> Result<(), CardError> {
+        if card_id == depends_on_card_id {
+            return Err(CardError::SelfDependency(card_id.to_string()));
+        }
+
+        self.transaction(|tx| {
+            for id in [card_id, depends_on_card_id] {
+                let exists = tx
+                    .query_row("SELECT 1 FROM cards WHERE id = ?1", [id], |_| Ok(()))
+                    .optional()?
+                    .is_some();
+                if !exists {
+                    return Err(CardError::CardNotFound(id.to_string()));
+                }
+            }
+
+            if let Some(path) = dependency_path_in(tx, depends_on_card_id, card_id)? {
+                let mut cycle = vec![card_id.to_string()];
+                cycle.extend(path);
+                return Err(CardError::DependencyCycle(cycle));
+            }
+
+            tx.execute(
+                "INSERT OR IGNORE INTO card_dependencies (card_id, depends_on_card_id) VALUES (?1, ?2)",
+                [card_id, depends_on_card_id],
+            )?;
+            Ok(())
+        })
+    }
+
+    pub fn remove_dependency(&self, card_id: &str, depends_on_card_id: &str) -> SqliteResult<()> {
+        self.with_conn_mut(|conn| {
+            conn.execute(
+                "DELETE FROM card_dependencies WHERE card_id = ?1 AND depends_on_card_id = ?2",
+                [card_id, depends_on_card_id],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// The cards `card_id` waits on and the cards waiting on it, oldest link first
+    pub fn get_dependencies(&self, card_id: &str) -> SqliteResult<CardDependencies> {
+        self.with_conn(|conn| {
+            let linked = |join: &str| -> SqliteResult<Vec<Card>> {
+                let mut stmt = conn.prepare(&format!(
+                    "{} JOIN card_dependencies d ON {} ORDER BY d.created_at, d.rowid",
+                    CARD_SELECT, join
+                ))?;
+                let cards = stmt
+                    .query_map([card_id], card_from_row)?
+                    .collect::<SqliteResult<Vec<_>>>()?;
+                Ok(cards)
+            };
+
+            Ok(CardDependencies {
+                blocked_by: linked("d.depends_on_card_id = c.id WHERE d.card_id = ?1")?,
+                blocks: linked("d.card_id = c.id WHERE d.depends_on_card_id = ?1")?,
+            })
+        })
+    }
+
+    /// Live cards in the project with at least one dependency that isn't done,
+    /// along with the ids of those dependencies
+    pub fn get_blocked_cards(&self, project_id: &str) -> SqliteResult<Vec<BlockedCard>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT d.card_id, d.depends_on_card_id FROM card_dependencies d
+                 JOIN cards dep ON d.depends_on_card_id = dep.id
+                 JOIN cards c ON d.card_id = c.id
+                 JOIN columns col ON c.column_id = col.id
+                 JOIN boards b ON col.board_id = b.id
+                 WHERE b.project_id = ?1 AND c.archived_at IS NULL AND dep.status != 'done'
+                 ORDER BY d.created_at, d.rowid",
+            )?;
+            let mut blockers: HashMap<String, Vec<String>> = HashMap::new();
+            for edge in stmt.query_map([project_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })? {
+                let (card_id, depends_on) = edge?;
+                blockers.entry(card_id).or_default().push(depends_on);
+            }
+
+            let mut stmt = conn.prepare(&format!(
+                "{} JOIN columns col ON c.column_id = col.id JOIN boards b ON col.board_id = b.id WHERE b.project_id = ?1 AND c.archived_at IS NULL ORDER BY b.position, col.position, c.position",
+                CARD_SELECT
+            ))?;
+            let cards = stmt
+                .query_map([project_id], card_from_row)?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            Ok(cards
+                .into_iter()
+                .filter_map(|card| {
+                    let blocked_by = blockers.remove(&card.id)?;
+                    Some(BlockedCard { card, blocked_by })
+                })
+                .collect())
+        })
+    }
+}
+
+// Checklist operations
+impl Database {
+    pub fn add_checklist_item(&self, card_id: &str, text: &str) -> SqliteResult<ChecklistItem> {
+        let id = uuid::Uuid::new_v4().to_string();
+
+        self.with_conn_mut(|conn| {
+            conn.query_row(
+                "INSERT INTO card_checklist_items (id, card_id, text, position)
+                 VALUES (?1, ?2, ?3, (SELECT COALESCE(MAX(position) + 1, 0) FROM card_checklist_items WHERE card_id = ?2))
+                 RETURNING id, card_id, text, done, position, created_at",
+                [id.as_str(), card_id, text],
+                checklist_item_from_row,
+            )
+        })
+    }
+
+    pub fn get_checklist(&self, card_id: &str) -> SqliteResult<Vec<ChecklistItem>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, card_id, text, done, position, created_at FROM card_checklist_items WHERE card_id = ?1 ORDER BY position"
+            )?;
+
+            let items = stmt
+                .query_map([card_id], checklist_item_from_row)?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            Ok(items)
+        })
+    }
+
+    pub fn toggle_checklist_item(&self, id: &str) -> SqliteResult<ChecklistItem> {
+        self.with_conn_mut(|conn| {
+            conn.query_row(
+                "UPDATE card_checklist_items SET done = NOT done WHERE id = ?1 RETURNING id, card_id, text, done, position, created_at",
+                [id],
+                checklist_item_from_row,
+            )
+        })
+    }
+
+    /// Rewrite positions to follow `ordered_ids`
+    pub fn reorder_checklist_items(
+        &self,
+        card_id: &str,
+        ordered_ids: &[String],
+    ) -> SqliteResult<()> {
+        self.transaction(|tx| {
+            let mut stmt = tx.prepare(
+                "UPDATE card_checklist_items SET position = ?3 WHERE id = ?1 AND card_id = ?2",
+            )?;
+
+            for (position, id) in ordered_ids.iter().enumerate() {
+                stmt.execute(rusqlite::params![id, card_id, position as i32])?;
+            }
+            Ok(())
+        })
+    }
+
+    pub fn delete_checklist_item(&self, id: &str) -> SqliteResult<()> {
+        self.with_conn_mut(|conn| {
+            conn.execute("DELETE FROM card_checklist_items WHERE id = ?1", [id])?;
+            Ok(())
+        })
+    }
+}
+
+fn checklist_item_from_row(row: &rusqlite::Row) -> SqliteResult<ChecklistItem> {
+    Ok(ChecklistItem {
+        id: row.get(0)?,
+        card_id: row.get(1)?,
+        text: row.get(2)?,
+        done: row.get(3)?,
+        position: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+// Comment operations
+impl Database {
+    pub fn add_comment(&self, card_id: &str, author: &str, body: &str) -> SqliteResult<Comment> {
+        let id = uuid::Uuid::new_v4().to_string();
+
+        self.with_conn_mut(|conn| {
+            conn.query_row(
+                "INSERT INTO card_comments (id, card_id, author, body) VALUES (?1, ?2, ?3, ?4) RETURNING id, card_id, author, body, created_at, edited_at",
+                [id.as_str(), card_id, author, body],
+                comment_from_row,
+            )
+        })
+    }
+
+    pub fn get_comments(&self, card_id: &str) -> SqliteResult<Vec<Comment>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, card_id, author, body, created_at, edited_at FROM card_comments WHERE card_id = ?1 ORDER BY created_at, rowid"
+            )?;
+
+            let comments = stmt
+                .query_map([card_id], comment_from_row)?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            Ok(comments)
+        })
+    }
+
+    pub fn edit_comment(&self, id: &str, body: &str) -> SqliteResult<Comment> {
+        self.with_conn_mut(|conn| {
+            conn.query_row(
+                "UPDATE card_comments SET body = ?2, edited_at = strftime('%s', 'now') WHERE id = ?1 RETURNING id, card_id, author, body, created_at, edited_at",
+                [id, body],
+                comment_from_row,
+            )
+        })
+    }
+
+    pub fn delete_comment(&self, id: &str) -> SqliteResult<()> {
+        self.with_conn_mut(|conn| {
+            conn.execute("DELETE FROM card_comments WHERE id = ?1", [id])?;
+            Ok(())
+        })
+    }
+}
+
+fn comment_from_row(row: &rusqlite::Row) -> SqliteResult<Comment> {
+    Ok(Comment {
+        id: row.get(0)?,
+        card_id: row.get(1)?,
+        author: row.get(2)?,
+        body: row.get(3)?,
+        created_at: row.get(4)?,
+        edited_at: row.get(5)?,
+    })
+}
+
+// Activity operations
+impl Database {
+    pub fn add_card_activity(
+        &self,
+        card_id: &str,
+        kind: &str,
+        message: &str,
+    ) -> SqliteResult<CardActivity> {
+        let id = uuid::Uuid::new_v4().to_string();
+
+        self.with_conn_mut(|conn| {
+            conn.query_row(
+                "INSERT INTO card_activity (id, card_id, kind, message) VALUES (?1, ?2, ?3, ?4) RETURNING id, card_id, kind, message, created_at",
+                [id.as_str(), card_id, kind, message],
+                activity_from_row,
+            )
+        })
+    }
+
+    /// A card's activity, newest first
+    pub fn get_card_activity(&self, card_id: &str) -> SqliteResult<Vec<CardActivity>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, card_id, kind, message, created_at FROM card_activity WHERE card_id = ?1 ORDER BY created_at DESC, rowid DESC",
+            )?;
+
+            let activity = stmt
+                .query_map([card_id], activity_from_row)?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            Ok(activity)
+        })
+    }
+}
+
+fn activity_from_row(row: &rusqlite::Row) -> SqliteResult<CardActivity> {
+    Ok(CardActivity {
+        id: row.get(0)?,
+        card_id: row.get(1)?,
+        kind: row.get(2)?,
+        message: row.get(3)?,
+        created_at: row.get(4)?,
+    })
+}
+
+// Audit log operations
+impl Database {
+    /// Every recorded change to a card's fields, newest first
+    pub fn get_card_history(&self, card_id: &str) -> SqliteResult<Vec<CardAuditEntry>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, card_id, field, old_value, new_value, changed_at FROM card_audit_log WHERE card_id = ?1 ORDER BY changed_at DESC, rowid DESC",
+            )?;
+
+            let entries = stmt
+                .query_map([card_id], |row| {
+                    Ok(CardAuditEntry {
+                        id: row.get(0)?,
+                        card_id: row.get(1)?,
+                        field: row.get(2)?,
+                        old_value: row.get(3)?,
+                        new_value: row.get(4)?,
+                        changed_at: row.get(5)?,
+                    })
+                })?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            Ok(entries)
+        })
+    }
+}
+
+// Undo / redo operations
+impl Database {
+    /// Reverse the project's most recent undoable change, or `None` when there's
+    /// nothing left to undo
+    pub fn undo_last(&self, project_id: &str) -> Result<Option<AppliedOperation>, CardError> {
+        self.replay_last(project_id, true)
+    }
+
+    /// Re-apply the change most recently undone, or `None` when there's nothing
+    /// to redo
+    pub fn redo_last(&self, project_id: &str) -> Result<Option<AppliedOperation>, CardError> {
+        self.replay_last(project_id, false)
+    }
+
+    fn replay_last(
+        &self,
+        project_id: &str,
+        undo: bool,
+    ) -> Result<Option<AppliedOperation>, CardError> {
+        // Undone entries always form the tail of the stack: recording a new
+        // change drops them
+        let query = if undo {
+            "SELECT seq, card_id, operation FROM card_operations WHERE project_id = ?1 AND undone = 0 ORDER BY seq DESC LIMIT 1"
+        } else {
+            "SELECT seq, card_id, operation FROM card_operations WHERE project_id = ?1 AND undone = 1 ORDER BY seq LIMIT 1"
+        };
+
+        self.transaction(|tx| {
+            let entry: Option<(i64, String, String)> = tx
+                .query_row(query, [project_id], |row| {
+                    Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+                })
+                .optional()?;
+            let Some((seq, card_id, raw)) = entry else {
+                return Ok(Ok(None));
+            };
+            let operation: CardOperation = serde_json::from_str(&raw).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(2, Type::Text, Box::new(e))
+            })?;
+
+            let before = card_location_in(tx, &card_id)?;
+            tx.execute_batch("SAVEPOINT replay")?;
+            let replayed = if undo {
+                operation.undo_in(tx, &card_id)
+            } else {
+                operation.redo_in(tx, &card_id)
+            };
+
+            match replayed {
+                Ok(()) => {
+                    tx.execute_batch("RELEASE replay")?;
+                    tx.execute(
+                        "UPDATE card_operations SET undone = ?2 WHERE seq = ?1",
+                        rusqlite::params![seq, undo],
+                    )?;
+                    Ok(Ok(Some(AppliedOperation {
+                        kind: operation.kind().to_string(),
+                        after: card_location_in(tx, &card_id)?,
+                        card_id,
+                        before,
+                    })))
+                }
+                // The card or column it needs is gone, so it would fail every
+                // time; drop it instead of leaving it stuck on top of the stack
+                Err(e @ (CardError::CardNotFound(_) | CardError::ColumnNotFound(_))) => {
+                    tx.execute_batch("ROLLBACK TO replay; RELEASE replay")?;
+                    tx.execute("DELETE FROM card_operations WHERE seq = ?1", [seq])?;
+                    Ok(Err(e))
+                }
+                Err(e) => Err(e),
+            }
+        })?
+    }
+}
+
+// Execution operations
+impl Database {
+    /// Record a new running execution and flip the card to `executing`. Fails if
+    /// the card can't currently move to `executing`.
+    pub fn start_execution(
+        &self,
+        card_id: &str,
+        agent_type: &str,
+        input_context: &str,
+    ) -> Result<CardExecution, CardError> {
+        let id = uuid::Uuid::new_v4().to_string();
+
+        self.transaction(|tx| {
+            set_card_status_in(tx, card_id, "executing", false)?;
+
+            let execution = tx.query_row(
+                &format!(
+                    "INSERT INTO card_executions (id, card_id, agent_type, input_context) VALUES (?1, ?2, ?3, ?4) RETURNING {}",
+                    EXECUTION_COLUMNS
+                ),
+                [id.as_str(), card_id, agent_type, input_context],
+                execution_from_row,
+            )?;
+
+            Ok(execution)
+        })
+    }
+
+    /// Close out a running execution and move its card to `card_status` in one
+    /// step. Returns `None`, changing nothing, if the execution already finished.
+    pub fn finish_execution(
+        &self,
+        id: &str,
+        status: &str,
+        card_status: &str,
+        output_result: Option<&str>,
+        error_message: Option<&str>,
+    ) -> SqliteResult<Option<CardExecution>> {
+        self.transaction(|tx| {
+            let execution = tx
+                .query_row(
+                    &format!(
+                        "UPDATE card_executions SET status = ?2, completed_at = strftime('%s', 'now'), output_result = ?3, error_message = ?4 WHERE id = ?1 AND status = 'running' RETURNING {}",
+                        EXECUTION_COLUMNS
+                    ),
+                    rusqlite::params![id, status, output_result, error_message],
+                    execution_from_row,
+                )
+                .optional()?;
+
+            if let Some(execution) = &execution {
+                tx.execute(
+                    "UPDATE cards SET status = ?2, updated_at = strftime('%s', 'now') WHERE id = ?1",
+                    [execution.card_id.as_str(), card_status],
+                )?;
+            }
+
+            Ok(execution)
+        })
+    }
+
+    /// The card's most recently started execution, if it ever ran
+    pub fn get_latest_execution(&self, card_id: &str) -> SqliteResult<Option<CardExecution>> {
+        self.with_conn(|conn| {
+            conn.query_row(
+                &format!(
+                    "SELECT {} FROM card_executions WHERE card_id = ?1 ORDER BY started_at DESC, rowid DESC LIMIT 1",
+                    EXECUTION_COLUMNS
+                ),
+                [card_id],
+                execution_from_row,
+            )
+            .optional()
+        })
+    }
+
+    pub fn get_execution(&self, id: &str) -> SqliteResult<CardExecution> {
+        self.with_conn(|conn| {
+            conn.query_row(
+                &format!(
+                    "SELECT {} FROM card_executions WHERE id = ?1",
+                    EXECUTION_COLUMNS
+                ),
+                [id],
+                execution_from_row,
+            )
+        })
+    }
+}
+
+const EXECUTION_COLUMNS: &str = "id, card_id, started_at, completed_at, status, agent_type, input_context, output_result, error_message, ghost_branch";
+
+fn execution_from_row(row: &rusqlite::Row) -> SqliteResult<CardExecution> {
+    Ok(CardExecution {
+        id: row.get(0)?,
+        card_id: row.get(1)?,
+        started_at: row.get(2)?,
+        completed_at: row.get(3)?,
+        status: row.get(4)?,
+        agent_type: row.get(5)?,
+        input_context: row.get(6)?,
+        output_result: row.get(7)?,
+        error_message: row.get(8)?,
+        ghost_branch: row.get(9)?,
+    })
+}
+
+// Agent log operations
+impl Database {
+    pub fn append_log(
+        &self,
+        execution_id: &str,
+        level: &str,
+        message: &str,
+        metadata: Option<&str>,
+    ) -> SqliteResult<AgentLog> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let level = normalize_log_level(level);
+        let metadata = metadata.unwrap_or("{}");
+
+        self.with_conn_mut(|conn| {
+            conn.query_row(
+                "INSERT INTO agent_logs (id, execution_id, level, message, metadata) VALUES (?1, ?2, ?3, ?4, ?5) RETURNING seq, id, execution_id, timestamp, level, message, metadata",
+                rusqlite::params![id, execution_id, level, message, metadata],
+                log_from_row,
+            )
+        })
+    }
+
+    /// Append `(level, message, metadata)` entries to an execution's log in one
+    /// transaction, in order
+    pub fn append_logs(
+        &self,
+        execution_id: &str,
+        entries: &[(&str, &str, Option<&str>)],
+    ) -> SqliteResult<Vec<AgentLog>> {
+        self.transaction(|tx| {
+            let mut stmt = tx.prepare(
+                "INSERT INTO agent_logs (id, execution_id, level, message, metadata) VALUES (?1, ?2, ?3, ?4, ?5) RETURNING seq, id, execution_id, timestamp, level, message, metadata",
+            )?;
+            entries
+                .iter()
+                .map(|(level, message, metadata)| {
+                    let id = uuid::Uuid::new_v4().to_string();
+                    stmt.query_row(
+                        rusqlite::params![
+                            id,
+                            execution_id,
+                            normalize_log_level(level),
+                            message,
+                            metadata.unwrap_or("{}")
+                        ],
+                        log_from_row,
+                    )
+                })
+                .collect()
+        })
+    }
+
+    /// Logs for an execution in insertion order. `after_seq` lets a viewer tail
+    /// an execution by only asking for entries after the last `seq` it saw;
+    /// unlike timestamps, no two entries share one.
+    pub fn get_logs(
+        &self,
+        execution_id: &str,
+        after_seq: Option<i64>,
+        level_filter: Option<&str>,
+        limit: Option<i64>,
+    ) -> SqliteResult<Vec<AgentLog>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT seq, id, execution_id, timestamp, level, message, metadata FROM agent_logs WHERE execution_id = ?1 AND seq > ?2 AND (?3 IS NULL OR level = ?3) ORDER BY seq LIMIT ?4"
+            )?;
+
+            let logs = stmt
+                .query_map(
+                    rusqlite::params![
+                        execution_id,
+                        after_seq.unwrap_or(i64::MIN),
+                        level_filter,
+                        limit.unwrap_or(-1)
+                    ],
+                    log_from_row,
+                )?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            Ok(logs)
+        })
+    }
+
+    pub fn prune_logs(&self, older_than_days: i64) -> SqliteResult<usize> {
+        let cutoff = chrono::Utc::now().timestamp() - older_than_days * 24 * 60 * 60;
+
+        self.with_conn_mut(|conn| {
+            let deleted = conn.execute("DELETE FROM agent_logs WHERE timestamp < ?1", [cutoff])?;
+            Ok(deleted)
+        })
+    }
+}
+
+fn log_from_row(row: &rusqlite::Row) -> SqliteResult<AgentLog> {
+    Ok(AgentLog {
+        seq: row.get(0)?,
+        id: row.get(1)?,
+        execution_id: row.get(2)?,
+        timestamp: row.get(3)?,
+        level: row.get(4)?,
+        message: row.get(5)?,
+        metadata: row.get::<_, String>(6)?,
+    })
+}
+
+/// Map arbitrary level strings onto the values allowed by the agent_logs CHECK constraint
+fn normalize_log_level(level: &str) -> &'static str {
+    match level.to_ascii_lowercase().as_str() {
+        "debug" => "debug",
+        "warn" | "warning" => "warn",
+        "error" => "error",
+        _ => "info",
+    }
+}
+
+// File index operations
+impl Database {
+    pub fn get_file_index(&self, project_id: &str) -> SqliteResult<Vec<FileIndexEntry>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT project_id, path, hash, size, mtime, indexed_at FROM file_index WHERE project_id = ?1 ORDER BY path"
+            )?;
+
+            let entries = stmt
+                .query_map([project_id], |row| {
+                    Ok(FileIndexEntry {
+                        project_id: row.get(0)?,
+                        path: row.get(1)?,
+                        hash: row.get(2)?,
+                        size: row.get(3)?,
+                        mtime: row.get(4)?,
+                        indexed_at: row.get(5)?,
+                    })
+                })?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            Ok(entries)
+        })
+    }
+
+    pub fn upsert_file_index(&self, entries: &[FileIndexEntry]) -> SqliteResult<()> {
+        self.transaction(|tx| {
+            let mut stmt = tx.prepare(
+                "INSERT INTO file_index (project_id, path, hash, size, mtime, indexed_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                 ON CONFLICT(project_id, path) DO UPDATE SET hash = excluded.hash, size = excluded.size, mtime = excluded.mtime, indexed_at = excluded.indexed_at"
+            )?;
+
+            for entry in entries {
+                stmt.execute(rusqlite::params![
+                    entry.project_id,
+                    entry.path,
+                    entry.hash,
+                    entry.size,
+                    entry.mtime,
+                    entry.indexed_at
+                ])?;
+            }
+            Ok(())
+        })
+    }
+
+    pub fn remove_file_index_paths(&self, project_id: &str, paths: &[String]) -> SqliteResult<()> {
+        self.transaction(|tx| {
+            let mut stmt =
+                tx.prepare("DELETE FROM file_index WHERE project_id = ?1 AND path = ?2")?;
+
+            for path in paths {
+                stmt.execute([project_id, path.as_str()])?;
+            }
+            Ok(())
+        })
+    }
+
+    pub fn get_index_status(&self, project_id: &str) -> SqliteResult<IndexStatus> {
+        self.with_conn(|conn| {
+            conn.query_row(
+                "SELECT COUNT(*), COALESCE(SUM(size), 0), MAX(indexed_at) FROM file_index WHERE project_id = ?1",
+                [project_id],
+                |row| {
+                    Ok(IndexStatus {
+                        project_id: project_id.to_string(),
+                        file_count: row.get(0)?,
+                        total_size: row.get(1)?,
+                        last_indexed_at: row.get(2)?,
+                    })
+                },
+            )
+        })
+    }
+}
+
+// Export / import operations
+impl Database {
+    pub fn export_project(&self, project_id: &str) -> SqliteResult<ProjectBundle> {
+        let project = self.get_project(project_id)?;
+
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, project_id, name, position, created_at FROM boards WHERE project_id = ?1 ORDER BY position"
+            )?;
+            let boards = stmt
+                .query_map([project_id], |row| {
+                    Ok(Board {
+                        id: row.get(0)?,
+                        project_id: row.get(1)?,
+                        name: row.get(2)?,
+                        position: row.get(3)?,
+                        created_at: row.get(4)?,
+                    })
+                })?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            let mut stmt = conn.prepare(&format!(
+                "{} JOIN boards b ON col.board_id = b.id WHERE b.project_id = ?1 ORDER BY col.position",
+                COLUMN_SELECT
+            ))?;
+            let columns = stmt
+                .query_map([project_id], column_from_row)?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            let mut stmt = conn.prepare(&format!(
+                "{} JOIN columns col ON c.column_id = col.id JOIN boards b ON col.board_id = b.id WHERE b.project_id = ?1 ORDER BY c.position",
+                CARD_SELECT
+            ))?;
+            let cards = stmt
+                .query_map([project_id], card_from_row)?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            let mut stmt = conn.prepare(
+                "SELECT e.id, e.card_id, e.started_at, e.completed_at, e.status, e.agent_type, e.input_context, e.output_result, e.error_message, e.ghost_branch FROM card_executions e JOIN cards c ON e.card_id = c.id JOIN columns col ON c.column_id = col.id JOIN boards b ON col.board_id = b.id WHERE b.project_id = ?1 ORDER BY e.started_at"
+            )?;
+            let executions = stmt
+                .query_map([project_id], execution_from_row)?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            let mut stmt = conn.prepare(
+                "SELECT i.id, i.card_id, i.text, i.done, i.position, i.created_at FROM card_checklist_items i JOIN cards c ON i.card_id = c.id JOIN columns col ON c.column_id = col.id JOIN boards b ON col.board_id = b.id WHERE b.project_id = ?1 ORDER BY i.card_id, i.position"
+            )?;
+            let checklist_items = stmt
+                .query_map([project_id], checklist_item_from_row)?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            let mut stmt = conn.prepare(
+                "SELECT m.id, m.card_id, m.author, m.body, m.created_at, m.edited_at FROM card_comments m JOIN cards c ON m.card_id = c.id JOIN columns col ON c.column_id = col.id JOIN boards b ON col.board_id = b.id WHERE b.project_id = ?1 ORDER BY m.created_at, m.rowid"
+            )?;
+            let comments = stmt
+                .query_map([project_id], comment_from_row)?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            let mut stmt = conn.prepare(
+                "SELECT d.card_id, d.depends_on_card_id, d.created_at FROM card_dependencies d JOIN cards c ON d.card_id = c.id JOIN columns col ON c.column_id = col.id JOIN boards b ON col.board_id = b.id WHERE b.project_id = ?1 ORDER BY d.created_at, d.rowid"
+            )?;
+            let dependencies = stmt
+                .query_map([project_id], |row| {
+                    Ok(BundleDependency {
+                        card_id: row.get(0)?,
+                        depends_on_card_id: row.get(1)?,
+                        created_at: row.get(2)?,
+                    })
+                })?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            Ok(ProjectBundle {
+                schema_version: BUNDLE_VERSION,
+                exported_at: chrono::Utc::now().timestamp(),
+                project,
+                boards,
+                columns,
+                cards,
+                executions,
+                checklist_items,
+                comments,
+                dependencies,
+            })
+        })
+    }
+
+    /// Recreate a bundle as a new project rooted at `root_path`. Every row gets a
+    /// fresh id and foreign keys are remapped, so importing never clashes with
+    /// existing data.
+    pub fn import_project(&self, bundle: &ProjectBundle, root_path: &str) -> SqliteResult<Project> {
+        let new_id = || uuid::Uuid::new_v4().to_string();
+        let project_id = new_id();
+
+        self.transaction::<_, _, rusqlite::Error>(|tx| {
+            tx.execute(
+                "INSERT INTO projects (id, name, root_path, settings) VALUES (?1, ?2, ?3, ?4)",
+                [
+                    project_id.as_str(),
+                    bundle.project.name.as_str(),
+                    root_path,
+                    bundle.project.settings.as_str(),
+                ],
+            )?;
+
+            let mut ids: HashMap<&str, String> = HashMap::new();
+
+            for board in &bundle.boards {
+                let id = new_id();
+                tx.execute(
+                    "INSERT INTO boards (id, project_id, name, position, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+                    rusqlite::params![id, project_id, board.name, board.position, board.created_at],
+                )?;
+                ids.insert(&board.id, id);
+            }
+
+            for column in &bundle.columns {
+                let id = new_id();
+                tx.execute(
+                    "INSERT INTO columns (id, board_id, name, position, automation_rules, created_at, wip_limit) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    rusqlite::params![
+                        id,
+                        ids.get(column.board_id.as_str()),
+                        column.name,
+                        column.position,
+                        column.automation_rules,
+                        column.created_at,
+                        column.wip_limit
+                    ],
+                )?;
+                ids.insert(&column.id, id);
+            }
+
+            for card in &bundle.cards {
+                let id = new_id();
+                tx.execute(
+                    "INSERT INTO cards (id, column_id, title, description, folder_path, file_paths, agent_config, position, status, created_at, updated_at, metadata, due_at, priority, archived_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+                    rusqlite::params![
+                        id,
+                        ids.get(card.column_id.as_str()),
+                        card.title,
+                        card.description,
+                        card.folder_path,
+                        card.file_paths,
+                        card.agent_config,
+                        card.position,
+                        card.status,
+                        card.created_at,
+                        card.updated_at,
+                        card.metadata,
+                        card.due_at,
+                        card.priority,
+                        card.archived_at
+                    ],
+                )?;
+                ids.insert(&card.id, id);
+            }
+
+            for execution in &bundle.executions {
+                tx.execute(
+                    "INSERT INTO card_executions (id, card_id, started_at, completed_at, status, agent_type, input_context, output_result, error_message, ghost_branch) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                    rusqlite::params![
+                        new_id(),
+                        ids.get(execution.card_id.as_str()),
+                        execution.started_at,
+                        execution.completed_at,
+                        execution.status,
+                        execution.agent_type,
+                        execution.input_context,
+                        execution.output_result,
+                        execution.error_message,
+                        execution.ghost_branch
+                    ],
+                )?;
+            }
+
+            for item in &bundle.checklist_items {
+                tx.execute(
+                    "INSERT INTO card_checklist_items (id, card_id, text, done, position, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    rusqlite::params![
+                        new_id(),
+                        ids.get(item.card_id.as_str()),
+                        item.text,
+                        item.done,
+                        item.position,
+                        item.created_at
+                    ],
+                )?;
+            }
+
+            for comment in &bundle.comments {
+                tx.execute(
+                    "INSERT INTO card_comments (id, card_id, author, body, created_at, edited_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    rusqlite::params![
+                        new_id(),
+                        ids.get(comment.card_id.as_str()),
+                        comment.author,
+                        comment.body,
+                        comment.created_at,
+                        comment.edited_at
+                    ],
+                )?;
+            }
+
+            // An edge to a card outside the project has nothing to point at here
+            for dependency in &bundle.dependencies {
+                let (Some(card_id), Some(depends_on)) = (
+                    ids.get(dependency.card_id.as_str()),
+                    ids.get(dependency.depends_on_card_id.as_str()),
+                ) else {
+                    continue;
+                };
+                tx.execute(
+                    "INSERT INTO card_dependencies (card_id, depends_on_card_id, created_at) VALUES (?1, ?2, ?3)",
+                    rusqlite::params![card_id, depends_on, dependency.created_at],
+                )?;
+            }
+
+            Ok(())
+        })?;
+
+        self.get_project(&project_id)
+    }
+}
+
+// Report operations
+impl Database {
+    /// Snapshot of a project for `export_project_report`. Every list has a
+    /// total order (ties fall back to ids) so two reports of the same data
+    /// render identically. `now` decides what is overdue and `recent_since`
+    /// what counts as recently updated.
+    pub fn get_project_report(
+        &self,
+        project_id: &str,
+        now: i64,
+        recent_since: i64,
+    ) -> SqliteResult<ProjectReport> {
+        let project = self.get_project(project_id)?;
+
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, project_id, name, position, created_at FROM boards WHERE project_id = ?1 ORDER BY position, id"
+            )?;
+            let boards = stmt
+                .query_map([project_id], |row| {
+                    Ok(Board {
+                        id: row.get(0)?,
+                        project_id: row.get(1)?,
+                        name: row.get(2)?,
+                        position: row.get(3)?,
+                        created_at: row.get(4)?,
+                    })
+                })?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            let mut stmt = conn.prepare(&format!(
+                "{} JOIN boards b ON col.board_id = b.id WHERE b.project_id = ?1 ORDER BY col.position, col.id",
+                COLUMN_SELECT
+            ))?;
+            let columns = stmt
+                .query_map([project_id], column_from_row)?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            let mut stmt = conn.prepare(&format!(
+                "{} JOIN columns col ON c.column_id = col.id JOIN boards b ON col.board_id = b.id WHERE b.project_id = ?1 AND c.archived_at IS NULL ORDER BY c.position, c.id",
+                CARD_SELECT
+            ))?;
+            let cards = stmt
+                .query_map([project_id], card_from_row)?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            let mut stmt = conn.prepare(&format!(
+                "{} JOIN columns col ON c.column_id = col.id JOIN boards b ON col.board_id = b.id WHERE b.project_id = ?1 AND c.archived_at IS NULL AND c.due_at < ?2 AND c.status != 'done' ORDER BY c.due_at, c.id",
+                CARD_SELECT
+            ))?;
+            let overdue = stmt
+                .query_map(rusqlite::params![project_id, now], card_from_row)?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            let mut stmt = conn.prepare(&format!(
+                "{} JOIN columns col ON c.column_id = col.id JOIN boards b ON col.board_id = b.id WHERE b.project_id = ?1 AND c.archived_at IS NULL AND c.updated_at >= ?2 ORDER BY c.updated_at DESC, c.id",
+                CARD_SELECT
+            ))?;
+            let recently_updated = stmt
+                .query_map(rusqlite::params![project_id, recent_since], card_from_row)?
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            let board_names: HashMap<&str, &str> = boards
+                .iter()
+                .map(|board| (board.id.as_str(), board.name.as_str()))
+                .collect();
+            let locations: HashMap<String, (String, String, String)> = columns
+                .iter()
+                .filter_map(|column| {
+                    let board_name = board_names.get(column.board_id.as_str())?;
+                    Some((
+                        column.id.clone(),
+                        (
+                            column.name.clone(),
+                            column.board_id.clone(),
+                            board_name.to_string(),
+                        ),
+                    ))
+                })
+                .collect();
+            let locate = |cards: Vec<Card>| -> Vec<RecentCard> {
+                cards
+                    .into_iter()
+                    .filter_map(|card| {
+                        let (column_name, board_id, board_name) =
+                            locations.get(&card.column_id)?.clone();
+                        Some(RecentCard {
+                            card,
+                            board_id,
+                            board_name,
+                            column_name,
+                        })
+                    })
+                    .collect()
+            };
+            let overdue = locate(overdue);
+            let recently_updated = locate(recently_updated);
+
+            let mut cards_by_column: HashMap<String, Vec<Card>> = HashMap::new();
+            for card in cards {
+                cards_by_column
+                    .entry(card.column_id.clone())
+                    .or_default()
+                    .push(card);
+            }
+
+            let mut columns_by_board: HashMap<String, Vec<ReportColumn>> = HashMap::new();
+            for column in columns {
+                let cards = cards_by_column.remove(&column.id).unwrap_or_default();
+                columns_by_board
+                    .entry(column.board_id.clone())
+                    .or_default()
+                    .push(ReportColumn { column, cards });
+            }
+
+            let boards = boards
+                .into_iter()
+                .map(|board| {
+                    let columns = columns_by_board.remove(&board.id).unwrap_or_default();
+                    ReportBoard { board, columns }
+                })
+                .collect();
+
+            Ok(ProjectReport {
+                project,
+                boards,
+                overdue,
+                recently_updated,
+            })
+        })
+    }
+
+    /// Dashboard figures for a project. Weekly buckets start on Monday (UTC)
+    /// and run oldest first, ending with the week containing `now`; quiet
+    /// weeks are present as zeros. A card counts as completed in the week it
+    /// first reached `done`, going by its status history.
+    pub fn get_project_stats(&self, project_id: &str, now: i64) -> SqliteResult<ProjectStats> {
+        let current_week = (now - FIRST_MONDAY).div_euclid(WEEK_SECONDS);
+        let first_week = current_week - (STATS_WEEKS as i64 - 1);
+        let since = FIRST_MONDAY + first_week * WEEK_SECONDS;
+
+        self.with_conn(|conn| {
+            conn.query_row("SELECT 1 FROM projects WHERE id = ?1", [project_id], |_| {
+                Ok(())
+            })?;
+
+            let (boards, columns, cards) = conn.query_row(
+                "SELECT
+                    (SELECT COUNT(*) FROM boards WHERE project_id = ?1),
+                    (SELECT COUNT(*) FROM columns col JOIN boards b ON col.board_id = b.id WHERE b.project_id = ?1),
+                    (SELECT COUNT(*) FROM cards c JOIN columns col ON c.column_id = col.id JOIN boards b ON col.board_id = b.id WHERE b.project_id = ?1 AND c.archived_at IS NULL)",
+                [project_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )?;
+
+            let mut cards_by_status: HashMap<String, i64> = crate::status::CARD_STATUSES
+                .iter()
+                .map(|status| (status.to_string(), 0))
+                .collect();
+            let mut stmt = conn.prepare(
+                "SELECT c.status, COUNT(*) FROM cards c JOIN columns col ON c.column_id = col.id JOIN boards b ON col.board_id = b.id WHERE b.project_id = ?1 AND c.archived_at IS NULL GROUP BY c.status",
+            )?;
+            for row in stmt.query_map([project_id], |row| Ok((row.get(0)?, row.get(1)?)))? {
+                let (status, count): (String, i64) = row?;
+                cards_by_status.insert(status, count);
+            }
+
+            let mut weekly: Vec<WeeklyCardCounts> = (first_week..=current_week)
+                .map(|week| WeeklyCardCounts {
+                    week_start: FIRST_MONDAY + week * WEEK_SECONDS,
+                    created: 0,
+                    completed: 0,
+                })
+                .collect();
+
+            // Archived cards still count towards history
+            let mut stmt = conn.prepare(
+                "SELECT (c.created_at - ?3) / ?4, COUNT(*) FROM cards c JOIN columns col ON c.column_id = col.id JOIN boards b ON col.board_id = b.id WHERE b.project_id = ?1 AND c.created_at >= ?2 GROUP BY 1",
+            )?;
+            let created = stmt.query_map(
+                rusqlite::params![project_id, since, FIRST_MONDAY, WEEK_SECONDS],
+                |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+            )?;
+            for row in created {
+                let (week, count) = row?;
+                if let Some(bucket) = weekly.get_mut((week - first_week) as usize) {
+                    bucket.created = count;
+                }
+            }
+
+            let done_sql = "SELECT a.card_id, MIN(a.changed_at) AS done_at, c.created_at FROM card_audit_log a JOIN cards c ON a.card_id = c.id JOIN columns col ON c.column_id = col.id JOIN boards b ON col.board_id = b.id WHERE b.project_id = ?1 AND a.field = 'status' AND a.new_value = 'done' GROUP BY a.card_id";
+
+            let mut stmt = conn.prepare(&format!(
+                "SELECT (done_at - ?3) / ?4, COUNT(*) FROM ({}) WHERE done_at >= ?2 GROUP BY 1",
+                done_sql
+            ))?;
+            let completed = stmt.query_map(
+                rusqlite::params![project_id, since, FIRST_MONDAY, WEEK_SECONDS],
+                |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+            )?;
+            for row in completed {
+                let (week, count) = row?;
+                if let Some(bucket) = weekly.get_mut((week - first_week) as usize) {
+                    bucket.completed = count;
+                }
+            }
+
+            let avg_seconds_to_done: Option<f64> = conn.query_row(
+                &format!("SELECT AVG(done_at - created_at) FROM ({})", done_sql),
+                [project_id],
+                |row| row.get(0),
+            )?;
+
+            let mut executions = ExecutionCounts::default();
+            let mut stmt = conn.prepare(
+                "SELECT e.status, COUNT(*) FROM card_executions e JOIN cards c ON e.card_id = c.id JOIN columns col ON c.column_id = col.id JOIN boards b ON col.board_id = b.id WHERE b.project_id = ?1 GROUP BY e.status",
+            )?;
+            for row in stmt.query_map([project_id], |row| Ok((row.get(0)?, row.get(1)?)))? {
+                let (status, count): (Option<String>, i64) = row?;
+                match status.as_deref() {
+                    Some("success") => executions.success = count,
+                    Some("failed") => executions.failed = count,
+                    Some("cancelled") => executions.cancelled = count,
+                    _ => executions.running += count,
+                }
+            }
+
+            Ok(ProjectStats {
+                boards,
+                columns,
+                cards,
+                cards_by_status,
+                weekly,
+                avg_seconds_to_done,
+                executions,
+            })
+        })
+    }
+}
+
+// Maintenance operations
+impl Database {
+    /// Result of the integrity check run when the database was opened
+    pub fn startup_health(&self) -> StartupHealth {
+        self.startup_health.clone()
+    }
+
+    /// Copy the whole database into a new file at `dest` with SQLite's online
+    /// backup API. An encrypted database's copy uses the same key.
+    pub fn backup_to(&self, dest: &Path) -> SqliteResult<()> {
+        let key = self.key.lock().clone();
+        let mut target = Self::open_file(dest, key.as_deref())?;
+
+        self.with_conn(|conn| {
+            Backup::new(conn, &mut target)?.run_to_completion(
+                BACKUP_PAGES_PER_STEP,
+                Duration::ZERO,
+                None,
+            )
+        })
+    }
+
+    /// Replace everything in the database with the contents of the backup at
+    /// `src`, then migrate it to the current schema. The backup is checked
+    /// for damage first, and nothing else runs while it is copied in.
+    pub fn restore_from(&self, src: &Path) -> SqliteResult<()> {
+        let key = self.key.lock().clone();
+        let source = Self::open_file(src, key.as_deref())?;
+
+        let problems = integrity_problems(&source)?;
+        if !problems.is_empty() {
+            return Err(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CORRUPT),
+                Some(format!("Backup is damaged: {}", problems.join("; "))),
+            ));
+        }
+
+        let mut conns = self.lock_all();
+        self.ensure_open()?;
+        let target = &mut *conns[0];
+        Backup::new(&source, target)?.run_to_completion(
+            BACKUP_PAGES_PER_STEP,
+            Duration::ZERO,
+            None,
+        )?;
+        Self::run_migrations(target)
+    }
+
+    /// Whether the file on disk is encrypted with SQLCipher
+    pub fn is_encrypted(&self) -> bool {
+        cfg!(feature = "encrypted-db") && self.key.lock().is_some()
+    }
+
+    /// Copy everything into a new file encrypted with `key`, or a plaintext
+    /// one for `None`, swap it in for the current file and reopen every
+    /// connection with the new key. Works on a plaintext database as well as
+    /// on one that is already encrypted. On failure the current file stays in
+    /// use; if it can't even be reopened, the database is closed for good.
+    #[cfg(feature = "encrypted-db")]
+    pub fn reencrypt(&self, key: Option<&str>) -> SqliteResult<()> {
+        // Hold every connection so nothing is written between copy and swap
+        let mut conns = self.lock_all();
+        self.ensure_open()?;
+        let mut current_key = self.key.lock();
+
+        let staging = sibling_path(&self.path, "rekey");
+        let _ = std::fs::remove_file(&staging);
+
+        let staging_name = staging.to_string_lossy();
+        conns[0].execute(
+            "ATTACH DATABASE ?1 AS rekeyed KEY ?2",
+            [staging_name.as_ref(), key.unwrap_or_default()],
+        )?;
+        let exported = conns[0].query_row("SELECT sqlcipher_export('rekeyed')", [], |_| Ok(()));
+        conns[0].execute("DETACH DATABASE rekeyed", [])?;
+        if let Err(e) = exported {
+            let _ = std::fs::remove_file(&staging);
+            return Err(e);
+        }
+
+        // Close the old file everywhere before replacing it
+        let released = conns.iter_mut().try_for_each(|conn| {
+            **conn = Connection::open_in_memory()?;
+            Ok(())
+        });
+        if let Err(e) = released {
+            let _ = std::fs::remove_file(&staging);
+            self.reopen(&mut conns, current_key.as_deref())?;
+            return Err(e);
+        }
+        for suffix in ["wal", "shm"] {
+            let _ = std::fs::remove_file(sibling_path(&self.path, suffix));
+        }
+
+        if let Err(e) = std::fs::rename(&staging, &self.path) {
+            let _ = std::fs::remove_file(&staging);
+            self.reopen(&mut conns, current_key.as_deref())?;
+            return Err(rusqlite::Error::ToSqlConversionFailure(Box::new(e)));
+        }
+
+        *current_key = key.map(str::to_string);
+        self.reopen(&mut conns, key)
+    }
+
+    /// Open the file again on every connection `lock_all` returned. If any
+    /// fails, the database is marked closed.
+    #[cfg(feature = "encrypted-db")]
+    fn reopen(
+        &self,
+        conns: &mut [MutexGuard<'_, Connection>],
+        key: Option<&str>,
+    ) -> SqliteResult<()> {
+        let reopened = conns.iter_mut().enumerate().try_for_each(|(i, conn)| {
+            **conn = if i == 0 {
+                Self::open_connection(&self.path, key)?
+            } else {
+                Self::open_reader(&self.path, key)?
+            };
+            Ok(())
+        });
+        if reopened.is_err() {
+            self.closed.store(true, Ordering::Release);
+        }
+        reopened
+    }
+
+    /// Check the file for corruption and orphaned rows, optionally `VACUUM` it,
+    /// and report its size before and after plus the rows in each table
+    pub fn run_maintenance(&self, vacuum: bool) -> SqliteResult<MaintenanceReport> {
+        self.with_conn_mut(|conn| {
+            let integrity_problems = integrity_problems(conn)?;
+
+            let orphaned_rows = {
+                let mut stmt = conn.prepare("PRAGMA foreign_key_check")?;
+                let rows = stmt
+                    .query_map([], |row| {
+                        Ok(OrphanedRow {
+                            table: row.get(0)?,
+                            rowid: row.get(1)?,
+                            parent: row.get(2)?,
+                        })
+                    })?
+                    .collect::<SqliteResult<Vec<_>>>()?;
+                rows
+            };
+
+            let size_before = database_size(conn)?;
+            if vacuum {
+                conn.execute_batch("VACUUM")?;
+            }
+            let size_after = database_size(conn)?;
+
+            let tables = {
+                let mut stmt = conn.prepare(
+                    "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+                )?;
+                let tables = stmt
+                    .query_map([], |row| row.get::<_, String>(0))?
+                    .collect::<SqliteResult<Vec<_>>>()?;
+                tables
+            };
+
+            let row_counts = tables
+                .into_iter()
+                .map(|table| {
+                    let rows = conn.query_row(
+                        &format!("SELECT COUNT(*) FROM \"{}\"", table),
+                        [],
+                        |row| row.get(0),
+                    )?;
+                    Ok(TableRowCount { table, rows })
+                })
+                .collect::<SqliteResult<Vec<_>>>()?;
+
+            Ok(MaintenanceReport {
+                integrity_ok: integrity_problems.is_empty(),
+                integrity_problems,
+                orphaned_rows,
+                vacuumed: vacuum,
+                size_before,
+                size_after,
+                row_counts,
+            })
+        })
+    }
+}
+
+/// `path` with `suffix` appended to its file name, e.g. `viraith.db-wal`
+#[cfg(feature = "encrypted-db")]
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push("-");
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// `PRAGMA integrity_check` findings; empty when the file is sound
+fn integrity_problems(conn: &Connection) -> SqliteResult<Vec<String>> {
+    let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+    let problems = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .filter(|result| !matches!(result.as_deref(), Ok("ok")))
+        .collect::<SqliteResult<Vec<_>>>()?;
+    Ok(problems)
+}
+
+/// Size of the main database file in bytes, from its page count
+fn database_size(conn: &Connection) -> SqliteResult<u64> {
+    let pages: u64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+    let page_size: u64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+    Ok(pages * page_size)
+}
+
+fn parse_file_paths(raw: &str) -> Vec<String> {
+    serde_json::from_str(raw).unwrap_or_default()
+}
+
+fn write_file_paths(tx: &Transaction, id: &str, file_paths: &[String]) -> SqliteResult<()> {
+    let raw = serde_json::to_string(file_paths)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+
+    tx.execute(
+        "UPDATE cards SET file_paths = ?2, updated_at = strftime('%s', 'now') WHERE id = ?1",
+        [id, raw.as_str()],
+    )?;
+    Ok(())
+}
+
+/// RFC 7396 merge: null removes a key, objects merge recursively, anything else replaces
+fn merge_patch(target: &mut serde_json::Value, patch: &serde_json::Value) {
+    let serde_json::Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+
+    if !target.is_object() {
+        *target = serde_json::json!({});
+    }
+
+    if let serde_json::Value::Object(target) = target {
+        for (key, value) in patch {
+            if value.is_null() {
+                target.remove(key);
+            } else {
+                let entry = target.entry(key.clone()).or_insert(serde_json::Value::Null);
+                merge_patch(entry, value);
+            }
+        }
+    }
+}
+
+/// Board and columns every scaffolded project starts with
+const STARTER_BOARD: &str = "Main";
+const STARTER_COLUMNS: &[&str] = &["Backlog", "In Progress", "Review", "Done"];
+
+/// Card columns in the order `card_from_row` expects, aliased as `c` so callers
+/// can append joins and filters
+const CARD_SELECT: &str = concat!("SELECT ", card_fields!(), " FROM cards c");
+
+/// Every value `cards.priority` can hold, lowest first
+pub const CARD_PRIORITIES: &[&str] = &["low", "normal", "high", "urgent"];
+
+/// ORDER BY clause for `CardSort::Priority`: urgent first, ties broken by position
+const PRIORITY_ORDER: &str = "CASE c.priority WHEN 'urgent' THEN 0 WHEN 'high' THEN 1 WHEN 'normal' THEN 2 ELSE 3 END, c.position";
+
+/// Column fields in the order `column_from_row` expects, aliased as `col`
+const COLUMN_SELECT: &str = "SELECT col.id, col.board_id, col.name, col.position, col.automation_rules, col.created_at, col.wip_limit,
+    (SELECT COUNT(*) FROM cards c WHERE c.column_id = col.id AND c.archived_at IS NULL)
+    FROM columns col";
+
+fn column_from_row(row: &rusqlite::Row) -> SqliteResult<Column> {
+    Ok(Column {
+        id: row.get(0)?,
+        board_id: row.get(1)?,
+        name: row.get(2)?,
+        position: row.get(3)?,
+        automation_rules: row.get::<_, String>(4)?,
+        created_at: row.get(5)?,
+        wip_limit: row.get(6)?,
+        card_count: row.get(7)?,
+    })
+}
+
+fn card_from_row(row: &rusqlite::Row) -> SqliteResult<Card> {
+    Ok(Card {
+        id: row.get(0)?,
+        column_id: row.get(1)?,
+        title: row.get(2)?,
+        description: row.get(3)?,
+        folder_path: row.get(4)?,
+        file_paths: row.get::<_, String>(5)?,
+        agent_config: row.get::<_, String>(6)?,
+        position: row.get(7)?,
+        status: row.get(8)?,
+        created_at: row.get(9)?,
+        updated_at: row.get(10)?,
+        metadata: row.get::<_, String>(11)?,
+        due_at: row.get(12)?,
+        priority: row.get(13)?,
+        archived_at: row.get(14)?,
+        version: row.get(15)?,
+        checklist_progress: ChecklistProgress {
+            done: row.get(16)?,
+            total: row.get(17)?,
+        },
+    })
+}
+
+/// Reposition a card within an open transaction and return the column it came
+/// from. `None` appends it to the end of the destination column; out-of-range
+/// positions are clamped. Moving an archived card restores it.
+fn card_location_in(conn: &Connection, id: &str) -> SqliteResult<Option<CardLocation>> {
+    conn.query_row(
+        "SELECT b.project_id, col.board_id, c.column_id, c.id FROM cards c JOIN columns col ON c.column_id = col.id JOIN boards b ON col.board_id = b.id WHERE c.id = ?1",
+        [id],
+        |row| {
+            Ok(CardLocation {
+                project_id: row.get(0)?,
+                board_id: row.get(1)?,
+                column_id: row.get(2)?,
+                card_id: row.get(3)?,
+            })
+        },
+    )
+    .optional()
+}
+
+fn move_card_in(
+    tx: &Transaction,
+    id: &str,
+    column_id: &str,
+    position: Option<i32>,
+) -> Result<String, CardError> {
+    let source: Option<(String, i32, Option<i64>)> = tx
+        .query_row(
+            "SELECT column_id, position, archived_at FROM cards WHERE id = ?1",
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?;
+    let Some((source_column, source_position, archived_at)) = source else {
+        return Err(CardError::CardNotFound(id.to_string()));
+    };
+
+    if archived_at.is_none() {
+        close_position_gap(tx, &source_column, source_position)?;
+    }
+
+    let len: i32 = tx.query_row(
+        "SELECT COUNT(*) FROM cards WHERE column_id = ?1 AND id != ?2 AND archived_at IS NULL",
+        [column_id, id],
+        |row| row.get(0),
+    )?;
+    let position = position.map_or(len, |p| p.clamp(0, len));
+
+    tx.execute(
+        "UPDATE cards SET position = position + 1 WHERE column_id = ?1 AND id != ?2 AND position >= ?3 AND archived_at IS NULL",
+        rusqlite::params![column_id, id, position],
+    )?;
+
+    tx.execute(
+        "UPDATE cards SET column_id = ?2, position = ?3, archived_at = NULL, updated_at = strftime('%s', 'now') WHERE id = ?1",
+        rusqlite::params![id, column_id, position],
+    )?;
+
+    log_card_change(tx, id, "column_id", Some(&source_column), Some(column_id))?;
+    log_card_change(
+        tx,
+        id,
+        "position",
+        Some(&source_position.to_string()),
+        Some(&position.to_string()),
+    )?;
+    if let Some(archived_at) = archived_at {
+        log_card_change(tx, id, "archived_at", Some(&archived_at.to_string()), None)?;
+    }
+
+    Ok(source_column)
+}
+
+/// Shift live cards after `position` up one slot once a card has left it
+fn close_position_gap(tx: &Transaction, column_id: &str, position: i32) -> SqliteResult<()> {
+    tx.execute(
+        "UPDATE cards SET position = position - 1 WHERE column_id = ?1 AND position > ?2 AND archived_at IS NULL",
+        rusqlite::params![column_id, position],
+    )?;
+    Ok(())
+}
+
+/// Insert a copy of card `source_id` as `new_id` at `column_id`/`position`,
+/// with `title_suffix` appended to its title. The copy starts idle with an
+/// unchecked copy of the checklist; of the metadata only labels carry over, since
+/// the rest (ghost branch, last execution, ...) belongs to the original.
+fn copy_card_in(
+    tx: &Transaction,
+    source_id: &str,
+    new_id: &str,
+    column_id: &str,
+    position: i32,
+    title_suffix: &str,
+) -> SqliteResult<()> {
+    let metadata: String = tx.query_row(
+        "SELECT metadata FROM cards WHERE id = ?1",
+        [source_id],
+        |row| row.get(0),
+    )?;
+    let labels = serde_json::from_str::<serde_json::Value>(&metadata)
+        .ok()
+        .and_then(|m| m.get("labels").cloned());
+    let metadata = match labels {
+        Some(labels) => serde_json::json!({ "labels": labels }),
+        None => serde_json::json!({}),
+    };
+
+    tx.execute(
+        "INSERT INTO cards (id, column_id, title, description, folder_path, file_paths, agent_config, position, status, metadata, due_at, priority)
+         SELECT ?2, ?3, title || ?6, description, folder_path, file_paths, agent_config, ?4, 'idle', ?5, due_at, priority FROM cards WHERE id = ?1",
+        rusqlite::params![source_id, new_id, column_id, position, metadata.to_string(), title_suffix],
+    )?;
+
+    let items = {
+        let mut stmt = tx.prepare(
+            "SELECT text, position FROM card_checklist_items WHERE card_id = ?1 ORDER BY position",
+        )?;
+        let items = stmt
+            .query_map([source_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i32>(1)?))
+            })?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        items
+    };
+
+    for (text, item_position) in items {
+        tx.execute(
+            "INSERT INTO card_checklist_items (id, card_id, text, position) VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![uuid::Uuid::new_v4().to_string(), new_id, text, item_position],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Add `label` to a card's `metadata.labels` within an open transaction.
+/// Returns false if it was already there.
+pub(crate) fn attach_label_in(tx: &Transaction, id: &str, label: &str) -> SqliteResult<bool> {
+    let raw: String = tx.query_row("SELECT metadata FROM cards WHERE id = ?1", [id], |row| {
+        row.get(0)
+    })?;
+
+    let mut metadata = serde_json::from_str::<serde_json::Value>(&raw)
+        .ok()
+        .filter(|v| v.is_object())
+        .unwrap_or_else(|| serde_json::json!({}));
+
+    let labels = metadata
+        .as_object_mut()
+        .expect("metadata is an object")
+        .entry("labels")
+        .or_insert_with(|| serde_json::json!([]));
+    if !labels.is_array() {
+        *labels = serde_json::json!([]);
+    }
+    let labels = labels.as_array_mut().expect("labels is an array");
+
+    if labels.iter().any(|l| l.as_str() == Some(label)) {
+        return Ok(false);
+    }
+    labels.push(serde_json::Value::from(label));
+
+    tx.execute(
+        "UPDATE cards SET metadata = ?2, updated_at = strftime('%s', 'now') WHERE id = ?1",
+        [id, metadata.to_string().as_str()],
+    )?;
+
+    Ok(true)
+}
+
+/// Write a card's editable fields within an open transaction, recording each
+/// change in the audit log. `priority` is left unchanged when `None`. Returns the
+/// fields as they were before and after.
+fn update_card_in(
+    tx: &Transaction,
+    id: &str,
+    title: &str,
+    description: Option<&str>,
+    status: &str,
+    priority: Option<&str>,
+    force: bool,
+) -> Result<(CardFields, CardFields), CardError> {
+    let before = tx
+        .query_row(
+            "SELECT title, description, status, priority FROM cards WHERE id = ?1",
+            [id],
+            |row| {
+                Ok(CardFields {
+                    title: row.get(0)?,
+                    description: row.get(1)?,
+                    status: row.get(2)?,
+                    priority: row.get(3)?,
+                })
+            },
+        )
+        .optional()?
+        .ok_or_else(|| CardError::CardNotFound(id.to_string()))?;
+
+    set_card_status_in(tx, id, status, force)?;
+
+    tx.execute(
+        "UPDATE cards SET title = ?2, description = ?3, priority = COALESCE(?4, priority), updated_at = strftime('%s', 'now') WHERE id = ?1",
+        rusqlite::params![id, title, description, priority],
+    )?;
+
+    log_card_change(tx, id, "title", Some(&before.title), Some(title))?;
+    log_card_change(
+        tx,
+        id,
+        "description",
+        before.description.as_deref(),
+        description,
+    )?;
+    if let Some(priority) = priority {
+        log_card_change(tx, id, "priority", Some(&before.priority), Some(priority))?;
+    }
+
+    let after = CardFields {
+        title: title.to_string(),
+        description: description.map(str::to_string),
+        status: status.to_string(),
+        priority: priority.map_or_else(|| before.priority.clone(), str::to_string),
+    };
+    Ok((before, after))
+}
+
+fn card_place_in(tx: &Transaction, id: &str) -> Result<CardPlace, CardError> {
+    tx.query_row(
+        "SELECT column_id, position FROM cards WHERE id = ?1",
+        [id],
+        |row| {
+            Ok(CardPlace {
+                column_id: row.get(0)?,
+                position: row.get(1)?,
+            })
+        },
+    )
+    .optional()?
+    .ok_or_else(|| CardError::CardNotFound(id.to_string()))
+}
+
+fn column_exists_in(tx: &Transaction, column_id: &str) -> SqliteResult<bool> {
+    Ok(tx
+        .query_row("SELECT 1 FROM columns WHERE id = ?1", [column_id], |_| {
+            Ok(())
+        })
+        .optional()?
+        .is_some())
+}
+
+/// Everything needed to bring a deleted card back: the row itself plus the
+/// checklist and comments that cascade with it. Executions, logs and
+/// dependencies aren't kept.
+fn deleted_card_in(tx: &Transaction, id: &str) -> SqliteResult<Option<DeletedCard>> {
+    let card = tx
+        .query_row(
+            &format!("{} WHERE c.id = ?1", CARD_SELECT),
+            [id],
+            card_from_row,
+        )
+        .optional()?;
+    let Some(card) = card else {
+        return Ok(None);
+    };
+
+    let checklist = {
+        let mut stmt = tx.prepare(
+            "SELECT id, card_id, text, done, position, created_at FROM card_checklist_items WHERE card_id = ?1 ORDER BY position",
+        )?;
+        let items = stmt
+            .query_map([id], checklist_item_from_row)?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        items
+    };
+
+    let comments = {
+        let mut stmt = tx.prepare(
+            "SELECT id, card_id, author, body, created_at, edited_at FROM card_comments WHERE card_id = ?1 ORDER BY created_at, rowid",
+        )?;
+        let comments = stmt
+            .query_map([id], comment_from_row)?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        comments
+    };
+
+    Ok(Some(DeletedCard {
+        card,
+        checklist,
+        comments,
+    }))
+}
+
+/// Re-insert a deleted card with its original id at its original position
+fn restore_card_in(tx: &Transaction, deleted: &DeletedCard) -> Result<(), CardError> {
+    let card = &deleted.card;
+    if !column_exists_in(tx, &card.column_id)? {
+        return Err(CardError::ColumnNotFound(card.column_id.clone()));
+    }
+
+    if card.archived_at.is_none() {
+        check_wip_limit_in(tx, &card.column_id, None)?;
+        tx.execute(
+            "UPDATE cards SET position = position + 1 WHERE column_id = ?1 AND position >= ?2 AND archived_at IS NULL",
+            rusqlite::params![card.column_id, card.position],
+        )?;
+    }
+
+    // One past the deleted copy's version, so an edit based on any copy from
+    // before the delete is still seen as stale
+    tx.execute(
+        "INSERT INTO cards (id, column_id, title, description, folder_path, file_paths, agent_config, position, status, created_at, updated_at, metadata, due_at, priority, archived_at, version) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+        rusqlite::params![
+            card.id,
+            card.column_id,
+            card.title,
+            card.description,
+            card.folder_path,
+            card.file_paths,
+            card.agent_config,
+            card.position,
+            card.status,
+            card.created_at,
+            card.updated_at,
+            card.metadata,
+            card.due_at,
+            card.priority,
+            card.archived_at,
+            card.version + 1
+        ],
+    )?;
+
+    for item in &deleted.checklist {
+        tx.execute(
+            "INSERT INTO card_checklist_items (id, card_id, text, done, position, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![item.id, item.card_id, item.text, item.done, item.position, item.created_at],
+        )?;
+    }
+
+    for comment in &deleted.comments {
+        tx.execute(
+            "INSERT INTO card_comments (id, card_id, author, body, created_at, edited_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![
+                comment.id,
+                comment.card_id,
+                comment.author,
+                comment.body,
+                comment.created_at,
+                comment.edited_at
+            ],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Push `operation` onto the undo stack of the card's project. Anything undone
+/// is dropped, since it can't be redone after a new change, and so are the
+/// oldest entries past `MAX_UNDO_OPERATIONS`.
+fn record_operation_in(
+    tx: &Transaction,
+    card_id: &str,
+    operation: &CardOperation,
+) -> SqliteResult<()> {
+    let project_id: Option<String> = tx
+        .query_row(
+            "SELECT b.project_id FROM cards c JOIN columns col ON c.column_id = col.id JOIN boards b ON col.board_id = b.id WHERE c.id = ?1",
+            [card_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let Some(project_id) = project_id else {
+        return Ok(());
+    };
+    let raw = serde_json::to_string(operation)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+
+    tx.execute(
+        "DELETE FROM card_operations WHERE project_id = ?1 AND undone = 1",
+        [&project_id],
+    )?;
+    tx.execute(
+        "INSERT INTO card_operations (project_id, card_id, operation) VALUES (?1, ?2, ?3)",
+        [project_id.as_str(), card_id, raw.as_str()],
+    )?;
+    tx.execute(
+        "DELETE FROM card_operations WHERE project_id = ?1 AND seq NOT IN (SELECT seq FROM card_operations WHERE project_id = ?1 ORDER BY seq DESC LIMIT ?2)",
+        rusqlite::params![project_id, MAX_UNDO_OPERATIONS],
+    )?;
+    Ok(())
+}
+
+/// Refuse to add a card to a column that's already at its WIP limit. `card_id`
+/// is the card being moved; one already live in the column (a reorder) passes
+/// even when the limit was lowered below the column's current count. Every
+/// path that puts a card in a column checks this: create, move, bulk move,
+/// move to another board, duplicate and undo / redo.
+fn check_wip_limit_in(
+    tx: &Transaction,
+    column_id: &str,
+    card_id: Option<&str>,
+) -> Result<(), CardError> {
+    let (limit, current, already_in): (Option<i64>, i64, bool) = tx
+        .query_row(
+            "SELECT wip_limit,
+                (SELECT COUNT(*) FROM cards WHERE column_id = ?1 AND archived_at IS NULL),
+                EXISTS (SELECT 1 FROM cards WHERE id = ?2 AND column_id = ?1 AND archived_at IS NULL)
+             FROM columns WHERE id = ?1",
+            rusqlite::params![column_id, card_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?
+        .ok_or_else(|| CardError::ColumnNotFound(column_id.to_string()))?;
+
+    match limit {
+        Some(limit) if current >= limit && !already_in => Err(CardError::WipLimitExceeded {
+            column_id: column_id.to_string(),
+            limit,
+            current,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Follow dependency edges depth-first from `from`, returning the chain of card
+/// ids that leads to `to` (both ends included) if there is one
+fn dependency_path_in(tx: &Transaction, from: &str, to: &str) -> SqliteResult<Option<Vec<String>>> {
+    let mut stmt =
+        tx.prepare("SELECT depends_on_card_id FROM card_dependencies WHERE card_id = ?1")?;
+
+    // Each reached card maps to the card it was reached from
+    let mut parents: HashMap<String, String> = HashMap::new();
+    let mut seen = HashSet::from([from.to_string()]);
+    let mut stack = vec![from.to_string()];
+
+    while let Some(id) = stack.pop() {
+        if id == to {
+            let mut path = vec![id];
+            while let Some(parent) = path.last().and_then(|last| parents.get(last)) {
+                path.push(parent.clone());
+            }
+            path.reverse();
+            return Ok(Some(path));
+        }
+
+        let next = stmt
+            .query_map([&id], |row| row.get::<_, String>(0))?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        for dep in next {
+            if seen.insert(dep.clone()) {
+                parents.insert(dep.clone(), id.clone());
+                stack.push(dep);
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Reject a write when the card changed after the caller read it at
+/// `expected_version`. Call it inside the write's transaction: that holds the
+/// write lock, so nothing can land between the check and the update.
+fn check_card_version_in(
+    tx: &Transaction,
+    id: &str,
+    expected_version: Option<i64>,
+) -> Result<(), CardError> {
+    let Some(expected) = expected_version else {
+        return Ok(());
+    };
+
+    let card = tx
+        .query_row(
+            &format!("{} WHERE c.id = ?1", CARD_SELECT),
+            [id],
+            card_from_row,
+        )
+        .optional()?;
+    match card {
+        None => Err(CardError::CardNotFound(id.to_string())),
+        Some(card) if card.version != expected => Err(CardError::Stale(Box::new(card))),
+        Some(_) => Ok(()),
+    }
+}
+
+/// Change a card's status within an open transaction, enforcing the transition
+/// rules in `status` unless `force` is set
+pub(crate) fn set_card_status_in(
+    tx: &Transaction,
+    id: &str,
+    status: &str,
+    force: bool,
+) -> Result<(), CardError> {
+    if !crate::status::is_valid_status(status) {
+        return Err(CardError::InvalidStatus(status.to_string()));
+    }
+
+    let current: Option<String> = tx
+        .query_row("SELECT status FROM cards WHERE id = ?1", [id], |row| {
+            row.get(0)
+        })
+        .optional()?;
+    let Some(current) = current else {
+        return Err(CardError::CardNotFound(id.to_string()));
+    };
+
+    if !force && !crate::status::can_transition(&current, status) {
+        return Err(CardError::InvalidTransition {
+            from: current,
+            to: status.to_string(),
+        });
+    }
+
+    tx.execute(
+        "UPDATE cards SET status = ?2, updated_at = strftime('%s', 'now') WHERE id = ?1",
+        [id, status],
+    )?;
+    log_card_change(tx, id, "status", Some(&current), Some(status))?;
+    Ok(())
+}
+
+/// Record a change to one of a card's fields in `card_audit_log`. No-op
+/// updates, where the value didn't actually change, aren't recorded.
+fn log_card_change(
+    tx: &Transaction,
+    card_id: &str,
+    field: &str,
+    old_value: Option<&str>,
+    new_value: Option<&str>,
+) -> SqliteResult<()> {
+    if old_value == new_value {
+        return Ok(());
+    }
+
+    tx.execute(
+        "INSERT INTO card_audit_log (id, card_id, field, old_value, new_value) VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![
+            uuid::Uuid::new_v4().to_string(),
+            card_id,
+            field,
+            old_value,
+            new_value
+        ],
+    )?;
+    Ok(())
+}
+
+// Undo stack entries
+
+/// Most operations kept on each project's undo stack
+const MAX_UNDO_OPERATIONS: i64 = 100;
+
+/// An undoable change, stored with what's needed to replay it either way. Only
+/// `update_card`, `move_card`, `move_card_to_board` and `delete_card` are
+/// undoable; other changes (status from automation, metadata, bulk edits, ...)
+/// aren't recorded.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum CardOperation {
+    Update {
+        before: CardFields,
+        after: CardFields,
+    },
+    Move {
+        before: CardPlace,
+        after: CardPlace,
+    },
+    Delete {
+        card: Box<DeletedCard>,
+    },
+}
+
+impl CardOperation {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Update { .. } => "update",
+            Self::Move { .. } => "move",
+            Self::Delete { .. } => "delete",
+        }
+    }
+
+    fn undo_in(&self, tx: &Transaction, card_id: &str) -> Result<(), CardError> {
+        match self {
+            Self::Update { before, .. } => before.apply_in(tx, card_id),
+            Self::Move { before, .. } => before.apply_in(tx, card_id),
+            Self::Delete { card } => restore_card_in(tx, card),
+        }
+    }
+
+    fn redo_in(&self, tx: &Transaction, card_id: &str) -> Result<(), CardError> {
+        match self {
+            Self::Update { after, .. } => after.apply_in(tx, card_id),
+            Self::Move { after, .. } => after.apply_in(tx, card_id),
+            Self::Delete { card } => {
+                if tx.execute("DELETE FROM cards WHERE id = ?1", [card_id])? == 0 {
+                    return Err(CardError::CardNotFound(card_id.to_string()));
+                }
+                if card.card.archived_at.is_none() {
+                    close_position_gap(tx, &card.card.column_id, card.card.position)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The fields `update_card` edits
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+struct CardFields {
+    title: String,
+    description: Option<String>,
+    status: String,
+    priority: String,
+}
+
+impl CardFields {
+    fn apply_in(&self, tx: &Transaction, card_id: &str) -> Result<(), CardError> {
+        update_card_in(
+            tx,
+            card_id,
+            &self.title,
+            self.description.as_deref(),
+            &self.status,
+            Some(&self.priority),
+            true,
+        )
+        .map(|_| ())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+struct CardPlace {
+    column_id: String,
+    position: i32,
+}
+
+impl CardPlace {
+    fn apply_in(&self, tx: &Transaction, card_id: &str) -> Result<(), CardError> {
+        if !column_exists_in(tx, &self.column_id)? {
+            return Err(CardError::ColumnNotFound(self.column_id.clone()));
+        }
+        check_wip_limit_in(tx, &self.column_id, Some(card_id))?;
+        move_card_in(tx, card_id, &self.column_id, Some(self.position)).map(|_| ())
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct DeletedCard {
+    card: Card,
+    checklist: Vec<ChecklistItem>,
+    comments: Vec<Comment>,
+}
+
+// Errors
+
+/// Why a card operation was rejected
+#[derive(Debug)]
+pub enum CardError {
+    CardNotFound(String),
+    ColumnNotFound(String),
+    BoardHasNoColumns(String),
+    InvalidStatus(String),
+    InvalidTransition {
+        from: String,
+        to: String,
+    },
+    /// The card changed since the caller loaded it; holds its current state
+    Stale(Box<Card>),
+    SelfDependency(String),
+    /// Adding the dependency would close this loop of card ids
+    DependencyCycle(Vec<String>),
+    /// The column exists but belongs to some other board
+    ColumnNotOnBoard {
+        column_id: String,
+        board_id: String,
+    },
+    /// The board is in a different project from the card
+    OtherProject {
+        card_id: String,
+        board_id: String,
+    },
+    /// The destination column already holds `current` live cards of `limit`
+    WipLimitExceeded {
+        column_id: String,
+        limit: i64,
+        current: i64,
+    },
+    /// A column's cards can't be reassigned to the column being deleted
+    ReassignToSelf(String),
+    /// Metadata patches are merged key by key, so they have to be objects
+    MetadataPatchNotObject,
+    Database(rusqlite::Error),
+}
+
+impl std::fmt::Display for CardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CardNotFound(id) => write!(f, "Card not found: {}", id),
+            Self::ColumnNotFound(id) => write!(f, "Column not found: {}", id),
+            Self::BoardHasNoColumns(id) => write!(f, "Board {} has no columns", id),
+            Self::InvalidStatus(status) => write!(f, "Unknown card status: {}", status),
+            Self::InvalidTransition { from, to } => {
+                write!(f, "Cannot move card from '{}' to '{}'", from, to)
+            }
+            Self::Stale(card) => write!(f, "Card {} was changed by someone else", card.id),
+            Self::SelfDependency(id) => write!(f, "Card {} can't depend on itself", id),
+            Self::DependencyCycle(path) => {
+                write!(f, "Dependency would create a cycle: {}", path.join(" -> "))
+            }
+            Self::ColumnNotOnBoard {
+                column_id,
+                board_id,
+            } => write!(f, "Column {} is not on board {}", column_id, board_id),
+            Self::OtherProject { card_id, board_id } => write!(
+                f,
+                "Card {} can't move to board {} in another project",
+                card_id, board_id
+            ),
+            Self::WipLimitExceeded {
+                column_id,
+                limit,
+                current,
+            } => write!(
+                f,
+                "Column {} is at its WIP limit ({}/{})",
+                column_id, current, limit
+            ),
+            Self::ReassignToSelf(id) => {
+                write!(f, "Cannot reassign the cards of column {} to itself", id)
+            }
+            Self::MetadataPatchNotObject => write!(f, "Metadata patch must be a JSON object"),
+            Self::Database(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for CardError {}
+
+impl From<rusqlite::Error> for CardError {
+    fn from(e: rusqlite::Error) -> Self {
+        Self::Database(e)
+    }
+}
+
+// Data structures
+
+/// Version of the export bundle format, bumped whenever its shape changes
+pub const BUNDLE_VERSION: i64 = 2;
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Project {
+    pub id: String,
+    pub name: String,
+    pub root_path: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub settings: String,
+    /// Read-only: its boards, columns and cards can't be changed
+    #[serde(default)]
+    pub locked: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct StartupHealth {
+    pub ok: bool,
+    pub problems: Vec<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct MaintenanceReport {
+    pub integrity_ok: bool,
+    pub integrity_problems: Vec<String>,
+    pub orphaned_rows: Vec<OrphanedRow>,
+    pub vacuumed: bool,
+    /// Database file size in bytes
+    pub size_before: u64,
+    pub size_after: u64,
+    pub row_counts: Vec<TableRowCount>,
+}
+
+/// A row whose foreign key points at a parent that no longer exists
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct OrphanedRow {
+    pub table: String,
+    /// `None` for tables without a rowid
+    pub rowid: Option<i64>,
+    /// The table the missing parent belongs to
+    pub parent: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TableRowCount {
+    pub table: String,
+    pub rows: i64,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RecentProject {
+    #[serde(flatten)]
+    pub project: Project,
+    pub last_opened_at: Option<i64>,
+    /// Whether `root_path` is still a folder on disk, so moved projects can be flagged
+    pub path_exists: bool,
+}
+
+/// Typed view of `projects.settings`. Keys this version doesn't know about are
+/// kept in `extra` so they survive a read-modify-write round trip.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ProjectSettings {
+    pub default_branch: String,
+    /// File tree ignore patterns, added to the `file_tree.ignore_patterns`
+    /// preference
+    pub ignore_patterns: Vec<String>,
+    /// Use `ignore_patterns` instead of the preference's patterns and the
+    /// hidden-entry rule, so an empty list shows everything
+    pub replace_ignore_patterns: bool,
+    pub agent_defaults: serde_json::Map<String, serde_json::Value>,
+    pub theme: Option<String>,
+    /// Lets column automation rules run shell commands
+    pub allow_automation_commands: bool,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Default for ProjectSettings {
+    fn default() -> Self {
+        Self {
+            default_branch: "main".to_string(),
+            ignore_patterns: Vec::new(),
+            replace_ignore_patterns: false,
+            agent_defaults: serde_json::Map::new(),
+            theme: None,
+            allow_automation_commands: false,
+            extra: serde_json::Map::new(),
+        }
+    }
+}
+
+/// Unsaved editor content for one file
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Draft {
+    pub id: String,
+    pub project_id: String,
+    pub file_path: String,
+    pub content: String,
+    pub saved_at: i64,
+}
+
+/// A draft without its content, for listing
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DraftInfo {
+    pub id: String,
+    pub project_id: String,
+    pub file_path: String,
+    pub saved_at: i64,
+    /// Size of the content in bytes
+    pub size: i64,
+}
+
+fn draft_info_from_row(row: &rusqlite::Row) -> SqliteResult<DraftInfo> {
+    Ok(DraftInfo {
+        id: row.get(0)?,
+        project_id: row.get(1)?,
+        file_path: row.get(2)?,
+        saved_at: row.get(3)?,
+        size: row.get(4)?,
+    })
+}
+
+/// Outer position and inner size of a window, in physical pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct WindowState {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub maximized: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Board {
+    pub id: String,
+    pub project_id: String,
+    pub name: String,
+    pub position: i32,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ScaffoldedBoard {
+    pub board_id: String,
+    /// Starter column ids, in board order
+    pub column_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DuplicatedBoard {
+    pub board: Board,
+    pub column_count: usize,
+    pub card_count: usize,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BoardTemplate {
+    pub id: String,
+    pub name: String,
+    pub snapshot: TemplateSnapshot,
+    pub builtin: bool,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct TemplateSnapshot {
+    pub columns: Vec<TemplateColumn>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TemplateColumn {
+    pub name: String,
+    pub position: i32,
+    pub automation_rules: String,
+    #[serde(default)]
+    pub cards: Vec<TemplateCard>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TemplateCard {
+    pub title: String,
+    pub description: Option<String>,
+    pub position: i32,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Column {
+    pub id: String,
+    pub board_id: String,
     pub name: String,
     pub position: i32,
+    pub automation_rules: String,
+    pub created_at: i64,
+    /// Most live cards the column accepts; `None` is unlimited
+    #[serde(default)]
+    pub wip_limit: Option<i64>,
+    /// Live (unarchived) cards currently in the column
+    #[serde(default)]
+    pub card_count: i64,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Card {
+    pub id: String,
+    pub column_id: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub folder_path: Option<String>,
+    pub file_paths: String,
+    pub agent_config: String,
+    pub position: i32,
+    pub status: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub metadata: String,
+    pub due_at: Option<i64>,
+    #[serde(default = "default_priority")]
+    pub priority: String,
+    pub archived_at: Option<i64>,
+    /// Bumped on every change; pass it back as `expected_version` to have an
+    /// edit rejected when the card changed in the meantime
+    #[serde(default)]
+    pub version: i64,
+    #[serde(default)]
+    pub checklist_progress: ChecklistProgress,
+}
+
+/// A card from `get_cards_for_board`, with its column's name
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BoardCard {
+    #[serde(flatten)]
+    pub card: Card,
+    pub column_name: String,
+}
+
+/// Everything needed to open a card, from its project down
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CardLocation {
+    pub project_id: String,
+    pub board_id: String,
+    pub column_id: String,
+    pub card_id: String,
+}
+
+/// Both directions of a card's dependencies
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CardDependencies {
+    /// Cards this one waits on
+    pub blocked_by: Vec<Card>,
+    /// Cards waiting on this one
+    pub blocks: Vec<Card>,
+}
+
+/// A card from `get_blocked_cards`, with the dependencies still not done
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BlockedCard {
+    #[serde(flatten)]
+    pub card: Card,
+    pub blocked_by: Vec<String>,
+}
+
+/// Entries kept per kind in the recent items list
+const MAX_RECENT_ITEMS: i64 = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecentKind {
+    /// Item is a project id
+    Project,
+    /// Item is a file path
+    File,
+}
+
+impl RecentKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Project => "project",
+            Self::File => "file",
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RecentItem {
+    pub kind: RecentKind,
+    pub item: String,
+    pub opened_at: i64,
+}
+
+/// A card to create from a TODO-style source comment
+#[derive(Debug, Clone)]
+pub struct TodoCard {
+    /// Relative to the project root; with `line`, identifies the comment
+    pub path: String,
+    pub line: usize,
+    pub title: String,
+    pub description: String,
+    /// Attached to the card
+    pub file_path: String,
+}
+
+/// A card from `get_recent_cards`, with where it lives
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RecentCard {
+    #[serde(flatten)]
+    pub card: Card,
+    pub board_id: String,
+    pub board_name: String,
+    pub column_name: String,
+}
+
+fn default_priority() -> String {
+    "normal".to_string()
+}
+
+/// Operation applied by `bulk_update_cards` and the other bulk card methods,
+/// tagged by `type`
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BulkCardOp {
+    MoveToColumn { column_id: String },
+    SetStatus { status: String },
+    Delete,
+    Archive,
+    AssignLabel { label_id: String },
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BulkCardResult {
+    pub id: String,
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+impl BulkCardResult {
+    fn failed(id: &str, error: String) -> Self {
+        Self {
+            id: id.to_string(),
+            ok: false,
+            error: Some(error),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CardSort {
+    #[default]
+    Position,
+    Priority,
+    /// Most recently updated first
+    Updated,
+}
+
+/// Typed view of `cards.agent_config`, the contract handed to the execution agent
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AgentConfig {
+    #[serde(alias = "type", alias = "kind")]
+    pub agent_type: String,
+    /// Program `execute_card` runs, with `args`, in `cwd` (relative to the
+    /// card's folder) or the card's folder itself
+    pub command: Option<String>,
+    pub args: Vec<String>,
+    pub cwd: Option<String>,
+    pub model: Option<String>,
+    pub system_prompt: Option<String>,
+    pub max_tokens: Option<u32>,
+    pub max_iterations: u32,
+    pub allowed_paths: Vec<String>,
+    pub env: HashMap<String, String>,
+}
+
+/// Upper bound on agent iterations per execution
+pub const MAX_AGENT_ITERATIONS: u32 = 100;
+
+impl Default for AgentConfig {
+    fn default() -> Self {
+        Self {
+            agent_type: "general".to_string(),
+            command: None,
+            args: Vec::new(),
+            cwd: None,
+            model: None,
+            system_prompt: None,
+            max_tokens: None,
+            max_iterations: 10,
+            allowed_paths: Vec::new(),
+            env: HashMap::new(),
+        }
+    }
+}
+
+impl AgentConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.agent_type.trim().is_empty() {
+            return Err("agent_type must not be empty".to_string());
+        }
+
+        if self.command.as_deref().is_some_and(|c| c.trim().is_empty()) {
+            return Err("command must not be empty".to_string());
+        }
+
+        if self.max_iterations == 0 || self.max_iterations > MAX_AGENT_ITERATIONS {
+            return Err(format!(
+                "max_iterations must be between 1 and {}",
+                MAX_AGENT_ITERATIONS
+            ));
+        }
+
+        if self.max_tokens == Some(0) {
+            return Err("max_tokens must be greater than 0".to_string());
+        }
+
+        if self.allowed_paths.iter().any(|p| p.trim().is_empty()) {
+            return Err("allowed_paths must not contain empty entries".to_string());
+        }
+
+        if let Some(key) = self
+            .env
+            .keys()
+            .find(|k| k.is_empty() || k.contains('=') || k.contains('\0'))
+        {
+            return Err(format!("Invalid environment variable name: {:?}", key));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct ChecklistProgress {
+    pub done: i64,
+    pub total: i64,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ChecklistItem {
+    pub id: String,
+    pub card_id: String,
+    pub text: String,
+    pub done: bool,
+    pub position: i32,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Comment {
+    pub id: String,
+    pub card_id: String,
+    pub author: String,
+    pub body: String,
+    pub created_at: i64,
+    pub edited_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CardActivity {
+    pub id: String,
+    pub card_id: String,
+    pub kind: String,
+    pub message: String,
     pub created_at: i64,
 }
 
+/// One field change from `card_audit_log`. Values are stored as text; `None`
+/// means the field was empty (e.g. `archived_at` of a live card).
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CardAuditEntry {
+    pub id: String,
+    pub card_id: String,
+    pub field: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub changed_at: i64,
+}
+
+/// What `undo_last` / `redo_last` replayed
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AppliedOperation {
+    /// "update", "move" or "delete"
+    pub kind: String,
+    pub card_id: String,
+    /// Where the card was before and after the replay; `None` while it was
+    /// deleted. For the `card-changed` event, so not sent to the frontend.
+    #[serde(skip)]
+    pub before: Option<CardLocation>,
+    #[serde(skip)]
+    pub after: Option<CardLocation>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AgentLog {
+    /// Insertion order within the log table, the cursor for tailing
+    pub seq: i64,
+    pub id: String,
+    pub execution_id: String,
+    pub timestamp: i64,
+    pub level: String,
+    pub message: String,
+    pub metadata: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FileIndexEntry {
+    pub project_id: String,
+    pub path: String,
+    pub hash: String,
+    pub size: i64,
+    pub mtime: i64,
+    pub indexed_at: i64,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct IndexStatus {
+    pub project_id: String,
+    pub file_count: i64,
+    pub total_size: i64,
+    pub last_indexed_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BoardData {
+    pub board: Board,
+    pub columns: Vec<Column>,
+    pub cards_by_column: HashMap<String, Vec<Card>>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ColumnCardCount {
+    pub column_id: String,
+    pub total: i64,
+    pub by_status: HashMap<String, i64>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CardExecution {
+    pub id: String,
+    pub card_id: String,
+    pub started_at: i64,
+    pub completed_at: Option<i64>,
+    pub status: String,
+    pub agent_type: String,
+    pub input_context: String,
+    pub output_result: Option<String>,
+    pub error_message: Option<String>,
+    pub ghost_branch: Option<String>,
+}
+
+/// Weeks of history in `get_project_stats`
+pub const STATS_WEEKS: usize = 12;
+
+const WEEK_SECONDS: i64 = 7 * 24 * 60 * 60;
+
+/// 1970-01-05, the first Monday of the Unix epoch; weekly buckets align to it
+const FIRST_MONDAY: i64 = 4 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ProjectStats {
+    pub boards: i64,
+    pub columns: i64,
+    /// Live cards; archived ones only show up in `weekly`
+    pub cards: i64,
+    /// Every status is present, zero when no card has it
+    pub cards_by_status: HashMap<String, i64>,
+    /// Oldest week first
+    pub weekly: Vec<WeeklyCardCounts>,
+    /// From creation to first reaching `done`; `None` until a card has
+    pub avg_seconds_to_done: Option<f64>,
+    pub executions: ExecutionCounts,
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct Column {
-    pub id: String,
-    pub board_id: String,
-    pub name: String,
-    pub position: i32,
-    pub automation_rules: String,
-    pub created_at: i64,
+pub struct WeeklyCardCounts {
+    /// Monday 00:00 UTC, as a Unix timestamp
+    pub week_start: i64,
+    pub created: i64,
+    pub completed: i64,
 }
 
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct ExecutionCounts {
+    pub success: i64,
+    pub failed: i64,
+    pub cancelled: i64,
+    pub running: i64,
+}
+
+/// A project's boards, columns and live cards for a status report
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct Card {
-    pub id: String,
-    pub column_id: String,
-    pub title: String,
-    pub description: Option<String>,
-    pub folder_path: Option<String>,
-    pub file_paths: String,
-    pub agent_config: String,
-    pub position: i32,
-    pub status: String,
+pub struct ProjectReport {
+    pub project: Project,
+    pub boards: Vec<ReportBoard>,
+    /// Past due and not done, most overdue first
+    pub overdue: Vec<RecentCard>,
+    /// Newest first
+    pub recently_updated: Vec<RecentCard>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ReportBoard {
+    pub board: Board,
+    pub columns: Vec<ReportColumn>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ReportColumn {
+    pub column: Column,
+    pub cards: Vec<Card>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ProjectBundle {
+    pub schema_version: i64,
+    pub exported_at: i64,
+    pub project: Project,
+    pub boards: Vec<Board>,
+    pub columns: Vec<Column>,
+    pub cards: Vec<Card>,
+    pub executions: Vec<CardExecution>,
+    /// Added in version 2; older bundles import without them
+    #[serde(default)]
+    pub checklist_items: Vec<ChecklistItem>,
+    #[serde(default)]
+    pub comments: Vec<Comment>,
+    #[serde(default)]
+    pub dependencies: Vec<BundleDependency>,
+}
+
+/// A dependency edge as stored in a bundle
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BundleDependency {
+    pub card_id: String,
+    pub depends_on_card_id: String,
     pub created_at: i64,
-    pub updated_at: i64,
-    pub metadata: String,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct CardFilter {
+    pub statuses: Vec<String>,
+    pub column_ids: Vec<String>,
+    pub title_contains: Option<String>,
+    pub updated_after: Option<i64>,
+}
+
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::*;
+
+    /// A private in-memory database; the shared cache lets the writer and
+    /// the readers see the same data
+    pub fn memory_db() -> Database {
+        let uri = format!(
+            "file:viraith-{}?mode=memory&cache=shared",
+            uuid::Uuid::new_v4()
+        );
+        Database::new(Path::new(&uri), None).expect("open in-memory database")
+    }
+
+    pub struct Fixture {
+        pub project_id: String,
+        pub board_id: String,
+        pub column_id: String,
+    }
+
+    /// A project rooted at `root` with one board holding one column
+    pub fn project_with_column(db: &Database, root: &str) -> Fixture {
+        let fixture = Fixture {
+            project_id: uuid::Uuid::new_v4().to_string(),
+            board_id: uuid::Uuid::new_v4().to_string(),
+            column_id: uuid::Uuid::new_v4().to_string(),
+        };
+        db.create_project(&fixture.project_id, "Project", root)
+            .unwrap();
+        db.create_board(&fixture.board_id, &fixture.project_id, "Board", 0)
+            .unwrap();
+        db.create_column(&fixture.column_id, &fixture.board_id, "Todo", 0)
+            .unwrap();
+        fixture
+    }
+
+    /// A card at the end of `column_id`, ignoring its WIP limit
+    pub fn add_card(db: &Database, column_id: &str, title: &str) -> String {
+        let id = uuid::Uuid::new_v4().to_string();
+        let position = db.get_cards(column_id, CardSort::Position).unwrap().len() as i32;
+        db.create_card(&id, column_id, title, None, position, None, true)
+            .unwrap();
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::*;
+    use super::*;
+
+    #[test]
+    fn window_state_round_trips_and_survives_maximizing() {
+        let db = memory_db();
+        assert_eq!(db.get_window_state("main").unwrap(), None);
+
+        let normal = WindowState {
+            x: -1200,
+            y: 40,
+            width: 1440,
+            height: 900,
+            maximized: false,
+        };
+        db.save_window_state("main", &normal).unwrap();
+        assert_eq!(db.get_window_state("main").unwrap(), Some(normal));
+
+        let json = serde_json::to_value(normal).unwrap();
+        assert_eq!(serde_json::from_value::<WindowState>(json).unwrap(), normal);
+
+        // A maximized save keeps the geometry to restore to
+        let maximized = WindowState {
+            x: 0,
+            y: 0,
+            width: 2560,
+            height: 1440,
+            maximized: true,
+        };
+        db.save_window_state("main", &maximized).unwrap();
+        assert_eq!(
+            db.get_window_state("main").unwrap(),
+            Some(WindowState {
+                maximized: true,
+                ..normal
+            })
+        );
+        assert_eq!(db.get_window_state("board-other").unwrap(), None);
+    }
+
+    #[test]
+    fn every_way_into_a_full_column_respects_its_wip_limit() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/wip");
+        let full = uuid::Uuid::new_v4().to_string();
+        db.create_column(&full, &fixture.board_id, "Doing", 1)
+            .unwrap();
+        let inside = add_card(&db, &full, "Inside");
+        db.set_wip_limit(&full, Some(1)).unwrap();
+
+        let other_board = uuid::Uuid::new_v4().to_string();
+        db.create_board(&other_board, &fixture.project_id, "Other", 1)
+            .unwrap();
+        let other_full = uuid::Uuid::new_v4().to_string();
+        db.create_column(&other_full, &other_board, "Doing", 0)
+            .unwrap();
+        add_card(&db, &other_full, "Occupant");
+        db.set_wip_limit(&other_full, Some(1)).unwrap();
+
+        let waiting = add_card(&db, &fixture.column_id, "Waiting");
+        let over = |e: CardError| matches!(e, CardError::WipLimitExceeded { .. });
+
+        let bulk = db.bulk_move_cards(std::slice::from_ref(&waiting), &full);
+        assert!(over(bulk.unwrap_err()));
+        let results = db
+            .bulk_update_cards(
+                std::slice::from_ref(&waiting),
+                &BulkCardOp::MoveToColumn {
+                    column_id: full.clone(),
+                },
+                false,
+            )
+            .unwrap();
+        assert!(!results[0].ok);
+        let to_board = db.move_card_to_board(&waiting, &other_board, Some(&other_full), true);
+        assert!(over(to_board.unwrap_err()));
+        assert!(over(db.duplicate_card(&inside, None).unwrap_err()));
+        assert_eq!(
+            db.get_card(&waiting).unwrap().unwrap().column_id,
+            fixture.column_id
+        );
+        assert_eq!(db.get_cards(&full, CardSort::Position).unwrap().len(), 1);
+
+        // The slot a deleted card left was taken before the undo
+        db.delete_card(&inside).unwrap();
+        add_card(&db, &full, "Newcomer");
+        assert!(over(db.undo_last(&fixture.project_id).unwrap_err()));
+        assert!(db.get_card(&inside).unwrap().is_none());
+    }
+
+    #[test]
+    fn undo_and_redo_replay_a_move() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/undo-move");
+        let done = uuid::Uuid::new_v4().to_string();
+        db.create_column(&done, &fixture.board_id, "Done", 1)
+            .unwrap();
+        let first = add_card(&db, &fixture.column_id, "First");
+        let moved = add_card(&db, &fixture.column_id, "Moved");
+        let place = |id: &str| {
+            let card = db.get_card(id).unwrap().unwrap();
+            (card.column_id, card.position)
+        };
+
+        db.move_card_and(&moved, &done, 0, false, |_| ()).unwrap();
+        assert_eq!(place(&moved), (done.clone(), 0));
+
+        let undone = db.undo_last(&fixture.project_id).unwrap().unwrap();
+        assert_eq!(
+            (undone.kind.as_str(), undone.card_id.as_str()),
+            ("move", moved.as_str())
+        );
+        assert_eq!(place(&moved), (fixture.column_id.clone(), 1));
+        assert_eq!(place(&first), (fixture.column_id.clone(), 0));
+
+        db.redo_last(&fixture.project_id).unwrap().unwrap();
+        assert_eq!(place(&moved), (done.clone(), 0));
+        assert!(db.redo_last(&fixture.project_id).unwrap().is_none());
+    }
+
+    #[test]
+    fn undo_and_redo_replay_an_update() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/undo-update");
+        let card_id = add_card(&db, &fixture.column_id, "Before");
+        db.update_card(
+            &card_id,
+            "After",
+            Some("Details"),
+            "idle",
+            Some("high"),
+            false,
+            None,
+        )
+        .unwrap();
+
+        let undone = db.undo_last(&fixture.project_id).unwrap().unwrap();
+        assert_eq!(undone.kind, "update");
+        let card = db.get_card(&card_id).unwrap().unwrap();
+        assert_eq!(
+            (
+                card.title.as_str(),
+                card.description,
+                card.priority.as_str()
+            ),
+            ("Before", None, "normal")
+        );
+
+        db.redo_last(&fixture.project_id).unwrap().unwrap();
+        let card = db.get_card(&card_id).unwrap().unwrap();
+        assert_eq!(
+            (
+                card.title.as_str(),
+                card.description.as_deref(),
+                card.priority.as_str()
+            ),
+            ("After", Some("Details"), "high")
+        );
+        // Undoing again goes back to where it was before the update
+        db.undo_last(&fixture.project_id).unwrap().unwrap();
+        assert!(db.undo_last(&fixture.project_id).unwrap().is_none());
+    }
+
+    #[test]
+    fn undo_brings_a_card_back_from_another_board() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/undo-board-move");
+        let card_id = add_card(&db, &fixture.column_id, "Traveller");
+        let other_board = uuid::Uuid::new_v4().to_string();
+        db.create_board(&other_board, &fixture.project_id, "Other", 1)
+            .unwrap();
+        let columns = db
+            .create_columns(&other_board, &["Inbox"].map(String::from))
+            .unwrap();
+
+        db.move_card_to_board(&card_id, &other_board, None, true)
+            .unwrap();
+        assert_eq!(
+            db.get_card(&card_id).unwrap().unwrap().column_id,
+            columns[0].id
+        );
+
+        let undone = db.undo_last(&fixture.project_id).unwrap().unwrap();
+        assert_eq!(undone.kind, "move");
+        assert_eq!(
+            db.get_card(&card_id).unwrap().unwrap().column_id,
+            fixture.column_id
+        );
+    }
+
+    #[test]
+    fn bulk_move_appends_to_an_empty_column_in_selection_order() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/bulk");
+        let ids: Vec<String> = ["One", "Two", "Three"]
+            .into_iter()
+            .map(|title| add_card(&db, &fixture.column_id, title))
+            .collect();
+        let empty = uuid::Uuid::new_v4().to_string();
+        db.create_column(&empty, &fixture.board_id, "Done", 1)
+            .unwrap();
+
+        let selection = vec![ids[2].clone(), ids[0].clone(), ids[1].clone()];
+        assert_eq!(db.bulk_move_cards(&selection, &empty).unwrap(), 3);
+
+        let moved: Vec<(String, i32)> = db
+            .get_cards(&empty, CardSort::Position)
+            .unwrap()
+            .into_iter()
+            .map(|card| (card.id, card.position))
+            .collect();
+        assert_eq!(
+            moved,
+            vec![
+                (ids[2].clone(), 0),
+                (ids[0].clone(), 1),
+                (ids[1].clone(), 2)
+            ]
+        );
+        assert!(db
+            .get_cards(&fixture.column_id, CardSort::Position)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn logs_page_by_seq_within_the_same_second() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/logs");
+        let card_id = add_card(&db, &fixture.column_id, "Logged");
+        let execution = db.start_execution(&card_id, "claude", "{}").unwrap();
+        let other = db
+            .start_execution(&add_card(&db, &fixture.column_id, "Other"), "claude", "{}")
+            .unwrap();
+
+        for n in 0..5 {
+            db.append_log(&execution.id, "info", &format!("line {}", n), None)
+                .unwrap();
+            db.append_log(&other.id, "info", "elsewhere", None).unwrap();
+        }
+
+        let first = db.get_logs(&execution.id, None, None, Some(2)).unwrap();
+        let rest = db
+            .get_logs(&execution.id, Some(first[1].seq), None, None)
+            .unwrap();
+        let messages: Vec<&str> = first
+            .iter()
+            .chain(&rest)
+            .map(|log| log.message.as_str())
+            .collect();
+        assert_eq!(messages, ["line 0", "line 1", "line 2", "line 3", "line 4"]);
+    }
+
+    #[test]
+    fn log_cursors_survive_a_vacuum() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/vacuumed-logs");
+        let card_id = add_card(&db, &fixture.column_id, "Logged");
+        let execution = db.start_execution(&card_id, "claude", "{}").unwrap();
+        let logs: Vec<AgentLog> = (0..4)
+            .map(|n| {
+                db.append_log(&execution.id, "info", &format!("line {}", n), None)
+                    .unwrap()
+            })
+            .collect();
+        db.with_conn_mut(|conn| {
+            conn.execute(
+                "DELETE FROM agent_logs WHERE id IN (?1, ?2)",
+                rusqlite::params![logs[0].id, logs[1].id],
+            )
+        })
+        .unwrap();
+
+        db.run_maintenance(true).unwrap();
+
+        let rest = db
+            .get_logs(&execution.id, Some(logs[2].seq), None, None)
+            .unwrap();
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest[0].message, "line 3");
+        assert_eq!(rest[0].seq, logs[3].seq);
+    }
+
+    #[test]
+    fn export_import_round_trips_checklists_comments_and_dependencies() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/exported");
+        let first = add_card(&db, &fixture.column_id, "First");
+        let second = add_card(&db, &fixture.column_id, "Second");
+        db.add_checklist_item(&first, "Write it").unwrap();
+        db.add_comment(&second, "sam", "Looks good").unwrap();
+        db.add_dependency(&second, &first).unwrap();
+
+        let json = serde_json::to_string(&db.export_project(&fixture.project_id).unwrap()).unwrap();
+        let bundle: ProjectBundle = serde_json::from_str(&json).unwrap();
+        assert_eq!(bundle.schema_version, BUNDLE_VERSION);
+        let imported = db.import_project(&bundle, "/imported").unwrap();
+
+        let copy = db.export_project(&imported.id).unwrap();
+        let title_of = |id: &str| {
+            copy.cards
+                .iter()
+                .find(|card| card.id == id)
+                .map(|card| card.title.clone())
+                .unwrap()
+        };
+        assert_eq!(copy.cards.len(), 2);
+        assert!(copy
+            .cards
+            .iter()
+            .all(|card| card.id != first && card.id != second));
+
+        assert_eq!(copy.checklist_items.len(), 1);
+        assert_eq!(copy.checklist_items[0].text, "Write it");
+        assert_eq!(title_of(&copy.checklist_items[0].card_id), "First");
+
+        assert_eq!(copy.comments.len(), 1);
+        assert_eq!(copy.comments[0].body, "Looks good");
+        assert_eq!(title_of(&copy.comments[0].card_id), "Second");
+
+        assert_eq!(copy.dependencies.len(), 1);
+        assert_eq!(title_of(&copy.dependencies[0].card_id), "Second");
+        assert_eq!(title_of(&copy.dependencies[0].depends_on_card_id), "First");
+
+        // A version 1 bundle has none of the three lists
+        let mut old: serde_json::Value = serde_json::from_str(&json).unwrap();
+        for key in ["checklist_items", "comments", "dependencies"] {
+            old.as_object_mut().unwrap().remove(key);
+        }
+        let old: ProjectBundle = serde_json::from_value(old).unwrap();
+        assert!(old.checklist_items.is_empty() && old.dependencies.is_empty());
+        db.import_project(&old, "/imported-v1").unwrap();
+    }
+
+    #[test]
+    fn window_states_of_deleted_boards_are_dropped() {
+        let db = memory_db();
+        let kept = project_with_column(&db, "/tmp/kept");
+        let gone = project_with_column(&db, "/tmp/gone");
+        let state = WindowState {
+            x: 0,
+            y: 0,
+            width: 800,
+            height: 600,
+            maximized: false,
+        };
+        for label in [
+            "main".to_string(),
+            format!("board-{}", kept.board_id),
+            format!("board-{}", gone.board_id),
+            "board-orphan".to_string(),
+        ] {
+            db.save_window_state(&label, &state).unwrap();
+        }
+
+        db.delete_project(&gone.project_id).unwrap();
+        assert_eq!(
+            db.get_window_state(&format!("board-{}", gone.board_id))
+                .unwrap(),
+            None
+        );
+
+        assert_eq!(db.prune_window_states().unwrap(), 1);
+        assert_eq!(db.get_window_state("board-orphan").unwrap(), None);
+        assert!(db.get_window_state("main").unwrap().is_some());
+        assert!(db
+            .get_window_state(&format!("board-{}", kept.board_id))
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn recent_projects_come_from_last_opened_at() {
+        let db = memory_db();
+        let first = project_with_column(&db, "/tmp/first");
+        let second = project_with_column(&db, "/tmp/second");
+        let never = project_with_column(&db, "/tmp/never");
+
+        db.touch_project(&first.project_id).unwrap();
+        db.record_recent(RecentKind::Project, &second.project_id)
+            .unwrap();
+        db.with_conn_mut(|conn| {
+            conn.execute(
+                "UPDATE projects SET last_opened_at = last_opened_at - 10 WHERE id = ?1",
+                [&first.project_id],
+            )
+        })
+        .unwrap();
+
+        let recent: Vec<String> = db
+            .get_recent(RecentKind::Project, 10)
+            .unwrap()
+            .into_iter()
+            .map(|item| item.item)
+            .collect();
+        assert_eq!(
+            recent,
+            [second.project_id.clone(), first.project_id.clone()]
+        );
+
+        let projects: Vec<String> = db
+            .get_recent_projects(10)
+            .unwrap()
+            .into_iter()
+            .map(|recent| recent.project.id)
+            .collect();
+        assert_eq!(
+            projects,
+            [
+                second.project_id.clone(),
+                first.project_id,
+                never.project_id
+            ]
+        );
+
+        db.delete_project(&second.project_id).unwrap();
+        assert_eq!(db.get_recent(RecentKind::Project, 10).unwrap().len(), 1);
+        assert!(db
+            .record_recent(RecentKind::Project, &second.project_id)
+            .is_err());
+    }
+
+    #[test]
+    fn recent_files_are_deduplicated_newest_first_and_capped() {
+        let db = memory_db();
+        for file in ["/a.rs", "/b.rs", "/a.rs", "/c.rs"] {
+            db.record_recent(RecentKind::File, file).unwrap();
+        }
+        let recent: Vec<String> = db
+            .get_recent(RecentKind::File, 10)
+            .unwrap()
+            .into_iter()
+            .map(|item| item.item)
+            .collect();
+        assert_eq!(recent, ["/c.rs", "/a.rs", "/b.rs"]);
+
+        for i in 0..MAX_RECENT_ITEMS + 5 {
+            db.record_recent(RecentKind::File, &format!("/many/{}.rs", i))
+                .unwrap();
+        }
+        let recent = db.get_recent(RecentKind::File, 1000).unwrap();
+        assert_eq!(recent.len() as i64, MAX_RECENT_ITEMS);
+        assert_eq!(recent[0].item, format!("/many/{}.rs", MAX_RECENT_ITEMS + 4));
+    }
+
+    #[test]
+    fn card_counts_include_empty_columns_and_split_by_status() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/tmp/counts");
+        db.create_column("empty", &fixture.board_id, "Empty", 1)
+            .unwrap();
+        for status in ["idle", "done", "done", "error"] {
+            let id = add_card(&db, &fixture.column_id, status);
+            db.update_card_status(&id, status, true).unwrap();
+        }
+
+        let counts = db.get_card_counts(&fixture.board_id).unwrap();
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts[0].column_id, fixture.column_id);
+        assert_eq!(counts[0].total, 4);
+        assert_eq!(
+            counts[0].by_status,
+            HashMap::from([
+                ("idle".to_string(), 1),
+                ("done".to_string(), 2),
+                ("error".to_string(), 1),
+            ])
+        );
+        assert_eq!(counts[1].column_id, "empty");
+        assert_eq!(counts[1].total, 0);
+        assert!(counts[1].by_status.is_empty());
+    }
+
+    #[test]
+    fn card_filters_combine_and_treat_text_literally() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/tmp/filters");
+        db.create_column("other", &fixture.board_id, "Other", 1)
+            .unwrap();
+        let failing = add_card(&db, &fixture.column_id, "Build fails");
+        db.update_card_status(&failing, "error", true).unwrap();
+        let quoted = add_card(
+            &db,
+            &fixture.column_id,
+            "Fix 'quotes'; DROP TABLE cards; --",
+        );
+        db.update_card_status(&quoted, "error", true).unwrap();
+        add_card(&db, &fixture.column_id, "Build docs");
+        let elsewhere = add_card(&db, "other", "Build release");
+        db.update_card_status(&elsewhere, "error", true).unwrap();
+
+        let ids = |filter: CardFilter| -> Vec<String> {
+            let mut ids: Vec<String> = db
+                .query_cards(&fixture.board_id, &filter)
+                .unwrap()
+                .into_values()
+                .flatten()
+                .map(|card| card.id)
+                .collect();
+            ids.sort();
+            ids
+        };
+        let sorted = |mut ids: Vec<String>| {
+            ids.sort();
+            ids
+        };
+
+        let errors_about_builds = CardFilter {
+            statuses: vec!["error".to_string()],
+            title_contains: Some("BUILD".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            ids(errors_about_builds.clone()),
+            sorted(vec![failing.clone(), elsewhere])
+        );
+        assert_eq!(
+            ids(CardFilter {
+                column_ids: vec![fixture.column_id.clone()],
+                ..errors_about_builds
+            }),
+            [failing]
+        );
+
+        // LIKE wildcards match nothing and the injection is just text
+        for (literal, expected) in [
+            ("'; DROP TABLE cards; --", vec![quoted]),
+            ("%", vec![]),
+            ("_", vec![]),
+        ] {
+            let found = ids(CardFilter {
+                title_contains: Some(literal.to_string()),
+                ..Default::default()
+            });
+            assert_eq!(found, expected, "{}", literal);
+        }
+        assert_eq!(
+            db.get_cards(&fixture.column_id, CardSort::Position)
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn checklist_progress_rides_along_with_the_card() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/tmp/checklist");
+        let card_id = add_card(&db, &fixture.column_id, "Checklist");
+        let items: Vec<ChecklistItem> = ["Write", "Test", "Ship"]
+            .iter()
+            .map(|text| db.add_checklist_item(&card_id, text).unwrap())
+            .collect();
+        assert_eq!(
+            items.iter().map(|item| item.position).collect::<Vec<_>>(),
+            [0, 1, 2]
+        );
+
+        assert!(db.toggle_checklist_item(&items[1].id).unwrap().done);
+
+        let progress = db.get_card(&card_id).unwrap().unwrap().checklist_progress;
+        assert_eq!((progress.done, progress.total), (1, 3));
+        let listed = db
+            .get_cards(&fixture.column_id, CardSort::Position)
+            .unwrap();
+        let progress = &listed[0].checklist_progress;
+        assert_eq!((progress.done, progress.total), (1, 3));
+
+        db.delete_card(&card_id).unwrap();
+        assert!(db.get_checklist(&card_id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn only_past_due_unfinished_cards_are_overdue() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/tmp/due");
+        let now = chrono::Utc::now().timestamp();
+        let late = add_card(&db, &fixture.column_id, "Late");
+        db.set_card_due(&late, Some(now - 3600)).unwrap();
+        let upcoming = add_card(&db, &fixture.column_id, "Upcoming");
+        db.set_card_due(&upcoming, Some(now + 3600)).unwrap();
+        let finished = add_card(&db, &fixture.column_id, "Finished late");
+        db.set_card_due(&finished, Some(now - 7200)).unwrap();
+        db.update_card_status(&finished, "done", true).unwrap();
+        add_card(&db, &fixture.column_id, "No due date");
+
+        let overdue = db.get_overdue_cards(&fixture.project_id).unwrap();
+        assert_eq!(
+            overdue.iter().map(|card| &card.id).collect::<Vec<_>>(),
+            [&late]
+        );
+        assert_eq!(overdue[0].due_at, Some(now - 3600));
+
+        db.set_card_due(&late, None).unwrap();
+        assert!(db
+            .get_overdue_cards(&fixture.project_id)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn cards_sort_by_position_or_priority() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/tmp/priority");
+        for (position, (title, priority)) in [
+            ("a", Some("low")),
+            ("b", Some("urgent")),
+            ("c", None),
+            ("d", Some("high")),
+            ("e", Some("urgent")),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            db.create_card(
+                title,
+                &fixture.column_id,
+                title,
+                None,
+                position as i32,
+                priority,
+                false,
+            )
+            .unwrap();
+        }
+
+        let titles = |sort| -> Vec<String> {
+            db.get_cards(&fixture.column_id, sort)
+                .unwrap()
+                .into_iter()
+                .map(|card| card.title)
+                .collect()
+        };
+        assert_eq!(titles(CardSort::Position), ["a", "b", "c", "d", "e"]);
+        // Ties keep their position order
+        assert_eq!(titles(CardSort::Priority), ["b", "e", "d", "c", "a"]);
+
+        assert_eq!(db.get_card("c").unwrap().unwrap().priority, "normal");
+        let urgent: Vec<String> = db
+            .get_cards_by_priority(&fixture.project_id, "urgent")
+            .unwrap()
+            .into_iter()
+            .map(|card| card.id)
+            .collect();
+        assert_eq!(urgent, ["b", "e"]);
+    }
+
+    #[test]
+    fn a_duplicated_card_gets_its_own_unchecked_checklist() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/tmp/duplicate-card");
+        let original = add_card(&db, &fixture.column_id, "Ship it");
+        let after = add_card(&db, &fixture.column_id, "After");
+        let first = db.add_checklist_item(&original, "Write code").unwrap();
+        db.add_checklist_item(&original, "Write tests").unwrap();
+        db.toggle_checklist_item(&first.id).unwrap();
+
+        let copy = db.duplicate_card(&original, None).unwrap();
+        assert_eq!(copy.title, "Ship it (copy)");
+        let order: Vec<String> = db
+            .get_cards(&fixture.column_id, CardSort::Position)
+            .unwrap()
+            .into_iter()
+            .map(|card| card.id)
+            .collect();
+        assert_eq!(order, [original.clone(), copy.id.clone(), after]);
+
+        let copied = db.get_checklist(&copy.id).unwrap();
+        let texts: Vec<&str> = copied.iter().map(|item| item.text.as_str()).collect();
+        assert_eq!(texts, ["Write code", "Write tests"]);
+        assert!(copied.iter().all(|item| !item.done && item.id != first.id));
+
+        db.toggle_checklist_item(&copied[1].id).unwrap();
+        assert!(!db.get_checklist(&original).unwrap()[1].done);
+
+        db.delete_card(&original).unwrap();
+        assert_eq!(db.get_checklist(&copy.id).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn a_duplicated_board_copies_columns_and_live_cards_in_order() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/tmp/duplicate-board");
+        let doing = uuid::Uuid::new_v4().to_string();
+        db.create_column(&doing, &fixture.board_id, "Doing", 1)
+            .unwrap();
+        db.set_wip_limit(&doing, Some(3)).unwrap();
+
+        add_card(&db, &fixture.column_id, "First");
+        let archived = add_card(&db, &fixture.column_id, "Archived");
+        add_card(&db, &fixture.column_id, "Second");
+        add_card(&db, &doing, "Third");
+        db.bulk_update_cards(&[archived], &BulkCardOp::Archive, true)
+            .unwrap();
+
+        let copy = db
+            .duplicate_board(&fixture.board_id, "Board copy", true)
+            .unwrap();
+        assert_eq!((copy.column_count, copy.card_count), (2, 3));
+        assert_eq!(copy.board.project_id, fixture.project_id);
+        assert_eq!(copy.board.name, "Board copy");
+        assert_eq!(db.get_boards(&fixture.project_id).unwrap().len(), 2);
+
+        let columns = db.get_columns(&copy.board.id).unwrap();
+        let shape: Vec<(&str, i32, Option<i64>)> = columns
+            .iter()
+            .map(|column| (column.name.as_str(), column.position, column.wip_limit))
+            .collect();
+        assert_eq!(shape, [("Todo", 0, None), ("Doing", 1, Some(3))]);
+
+        let cards_in = |column_id: &str| -> Vec<(String, i32, String)> {
+            db.get_cards(column_id, CardSort::Position)
+                .unwrap()
+                .into_iter()
+                .map(|card| (card.title, card.position, card.status))
+                .collect()
+        };
+        let idle = |title: &str, position| (title.to_string(), position, "idle".to_string());
+        assert_eq!(
+            cards_in(&columns[0].id),
+            [idle("First", 0), idle("Second", 1)]
+        );
+        assert_eq!(cards_in(&columns[1].id), [idle("Third", 0)]);
+
+        let empty = db
+            .duplicate_board(&fixture.board_id, "Columns only", false)
+            .unwrap();
+        assert_eq!((empty.column_count, empty.card_count), (2, 0));
+    }
+
+    #[test]
+    fn each_title_change_is_logged_and_no_ops_are_not() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/tmp/card-history");
+        let card_id = add_card(&db, &fixture.column_id, "Draft");
+
+        for title in ["Second", "Third"] {
+            db.update_card(&card_id, title, None, "idle", None, false, None)
+                .unwrap();
+        }
+        let logged = db.get_card_history(&card_id).unwrap().len();
+        db.update_card(&card_id, "Third", None, "idle", None, false, None)
+            .unwrap();
+
+        let history = db.get_card_history(&card_id).unwrap();
+        assert_eq!(history.len(), logged);
+        let titles: Vec<(Option<&str>, Option<&str>)> = history
+            .iter()
+            .filter(|entry| entry.field == "title")
+            .map(|entry| (entry.old_value.as_deref(), entry.new_value.as_deref()))
+            .collect();
+        assert_eq!(
+            titles,
+            [
+                (Some("Second"), Some("Third")),
+                (Some("Draft"), Some("Second"))
+            ]
+        );
+    }
+
+    #[test]
+    fn dependencies_that_would_close_a_loop_are_rejected() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/tmp/dependency-cycle");
+        let [a, b, c] = ["A", "B", "C"].map(|title| add_card(&db, &fixture.column_id, title));
+
+        db.add_dependency(&a, &b).unwrap();
+        db.add_dependency(&b, &c).unwrap();
+
+        assert!(matches!(
+            db.add_dependency(&a, &a),
+            Err(CardError::SelfDependency(id)) if id == a
+        ));
+        match db.add_dependency(&c, &a) {
+            Err(CardError::DependencyCycle(path)) => assert_eq!(path, [c.as_str(), &a, &b, &c]),
+            other => panic!("expected a cycle, got {:?}", other),
+        }
+        assert!(db.get_dependencies(&c).unwrap().blocked_by.is_empty());
+    }
+
+    #[test]
+    fn a_card_is_unblocked_once_its_dependency_is_done() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/tmp/dependency-done");
+        let card = add_card(&db, &fixture.column_id, "Deploy");
+        let dependency = add_card(&db, &fixture.column_id, "Build");
+        db.add_dependency(&card, &dependency).unwrap();
+
+        let blocked = db.get_blocked_cards(&fixture.project_id).unwrap();
+        assert_eq!(blocked.len(), 1);
+        assert_eq!(blocked[0].card.id, card);
+        assert_eq!(blocked[0].blocked_by, [dependency.as_str()]);
+
+        db.update_card_status(&dependency, "done", true).unwrap();
+        assert!(db
+            .get_blocked_cards(&fixture.project_id)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn a_batch_of_columns_gets_sequential_positions() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/tmp/create-columns");
+        let board_id = uuid::Uuid::new_v4().to_string();
+        db.create_board(&board_id, &fixture.project_id, "Fresh", 1)
+            .unwrap();
+
+        let names = ["Backlog", "Ready", "Doing", "Review", "Done"].map(String::from);
+        let created = db.create_columns(&board_id, &names).unwrap();
+
+        let shape: Vec<(&str, i32)> = created
+            .iter()
+            .map(|column| (column.name.as_str(), column.position))
+            .collect();
+        assert_eq!(
+            shape,
+            [
+                ("Backlog", 0),
+                ("Ready", 1),
+                ("Doing", 2),
+                ("Review", 3),
+                ("Done", 4)
+            ]
+        );
+        let stored: Vec<String> = db
+            .get_columns(&board_id)
+            .unwrap()
+            .into_iter()
+            .map(|column| column.id)
+            .collect();
+        let returned: Vec<String> = created.into_iter().map(|column| column.id).collect();
+        assert_eq!(stored, returned);
+    }
+
+    #[test]
+    fn columns_are_renumbered_in_the_requested_order() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/tmp/reorder-columns");
+        let created = db
+            .create_columns(&fixture.board_id, &["Doing", "Done"].map(String::from))
+            .unwrap();
+        let [todo, doing, done] = [
+            fixture.column_id.clone(),
+            created[0].id.clone(),
+            created[1].id.clone(),
+        ];
+        let names = |columns: Vec<Column>| -> Vec<(String, i32)> {
+            columns
+                .into_iter()
+                .map(|column| (column.name, column.position))
+                .collect()
+        };
+
+        let reordered = db
+            .reorder_columns(
+                &fixture.board_id,
+                &[done.clone(), todo.clone(), doing.clone()],
+            )
+            .unwrap()
+            .unwrap();
+        let expected = [
+            ("Done".to_string(), 0),
+            ("Todo".to_string(), 1),
+            ("Doing".to_string(), 2),
+        ];
+        assert_eq!(names(reordered), expected);
+
+        let other_board = uuid::Uuid::new_v4().to_string();
+        db.create_board(&other_board, &fixture.project_id, "Other", 1)
+            .unwrap();
+        let foreign = db
+            .create_columns(&other_board, &["Elsewhere".into()])
+            .unwrap()
+            .remove(0)
+            .id;
+        for rejected in [
+            vec![todo.clone(), doing.clone()],
+            vec![todo.clone(), doing.clone(), done.clone(), foreign],
+            vec![todo.clone(), doing.clone(), doing],
+        ] {
+            assert!(db
+                .reorder_columns(&fixture.board_id, &rejected)
+                .unwrap()
+                .is_none());
+        }
+        assert_eq!(names(db.get_columns(&fixture.board_id).unwrap()), expected);
+    }
+
+    #[test]
+    fn a_card_moves_to_the_end_of_a_column_on_another_board() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/tmp/move-to-board");
+        let [first, moved, last] =
+            ["First", "Moved", "Last"].map(|title| add_card(&db, &fixture.column_id, title));
+
+        let board_b = uuid::Uuid::new_v4().to_string();
+        db.create_board(&board_b, &fixture.project_id, "Board B", 1)
+            .unwrap();
+        let columns_b = db
+            .create_columns(&board_b, &["Inbox", "Doing"].map(String::from))
+            .unwrap();
+        let existing = add_card(&db, &columns_b[1].id, "Already there");
+
+        let (card, from_column_id) = db
+            .move_card_to_board(&moved, &board_b, Some(&columns_b[1].id), true)
+            .unwrap();
+        assert_eq!(
+            (card.column_id.as_str(), card.position),
+            (columns_b[1].id.as_str(), 1)
+        );
+        assert_eq!(from_column_id, fixture.column_id);
+
+        let ids_in = |column_id: &str| -> Vec<String> {
+            db.get_cards(column_id, CardSort::Position)
+                .unwrap()
+                .into_iter()
+                .map(|card| card.id)
+                .collect()
+        };
+        assert_eq!(ids_in(&fixture.column_id), [first.clone(), last]);
+        assert_eq!(ids_in(&columns_b[1].id), [existing, moved]);
+        let positions: Vec<i32> = db
+            .get_cards(&fixture.column_id, CardSort::Position)
+            .unwrap()
+            .iter()
+            .map(|card| card.position)
+            .collect();
+        assert_eq!(positions, [0, 1]);
+
+        let (card, _) = db.move_card_to_board(&first, &board_b, None, true).unwrap();
+        assert_eq!(card.column_id, columns_b[0].id);
+
+        let elsewhere = project_with_column(&db, "/tmp/move-to-board-elsewhere");
+        assert!(matches!(
+            db.move_card_to_board(&first, &elsewhere.board_id, None, true),
+            Err(CardError::OtherProject { .. })
+        ));
+    }
+
+    #[test]
+    fn reassigning_into_a_full_column_commits_nothing() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/tmp/reassign-rollback");
+        let target = uuid::Uuid::new_v4().to_string();
+        db.create_column(&target, &fixture.board_id, "Small", 1)
+            .unwrap();
+        db.set_wip_limit(&target, Some(2)).unwrap();
+        let resident = add_card(&db, &target, "Resident");
+        let moving = ["One", "Two", "Three"].map(|title| add_card(&db, &fixture.column_id, title));
+
+        // "One" fits, "Two" hits the limit, and "One" must not stay moved
+        assert!(matches!(
+            db.delete_column_and_reassign(&fixture.column_id, &target),
+            Err(CardError::WipLimitExceeded { limit: 2, .. })
+        ));
+
+        let ids_in = |column_id: &str| -> Vec<String> {
+            db.get_cards(column_id, CardSort::Position)
+                .unwrap()
+                .into_iter()
+                .map(|card| card.id)
+                .collect()
+        };
+        assert_eq!(db.get_columns(&fixture.board_id).unwrap().len(), 2);
+        assert_eq!(ids_in(&fixture.column_id), moving);
+        assert_eq!(ids_in(&target), [resident]);
+    }
+
+    #[test]
+    fn reassigned_cards_land_after_the_targets_live_cards() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/tmp/reassign");
+        let target = uuid::Uuid::new_v4().to_string();
+        db.create_column(&target, &fixture.board_id, "Target", 1)
+            .unwrap();
+        let resident = add_card(&db, &target, "Resident");
+        let gone = add_card(&db, &target, "Archived in target");
+        let [first, archived, second] =
+            ["First", "Archived", "Second"].map(|title| add_card(&db, &fixture.column_id, title));
+        db.bulk_update_cards(&[gone, archived.clone()], &BulkCardOp::Archive, true)
+            .unwrap();
+
+        assert!(matches!(
+            db.delete_column_and_reassign(&fixture.column_id, &fixture.column_id),
+            Err(CardError::ReassignToSelf(_))
+        ));
+        let elsewhere = project_with_column(&db, "/tmp/reassign-elsewhere");
+        assert!(matches!(
+            db.delete_column_and_reassign(&fixture.column_id, &elsewhere.column_id),
+            Err(CardError::ColumnNotOnBoard { .. })
+        ));
+        assert_eq!(
+            db.get_cards(&fixture.column_id, CardSort::Position)
+                .unwrap()
+                .len(),
+            2
+        );
+
+        db.delete_column_and_reassign(&fixture.column_id, &target)
+            .unwrap();
+        let placed: Vec<(String, i32)> = db
+            .get_cards(&target, CardSort::Position)
+            .unwrap()
+            .into_iter()
+            .map(|card| (card.id, card.position))
+            .collect();
+        assert_eq!(placed, [(resident, 0), (first, 1), (second, 2)]);
+        let location = db.get_card_location(&archived).unwrap().unwrap();
+        assert_eq!(location.column_id, target);
+    }
+
+    #[test]
+    fn empty_project_settings_read_as_the_defaults() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/settings-defaults");
+        let set_raw = |raw: &str| {
+            db.with_conn_mut(|conn| {
+                conn.execute(
+                    "UPDATE projects SET settings = ?2 WHERE id = ?1",
+                    [fixture.project_id.as_str(), raw],
+                )
+            })
+            .unwrap();
+        };
+
+        set_raw("{}");
+        assert_eq!(
+            db.get_project_settings(&fixture.project_id).unwrap(),
+            ProjectSettings::default()
+        );
+        set_raw("");
+        assert_eq!(
+            db.get_project_settings(&fixture.project_id).unwrap(),
+            ProjectSettings::default()
+        );
+    }
+
+    #[test]
+    fn project_settings_keep_keys_they_dont_know() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/settings-extra");
+        db.with_conn_mut(|conn| {
+            conn.execute(
+                "UPDATE projects SET settings = ?2 WHERE id = ?1",
+                [
+                    fixture.project_id.as_str(),
+                    r#"{"theme":"dark","editor":{"tab_size":2}}"#,
+                ],
+            )
+        })
+        .unwrap();
+
+        let mut settings = db.get_project_settings(&fixture.project_id).unwrap();
+        assert_eq!(settings.theme.as_deref(), Some("dark"));
+        assert_eq!(
+            settings.extra["editor"],
+            serde_json::json!({ "tab_size": 2 })
+        );
+
+        settings.theme = Some("light".to_string());
+        db.update_project_settings(&fixture.project_id, &settings)
+            .unwrap();
+
+        let raw: String = db
+            .with_conn(|conn| {
+                conn.query_row(
+                    "SELECT settings FROM projects WHERE id = ?1",
+                    [&fixture.project_id],
+                    |row| row.get(0),
+                )
+            })
+            .unwrap();
+        let stored: serde_json::Value = serde_json::from_str(&raw).unwrap();
+        assert_eq!(stored["theme"], "light");
+        assert_eq!(stored["editor"], serde_json::json!({ "tab_size": 2 }));
+        assert_eq!(
+            db.get_project_settings(&fixture.project_id).unwrap(),
+            settings
+        );
+    }
+
+    #[test]
+    fn metadata_patches_merge_nested_objects() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/metadata-nested");
+        let card_id = add_card(&db, &fixture.column_id, "Nested");
+        let patch =
+            |patch: serde_json::Value| db.update_card_metadata(&card_id, &patch, None).unwrap();
+
+        patch(serde_json::json!({ "agent": { "model": "a", "retries": 1 } }));
+        let merged = patch(serde_json::json!({ "agent": { "model": "b" } }));
+        assert_eq!(
+            merged,
+            serde_json::json!({ "agent": { "model": "b", "retries": 1 } })
+        );
+    }
+
+    #[test]
+    fn a_null_in_a_metadata_patch_removes_the_key() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/metadata-null");
+        let card_id = add_card(&db, &fixture.column_id, "Nulled");
+        db.update_card_metadata(
+            &card_id,
+            &serde_json::json!({ "keep": 1, "drop": 2, "nested": { "drop": 3, "keep": 4 } }),
+            None,
+        )
+        .unwrap();
+
+        let metadata = db
+            .update_card_metadata(
+                &card_id,
+                &serde_json::json!({ "drop": null, "nested": { "drop": null } }),
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            metadata,
+            serde_json::json!({ "keep": 1, "nested": { "keep": 4 } })
+        );
+    }
+
+    #[test]
+    fn sequential_metadata_patches_keep_each_others_keys() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/metadata-sequential");
+        let card_id = add_card(&db, &fixture.column_id, "Patched twice");
+
+        db.update_card_metadata(&card_id, &serde_json::json!({ "first": 1 }), None)
+            .unwrap();
+        db.update_card_metadata(&card_id, &serde_json::json!({ "second": 2 }), None)
+            .unwrap();
+
+        let stored = db.get_card(&card_id).unwrap().unwrap().metadata;
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&stored).unwrap(),
+            serde_json::json!({ "first": 1, "second": 2 })
+        );
+    }
+
+    #[test]
+    fn a_metadata_patch_has_to_be_an_object() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/metadata-rejected");
+        let card_id = add_card(&db, &fixture.column_id, "Untouched");
+        db.update_card_metadata(&card_id, &serde_json::json!({ "kept": true }), None)
+            .unwrap();
+
+        for patch in [
+            serde_json::json!(null),
+            serde_json::json!([1, 2]),
+            serde_json::json!("text"),
+        ] {
+            assert!(matches!(
+                db.update_card_metadata(&card_id, &patch, None),
+                Err(CardError::MetadataPatchNotObject)
+            ));
+        }
+        let stored = db.get_card(&card_id).unwrap().unwrap().metadata;
+        assert_eq!(stored, r#"{"kept":true}"#);
+    }
+
+    #[test]
+    fn comments_read_back_oldest_first_and_can_be_deleted() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/comments");
+        let card_id = add_card(&db, &fixture.column_id, "Discussed");
+        let first = db.add_comment(&card_id, "ana", "First").unwrap();
+        let second = db.add_comment(&card_id, "sam", "Second").unwrap();
+        let bodies = || -> Vec<String> {
+            db.get_comments(&card_id)
+                .unwrap()
+                .into_iter()
+                .map(|comment| comment.body)
+                .collect()
+        };
+
+        // Same second: insertion order breaks the tie
+        assert_eq!(bodies(), ["First", "Second"]);
+
+        db.with_conn_mut(|conn| {
+            conn.execute(
+                "UPDATE card_comments SET created_at = created_at + 60 WHERE id = ?1",
+                [&first.id],
+            )
+        })
+        .unwrap();
+        assert_eq!(bodies(), ["Second", "First"]);
+
+        db.delete_comment(&second.id).unwrap();
+        assert_eq!(bodies(), ["First"]);
+    }
+
+    #[test]
+    fn a_builtin_template_creates_its_columns_in_order() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/templated");
+        let template = db
+            .list_templates()
+            .unwrap()
+            .into_iter()
+            .find(|template| template.builtin && template.name == "Bug triage")
+            .unwrap();
+
+        let board = db
+            .create_board_from_template(&fixture.project_id, &template.id, "Bugs")
+            .unwrap();
+        assert_eq!(board.name, "Bugs");
+
+        let columns = db.get_columns(&board.id).unwrap();
+        let names: Vec<&str> = columns.iter().map(|column| column.name.as_str()).collect();
+        assert_eq!(names, ["New", "Triaged", "Fixing", "Verifying", "Closed"]);
+        let positions: Vec<i32> = columns.iter().map(|column| column.position).collect();
+        assert_eq!(positions, [0, 1, 2, 3, 4]);
+
+        let seeded = db.get_cards(&columns[0].id, CardSort::Position).unwrap();
+        assert_eq!(seeded.len(), 1);
+        assert_eq!(seeded[0].title, "Example bug report");
+    }
+
+    #[test]
+    fn recent_cards_come_newest_first_and_honour_the_status_filter() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/recent");
+        let other_column = uuid::Uuid::new_v4().to_string();
+        db.create_column(&other_column, &fixture.board_id, "Done", 1)
+            .unwrap();
+        let elsewhere = project_with_column(&db, "/recent-elsewhere");
+
+        // Inserted directly: the timestamp trigger would overwrite an UPDATE
+        let statuses = ["idle", "done", "review"];
+        db.transaction(|tx| {
+            for i in 0..300 {
+                let column_id = if i % 2 == 0 { &fixture.column_id } else { &other_column };
+                tx.execute(
+                    "INSERT INTO cards (id, column_id, title, position, status, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    rusqlite::params![
+                        format!("card-{}", i),
+                        column_id,
+                        format!("Card {}", i),
+                        i / 2,
+                        statuses[i as usize % 3],
+                        1_000 + i * 10
+                    ],
+                )?;
+            }
+            tx.execute(
+                "INSERT INTO cards (id, column_id, title, position, updated_at, archived_at) VALUES ('archived', ?1, 'Archived', 999, 99999, 1)",
+                [&fixture.column_id],
+            )?;
+            tx.execute(
+                "INSERT INTO cards (id, column_id, title, position, updated_at) VALUES ('foreign', ?1, 'Foreign', 0, 99999)",
+                [&elsewhere.column_id],
+            )?;
+            Ok::<_, rusqlite::Error>(())
+        })
+        .unwrap();
+        let ids = |cards: Vec<RecentCard>| -> Vec<String> {
+            cards.into_iter().map(|recent| recent.card.id).collect()
+        };
+
+        let recent = db
+            .get_recent_cards(&fixture.project_id, 20, None, &[])
+            .unwrap();
+        assert_eq!(recent[0].column_name, "Done");
+        assert_eq!(recent[1].column_name, "Todo");
+        let expected: Vec<String> = (280..300).rev().map(|i| format!("card-{}", i)).collect();
+        assert_eq!(ids(recent), expected);
+
+        let done = db
+            .get_recent_cards(&fixture.project_id, 1000, None, &["done".to_string()])
+            .unwrap();
+        let expected: Vec<String> = (0..300)
+            .rev()
+            .filter(|i| i % 3 == 1)
+            .map(|i| format!("card-{}", i))
+            .collect();
+        assert_eq!(expected.len(), 100);
+        assert_eq!(ids(done), expected);
+
+        let filtered = db
+            .get_recent_cards(
+                &fixture.project_id,
+                5,
+                Some(1_000 + 250 * 10),
+                &["idle".to_string(), "review".to_string()],
+            )
+            .unwrap();
+        let expected: Vec<String> = (250..300)
+            .rev()
+            .filter(|i| i % 3 != 1)
+            .take(5)
+            .map(|i| format!("card-{}", i))
+            .collect();
+        assert_eq!(ids(filtered), expected);
+    }
+
+    #[test]
+    fn board_cards_match_reading_each_column_in_turn() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/board-cards");
+        let mut column_ids = vec![fixture.column_id.clone()];
+        for (position, name) in ["Doing", "Review", "Done"].into_iter().enumerate() {
+            let id = uuid::Uuid::new_v4().to_string();
+            db.create_column(&id, &fixture.board_id, name, position as i32 + 1)
+                .unwrap();
+            column_ids.push(id);
+        }
+
+        db.transaction(|tx| {
+            for i in 0..1000 {
+                // Positions interleave so the per-column order isn't insertion order
+                tx.execute(
+                    "INSERT INTO cards (id, column_id, title, position, archived_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+                    rusqlite::params![
+                        format!("card-{}", i),
+                        column_ids[i % 4],
+                        format!("Card {}", i),
+                        (1000 - i) as i64,
+                        (i % 50 == 0).then_some(1_i64)
+                    ],
+                )?;
+            }
+            Ok::<_, rusqlite::Error>(())
+        })
+        .unwrap();
+        for i in (0..1000).step_by(7) {
+            let item = db
+                .add_checklist_item(&format!("card-{}", i), "Step")
+                .unwrap();
+            if i % 2 == 0 {
+                db.toggle_checklist_item(&item.id).unwrap();
+            }
+        }
+
+        let board_cards = db.get_cards_for_board(&fixture.board_id).unwrap();
+        let mut per_column = Vec::new();
+        for column in db.get_columns(&fixture.board_id).unwrap() {
+            for card in db.get_cards(&column.id, CardSort::Position).unwrap() {
+                per_column.push((card, column.name.clone()));
+            }
+        }
+
+        assert_eq!(board_cards.len(), 980);
+        assert_eq!(board_cards.len(), per_column.len());
+        for (board_card, (card, column_name)) in board_cards.iter().zip(&per_column) {
+            assert_eq!(
+                serde_json::to_value(&board_card.card).unwrap(),
+                serde_json::to_value(card).unwrap()
+            );
+            assert_eq!(&board_card.column_name, column_name);
+        }
+    }
+
+    #[test]
+    fn readers_refuse_to_write() {
+        let db = memory_db();
+        project_with_column(&db, "/readers");
+
+        assert!(db
+            .with_conn(|conn| conn.execute("DELETE FROM projects", []))
+            .is_err());
+        assert_eq!(db.get_projects().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn locked_project_refuses_card_changes_until_unlocked() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/locked");
+        let card_id = add_card(&db, &fixture.column_id, "Existing");
+        let other_id = add_card(&db, &fixture.column_id, "Other");
+        db.set_project_locked(&fixture.project_id, true).unwrap();
+
+        let locked = |e: CardError| matches!(e, CardError::Database(ref e) if is_project_locked(e));
+        let created = db.create_card("new", &fixture.column_id, "New", None, 2, None, true);
+        assert!(locked(created.unwrap_err()));
+        assert!(is_project_locked(
+            &db.add_checklist_item(&card_id, "Step").unwrap_err()
+        ));
+        assert!(is_project_locked(
+            &db.add_comment(&card_id, "me", "Note").unwrap_err()
+        ));
+        assert!(locked(db.add_dependency(&card_id, &other_id).unwrap_err()));
+
+        db.set_project_locked(&fixture.project_id, false).unwrap();
+        db.create_card("new", &fixture.column_id, "New", None, 2, None, true)
+            .unwrap();
+        db.add_checklist_item(&card_id, "Step").unwrap();
+        db.add_comment(&card_id, "me", "Note").unwrap();
+        db.add_dependency(&card_id, &other_id).unwrap();
+    }
+
+    #[cfg(feature = "encrypted-db")]
+    #[test]
+    fn reencrypt_swaps_the_key_and_back_to_plaintext() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("viraith.db");
+        let db = Database::new(&path, None).unwrap();
+        project_with_column(&db, "/encrypted");
+
+        db.reencrypt(Some("secret")).unwrap();
+        assert!(db.is_encrypted());
+        assert_eq!(db.get_projects().unwrap().len(), 1);
+        assert!(Database::open_file(&path, None).is_err());
+        assert!(Database::open_file(&path, Some("secret")).is_ok());
+
+        db.reencrypt(None).unwrap();
+        assert!(!db.is_encrypted());
+        assert_eq!(db.get_projects().unwrap().len(), 1);
+        assert!(Database::open_file(&path, None).is_ok());
+    }
+
+    #[test]
+    fn closed_database_refuses_every_call() {
+        let db = memory_db();
+        project_with_column(&db, "/closed");
+        db.closed.store(true, Ordering::Release);
+
+        assert!(db.get_projects().is_err());
+        assert!(db.create_project("p", "P", "/closed-too").is_err());
+        assert!(db.delete_project("p").is_err());
+    }
+
+    #[test]
+    fn edit_based_on_an_older_version_is_stale_within_the_same_second() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/versions");
+        let card_id = add_card(&db, &fixture.column_id, "Card");
+        let read = db.get_card(&card_id).unwrap().unwrap();
+
+        db.update_card(
+            &card_id,
+            "First",
+            None,
+            "idle",
+            None,
+            false,
+            Some(read.version),
+        )
+        .unwrap();
+        let current = db.get_card(&card_id).unwrap().unwrap();
+        assert!(current.version > read.version);
+        assert_eq!(current.updated_at, read.updated_at);
+
+        let second = db.update_card(
+            &card_id,
+            "Second",
+            None,
+            "idle",
+            None,
+            false,
+            Some(read.version),
+        );
+        match second {
+            Err(CardError::Stale(card)) => assert_eq!(card.title, "First"),
+            other => panic!("expected a stale edit, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn concurrent_readers_and_writers_never_hit_a_locked_database() {
+        let path = std::env::temp_dir().join(format!("viraith-stress-{}.db", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(&path, None).unwrap());
+        let fixture = project_with_column(&db, "/stress");
+
+        let writers: Vec<_> = (0..4)
+            .map(|w| {
+                let db = Arc::clone(&db);
+                let column_id = fixture.column_id.clone();
+                std::thread::spawn(move || {
+                    for i in 0..50 {
+                        let id = uuid::Uuid::new_v4().to_string();
+                        let title = format!("card {}-{}", w, i);
+                        db.create_card(&id, &column_id, &title, None, i, None, true)
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        let readers: Vec<_> = (0..16)
+            .map(|_| {
+                let db = Arc::clone(&db);
+                let board_id = fixture.board_id.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..50 {
+                        db.get_board_data(&board_id).unwrap();
+                        db.get_card_counts(&board_id).unwrap();
+                    }
+                })
+            })
+            .collect();
+
+        for handle in writers.into_iter().chain(readers) {
+            handle.join().expect("no database call failed");
+        }
+        assert_eq!(
+            db.get_cards(&fixture.column_id, CardSort::Position)
+                .unwrap()
+                .len(),
+            200
+        );
+
+        drop(db);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[tokio::test]
+    async fn readers_and_the_event_loop_keep_going_during_a_long_write() {
+        let path = std::env::temp_dir().join(format!("viraith-stress-{}.db", uuid::Uuid::new_v4()));
+        let db = Arc::new(Database::new(&path, None).unwrap());
+        let fixture = project_with_column(&db, "/long-write");
+        add_card(&db, &fixture.column_id, "Existing");
+
+        let column_id = fixture.column_id.clone();
+        let write = db.blocking(move |db| {
+            db.transaction(|tx| {
+                tx.execute(
+                    "UPDATE columns SET name = 'Renamed' WHERE id = ?1",
+                    rusqlite::params![column_id],
+                )?;
+                std::thread::sleep(std::time::Duration::from_millis(400));
+                Ok::<_, rusqlite::Error>(())
+            })
+        });
+        let reads: Vec<_> = (0..50)
+            .map(|_| {
+                let db = Arc::clone(&db);
+                let column_id = fixture.column_id.clone();
+                tokio::spawn(async move {
+                    db.blocking(move |db| db.get_cards(&column_id, CardSort::Position))
+                        .await
+                })
+            })
+            .collect();
+        // A stalled runtime would hold these ticks back for the whole write
+        let ticker = async {
+            let mut slowest = std::time::Duration::ZERO;
+            for _ in 0..20 {
+                let started = std::time::Instant::now();
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                slowest = slowest.max(started.elapsed());
+            }
+            slowest
+        };
+
+        let (written, slowest) = tokio::join!(write, ticker);
+        written.unwrap();
+        for read in reads {
+            let cards = read.await.unwrap().expect("no lock or poison error");
+            assert_eq!(cards.len(), 1);
+        }
+        assert!(
+            slowest < std::time::Duration::from_millis(200),
+            "a tick took {:?}",
+            slowest
+        );
+
+        drop(db);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
 }
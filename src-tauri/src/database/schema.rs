@@ -55,6 +55,47 @@ CREATE TABLE IF NOT EXISTS cards (
   FOREIGN KEY (column_id) REFERENCES columns(id) ON DELETE CASCADE
 );
 
+-- Board templates (JSON snapshot of columns and optional seed cards)
+CREATE TABLE IF NOT EXISTS board_templates (
+  id TEXT PRIMARY KEY,
+  name TEXT NOT NULL,
+  snapshot JSON NOT NULL,
+  builtin INTEGER NOT NULL DEFAULT 0,
+  created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+);
+
+-- Card checklist items
+CREATE TABLE IF NOT EXISTS card_checklist_items (
+  id TEXT PRIMARY KEY,
+  card_id TEXT NOT NULL,
+  text TEXT NOT NULL,
+  done INTEGER NOT NULL DEFAULT 0,
+  position INTEGER NOT NULL,
+  created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+  FOREIGN KEY (card_id) REFERENCES cards(id) ON DELETE CASCADE
+);
+
+-- Card comments
+CREATE TABLE IF NOT EXISTS card_comments (
+  id TEXT PRIMARY KEY,
+  card_id TEXT NOT NULL,
+  author TEXT NOT NULL,
+  body TEXT NOT NULL,
+  created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+  edited_at INTEGER,
+  FOREIGN KEY (card_id) REFERENCES cards(id) ON DELETE CASCADE
+);
+
+-- Card activity (automation runs and other system events)
+CREATE TABLE IF NOT EXISTS card_activity (
+  id TEXT PRIMARY KEY,
+  card_id TEXT NOT NULL,
+  kind TEXT NOT NULL,
+  message TEXT NOT NULL,
+  created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+  FOREIGN KEY (card_id) REFERENCES cards(id) ON DELETE CASCADE
+);
+
 -- Card Executions (for Phase 2 - AI execution tracking)
 CREATE TABLE IF NOT EXISTS card_executions (
   id TEXT PRIMARY KEY,
@@ -81,14 +122,31 @@ CREATE TABLE IF NOT EXISTS agent_logs (
   FOREIGN KEY (execution_id) REFERENCES card_executions(id) ON DELETE CASCADE
 );
 
+-- File index (content hashes for change detection)
+CREATE TABLE IF NOT EXISTS file_index (
+  project_id TEXT NOT NULL,
+  path TEXT NOT NULL,
+  hash TEXT NOT NULL,
+  size INTEGER NOT NULL,
+  mtime INTEGER NOT NULL,
+  indexed_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+  PRIMARY KEY (project_id, path),
+  FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+);
+
 -- Indexes
 CREATE INDEX IF NOT EXISTS idx_cards_column ON cards(column_id);
 CREATE INDEX IF NOT EXISTS idx_cards_status ON cards(status);
 CREATE INDEX IF NOT EXISTS idx_cards_updated ON cards(updated_at DESC);
+CREATE INDEX IF NOT EXISTS idx_checklist_card ON card_checklist_items(card_id, position);
+CREATE INDEX IF NOT EXISTS idx_comments_card ON card_comments(card_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_activity_card ON card_activity(card_id, created_at);
 CREATE INDEX IF NOT EXISTS idx_executions_card ON card_executions(card_id);
 CREATE INDEX IF NOT EXISTS idx_executions_status ON card_executions(status);
 CREATE INDEX IF NOT EXISTS idx_logs_execution ON agent_logs(execution_id);
 CREATE INDEX IF NOT EXISTS idx_logs_timestamp ON agent_logs(timestamp DESC);
+CREATE INDEX IF NOT EXISTS idx_logs_execution_timestamp ON agent_logs(execution_id, timestamp);
+CREATE INDEX IF NOT EXISTS idx_file_index_hash ON file_index(hash);
 
 -- Triggers (auto-update timestamps)
 CREATE TRIGGER IF NOT EXISTS update_project_timestamp
@@ -103,3 +161,342 @@ BEGIN
   UPDATE cards SET updated_at = strftime('%s', 'now') WHERE id = NEW.id;
 END;
 "#;
+
+/// Incremental migrations applied on top of SCHEMA, in order. Each entry runs
+/// once, inside a transaction, and is recorded in schema_version.
+pub const MIGRATIONS: &[(i64, &str)] = &[
+    (
+        2,
+        r#"
+ALTER TABLE cards ADD COLUMN due_at INTEGER;
+CREATE INDEX IF NOT EXISTS idx_cards_due ON cards(due_at);
+"#,
+    ),
+    (
+        3,
+        r#"
+ALTER TABLE cards ADD COLUMN priority TEXT NOT NULL DEFAULT 'normal' CHECK(priority IN ('low', 'normal', 'high', 'urgent'));
+"#,
+    ),
+    (
+        4,
+        r#"
+ALTER TABLE cards ADD COLUMN archived_at INTEGER;
+CREATE INDEX IF NOT EXISTS idx_cards_archived ON cards(archived_at);
+"#,
+    ),
+    (
+        5,
+        r#"
+ALTER TABLE projects ADD COLUMN last_opened_at INTEGER;
+
+-- Opening a project isn't a modification, so only real edits bump updated_at
+DROP TRIGGER IF EXISTS update_project_timestamp;
+CREATE TRIGGER update_project_timestamp
+AFTER UPDATE OF name, root_path, settings ON projects
+BEGIN
+  UPDATE projects SET updated_at = strftime('%s', 'now') WHERE id = NEW.id;
+END;
+"#,
+    ),
+    (
+        6,
+        r#"
+CREATE TABLE IF NOT EXISTS card_audit_log (
+  id TEXT PRIMARY KEY,
+  card_id TEXT NOT NULL,
+  field TEXT NOT NULL,
+  old_value TEXT,
+  new_value TEXT,
+  changed_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+  FOREIGN KEY (card_id) REFERENCES cards(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_card_audit_card ON card_audit_log(card_id, changed_at);
+"#,
+    ),
+    (
+        7,
+        r#"
+-- Undo stack: no foreign key on card_id so a deleted card's entry survives
+CREATE TABLE IF NOT EXISTS card_operations (
+  seq INTEGER PRIMARY KEY AUTOINCREMENT,
+  project_id TEXT NOT NULL,
+  card_id TEXT NOT NULL,
+  operation JSON NOT NULL,
+  undone INTEGER NOT NULL DEFAULT 0,
+  created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+  FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_card_operations_project ON card_operations(project_id, seq);
+"#,
+    ),
+    (
+        8,
+        r#"
+-- NULL means no limit
+ALTER TABLE columns ADD COLUMN wip_limit INTEGER;
+"#,
+    ),
+    (
+        9,
+        r#"
+CREATE TABLE IF NOT EXISTS recent_items (
+  kind TEXT NOT NULL CHECK (kind IN ('project', 'file')),
+  item TEXT NOT NULL,
+  opened_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+  PRIMARY KEY (kind, item)
+);
+CREATE INDEX IF NOT EXISTS idx_recent_items_opened ON recent_items(kind, opened_at);
+"#,
+    ),
+    (
+        10,
+        r#"
+-- card_id can't start until depends_on_card_id is done
+CREATE TABLE IF NOT EXISTS card_dependencies (
+  card_id TEXT NOT NULL,
+  depends_on_card_id TEXT NOT NULL,
+  created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+  PRIMARY KEY (card_id, depends_on_card_id),
+  CHECK (card_id != depends_on_card_id),
+  FOREIGN KEY (card_id) REFERENCES cards(id) ON DELETE CASCADE,
+  FOREIGN KEY (depends_on_card_id) REFERENCES cards(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_card_dependencies_depends_on ON card_dependencies(depends_on_card_id);
+"#,
+    ),
+    (
+        11,
+        r#"
+-- Last geometry of each window, by label, in physical pixels
+CREATE TABLE IF NOT EXISTS window_state (
+  label TEXT PRIMARY KEY,
+  x INTEGER NOT NULL,
+  y INTEGER NOT NULL,
+  width INTEGER NOT NULL,
+  height INTEGER NOT NULL,
+  maximized INTEGER NOT NULL DEFAULT 0,
+  updated_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+);
+"#,
+    ),
+    (
+        12,
+        r#"
+-- Unsaved editor buffers, so edits survive a crash; one per file
+CREATE TABLE IF NOT EXISTS drafts (
+  id TEXT PRIMARY KEY,
+  project_id TEXT NOT NULL,
+  file_path TEXT NOT NULL,
+  content TEXT NOT NULL,
+  saved_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+  UNIQUE (project_id, file_path),
+  FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+);
+"#,
+    ),
+    (
+        13,
+        r#"
+-- App-wide preferences; values are JSON
+CREATE TABLE IF NOT EXISTS preferences (
+  key TEXT PRIMARY KEY,
+  value TEXT NOT NULL CHECK (json_valid(value)),
+  updated_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+);
+"#,
+    ),
+    (
+        14,
+        r#"
+-- Locked projects can be read but not changed; the triggers refuse any
+-- change to their boards, columns and cards
+ALTER TABLE projects ADD COLUMN locked INTEGER NOT NULL DEFAULT 0;
+
+CREATE TRIGGER IF NOT EXISTS boards_locked_insert
+BEFORE INSERT ON boards
+WHEN EXISTS (SELECT 1 FROM projects WHERE locked AND id = NEW.project_id)
+BEGIN
+  SELECT RAISE(ABORT, 'project is locked');
+END;
+
+CREATE TRIGGER IF NOT EXISTS boards_locked_update
+BEFORE UPDATE ON boards
+WHEN EXISTS (SELECT 1 FROM projects WHERE locked AND (id = OLD.project_id OR id = NEW.project_id))
+BEGIN
+  SELECT RAISE(ABORT, 'project is locked');
+END;
+
+CREATE TRIGGER IF NOT EXISTS boards_locked_delete
+BEFORE DELETE ON boards
+WHEN EXISTS (SELECT 1 FROM projects WHERE locked AND id = OLD.project_id)
+BEGIN
+  SELECT RAISE(ABORT, 'project is locked');
+END;
+
+CREATE TRIGGER IF NOT EXISTS columns_locked_insert
+BEFORE INSERT ON columns
+WHEN EXISTS (SELECT 1 FROM boards b JOIN projects p ON p.id = b.project_id WHERE p.locked AND b.id = NEW.board_id)
+BEGIN
+  SELECT RAISE(ABORT, 'project is locked');
+END;
+
+CREATE TRIGGER IF NOT EXISTS columns_locked_update
+BEFORE UPDATE ON columns
+WHEN EXISTS (SELECT 1 FROM boards b JOIN projects p ON p.id = b.project_id WHERE p.locked AND (b.id = OLD.board_id OR b.id = NEW.board_id))
+BEGIN
+  SELECT RAISE(ABORT, 'project is locked');
+END;
+
+CREATE TRIGGER IF NOT EXISTS columns_locked_delete
+BEFORE DELETE ON columns
+WHEN EXISTS (SELECT 1 FROM boards b JOIN projects p ON p.id = b.project_id WHERE p.locked AND b.id = OLD.board_id)
+BEGIN
+  SELECT RAISE(ABORT, 'project is locked');
+END;
+
+CREATE TRIGGER IF NOT EXISTS cards_locked_insert
+BEFORE INSERT ON cards
+WHEN EXISTS (SELECT 1 FROM columns col JOIN boards b ON b.id = col.board_id JOIN projects p ON p.id = b.project_id WHERE p.locked AND col.id = NEW.column_id)
+BEGIN
+  SELECT RAISE(ABORT, 'project is locked');
+END;
+
+CREATE TRIGGER IF NOT EXISTS cards_locked_update
+BEFORE UPDATE ON cards
+WHEN EXISTS (SELECT 1 FROM columns col JOIN boards b ON b.id = col.board_id JOIN projects p ON p.id = b.project_id WHERE p.locked AND (col.id = OLD.column_id OR col.id = NEW.column_id))
+BEGIN
+  SELECT RAISE(ABORT, 'project is locked');
+END;
+
+CREATE TRIGGER IF NOT EXISTS cards_locked_delete
+BEFORE DELETE ON cards
+WHEN EXISTS (SELECT 1 FROM columns col JOIN boards b ON b.id = col.board_id JOIN projects p ON p.id = b.project_id WHERE p.locked AND col.id = OLD.column_id)
+BEGIN
+  SELECT RAISE(ABORT, 'project is locked');
+END;
+"#,
+    ),
+    (
+        15,
+        r#"
+-- A locked project's checklists, comments and dependencies are frozen along
+-- with its cards
+CREATE TRIGGER IF NOT EXISTS checklist_locked_insert
+BEFORE INSERT ON card_checklist_items
+WHEN EXISTS (SELECT 1 FROM cards c JOIN columns col ON col.id = c.column_id JOIN boards b ON b.id = col.board_id JOIN projects p ON p.id = b.project_id WHERE p.locked AND c.id = NEW.card_id)
+BEGIN
+  SELECT RAISE(ABORT, 'project is locked');
+END;
+
+CREATE TRIGGER IF NOT EXISTS checklist_locked_update
+BEFORE UPDATE ON card_checklist_items
+WHEN EXISTS (SELECT 1 FROM cards c JOIN columns col ON col.id = c.column_id JOIN boards b ON b.id = col.board_id JOIN projects p ON p.id = b.project_id WHERE p.locked AND (c.id = OLD.card_id OR c.id = NEW.card_id))
+BEGIN
+  SELECT RAISE(ABORT, 'project is locked');
+END;
+
+CREATE TRIGGER IF NOT EXISTS checklist_locked_delete
+BEFORE DELETE ON card_checklist_items
+WHEN EXISTS (SELECT 1 FROM cards c JOIN columns col ON col.id = c.column_id JOIN boards b ON b.id = col.board_id JOIN projects p ON p.id = b.project_id WHERE p.locked AND c.id = OLD.card_id)
+BEGIN
+  SELECT RAISE(ABORT, 'project is locked');
+END;
+
+CREATE TRIGGER IF NOT EXISTS comments_locked_insert
+BEFORE INSERT ON card_comments
+WHEN EXISTS (SELECT 1 FROM cards c JOIN columns col ON col.id = c.column_id JOIN boards b ON b.id = col.board_id JOIN projects p ON p.id = b.project_id WHERE p.locked AND c.id = NEW.card_id)
+BEGIN
+  SELECT RAISE(ABORT, 'project is locked');
+END;
+
+CREATE TRIGGER IF NOT EXISTS comments_locked_update
+BEFORE UPDATE ON card_comments
+WHEN EXISTS (SELECT 1 FROM cards c JOIN columns col ON col.id = c.column_id JOIN boards b ON b.id = col.board_id JOIN projects p ON p.id = b.project_id WHERE p.locked AND (c.id = OLD.card_id OR c.id = NEW.card_id))
+BEGIN
+  SELECT RAISE(ABORT, 'project is locked');
+END;
+
+CREATE TRIGGER IF NOT EXISTS comments_locked_delete
+BEFORE DELETE ON card_comments
+WHEN EXISTS (SELECT 1 FROM cards c JOIN columns col ON col.id = c.column_id JOIN boards b ON b.id = col.board_id JOIN projects p ON p.id = b.project_id WHERE p.locked AND c.id = OLD.card_id)
+BEGIN
+  SELECT RAISE(ABORT, 'project is locked');
+END;
+
+CREATE TRIGGER IF NOT EXISTS dependencies_locked_insert
+BEFORE INSERT ON card_dependencies
+WHEN EXISTS (SELECT 1 FROM cards c JOIN columns col ON col.id = c.column_id JOIN boards b ON b.id = col.board_id JOIN projects p ON p.id = b.project_id WHERE p.locked AND c.id = NEW.card_id)
+BEGIN
+  SELECT RAISE(ABORT, 'project is locked');
+END;
+
+CREATE TRIGGER IF NOT EXISTS dependencies_locked_update
+BEFORE UPDATE ON card_dependencies
+WHEN EXISTS (SELECT 1 FROM cards c JOIN columns col ON col.id = c.column_id JOIN boards b ON b.id = col.board_id JOIN projects p ON p.id = b.project_id WHERE p.locked AND (c.id = OLD.card_id OR c.id = NEW.card_id))
+BEGIN
+  SELECT RAISE(ABORT, 'project is locked');
+END;
+
+CREATE TRIGGER IF NOT EXISTS dependencies_locked_delete
+BEFORE DELETE ON card_dependencies
+WHEN EXISTS (SELECT 1 FROM cards c JOIN columns col ON col.id = c.column_id JOIN boards b ON b.id = col.board_id JOIN projects p ON p.id = b.project_id WHERE p.locked AND c.id = OLD.card_id)
+BEGIN
+  SELECT RAISE(ABORT, 'project is locked');
+END;
+"#,
+    ),
+    (
+        16,
+        r#"
+-- Bumped by every change to a card, so an edit based on an older copy can be
+-- told apart even within the same second
+ALTER TABLE cards ADD COLUMN version INTEGER NOT NULL DEFAULT 0;
+
+CREATE TRIGGER IF NOT EXISTS cards_bump_version
+AFTER UPDATE ON cards
+WHEN NEW.version = OLD.version
+BEGIN
+  UPDATE cards SET version = OLD.version + 1 WHERE id = NEW.id;
+END;
+"#,
+    ),
+    (
+        17,
+        r#"
+-- Recent projects are tracked by projects.last_opened_at alone
+UPDATE projects SET last_opened_at = (
+  SELECT MAX(opened_at) FROM recent_items WHERE kind = 'project' AND item = projects.id
+)
+WHERE last_opened_at IS NULL
+   OR last_opened_at < (SELECT MAX(opened_at) FROM recent_items WHERE kind = 'project' AND item = projects.id);
+
+DELETE FROM recent_items WHERE kind = 'project';
+"#,
+    ),
+    (
+        18,
+        r#"
+-- Logs are paged by seq; unlike the implicit rowid of a table with a TEXT
+-- key, VACUUM can't renumber it
+CREATE TABLE agent_logs_new (
+  seq INTEGER PRIMARY KEY AUTOINCREMENT,
+  id TEXT NOT NULL UNIQUE,
+  execution_id TEXT NOT NULL,
+  timestamp INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+  level TEXT CHECK(level IN ('debug', 'info', 'warn', 'error')) DEFAULT 'info',
+  message TEXT NOT NULL,
+  metadata JSON DEFAULT '{}',
+  FOREIGN KEY (execution_id) REFERENCES card_executions(id) ON DELETE CASCADE
+);
+INSERT INTO agent_logs_new (id, execution_id, timestamp, level, message, metadata)
+  SELECT id, execution_id, timestamp, level, message, metadata FROM agent_logs ORDER BY rowid;
+DROP TABLE agent_logs;
+ALTER TABLE agent_logs_new RENAME TO agent_logs;
+
+CREATE INDEX IF NOT EXISTS idx_logs_execution ON agent_logs(execution_id, seq);
+CREATE INDEX IF NOT EXISTS idx_logs_timestamp ON agent_logs(timestamp DESC);
+CREATE INDEX IF NOT EXISTS idx_logs_execution_timestamp ON agent_logs(execution_id, timestamp);
+"#,
+    ),
+];
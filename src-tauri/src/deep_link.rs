@@ -0,0 +1,92 @@
+// Deep link module
+// `viraith://project/<project_id>/card/<card_id>` links, e.g. from a PR
+// description. A link only names the project and card; the board and column
+// are looked up before the frontend is sent a `navigate` event, and links that
+// don't lead anywhere get a `navigate-error` with the reason instead.
+
+use crate::database::{CardLocation, Database};
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
+
+pub const SCHEME: &str = "viraith";
+
+/// Payload of the `navigate-error` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NavigateError {
+    pub url: String,
+    pub reason: String,
+}
+
+/// The canonical link to a card
+pub fn card_link(project_id: &str, card_id: &str) -> String {
+    format!("{}://project/{}/card/{}", SCHEME, project_id, card_id)
+}
+
+/// The project and card ids a link points at
+pub fn parse_card_link(raw: &str) -> Result<(String, String), String> {
+    let url = url::Url::parse(raw.trim()).map_err(|e| format!("Invalid link: {}", e))?;
+    if url.scheme() != SCHEME {
+        return Err(format!("Not a {} link", SCHEME));
+    }
+
+    // The first part after `//` is the URL's host, the rest its path
+    let parts: Vec<&str> = url
+        .host_str()
+        .into_iter()
+        .chain(url.path_segments().into_iter().flatten())
+        .filter(|part| !part.is_empty())
+        .collect();
+
+    match parts.as_slice() {
+        ["project", project_id, "card", card_id] => {
+            Ok((project_id.to_string(), card_id.to_string()))
+        }
+        _ => Err(format!(
+            "Expected a link like {}",
+            card_link("<project>", "<card>")
+        )),
+    }
+}
+
+/// Where the linked card is now, or why the link can't be followed
+pub fn resolve(db: &Database, raw: &str) -> Result<CardLocation, String> {
+    let (project_id, card_id) = parse_card_link(raw)?;
+    let lookup_failed = |e: rusqlite::Error| format!("Failed to look up the link: {}", e);
+
+    if db
+        .get_project(&project_id)
+        .optional()
+        .map_err(lookup_failed)?
+        .is_none()
+    {
+        return Err(format!("Project {} no longer exists", project_id));
+    }
+
+    match db.get_card_location(&card_id).map_err(lookup_failed)? {
+        None => Err(format!("Card {} no longer exists", card_id)),
+        Some(location) if location.project_id != project_id => {
+            Err(format!("Card {} is not in project {}", card_id, project_id))
+        }
+        Some(location) => Ok(location),
+    }
+}
+
+/// Follow a link in the background: bring the main window forward and emit
+/// `navigate`, or `navigate-error` when the link is bad or stale
+pub fn open(app: &AppHandle, raw: String) {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let db = app.state::<Arc<Database>>();
+        let _ = match resolve(&db, &raw) {
+            Ok(location) => {
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.set_focus();
+                }
+                app.emit("navigate", &location)
+            }
+            Err(reason) => app.emit("navigate-error", &NavigateError { url: raw, reason }),
+        };
+    });
+}
@@ -0,0 +1,369 @@
+// Command error module
+// Every command returns `AppError`, which reaches the frontend as
+// `{ code, kind, message, detail, ... }` so the UI can branch on `code` instead
+// of matching text
+
+use crate::database::{self, Card, CardError};
+use serde::ser::SerializeMap;
+use serde_json::json;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Conflict(String),
+    #[error("{message}")]
+    Validation { field: String, message: String },
+    #[error("{0}")]
+    Io(String),
+    #[error("{0}")]
+    Git(String),
+    #[error("{0}")]
+    Database(String),
+    /// The platform or filesystem can't do this (e.g. no recycle bin)
+    #[error("{0}")]
+    Unsupported(String),
+    /// An edit based on an outdated copy of the card; carries the current one
+    /// so the UI can offer to merge
+    #[error("{message}")]
+    StaleCard {
+        message: String,
+        current_card: Box<Card>,
+    },
+    /// The destination column is full; retry with an override to exceed it
+    #[error("{message}")]
+    WipLimitExceeded {
+        message: String,
+        limit: i64,
+        current: i64,
+    },
+    /// A card status change the status rules don't allow
+    #[error("{message}")]
+    InvalidTransition {
+        message: String,
+        from: String,
+        to: String,
+    },
+    #[error("A project already uses this folder: {existing_project_name}")]
+    ProjectPathExists {
+        existing_project_id: String,
+        existing_project_name: String,
+    },
+    /// HEAD is unborn, so there is no commit to branch from or merge into
+    #[error("{0}")]
+    RepositoryHasNoCommits(String),
+    /// Uncommitted changes are in the way of a branch switch
+    #[error("{message}")]
+    DirtyWorkingTree { message: String, files: Vec<String> },
+    /// Changes were applied but left conflicts to resolve in `files`
+    #[error("{message}")]
+    MergeConflict { message: String, files: Vec<String> },
+    /// The operation needs a branch checked out
+    #[error("{0}")]
+    DetachedHead(String),
+    /// A commit or stash was asked for but nothing differs from HEAD
+    #[error("{0}")]
+    NothingToCommit(String),
+    /// A change to the boards, columns or cards of a locked project
+    #[error("{0}")]
+    ProjectLocked(String),
+    /// A project-scoped file operation on a path outside the project folder
+    #[error("{0}")]
+    PathEscapesProject(String),
+    /// The operation was given up after `seconds`
+    #[error("{message}")]
+    Timeout { message: String, seconds: u64 },
+    /// Anything not yet given a more specific variant
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl AppError {
+    pub fn validation(field: &str, message: impl Into<String>) -> Self {
+        Self::Validation {
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+
+    /// Wrap an I/O error with what was being attempted, e.g. "Failed to read file"
+    pub fn io(context: &str, e: std::io::Error) -> Self {
+        let message = format!("{}: {}", context, e);
+        match e.kind() {
+            std::io::ErrorKind::NotFound => Self::NotFound(message),
+            _ => Self::Io(message),
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NotFound(_) => "NOT_FOUND",
+            Self::Conflict(_) | Self::StaleCard { .. } => "CONFLICT",
+            Self::Validation { .. } => "VALIDATION",
+            Self::Io(_) => "IO",
+            Self::Git(_) => "GIT",
+            Self::Database(_) => "DATABASE",
+            Self::Unsupported(_) => "UNSUPPORTED",
+            Self::WipLimitExceeded { .. } => "WIP_LIMIT_EXCEEDED",
+            Self::InvalidTransition { .. } => "INVALID_TRANSITION",
+            Self::ProjectPathExists { .. } => "PROJECT_PATH_EXISTS",
+            Self::RepositoryHasNoCommits(_) => "REPOSITORY_HAS_NO_COMMITS",
+            Self::DirtyWorkingTree { .. } => "DIRTY_WORKING_TREE",
+            Self::MergeConflict { .. } => "MERGE_CONFLICT",
+            Self::DetachedHead(_) => "DETACHED_HEAD",
+            Self::NothingToCommit(_) => "NOTHING_TO_COMMIT",
+            Self::ProjectLocked(_) => "PROJECT_LOCKED",
+            Self::PathEscapesProject(_) => "PATH_ESCAPES_PROJECT",
+            Self::Timeout { .. } => "TIMEOUT",
+            Self::Internal(_) => "INTERNAL",
+        }
+    }
+
+    /// The variant's name, e.g. "NotFound"
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::NotFound(_) => "NotFound",
+            Self::Conflict(_) => "Conflict",
+            Self::Validation { .. } => "Validation",
+            Self::Io(_) => "Io",
+            Self::Git(_) => "Git",
+            Self::Database(_) => "Database",
+            Self::Unsupported(_) => "Unsupported",
+            Self::StaleCard { .. } => "StaleCard",
+            Self::WipLimitExceeded { .. } => "WipLimitExceeded",
+            Self::InvalidTransition { .. } => "InvalidTransition",
+            Self::ProjectPathExists { .. } => "ProjectPathExists",
+            Self::RepositoryHasNoCommits(_) => "RepositoryHasNoCommits",
+            Self::DirtyWorkingTree { .. } => "DirtyWorkingTree",
+            Self::MergeConflict { .. } => "MergeConflict",
+            Self::DetachedHead(_) => "DetachedHead",
+            Self::NothingToCommit(_) => "NothingToCommit",
+            Self::ProjectLocked(_) => "ProjectLocked",
+            Self::PathEscapesProject(_) => "PathEscapesProject",
+            Self::Timeout { .. } => "Timeout",
+            Self::Internal(_) => "Internal",
+        }
+    }
+
+    /// The variant's fields besides its message, or `None` when it has none
+    pub fn detail(&self) -> Option<serde_json::Value> {
+        let detail = match self {
+            Self::Validation { field, .. } => json!({ "field": field }),
+            Self::StaleCard { current_card, .. } => json!({ "current_card": current_card }),
+            Self::WipLimitExceeded { limit, current, .. } => {
+                json!({ "limit": limit, "current": current })
+            }
+            Self::DirtyWorkingTree { files, .. } | Self::MergeConflict { files, .. } => {
+                json!({ "files": files })
+            }
+            Self::InvalidTransition { from, to, .. } => json!({ "from": from, "to": to }),
+            Self::Timeout { seconds, .. } => json!({ "seconds": seconds }),
+            Self::ProjectPathExists {
+                existing_project_id,
+                existing_project_name,
+            } => json!({
+                "existing_project_id": existing_project_id,
+                "existing_project_name": existing_project_name,
+            }),
+            _ => return None,
+        };
+        Some(detail)
+    }
+}
+
+impl serde::Serialize for AppError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("code", self.code())?;
+        map.serialize_entry("kind", self.kind())?;
+        map.serialize_entry("message", &self.to_string())?;
+
+        let detail = self.detail();
+        // The detail fields also sit at the top level, where the UI reads them
+        if let Some(serde_json::Value::Object(fields)) = &detail {
+            for (key, value) in fields {
+                map.serialize_entry(key, value)?;
+            }
+        }
+        map.serialize_entry("detail", &detail)?;
+
+        map.end()
+    }
+}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(e: rusqlite::Error) -> Self {
+        match e {
+            rusqlite::Error::QueryReturnedNoRows => Self::NotFound("Record not found".to_string()),
+            e if database::is_project_locked(&e) => {
+                Self::ProjectLocked("The project is locked; unlock it to make changes".to_string())
+            }
+            e => Self::Database(e.to_string()),
+        }
+    }
+}
+
+impl From<git2::Error> for AppError {
+    fn from(e: git2::Error) -> Self {
+        Self::Git(e.message().to_string())
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::NotFound => Self::NotFound(e.to_string()),
+            _ => Self::Io(e.to_string()),
+        }
+    }
+}
+
+impl From<CardError> for AppError {
+    fn from(e: CardError) -> Self {
+        match e {
+            CardError::CardNotFound(_) | CardError::ColumnNotFound(_) => {
+                Self::NotFound(e.to_string())
+            }
+            CardError::InvalidStatus(_) => Self::validation("status", e.to_string()),
+            CardError::SelfDependency(_) => Self::validation("depends_on_card_id", e.to_string()),
+            CardError::ColumnNotOnBoard { .. } => {
+                Self::validation("target_column_id", e.to_string())
+            }
+            CardError::OtherProject { .. } => Self::validation("board_id", e.to_string()),
+            CardError::ReassignToSelf(_) => Self::validation("target_column_id", e.to_string()),
+            CardError::MetadataPatchNotObject => Self::validation("patch", e.to_string()),
+            CardError::BoardHasNoColumns(_) | CardError::DependencyCycle(_) => {
+                Self::Conflict(e.to_string())
+            }
+            CardError::InvalidTransition { ref from, ref to } => Self::InvalidTransition {
+                message: e.to_string(),
+                from: from.clone(),
+                to: to.clone(),
+            },
+            CardError::Stale(ref card) => Self::StaleCard {
+                message: e.to_string(),
+                current_card: card.clone(),
+            },
+            CardError::WipLimitExceeded { limit, current, .. } => Self::WipLimitExceeded {
+                message: e.to_string(),
+                limit,
+                current,
+            },
+            CardError::Database(e) => e.into(),
+        }
+    }
+}
+
+/// Compatibility with helpers that still report errors as plain strings
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        Self::Internal(message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        Self::Internal(message.to_string())
+    }
+}
+
+impl From<tauri::Error> for AppError {
+    fn from(e: tauri::Error) -> Self {
+        Self::Internal(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Internal(e.to_string())
+    }
+}
+
+impl From<tokio::task::JoinError> for AppError {
+    fn from(e: tokio::task::JoinError) -> Self {
+        Self::Internal(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::testing::*;
+
+    fn serialized(e: impl Into<AppError>) -> serde_json::Value {
+        serde_json::to_value(e.into()).unwrap()
+    }
+
+    #[test]
+    fn source_errors_serialize_to_their_code_and_kind() {
+        let cases = [
+            (
+                serialized(rusqlite::Error::QueryReturnedNoRows),
+                "NOT_FOUND",
+                "NotFound",
+            ),
+            (
+                serialized(rusqlite::Error::InvalidQuery),
+                "DATABASE",
+                "Database",
+            ),
+            (serialized(git2::Error::from_str("bad ref")), "GIT", "Git"),
+            (
+                serialized(std::io::Error::from(std::io::ErrorKind::NotFound)),
+                "NOT_FOUND",
+                "NotFound",
+            ),
+            (
+                serialized(std::io::Error::from(std::io::ErrorKind::PermissionDenied)),
+                "IO",
+                "Io",
+            ),
+        ];
+        for (value, code, kind) in cases {
+            assert_eq!(value["code"], code, "{}", value);
+            assert_eq!(value["kind"], kind, "{}", value);
+            assert!(value["message"].is_string());
+            assert!(value["detail"].is_null());
+        }
+    }
+
+    #[test]
+    fn a_locked_project_serializes_as_project_locked() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/tmp/locked");
+        db.set_project_locked(&fixture.project_id, true).unwrap();
+
+        let err = db
+            .create_column("new-column", &fixture.board_id, "Doing", 1)
+            .unwrap_err();
+        let value = serialized(err);
+        assert_eq!(value["code"], "PROJECT_LOCKED");
+        assert_eq!(value["kind"], "ProjectLocked");
+    }
+
+    #[test]
+    fn variant_fields_are_in_detail_and_at_the_top_level() {
+        let value = serialized(AppError::WipLimitExceeded {
+            message: "Column is full".to_string(),
+            limit: 3,
+            current: 3,
+        });
+        assert_eq!(
+            value,
+            json!({
+                "code": "WIP_LIMIT_EXCEEDED",
+                "kind": "WipLimitExceeded",
+                "message": "Column is full",
+                "limit": 3,
+                "current": 3,
+                "detail": { "limit": 3, "current": 3 },
+            })
+        );
+
+        let value = serialized(AppError::validation("name", "Name is required"));
+        assert_eq!(value["code"], "VALIDATION");
+        assert_eq!(value["field"], "name");
+        assert_eq!(value["detail"], json!({ "field": "name" }));
+    }
+}
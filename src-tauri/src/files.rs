@@ -1,9 +1,16 @@
 // File operations module
 // This module handles file system operations for the IDE
 
-use notify::{Event, RecursiveMode, Watcher};
-use std::collections::HashMap;
-use std::path::Path;
+use crate::error::AppError;
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 pub struct FileWatcher {
     watchers: HashMap<String, notify::RecommendedWatcher>,
@@ -45,3 +52,1180 @@ impl Default for FileWatcher {
         Self::new()
     }
 }
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DirStats {
+    pub total_bytes: u64,
+    pub file_count: u64,
+    /// Subdirectories, not counting the directory itself
+    pub dir_count: u64,
+}
+
+/// Resolve symlinks and drop trailing separators so one folder always maps to
+/// one `root_path`. Paths that don't exist yet are only trimmed.
+pub fn canonical_root(root_path: &str) -> String {
+    let resolved = Path::new(root_path)
+        .canonicalize()
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| root_path.to_string());
+
+    // Windows canonical paths carry a verbatim prefix the rest of the app
+    // doesn't expect
+    let resolved = resolved.strip_prefix(r"\\?\").unwrap_or(&resolved);
+
+    let trimmed = resolved.trim_end_matches(['/', '\\']);
+    if trimmed.is_empty() || trimmed.ends_with(':') {
+        // Keep the separator of a filesystem root ("/" or "C:\")
+        resolved.to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// `path` with symlinks resolved, provided it stays inside `root`. Relative
+/// paths are taken from `root`. The path doesn't have to exist yet: its
+/// deepest existing ancestor is resolved and the remaining names appended.
+pub fn resolve_in_root(root: &Path, path: &Path) -> Result<PathBuf, AppError> {
+    let root = root
+        .canonicalize()
+        .map_err(|e| AppError::io("Project folder not found", e))?;
+    let escapes = || {
+        AppError::PathEscapesProject(format!("{} is outside the project folder", path.display()))
+    };
+
+    let joined = root.join(path);
+    let mut existing = joined.as_path();
+    let mut missing = Vec::new();
+    // symlink_metadata so a dangling link counts as existing and fails to
+    // resolve, rather than being written through
+    while existing.symlink_metadata().is_err() {
+        missing.push(existing.file_name().ok_or_else(escapes)?);
+        existing = existing.parent().ok_or_else(escapes)?;
+    }
+
+    let mut resolved = existing
+        .canonicalize()
+        .map_err(|e| AppError::io("Failed to resolve path", e))?;
+    resolved.extend(missing.into_iter().rev());
+
+    if !resolved.starts_with(&root) {
+        return Err(escapes());
+    }
+    Ok(resolved)
+}
+
+/// Add up the files under `root`, skipping hidden and gitignored entries
+pub fn directory_stats(root: &Path) -> DirStats {
+    let mut stats = DirStats::default();
+
+    let walker = ignore::WalkBuilder::new(root).build();
+
+    for entry in walker.filter_map(|e| e.ok()) {
+        let Some(file_type) = entry.file_type() else {
+            continue;
+        };
+
+        if file_type.is_dir() {
+            if entry.depth() > 0 {
+                stats.dir_count += 1;
+            }
+        } else if file_type.is_file() {
+            stats.file_count += 1;
+            stats.total_bytes += entry.metadata().map(|m| m.len()).unwrap_or(0);
+        }
+    }
+
+    stats
+}
+
+/// Relative paths of every file under `root`, skipping hidden and gitignored
+/// entries. Separators are always `/`.
+pub fn list_files(root: &Path) -> Vec<String> {
+    let walker = ignore::WalkBuilder::new(root).build();
+
+    walker
+        .filter_map(|e| e.ok())
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .filter_map(|entry| {
+            let relative = entry.path().strip_prefix(root).ok()?;
+            Some(relative.to_string_lossy().replace('\\', "/"))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TodoHit {
+    /// Relative to the scanned root, `/`-separated
+    pub path: String,
+    /// 1-based
+    pub line: usize,
+    /// `TODO`, `FIXME` or `HACK`
+    pub tag: String,
+    /// The rest of the comment after the tag
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TodoScan {
+    pub hits: Vec<TodoHit>,
+    pub files_scanned: usize,
+    /// The walk stopped at the file limit before seeing everything
+    pub truncated: bool,
+}
+
+/// Find TODO, FIXME and HACK comments in the text files under `root`, leaving
+/// out what the file tree's `ignored` rules match. Files over
+/// `max_file_size` bytes are skipped and the walk stops after `max_files`
+/// files. Results are in path order so rescans line up.
+pub fn scan_todos(
+    root: &Path,
+    ignored: &TreeIgnore,
+    max_file_size: u64,
+    max_files: usize,
+) -> TodoScan {
+    // The tag has to follow a comment marker, so identifiers and strings
+    // that merely contain the word don't count
+    let pattern = regex::Regex::new(
+        r"(?://+|#+|/\*+|<!--|--|^\s*\*+)\s*(TODO|FIXME|HACK)\b(?:\([^)]*\))?:?\s*(.*)",
+    )
+    .expect("valid TODO pattern");
+
+    let ignored = ignored.clone();
+    let walk_root = root.to_path_buf();
+    let walker = ignore::WalkBuilder::new(root)
+        .standard_filters(false)
+        .max_depth(Some(TREE_MAX_DEPTH))
+        .filter_entry(move |entry| {
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            entry.depth() == 0
+                || !entry
+                    .path()
+                    .strip_prefix(&walk_root)
+                    .is_ok_and(|relative| ignored.is_ignored(relative, is_dir))
+        })
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+
+    let mut scan = TodoScan::default();
+
+    for entry in walker.filter_map(|e| e.ok()) {
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        if scan.files_scanned >= max_files {
+            scan.truncated = true;
+            break;
+        }
+        scan.files_scanned += 1;
+
+        let too_big = entry.metadata().map_or(true, |m| m.len() > max_file_size);
+        if too_big {
+            continue;
+        }
+        let Ok(bytes) = std::fs::read(entry.path()) else {
+            continue;
+        };
+        // A NUL byte near the start is a good sign of a binary file
+        if bytes[..bytes.len().min(8000)].contains(&0) {
+            continue;
+        }
+        let Ok(relative) = entry.path().strip_prefix(root) else {
+            continue;
+        };
+        let relative = relative.to_string_lossy().replace('\\', "/");
+
+        for (index, line) in String::from_utf8_lossy(&bytes).lines().enumerate() {
+            let Some(captures) = pattern.captures(line) else {
+                continue;
+            };
+            let text = captures[2]
+                .trim()
+                .trim_end_matches("*/")
+                .trim_end_matches("-->")
+                .trim_end();
+
+            scan.hits.push(TodoHit {
+                path: relative.clone(),
+                line: index + 1,
+                tag: captures[1].to_string(),
+                text: text.to_string(),
+            });
+        }
+    }
+
+    scan
+}
+
+/// Directories a `WatchedCache` keeps watchers on; the least recently used
+/// one is dropped, along with what was cached under it, to make room
+const MAX_WATCHED_DIRS: usize = 16;
+
+/// Values a `WatchedCache` holds before dropping the least recently used
+const MAX_CACHED_ENTRIES: usize = 512;
+
+struct CachedEntries<V> {
+    /// Each value with the tick it was last read or written at
+    entries: HashMap<PathBuf, (V, u64)>,
+    tick: u64,
+    /// Bumped on every invalidation so a walk that raced a change isn't cached
+    generation: u64,
+}
+
+impl<V> CachedEntries<V> {
+    fn insert(&mut self, path: PathBuf, value: V) {
+        self.tick += 1;
+        self.entries.insert(path, (value, self.tick));
+        if self.entries.len() > MAX_CACHED_ENTRIES {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(path, _)| path.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// The watched directories, least recently used first. None is inside
+/// another, since watching a directory replaces watchers below it.
+struct WatchedRoots {
+    watcher: FileWatcher,
+    roots: Vec<PathBuf>,
+}
+
+/// Values computed from a directory's contents, by canonical path. Each
+/// queried directory is watched, and any change beneath it drops the cached
+/// values of every directory between the change and the root.
+pub struct WatchedCache<V> {
+    cached: Arc<Mutex<CachedEntries<V>>>,
+    watched: Mutex<WatchedRoots>,
+}
+
+/// `directory_stats` results
+pub type DirStatsCache = WatchedCache<DirStats>;
+
+/// `list_files` results, shared so a hit doesn't copy the list
+pub type FileListCache = WatchedCache<Arc<Vec<String>>>;
+
+impl<V: Clone + Send + 'static> WatchedCache<V> {
+    pub fn new() -> Self {
+        Self {
+            cached: Arc::new(Mutex::new(CachedEntries {
+                entries: HashMap::new(),
+                tick: 0,
+                generation: 0,
+            })),
+            watched: Mutex::new(WatchedRoots {
+                watcher: FileWatcher::new(),
+                roots: Vec::new(),
+            }),
+        }
+    }
+
+    /// The value for the directory at `path`, running `compute` only on a miss
+    pub fn get_or_compute(
+        &self,
+        path: &Path,
+        compute: impl FnOnce(&Path) -> V,
+    ) -> std::io::Result<V> {
+        let path = path.canonicalize()?;
+
+        let generation = {
+            let mut cached = self.cached.lock();
+            cached.tick += 1;
+            let tick = cached.tick;
+            if let Some((value, used)) = cached.entries.get_mut(&path) {
+                *used = tick;
+                let value = value.clone();
+                drop(cached);
+                // Already covered, so this only marks its watcher as used
+                self.watch(&path);
+                return Ok(value);
+            }
+            cached.generation
+        };
+
+        let value = compute(&path);
+
+        // Without a watcher nothing would tell us the value went stale
+        if self.watch(&path) {
+            let mut cached = self.cached.lock();
+            if cached.generation == generation {
+                cached.insert(path, value.clone());
+            }
+        }
+
+        Ok(value)
+    }
+
+    /// Make sure changes under `path` invalidate the cache. Returns false when
+    /// the path can't be watched.
+    fn watch(&self, path: &Path) -> bool {
+        let mut watched = self.watched.lock();
+        if let Some(index) = watched.roots.iter().position(|root| path.starts_with(root)) {
+            let root = watched.roots.remove(index);
+            watched.roots.push(root);
+            return true;
+        }
+
+        let cached = Arc::clone(&self.cached);
+        let key = path.to_string_lossy().into_owned();
+        let watching = watched
+            .watcher
+            .watch_folder(&key, &key, move |event| {
+                // Reads change nothing, and the watcher's own setup walk
+                // would otherwise throw away what was just computed
+                if matches!(event.kind, EventKind::Access(_)) {
+                    return;
+                }
+                let mut cached = cached.lock();
+                cached.generation += 1;
+                cached.entries.retain(|dir, _| {
+                    !event
+                        .paths
+                        .iter()
+                        .any(|changed| changed.starts_with(dir) || dir.starts_with(changed))
+                });
+            })
+            .is_ok();
+        if !watching {
+            return false;
+        }
+
+        // The new watcher covers everything below it, entries included
+        let WatchedRoots { watcher, roots } = &mut *watched;
+        roots.retain(|root| {
+            let inside = root.starts_with(path);
+            if inside {
+                watcher.unwatch(&root.to_string_lossy());
+            }
+            !inside
+        });
+        roots.push(path.to_path_buf());
+
+        if roots.len() > MAX_WATCHED_DIRS {
+            // Nothing would tell us its values went stale any more
+            let oldest = roots.remove(0);
+            watcher.unwatch(&oldest.to_string_lossy());
+            self.cached
+                .lock()
+                .entries
+                .retain(|dir, _| !dir.starts_with(&oldest));
+        }
+        true
+    }
+}
+
+impl<V: Clone + Send + 'static> Default for WatchedCache<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileNode {
+    pub name: String,
+    pub path: String,
+    pub is_directory: bool,
+    pub children: Option<Vec<FileNode>>,
+    pub size: Option<u64>,
+    pub modified: Option<i64>,
+}
+
+/// How many levels below the project root the file tree goes
+pub const TREE_MAX_DEPTH: usize = 10;
+
+/// Quiet period before watcher events are applied to the cached tree, long
+/// enough to swallow an editor's write-temp-then-rename save
+const TREE_DEBOUNCE: Duration = Duration::from_millis(150);
+
+const TREE_WATCH_KEY: &str = "file-tree";
+
+/// What the file tree leaves out: hidden entries unless told otherwise, plus
+/// anything matching one of the ignore patterns. A pattern is a glob matched
+/// against an entry's name (`node_modules`, `*.lock`), or against its path
+/// from the root when it contains a `/` (`src/generated`). A trailing `/`
+/// limits it to directories (`coverage/`).
+#[derive(Debug, Clone)]
+pub struct TreeIgnore {
+    patterns: Vec<String>,
+    hide_hidden: bool,
+    names: GlobSet,
+    names_dir_only: Vec<bool>,
+    paths: GlobSet,
+    paths_dir_only: Vec<bool>,
+}
+
+impl TreeIgnore {
+    /// Hidden entries plus `patterns`. Patterns that aren't valid globs are
+    /// skipped.
+    pub fn new(patterns: Vec<String>) -> Self {
+        Self::build(patterns, true)
+    }
+
+    /// Only what `patterns` match, hidden entries included, so an empty list
+    /// shows everything
+    pub fn only(patterns: Vec<String>) -> Self {
+        Self::build(patterns, false)
+    }
+
+    fn build(patterns: Vec<String>, hide_hidden: bool) -> Self {
+        let mut names = GlobSetBuilder::new();
+        let mut names_dir_only = Vec::new();
+        let mut paths = GlobSetBuilder::new();
+        let mut paths_dir_only = Vec::new();
+
+        for pattern in &patterns {
+            let Some((glob, anchored, dir_only)) = parse_ignore_pattern(pattern) else {
+                continue;
+            };
+            if anchored {
+                paths.add(glob);
+                paths_dir_only.push(dir_only);
+            } else {
+                names.add(glob);
+                names_dir_only.push(dir_only);
+            }
+        }
+
+        Self {
+            patterns,
+            hide_hidden,
+            names: names.build().unwrap_or_else(|_| GlobSet::empty()),
+            names_dir_only,
+            paths: paths.build().unwrap_or_else(|_| GlobSet::empty()),
+            paths_dir_only,
+        }
+    }
+
+    /// Whether the entry at `relative`, its path below the tree's root, is
+    /// left out. Only the entry itself is checked, not its parents.
+    pub fn is_ignored(&self, relative: &Path, is_dir: bool) -> bool {
+        let name = relative
+            .file_name()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_default();
+        if self.hide_hidden && name.starts_with('.') {
+            return true;
+        }
+
+        let path = relative.to_string_lossy().replace('\\', "/");
+        let hit = |set: &GlobSet, dir_only: &[bool], candidate: &str| {
+            set.matches(candidate)
+                .into_iter()
+                .any(|index| is_dir || !dir_only[index])
+        };
+        hit(&self.names, &self.names_dir_only, &name)
+            || hit(&self.paths, &self.paths_dir_only, &path)
+    }
+}
+
+impl PartialEq for TreeIgnore {
+    fn eq(&self, other: &Self) -> bool {
+        self.patterns == other.patterns && self.hide_hidden == other.hide_hidden
+    }
+}
+
+pub fn is_valid_ignore_pattern(pattern: &str) -> bool {
+    parse_ignore_pattern(pattern).is_some()
+}
+
+/// The glob for `pattern`, whether it is matched against the whole path, and
+/// whether it only applies to directories
+fn parse_ignore_pattern(pattern: &str) -> Option<(Glob, bool, bool)> {
+    let dir_only = pattern.ends_with('/');
+    let trimmed = pattern.trim_end_matches('/');
+    let anchored = trimmed.contains('/');
+    let trimmed = trimmed.trim_start_matches('/');
+    if trimmed.is_empty() {
+        return None;
+    }
+
+    let glob = GlobBuilder::new(trimmed)
+        .literal_separator(true)
+        .build()
+        .ok()?;
+    Some((glob, anchored, dir_only))
+}
+
+/// Directories first, then files, alphabetically
+fn sort_file_nodes(nodes: &mut [FileNode]) {
+    nodes.sort_by(|a, b| match (a.is_directory, b.is_directory) {
+        (true, false) => std::cmp::Ordering::Less,
+        (false, true) => std::cmp::Ordering::Greater,
+        _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+    });
+}
+
+/// Read `path` and everything below it from disk, leaving out what `ignored`
+/// matches. `current_depth` is how far `path` is below the tree's root.
+pub fn build_file_tree(
+    path: &Path,
+    ignored: &TreeIgnore,
+    max_depth: usize,
+    current_depth: usize,
+) -> Option<FileNode> {
+    if current_depth > max_depth {
+        return None;
+    }
+
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string());
+
+    let metadata = std::fs::metadata(path).ok();
+    let is_directory = path.is_dir();
+
+    // The root is matched by its name alone
+    let relative = match path.ancestors().nth(current_depth) {
+        Some(root) if current_depth > 0 => path.strip_prefix(root).unwrap_or(path),
+        _ => Path::new(&name),
+    };
+    if ignored.is_ignored(relative, is_directory) {
+        return None;
+    }
+
+    let (size, modified) = metadata
+        .map(|m| {
+            (
+                if is_directory { None } else { Some(m.len()) },
+                m.modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs() as i64),
+            )
+        })
+        .unwrap_or((None, None));
+
+    let children = if is_directory {
+        let mut entries: Vec<FileNode> = std::fs::read_dir(path)
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                build_file_tree(&entry.path(), ignored, max_depth, current_depth + 1)
+            })
+            .collect();
+        sort_file_nodes(&mut entries);
+        Some(entries)
+    } else {
+        None
+    };
+
+    Some(FileNode {
+        name,
+        path: path.to_string_lossy().to_string(),
+        is_directory,
+        children,
+        size,
+        modified,
+    })
+}
+
+/// A change to the cached file tree, sent so the UI can patch its copy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TreeUpdate {
+    /// `node` appeared in the directory at `parent`
+    Added {
+        parent: String,
+        node: FileNode,
+    },
+    Removed {
+        path: String,
+    },
+    /// Moved or renamed; `node` carries the new path
+    Renamed {
+        from: String,
+        parent: String,
+        node: FileNode,
+    },
+    /// A file's size or modified time changed, or it became a directory
+    Changed {
+        node: FileNode,
+    },
+}
+
+/// The `tree-updated` payload: an update tagged with the root it belongs to,
+/// so a listener can drop changes to a tree it no longer shows
+#[derive(Debug, Clone, Serialize)]
+pub struct TreeEvent {
+    pub root: String,
+    #[serde(flatten)]
+    pub update: TreeUpdate,
+}
+
+type TreeListener = Arc<dyn Fn(TreeEvent) + Send + Sync>;
+
+struct TreeState {
+    root: PathBuf,
+    ignored: TreeIgnore,
+    /// `None` until the first build finishes or once the root is gone. Events
+    /// arriving before then wait in `pending`.
+    tree: Option<FileNode>,
+    on_update: TreeListener,
+    /// Paths touched since the last flush
+    pending: BTreeSet<PathBuf>,
+    renames: Vec<(PathBuf, PathBuf)>,
+    last_event: Instant,
+    flush_scheduled: bool,
+}
+
+/// The active project's file tree, kept in memory and patched from watcher
+/// events instead of re-walking the disk on every request. Only one root is
+/// tracked; asking for another one moves the watcher there.
+pub struct FileTreeCache {
+    state: Arc<Mutex<Option<TreeState>>>,
+    watcher: Mutex<FileWatcher>,
+}
+
+impl FileTreeCache {
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(None)),
+            watcher: Mutex::new(FileWatcher::new()),
+        }
+    }
+
+    /// The tree rooted at `root` without what `ignored` matches, from memory
+    /// when it is already being watched with the same ignores. `on_update`
+    /// receives every later change to it. `None` when `root` is missing,
+    /// hidden or ignored.
+    pub fn get(
+        &self,
+        root: &Path,
+        ignored: TreeIgnore,
+        on_update: impl Fn(TreeEvent) + Send + Sync + 'static,
+    ) -> Option<FileNode> {
+        // The watcher reports resolved paths, so match them against the same
+        let root = root.canonicalize().ok()?;
+
+        if let Some(state) = self.state.lock().as_ref() {
+            if state.root == root && state.ignored == ignored && root.exists() {
+                if let Some(tree) = &state.tree {
+                    return Some(tree.clone());
+                }
+            }
+        }
+
+        // Watch before walking so nothing that changes mid-walk is missed
+        let mut watcher = self.watcher.lock();
+        watcher.unwatch(TREE_WATCH_KEY);
+        *self.state.lock() = Some(TreeState {
+            root: root.clone(),
+            ignored: ignored.clone(),
+            tree: None,
+            on_update: Arc::new(on_update),
+            pending: BTreeSet::new(),
+            renames: Vec::new(),
+            last_event: Instant::now(),
+            flush_scheduled: false,
+        });
+
+        let shared = Arc::clone(&self.state);
+        let watched_root = root.clone();
+        let watching = watcher
+            .watch_folder(TREE_WATCH_KEY, &root.to_string_lossy(), move |event| {
+                queue_tree_event(&shared, &watched_root, event)
+            })
+            .is_ok();
+
+        let tree = build_file_tree(&root, &ignored, TREE_MAX_DEPTH, 0)?;
+
+        // Without a watcher the copy could go stale, so don't keep it
+        if watching {
+            if let Some(state) = self.state.lock().as_mut() {
+                if state.root == root {
+                    state.tree = Some(tree.clone());
+                    // Whatever changed mid-walk is compared against the disk
+                    // again, so events the walk already saw produce nothing
+                    if !state.pending.is_empty() {
+                        schedule_flush(&self.state, state);
+                    }
+                }
+            }
+        }
+
+        Some(tree)
+    }
+}
+
+impl Default for FileTreeCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Where `path` sits below `root`, or `None` when the tree doesn't show it
+fn tree_relative<'a>(root: &Path, ignored: &TreeIgnore, path: &'a Path) -> Option<&'a Path> {
+    let relative = path.strip_prefix(root).ok()?;
+    let depth = relative.components().count();
+    if depth == 0 || depth > TREE_MAX_DEPTH {
+        return None;
+    }
+
+    // Every parent is a directory; the path itself may already be gone
+    let mut prefix = PathBuf::new();
+    for (index, part) in relative.components().enumerate() {
+        prefix.push(part);
+        let is_dir = index + 1 < depth || path.is_dir();
+        if ignored.is_ignored(&prefix, is_dir) {
+            return None;
+        }
+    }
+    Some(relative)
+}
+
+fn queue_tree_event(shared: &Arc<Mutex<Option<TreeState>>>, root: &Path, event: Event) {
+    if matches!(event.kind, EventKind::Access(_)) {
+        return;
+    }
+
+    let mut guard = shared.lock();
+    let Some(state) = guard.as_mut().filter(|state| state.root == root) else {
+        return;
+    };
+
+    if let EventKind::Modify(ModifyKind::Name(RenameMode::Both)) = event.kind {
+        if let [from, to] = event.paths.as_slice() {
+            state.renames.push((from.clone(), to.clone()));
+        }
+    }
+
+    let before = state.pending.len();
+    state.pending.extend(
+        event
+            .paths
+            .into_iter()
+            .filter(|path| tree_relative(root, &state.ignored, path).is_some()),
+    );
+    if state.pending.len() == before {
+        return;
+    }
+
+    state.last_event = Instant::now();
+    if state.tree.is_some() {
+        schedule_flush(shared, state);
+    }
+}
+
+fn schedule_flush(shared: &Arc<Mutex<Option<TreeState>>>, state: &mut TreeState) {
+    if !state.flush_scheduled {
+        state.flush_scheduled = true;
+        let shared = Arc::clone(shared);
+        std::thread::spawn(move || flush_tree_events(shared));
+    }
+}
+
+/// Wait for the events to go quiet, then patch the tree and notify
+fn flush_tree_events(shared: Arc<Mutex<Option<TreeState>>>) {
+    loop {
+        std::thread::sleep(TREE_DEBOUNCE);
+
+        let mut guard = shared.lock();
+        let Some(state) = guard.as_mut() else {
+            return;
+        };
+        if state.last_event.elapsed() < TREE_DEBOUNCE {
+            continue;
+        }
+
+        state.flush_scheduled = false;
+        let updates = state.apply_pending();
+        let on_update = Arc::clone(&state.on_update);
+        let root = state.root.to_string_lossy().into_owned();
+        drop(guard);
+
+        for update in updates {
+            on_update(TreeEvent {
+                root: root.clone(),
+                update,
+            });
+        }
+        return;
+    }
+}
+
+impl TreeState {
+    /// Bring every pending path in line with the disk. A path is compared as
+    /// it is now, so a file created and deleted within one debounce window
+    /// produces nothing and an atomic save shows up as a single change.
+    fn apply_pending(&mut self) -> Vec<TreeUpdate> {
+        // Nothing to patch yet; keep the paths for when the build lands
+        if self.tree.is_none() {
+            return Vec::new();
+        }
+        let pending = std::mem::take(&mut self.pending);
+        let renames = std::mem::take(&mut self.renames);
+        let root = self.root.clone();
+        let ignored = &self.ignored;
+        let Some(tree) = self.tree.as_mut() else {
+            return Vec::new();
+        };
+
+        let mut updates = Vec::new();
+        // Subtrees already replaced wholesale; later paths inside them are done
+        let mut handled: Vec<PathBuf> = Vec::new();
+
+        for (from, to) in renames {
+            let (Some(from_rel), Some(to_rel)) = (
+                tree_relative(&root, ignored, &from),
+                tree_relative(&root, ignored, &to),
+            ) else {
+                continue;
+            };
+            if from.exists() || !to.exists() || tree_node_mut(tree, to_rel).is_some() {
+                continue;
+            }
+            let Some(node) =
+                build_file_tree(&to, ignored, TREE_MAX_DEPTH, to_rel.components().count())
+            else {
+                continue;
+            };
+            if remove_tree_node(tree, from_rel) && insert_tree_node(tree, to_rel, node.clone()) {
+                updates.push(TreeUpdate::Renamed {
+                    from: from.to_string_lossy().into_owned(),
+                    parent: parent_path(&to),
+                    node,
+                });
+                handled.push(from);
+                handled.push(to);
+            }
+        }
+
+        for path in pending {
+            if handled.iter().any(|done| path.starts_with(done)) {
+                continue;
+            }
+            let Some(relative) = tree_relative(&root, ignored, &path) else {
+                continue;
+            };
+
+            let on_disk = path
+                .exists()
+                .then(|| {
+                    build_file_tree(
+                        &path,
+                        ignored,
+                        TREE_MAX_DEPTH,
+                        relative.components().count(),
+                    )
+                })
+                .flatten();
+            let cached = tree_node_mut(tree, relative);
+
+            match (cached, on_disk) {
+                (None, None) => {}
+                (None, Some(node)) => {
+                    if insert_tree_node(tree, relative, node.clone()) {
+                        updates.push(TreeUpdate::Added {
+                            parent: parent_path(&path),
+                            node,
+                        });
+                        handled.push(path);
+                    }
+                }
+                (Some(_), None) => {
+                    remove_tree_node(tree, relative);
+                    updates.push(TreeUpdate::Removed {
+                        path: path.to_string_lossy().into_owned(),
+                    });
+                    handled.push(path);
+                }
+                (Some(cached), Some(node)) => {
+                    // A directory's own timestamps change with every child;
+                    // the children report themselves
+                    let changed = cached.is_directory != node.is_directory
+                        || (!node.is_directory
+                            && (cached.size, cached.modified) != (node.size, node.modified));
+                    if changed {
+                        *cached = node.clone();
+                        updates.push(TreeUpdate::Changed { node });
+                    }
+                }
+            }
+        }
+
+        updates
+    }
+}
+
+fn parent_path(path: &Path) -> String {
+    path.parent()
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn tree_node_mut<'a>(tree: &'a mut FileNode, relative: &Path) -> Option<&'a mut FileNode> {
+    let mut node = tree;
+    for part in relative.components() {
+        let name = part.as_os_str().to_string_lossy();
+        node = node
+            .children
+            .as_mut()?
+            .iter_mut()
+            .find(|child| child.name == name)?;
+    }
+    Some(node)
+}
+
+/// Add `node` to its parent directory; false when the parent isn't in the tree
+fn insert_tree_node(tree: &mut FileNode, relative: &Path, node: FileNode) -> bool {
+    let Some(children) = relative
+        .parent()
+        .and_then(|parent| tree_node_mut(tree, parent))
+        .and_then(|parent| parent.children.as_mut())
+    else {
+        return false;
+    };
+
+    children.retain(|child| child.name != node.name);
+    children.push(node);
+    sort_file_nodes(children);
+    true
+}
+
+fn remove_tree_node(tree: &mut FileNode, relative: &Path) -> bool {
+    let (Some(parent), Some(name)) = (relative.parent(), relative.file_name()) else {
+        return false;
+    };
+    let Some(children) = tree_node_mut(tree, parent).and_then(|p| p.children.as_mut()) else {
+        return false;
+    };
+
+    let name = name.to_string_lossy();
+    let before = children.len();
+    children.retain(|child| child.name != name);
+    children.len() != before
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every path in `node`, below its root, directories with a trailing `/`
+    fn tree_paths(node: &FileNode, root: &Path, out: &mut Vec<String>) {
+        for child in node.children.iter().flatten() {
+            let relative = Path::new(&child.path).strip_prefix(root).unwrap();
+            let mut path = relative.to_string_lossy().replace('\\', "/");
+            if child.is_directory {
+                path.push('/');
+            }
+            out.push(path);
+            tree_paths(child, root, out);
+        }
+    }
+
+    #[test]
+    fn glob_patterns_prune_matching_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("project");
+        for folder in ["src/generated", "coverage", "docs"] {
+            std::fs::create_dir_all(root.join(folder)).unwrap();
+        }
+        for file in [
+            "Cargo.lock",
+            "src/main.rs",
+            "src/generated/api.rs",
+            "coverage/index.html",
+            "docs/coverage",
+            ".env",
+        ] {
+            std::fs::write(root.join(file), "").unwrap();
+        }
+
+        let shown = |ignored: TreeIgnore| {
+            let tree = build_file_tree(&root, &ignored, TREE_MAX_DEPTH, 0).unwrap();
+            let mut paths = Vec::new();
+            tree_paths(&tree, &root, &mut paths);
+            paths
+        };
+
+        let patterns = ["*.lock", "coverage/", "src/generated"].map(String::from);
+        assert_eq!(
+            shown(TreeIgnore::new(patterns.to_vec())),
+            ["docs/", "docs/coverage", "src/", "src/main.rs"]
+        );
+        assert_eq!(
+            shown(TreeIgnore::only(patterns.to_vec())),
+            ["docs/", "docs/coverage", "src/", "src/main.rs", ".env"]
+        );
+        assert_eq!(shown(TreeIgnore::only(Vec::new())).len(), 10);
+    }
+
+    #[test]
+    fn directory_stats_add_up_visible_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("project");
+        std::fs::create_dir_all(root.join("src/nested")).unwrap();
+        std::fs::create_dir_all(root.join("build")).unwrap();
+        git2::Repository::init(&root).unwrap();
+        for (file, size) in [
+            ("README.md", 10),
+            ("src/lib.rs", 200),
+            ("src/nested/mod.rs", 3000),
+            (".env", 40_000),
+            ("build/out.bin", 500_000),
+        ] {
+            std::fs::write(root.join(file), vec![b'x'; size]).unwrap();
+        }
+        std::fs::write(root.join(".gitignore"), "build/\n").unwrap();
+
+        assert_eq!(
+            directory_stats(&root),
+            DirStats {
+                total_bytes: 3210,
+                file_count: 3,
+                dir_count: 2,
+            }
+        );
+        assert_eq!(directory_stats(&root.join("src/nested")).total_bytes, 3000);
+    }
+
+    #[test]
+    fn resolve_in_root_rejects_paths_that_climb_out() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir(root.path().join("src")).unwrap();
+
+        for escape in ["../../etc", "src/../../etc/passwd", "/etc"] {
+            let err = resolve_in_root(root.path(), Path::new(escape)).unwrap_err();
+            assert_eq!(err.code(), "PATH_ESCAPES_PROJECT", "{}", escape);
+        }
+
+        let inside = resolve_in_root(root.path(), Path::new("src/new/file.rs")).unwrap();
+        let canonical = root.path().canonicalize().unwrap();
+        assert_eq!(inside, canonical.join("src/new/file.rs"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resolve_in_root_follows_symlinks_out_of_the_root() {
+        let root = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::os::unix::fs::symlink(outside.path(), root.path().join("link")).unwrap();
+
+        let err = resolve_in_root(root.path(), Path::new("link/file")).unwrap_err();
+        assert_eq!(err.code(), "PATH_ESCAPES_PROJECT");
+    }
+
+    fn tree_state(root: &Path, tx: std::sync::mpsc::Sender<TreeEvent>) -> TreeState {
+        TreeState {
+            root: root.to_path_buf(),
+            ignored: TreeIgnore::new(Vec::new()),
+            tree: None,
+            on_update: Arc::new(move |event| {
+                let _ = tx.send(event);
+            }),
+            pending: BTreeSet::new(),
+            renames: Vec::new(),
+            last_event: Instant::now(),
+            flush_scheduled: false,
+        }
+    }
+
+    #[test]
+    fn tree_events_wait_for_the_first_build() {
+        // Temp dirs start with a dot, which the tree would hide
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("project")).unwrap();
+        let root = dir.path().join("project").canonicalize().unwrap();
+        let ignored = TreeIgnore::new(Vec::new());
+        let (tx, rx) = std::sync::mpsc::channel();
+        let shared = Arc::new(Mutex::new(Some(tree_state(&root, tx))));
+
+        // The walk finished before the file appeared but hasn't been stored yet
+        let walked = build_file_tree(&root, &ignored, TREE_MAX_DEPTH, 0).unwrap();
+        std::fs::write(root.join("new.txt"), "x").unwrap();
+        let event = Event::new(EventKind::Create(notify::event::CreateKind::File))
+            .add_path(root.join("new.txt"));
+        queue_tree_event(&shared, &root, event);
+
+        std::thread::sleep(TREE_DEBOUNCE * 3);
+        assert!(rx.try_recv().is_err());
+
+        {
+            let mut guard = shared.lock();
+            let state = guard.as_mut().unwrap();
+            state.tree = Some(walked);
+            schedule_flush(&shared, state);
+        }
+
+        let event = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(event.root, root.to_string_lossy());
+        match event.update {
+            TreeUpdate::Added { parent, node } => {
+                assert_eq!(parent, root.to_string_lossy());
+                assert_eq!(node.name, "new.txt");
+            }
+            other => panic!("expected an addition, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn tree_cache_canonicalizes_the_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("project");
+        std::fs::create_dir_all(project.join("sub")).unwrap();
+        let roundabout = project.join("sub").join("..");
+
+        let cache = FileTreeCache::new();
+        let tree = cache
+            .get(&roundabout, TreeIgnore::new(Vec::new()), |_| {})
+            .unwrap();
+
+        let canonical = project.canonicalize().unwrap();
+        assert_eq!(tree.path, canonical.to_string_lossy());
+        assert_eq!(cache.state.lock().as_ref().unwrap().root, canonical);
+    }
+
+    #[test]
+    fn watching_a_parent_replaces_the_watchers_below_it() {
+        let dir = tempfile::tempdir().unwrap();
+        let parent = dir.path().canonicalize().unwrap();
+        let child = parent.join("child");
+        std::fs::create_dir(&child).unwrap();
+
+        let cache: WatchedCache<u32> = WatchedCache::new();
+        assert_eq!(cache.get_or_compute(&child, |_| 1).unwrap(), 1);
+        assert_eq!(cache.get_or_compute(&parent, |_| 2).unwrap(), 2);
+
+        let watched = cache.watched.lock();
+        assert_eq!(watched.roots, std::slice::from_ref(&parent));
+        assert_eq!(watched.watcher.watchers.len(), 1);
+        drop(watched);
+        // Still covered by the parent's watcher, so still served from memory
+        assert_eq!(cache.get_or_compute(&child, |_| 3).unwrap(), 1);
+    }
+
+    #[test]
+    fn least_recently_used_watch_is_dropped_with_its_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let dirs: Vec<PathBuf> = (0..=MAX_WATCHED_DIRS)
+            .map(|n| {
+                let path = dir.path().join(format!("dir{}", n));
+                std::fs::create_dir(&path).unwrap();
+                path.canonicalize().unwrap()
+            })
+            .collect();
+
+        let cache: WatchedCache<usize> = WatchedCache::new();
+        for (n, path) in dirs.iter().enumerate().take(MAX_WATCHED_DIRS) {
+            cache.get_or_compute(path, |_| n).unwrap();
+        }
+        // Touch the oldest so the second one becomes least recently used
+        assert_eq!(cache.get_or_compute(&dirs[0], |_| 99).unwrap(), 0);
+        cache
+            .get_or_compute(&dirs[MAX_WATCHED_DIRS], |_| MAX_WATCHED_DIRS)
+            .unwrap();
+
+        let watched = cache.watched.lock();
+        assert_eq!(watched.roots.len(), MAX_WATCHED_DIRS);
+        assert_eq!(watched.watcher.watchers.len(), MAX_WATCHED_DIRS);
+        assert!(!watched.roots.contains(&dirs[1]));
+        drop(watched);
+        assert!(!cache.cached.lock().entries.contains_key(&dirs[1]));
+        assert_eq!(cache.get_or_compute(&dirs[0], |_| 99).unwrap(), 0);
+    }
+}
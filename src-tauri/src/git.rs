@@ -1,7 +1,36 @@
 // Git operations module
 // Provides Ghost Mode functionality for safe, isolated code changes
 
-use git2::{BranchType, Repository, Signature};
+use crate::error::AppError;
+use git2::{BranchType, Commit, ErrorCode, Repository, Signature};
+use std::collections::HashSet;
+
+const DEFAULT_BRANCHES: [&str; 2] = ["main", "master"];
+
+pub(crate) fn is_default_branch(name: &str) -> bool {
+    DEFAULT_BRANCHES.contains(&name)
+}
+
+/// main, or else master
+pub(crate) fn default_branch_name(repo: &Repository) -> Option<&'static str> {
+    DEFAULT_BRANCHES
+        .into_iter()
+        .find(|name| repo.find_branch(name, BranchType::Local).is_ok())
+}
+
+/// The commit HEAD points at, or `RepositoryHasNoCommits` while HEAD is unborn
+pub(crate) fn head_commit(repo: &Repository) -> Result<Commit<'_>, AppError> {
+    match repo.head() {
+        Ok(head) => Ok(head.peel_to_commit()?),
+        Err(e) if e.code() == ErrorCode::UnbornBranch => {
+            Err(AppError::RepositoryHasNoCommits(format!(
+                "{} has no commits yet",
+                repo.workdir().unwrap_or_else(|| repo.path()).display()
+            )))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
 
 pub struct GhostMode {
     repo: Repository,
@@ -15,52 +44,40 @@ impl GhostMode {
         Ok(Self { repo })
     }
 
-    pub fn create_ghost_branch(&self, card_id: &str) -> Result<String, String> {
-        let head = self.repo.head().map_err(|e| e.to_string())?;
-
-        let commit = head.peel_to_commit().map_err(|e| e.to_string())?;
+    /// Branch off HEAD and check the branch out. Fails with
+    /// `RepositoryHasNoCommits` while there is nothing to branch from.
+    pub fn create_ghost_branch(&self, card_id: &str) -> Result<String, AppError> {
+        let commit = head_commit(&self.repo)?;
 
         let branch_name = format!("ghost/{}/{}", card_id, chrono::Utc::now().timestamp());
 
-        self.repo
-            .branch(&branch_name, &commit, false)
-            .map_err(|e| e.to_string())?;
+        self.repo.branch(&branch_name, &commit, false)?;
 
         // Checkout the ghost branch
         let obj = self
             .repo
-            .revparse_single(&format!("refs/heads/{}", branch_name))
-            .map_err(|e| e.to_string())?;
+            .revparse_single(&format!("refs/heads/{}", branch_name))?;
 
-        self.repo
-            .checkout_tree(&obj, None)
-            .map_err(|e| e.to_string())?;
+        self.repo.checkout_tree(&obj, None)?;
 
-        self.repo
-            .set_head(&format!("refs/heads/{}", branch_name))
-            .map_err(|e| e.to_string())?;
+        self.repo.set_head(&format!("refs/heads/{}", branch_name))?;
 
         Ok(branch_name)
     }
 
     pub fn merge_to_main(&self, ghost_branch: &str) -> Result<(), String> {
         // Checkout main
+        let main_branch = default_branch_name(&self.repo)
+            .ok_or_else(|| "The repository has no main or master branch".to_string())?;
         let main = self
             .repo
-            .revparse_single("main")
-            .or_else(|_| self.repo.revparse_single("master"))
+            .revparse_single(main_branch)
             .map_err(|e| e.to_string())?;
 
         self.repo
             .checkout_tree(&main, None)
             .map_err(|e| e.to_string())?;
 
-        let main_branch = if self.repo.revparse_single("main").is_ok() {
-            "main"
-        } else {
-            "master"
-        };
-
         self.repo
             .set_head(&format!("refs/heads/{}", main_branch))
             .map_err(|e| e.to_string())?;
@@ -147,6 +164,102 @@ impl GhostMode {
         Ok(diff_text)
     }
 
+    /// Delete ghost branches whose embedded timestamp is older than `max_age_secs`.
+    /// Unless `force` is set, only branches fully merged into main are removed,
+    /// so no work is lost; the currently checked-out branch is never touched.
+    pub fn prune_ghost_branches(
+        &self,
+        max_age_secs: i64,
+        force: bool,
+    ) -> Result<Vec<String>, String> {
+        let cutoff = chrono::Utc::now().timestamp() - max_age_secs;
+
+        let main_oid = default_branch_name(&self.repo)
+            .and_then(|name| self.repo.find_branch(name, BranchType::Local).ok())
+            .and_then(|b| b.get().target());
+
+        let head_name = self
+            .repo
+            .head()
+            .ok()
+            .and_then(|h| h.shorthand().map(|s| s.to_string()));
+
+        let mut deleted = Vec::new();
+
+        for name in self.list_ghost_branches()? {
+            if head_name.as_deref() == Some(name.as_str()) {
+                continue;
+            }
+
+            // Branch names look like ghost/<card_id>/<timestamp>
+            let created_at = match name.rsplit('/').next().map(str::parse::<i64>) {
+                Some(Ok(ts)) => ts,
+                _ => continue,
+            };
+
+            if created_at > cutoff {
+                continue;
+            }
+
+            let mut branch = self
+                .repo
+                .find_branch(&name, BranchType::Local)
+                .map_err(|e| e.to_string())?;
+
+            let merged = match (branch.get().target(), main_oid) {
+                (Some(tip), Some(main)) => {
+                    tip == main
+                        || self
+                            .repo
+                            .graph_descendant_of(main, tip)
+                            .map_err(|e| e.to_string())?
+                }
+                _ => false,
+            };
+
+            if !merged && !force {
+                continue;
+            }
+
+            branch.delete().map_err(|e| e.to_string())?;
+            deleted.push(name);
+        }
+
+        Ok(deleted)
+    }
+
+    /// Delete every ghost branch that belongs to one of `card_ids`, returning the
+    /// names deleted and the names that could not be (e.g. the checked-out one)
+    pub fn delete_card_branches(
+        &self,
+        card_ids: &HashSet<String>,
+    ) -> Result<(Vec<String>, Vec<String>), String> {
+        let mut deleted = Vec::new();
+        let mut failed = Vec::new();
+
+        for name in self.list_ghost_branches()? {
+            // Branch names look like ghost/<card_id>/<timestamp>
+            let belongs = name
+                .split('/')
+                .nth(1)
+                .is_some_and(|card_id| card_ids.contains(card_id));
+            if !belongs {
+                continue;
+            }
+
+            let result = self
+                .repo
+                .find_branch(&name, BranchType::Local)
+                .and_then(|mut branch| branch.delete());
+            match result {
+                Ok(()) => deleted.push(name),
+                Err(_) => failed.push(name),
+            }
+        }
+
+        Ok((deleted, failed))
+    }
+
     pub fn list_ghost_branches(&self) -> Result<Vec<String>, String> {
         let branches = self
             .repo
@@ -162,3 +275,89 @@ impl GhostMode {
         Ok(ghost_branches)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Commit an empty tree on `refs/heads/<branch>`, on top of `parent`
+    fn commit_on(repo: &Repository, branch: &str, parent: Option<git2::Oid>) -> git2::Oid {
+        let signature = Signature::now("Test", "test@example.com").unwrap();
+        let tree = repo
+            .find_tree(repo.treebuilder(None).unwrap().write().unwrap())
+            .unwrap();
+        let parent = parent.map(|oid| repo.find_commit(oid).unwrap());
+        let parents: Vec<_> = parent.iter().collect();
+        repo.commit(
+            Some(&format!("refs/heads/{}", branch)),
+            &signature,
+            &signature,
+            branch,
+            &tree,
+            &parents,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn prune_only_deletes_old_branches_merged_into_master() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let base = commit_on(&repo, "master", None);
+        repo.set_head("refs/heads/master").unwrap();
+        let base_commit = repo.find_commit(base).unwrap();
+
+        let recent = format!("ghost/recent/{}", chrono::Utc::now().timestamp());
+        for name in ["ghost/merged/1", recent.as_str()] {
+            repo.branch(name, &base_commit, false).unwrap();
+        }
+        commit_on(&repo, "ghost/unmerged/1", Some(base));
+
+        let ghost = GhostMode::new(&dir.path().to_string_lossy()).unwrap();
+        assert_eq!(default_branch_name(&ghost.repo), Some("master"));
+        assert_eq!(
+            ghost.prune_ghost_branches(3600, false).unwrap(),
+            ["ghost/merged/1"]
+        );
+
+        let mut left = ghost.list_ghost_branches().unwrap();
+        left.sort();
+        assert_eq!(left, [recent.as_str(), "ghost/unmerged/1"]);
+    }
+
+    #[test]
+    fn ghost_branch_needs_a_commit_to_branch_from() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let ghost = GhostMode::new(&dir.path().to_string_lossy()).unwrap();
+
+        let err = ghost.create_ghost_branch("card-1").unwrap_err();
+        assert_eq!(err.code(), "REPOSITORY_HAS_NO_COMMITS");
+        assert!(ghost.list_ghost_branches().unwrap().is_empty());
+
+        commit_on(&repo, "master", None);
+        let branch = ghost.create_ghost_branch("card-1").unwrap();
+        assert!(branch.starts_with("ghost/card-1/"));
+        assert_eq!(repo.head().unwrap().shorthand(), Some(branch.as_str()));
+    }
+
+    #[test]
+    fn forced_prune_also_deletes_stale_unmerged_branches() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let base = commit_on(&repo, "master", None);
+        repo.set_head("refs/heads/master").unwrap();
+
+        let recent = format!("ghost/recent/{}", chrono::Utc::now().timestamp());
+        commit_on(&repo, "ghost/unmerged/1", Some(base));
+        commit_on(&repo, &recent, Some(base));
+
+        let ghost = GhostMode::new(&dir.path().to_string_lossy()).unwrap();
+        assert!(ghost.prune_ghost_branches(3600, false).unwrap().is_empty());
+        assert_eq!(
+            ghost.prune_ghost_branches(3600, true).unwrap(),
+            ["ghost/unmerged/1"]
+        );
+        assert_eq!(ghost.list_ghost_branches().unwrap(), [recent]);
+    }
+}
@@ -0,0 +1,32 @@
+// OS keychain module
+// Holds the database encryption key so it never sits on disk next to the
+// database. Only built with the `encrypted-db` feature.
+
+use keyring::Entry;
+
+const SERVICE: &str = "viraith-ide";
+const DATABASE_KEY: &str = "database-key";
+
+fn entry() -> Result<Entry, String> {
+    Entry::new(SERVICE, DATABASE_KEY).map_err(|e| e.to_string())
+}
+
+/// The stored database key, or `None` when the database isn't encrypted
+pub fn database_key() -> Result<Option<String>, String> {
+    match entry()?.get_password() {
+        Ok(key) => Ok(Some(key)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+pub fn set_database_key(key: Option<&str>) -> Result<(), String> {
+    let entry = entry()?;
+    match key {
+        Some(key) => entry.set_password(key).map_err(|e| e.to_string()),
+        None => match entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        },
+    }
+}
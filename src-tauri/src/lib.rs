@@ -1,17 +1,50 @@
+mod automation;
+mod backup;
 mod commands;
 mod database;
+mod deep_link;
+mod error;
 mod files;
 mod git;
+#[cfg(feature = "encrypted-db")]
+mod keychain;
+mod logs;
+mod preferences;
+mod process;
+mod queue;
+mod status;
+mod terminal;
 
+use backup::Backups;
 use database::Database;
+use files::{DirStatsCache, FileListCache, FileTreeCache};
+use logs::LogBuffer;
+use process::ProcessManager;
+use queue::ExecutionQueue;
 use std::sync::Arc;
-use tauri::Manager;
+use tauri::{Manager, WindowEvent};
+use tauri_plugin_deep_link::DeepLinkExt;
+use terminal::TerminalManager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
+    let mut builder = tauri::Builder::default();
+
+    // A link clicked while the app is open reaches the running instance
+    // instead of starting a second one; has to be the first plugin
+    #[cfg(desktop)]
+    {
+        builder = builder.plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.set_focus();
+            }
+        }));
+    }
+
+    builder
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_deep_link::init())
         .setup(|app| {
             // Initialize database
             let app_data_dir = app
@@ -22,9 +55,42 @@ pub fn run() {
             std::fs::create_dir_all(&app_data_dir).expect("Failed to create app data directory");
 
             let db_path = app_data_dir.join("viraith.db");
-            let db = Database::new(&db_path).expect("Failed to initialize database");
+            #[cfg(feature = "encrypted-db")]
+            let key = keychain::database_key().expect("Failed to read the database key");
+            #[cfg(not(feature = "encrypted-db"))]
+            let key: Option<String> = None;
+            let db =
+                Database::new(&db_path, key.as_deref()).expect("Failed to initialize database");
 
             app.manage(Arc::new(db));
+            app.manage(Arc::new(LogBuffer::new()));
+            app.manage(Arc::new(ProcessManager::new()));
+            app.manage(Arc::new(ExecutionQueue::default()));
+            app.manage(Arc::new(TerminalManager::new()));
+            app.manage(Arc::new(DirStatsCache::new()));
+            app.manage(Arc::new(FileListCache::new()));
+            app.manage(Arc::new(FileTreeCache::new()));
+            app.manage(Arc::new(Backups::new(app_data_dir.join("backups"))));
+
+            backup::spawn_auto_backups(app.handle().clone());
+
+            // Follow viraith:// links handed over while the app is running
+            #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
+            app.deep_link().register_all()?;
+            let handle = app.handle().clone();
+            app.deep_link().on_open_url(move |event| {
+                for url in event.urls() {
+                    deep_link::open(&handle, url.to_string());
+                }
+            });
+
+            // Reopen the main window where it was left last time, and forget
+            // board windows whose board has since been deleted
+            if let Some(window) = app.get_webview_window("main") {
+                let db = app.state::<Arc<Database>>();
+                let _ = commands::window::restore_window_state(&window, &db);
+                let _ = db.prune_window_states();
+            }
 
             // Open devtools in development
             #[cfg(debug_assertions)]
@@ -36,48 +102,207 @@ pub fn run() {
 
             Ok(())
         })
+        .on_window_event(|window, event| match event {
+            // Only on close: Moved and Resized fire for every frame of a drag
+            WindowEvent::CloseRequested { .. } => {
+                if let Some(window) = window.get_webview_window(window.label()) {
+                    let db = window.state::<Arc<Database>>();
+                    let _ = commands::window::persist_window_state(&window, &db);
+                }
+            }
+            WindowEvent::ThemeChanged(theme) => {
+                if let Some(window) = window.get_webview_window(window.label()) {
+                    let db = window.state::<Arc<Database>>();
+                    commands::window::notify_system_theme_changed(&window, &db, *theme);
+                }
+            }
+            _ => {}
+        })
         .invoke_handler(tauri::generate_handler![
             // Project commands
             commands::project::create_project,
             commands::project::get_projects,
+            commands::project::touch_project,
+            commands::project::get_recent_projects,
             commands::project::delete_project,
+            commands::project::get_project_settings,
+            commands::project::set_project_locked,
+            commands::project::update_project_settings,
+            commands::project::export_project,
+            commands::project::export_project_report,
+            commands::project::get_project_stats,
+            commands::project::import_project,
             // Board commands
             commands::board::get_boards,
             commands::board::create_board,
+            commands::board::get_board_data,
+            commands::board::get_cards_for_board,
+            commands::board::duplicate_board,
+            // Template commands
+            commands::template::list_templates,
+            commands::template::save_board_as_template,
+            commands::template::create_board_from_template,
+            commands::template::delete_template,
             // Column commands
             commands::column::get_columns,
             commands::column::create_column,
+            commands::column::create_columns,
+            commands::column::get_card_counts,
             commands::column::update_column,
+            commands::column::reorder_columns,
+            commands::column::set_wip_limit,
             commands::column::delete_column,
+            commands::column::delete_column_and_reassign,
+            commands::column::validate_automation_rules,
+            commands::column::set_column_automation,
             // Card commands
             commands::card::get_cards,
+            commands::card::get_card,
+            commands::card::get_cards_by_priority,
+            commands::card::query_cards,
             commands::card::create_card,
             commands::card::update_card,
+            commands::card::update_card_status,
+            commands::card::transition_card_status,
             commands::card::delete_card,
+            commands::card::undo_last,
+            commands::card::redo_last,
+            commands::card::duplicate_card,
+            commands::card::bulk_update_cards,
+            commands::card::bulk_move_cards,
+            commands::card::bulk_delete_cards,
+            commands::card::bulk_update_status,
+            commands::card::bulk_assign_label,
             commands::card::move_card,
+            commands::card::move_card_to_board,
+            commands::card::get_card_activity,
+            commands::card::get_card_history,
+            commands::card::update_card_metadata,
+            commands::card::get_agent_config,
+            commands::card::set_agent_config,
             commands::card::attach_folder,
-            commands::card::execute_card_placeholder,
+            commands::card::attach_files,
+            commands::card::detach_file,
+            commands::card::get_card_files,
+            commands::card::export_card_markdown,
+            commands::card::set_card_due,
+            commands::card::get_overdue_cards,
+            commands::card::get_recent_cards,
+            // Execution commands
+            commands::execution::execute_card,
+            commands::execution::cancel_execution,
+            commands::execution::enqueue_card,
+            commands::execution::cancel_queued,
+            commands::execution::prioritize_execution,
+            commands::execution::reorder_queue,
+            commands::execution::get_queue,
+            commands::execution::set_max_concurrent,
+            // Checklist commands
+            commands::checklist::get_checklist,
+            commands::checklist::add_checklist_item,
+            commands::checklist::toggle_checklist_item,
+            commands::checklist::reorder_checklist_items,
+            commands::checklist::delete_checklist_item,
+            // Comment commands
+            commands::comment::get_comments,
+            commands::comment::add_comment,
+            commands::comment::edit_comment,
+            commands::comment::delete_comment,
+            // Dependency commands
+            commands::dependency::add_dependency,
+            commands::dependency::remove_dependency,
+            commands::dependency::get_dependencies,
+            commands::dependency::get_blocked_cards,
             // File commands
             commands::file::get_file_tree,
+            commands::file::get_directory_stats,
+            commands::file::fuzzy_find_files,
             commands::file::read_file,
+            commands::file::read_file_range,
             commands::file::write_file,
             commands::file::create_file,
             commands::file::create_directory,
             commands::file::delete_file,
+            commands::file::trash_path,
             commands::file::file_exists,
             commands::file::open_folder_dialog,
             // Git commands
             commands::git::get_branches,
+            commands::git::get_git_status,
+            commands::git::get_commit_history,
+            commands::git::get_ghost_branch_commits,
             commands::git::create_ghost_branch,
+            commands::git::checkout_branch,
+            commands::git::commit_changes,
             commands::git::get_branch_diff,
+            commands::git::get_branch_diff_structured,
+            commands::git::get_diff_stats,
+            commands::git::get_workdir_diff,
             commands::git::merge_ghost_branch,
+            commands::git::discard_ghost_branch,
+            commands::git::apply_card_stash,
+            commands::git::stash_changes,
+            commands::git::list_stashes,
+            commands::git::apply_stash,
+            commands::git::drop_stash,
+            commands::git::prune_ghost_branches,
+            // Index commands
+            commands::index::index_project,
+            commands::index::get_index_status,
+            // Agent log commands
+            commands::agent::append_agent_log,
+            commands::agent::get_agent_logs,
+            commands::agent::prune_agent_logs,
+            // Maintenance commands
+            commands::maintenance::run_db_maintenance,
+            commands::maintenance::get_startup_health,
+            commands::maintenance::create_backup,
+            commands::maintenance::list_backups,
+            commands::maintenance::restore_backup,
+            commands::maintenance::is_database_encrypted,
+            commands::maintenance::set_database_password,
+            // Link commands
+            commands::link::get_card_link,
+            commands::link::open_launch_links,
+            // Recent item commands
+            commands::recent::record_recent,
+            commands::recent::get_recent,
             // Shell commands
             commands::shell::reveal_in_finder,
             commands::shell::open_path,
+            commands::shell::open_external_url,
+            commands::shell::run_command,
+            commands::shell::run_command_streaming,
+            commands::shell::kill_command,
+            // Terminal commands
+            commands::terminal::terminal_create,
+            commands::terminal::terminal_write,
+            commands::terminal::terminal_resize,
+            commands::terminal::terminal_close,
+            // Draft commands
+            commands::draft::save_draft,
+            commands::draft::get_draft,
+            commands::draft::list_drafts,
+            commands::draft::discard_draft,
+            // Preference commands
+            commands::preference::get_preference,
+            commands::preference::set_preference,
+            commands::preference::get_all_preferences,
+            commands::preference::reset_preferences,
+            // Todo commands
+            commands::todo::scan_todos,
             // Window commands
             commands::window::minimize_window,
             commands::window::maximize_window,
             commands::window::close_window,
+            commands::window::toggle_fullscreen,
+            commands::window::set_always_on_top,
+            commands::window::save_window_state,
+            commands::window::get_window_state,
+            commands::window::open_board_window,
+            commands::window::get_theme,
+            commands::window::set_theme,
+            commands::window::get_startup_state,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
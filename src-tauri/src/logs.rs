@@ -0,0 +1,127 @@
+// Agent log streaming module
+// Keeps the most recent log entries per execution in memory so a log viewer that
+// attaches after events were emitted can catch up without hitting the database
+
+use crate::database::AgentLog;
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+
+/// Entries retained per execution
+const BUFFER_CAPACITY: usize = 256;
+
+pub struct LogBuffer {
+    entries: Mutex<HashMap<String, VecDeque<AgentLog>>>,
+}
+
+impl LogBuffer {
+    pub fn new() -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn push(&self, log: &AgentLog) {
+        let mut entries = self.entries.lock();
+        let buffer = entries.entry(log.execution_id.clone()).or_default();
+
+        if buffer.len() == BUFFER_CAPACITY {
+            buffer.pop_front();
+        }
+        buffer.push_back(log.clone());
+    }
+
+    /// Drop an execution's entries once it has finished; later tails read the
+    /// database instead
+    pub fn evict(&self, execution_id: &str) {
+        self.entries.lock().remove(execution_id);
+    }
+
+    /// Entries after `after_seq`, or `None` when the buffer no longer holds
+    /// everything in that range and the caller must fall back to the database.
+    pub fn tail(
+        &self,
+        execution_id: &str,
+        after_seq: i64,
+        level_filter: Option<&str>,
+        limit: Option<i64>,
+    ) -> Option<Vec<AgentLog>> {
+        let entries = self.entries.lock();
+        let buffer = entries.get(execution_id)?;
+
+        // Only a cursor at or past the oldest entry held proves nothing between
+        // it and the buffer was dropped; seqs are shared across executions
+        if buffer.front()?.seq > after_seq {
+            return None;
+        }
+
+        let limit = limit
+            .and_then(|l| usize::try_from(l).ok())
+            .unwrap_or(usize::MAX);
+
+        Some(
+            buffer
+                .iter()
+                .filter(|log| log.seq > after_seq)
+                .filter(|log| level_filter.is_none() || level_filter == Some(log.level.as_str()))
+                .take(limit)
+                .cloned()
+                .collect(),
+        )
+    }
+}
+
+impl Default for LogBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(execution_id: &str, seq: i64) -> AgentLog {
+        AgentLog {
+            seq,
+            id: format!("log-{}", seq),
+            execution_id: execution_id.to_string(),
+            // All in the same second, as a chatty process writes them
+            timestamp: 1_700_000_000,
+            level: "info".to_string(),
+            message: format!("line {}", seq),
+            metadata: "{}".to_string(),
+        }
+    }
+
+    #[test]
+    fn tail_pages_by_seq_and_falls_back_past_the_oldest_entry() {
+        let buffer = LogBuffer::new();
+        // Seqs are shared across executions, so one execution's have gaps
+        for seq in [10, 12, 13, 15] {
+            buffer.push(&log("run", seq));
+        }
+
+        let seqs = |logs: Vec<AgentLog>| logs.iter().map(|l| l.seq).collect::<Vec<_>>();
+        assert_eq!(seqs(buffer.tail("run", 12, None, None).unwrap()), [13, 15]);
+        assert_eq!(seqs(buffer.tail("run", 10, None, Some(1)).unwrap()), [12]);
+        assert!(buffer.tail("run", 9, None, None).is_none());
+        assert!(buffer.tail("other", 0, None, None).is_none());
+    }
+
+    #[test]
+    fn drops_the_oldest_entries_beyond_capacity_and_evicts_on_finish() {
+        let buffer = LogBuffer::new();
+        for seq in 0..BUFFER_CAPACITY as i64 + 10 {
+            buffer.push(&log("run", seq));
+        }
+
+        assert!(buffer.tail("run", 5, None, None).is_none());
+        assert_eq!(
+            buffer.tail("run", 10, None, None).unwrap().len(),
+            BUFFER_CAPACITY - 1
+        );
+
+        buffer.evict("run");
+        assert!(buffer.tail("run", 10, None, None).is_none());
+    }
+}
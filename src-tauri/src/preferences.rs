@@ -0,0 +1,161 @@
+// App preferences module
+// Typed access to the preference keys the backend reads itself. The table is
+// an open key / value store, so the frontend keeps its own keys there too.
+
+use crate::database::Database;
+use crate::error::AppError;
+use crate::files::{self, TreeIgnore};
+use serde_json::Value;
+
+/// "light", "dark", or "system" to follow the OS
+pub const THEME: &str = "theme";
+
+pub const THEMES: &[&str] = &["light", "dark", "system"];
+
+/// Names or glob patterns left out of the file tree, on top of hidden entries
+pub const FILE_TREE_IGNORE_PATTERNS: &str = "file_tree.ignore_patterns";
+
+/// How old a ghost branch has to be before pruning deletes it
+pub const GHOST_BRANCH_MAX_AGE_SECS: &str = "git.ghost_branch_max_age_secs";
+
+/// Hours between automatic database snapshots
+pub const BACKUP_INTERVAL_HOURS: &str = "backup.interval_hours";
+
+/// How many automatic snapshots are kept before the oldest are deleted
+pub const BACKUP_KEEP: &str = "backup.keep";
+
+const DEFAULT_THEME: &str = "system";
+
+const DEFAULT_FILE_TREE_IGNORE_PATTERNS: &[&str] = &["node_modules", "target", "dist"];
+
+const DEFAULT_GHOST_BRANCH_MAX_AGE_SECS: i64 = 7 * 24 * 60 * 60;
+
+const DEFAULT_BACKUP_INTERVAL_HOURS: u64 = 24;
+
+const DEFAULT_BACKUP_KEEP: u64 = 7;
+
+/// Reject values of the wrong shape for keys the backend reads. Any other
+/// key takes any JSON value.
+pub fn validate(key: &str, value: &Value) -> Result<(), AppError> {
+    match key {
+        THEME if !value.as_str().is_some_and(|theme| THEMES.contains(&theme)) => Err(
+            AppError::validation(key, "Expected \"light\", \"dark\" or \"system\""),
+        ),
+        FILE_TREE_IGNORE_PATTERNS if !is_pattern_list(value) => Err(AppError::validation(
+            key,
+            "Expected a list of file or folder names or glob patterns",
+        )),
+        GHOST_BRANCH_MAX_AGE_SECS if value.as_u64().is_none() => Err(AppError::validation(
+            key,
+            "Expected a non-negative number of seconds",
+        )),
+        BACKUP_INTERVAL_HOURS | BACKUP_KEEP if value.as_u64().unwrap_or(0) == 0 => Err(
+            AppError::validation(key, "Expected a whole number greater than zero"),
+        ),
+        _ => Ok(()),
+    }
+}
+
+fn is_pattern_list(value: &Value) -> bool {
+    value.as_array().is_some_and(|items| {
+        items
+            .iter()
+            .all(|item| item.as_str().is_some_and(files::is_valid_ignore_pattern))
+    })
+}
+
+pub fn theme(db: &Database) -> String {
+    stored(db, THEME)
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_else(|| DEFAULT_THEME.to_string())
+}
+
+pub fn file_tree_ignore_patterns(db: &Database) -> Vec<String> {
+    stored(db, FILE_TREE_IGNORE_PATTERNS)
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_else(|| {
+            DEFAULT_FILE_TREE_IGNORE_PATTERNS
+                .iter()
+                .map(|name| name.to_string())
+                .collect()
+        })
+}
+
+/// The file tree's ignore rules for the project at `project_root`: the
+/// preference's patterns plus the project's own. When its settings say to
+/// replace them only the project's patterns apply, without hiding dotfiles,
+/// so an empty list shows everything. A folder that isn't a project gets the
+/// preference's patterns.
+pub fn file_tree_ignore(db: &Database, project_root: &str) -> TreeIgnore {
+    let mut patterns = file_tree_ignore_patterns(db);
+
+    let settings = db
+        .get_project_by_root(&files::canonical_root(project_root))
+        .ok()
+        .flatten()
+        .and_then(|project| db.get_project_settings(&project.id).ok());
+    if let Some(settings) = settings {
+        if settings.replace_ignore_patterns {
+            return TreeIgnore::only(settings.ignore_patterns);
+        }
+        patterns.extend(settings.ignore_patterns);
+    }
+
+    TreeIgnore::new(patterns)
+}
+
+pub fn ghost_branch_max_age_secs(db: &Database) -> i64 {
+    stored(db, GHOST_BRANCH_MAX_AGE_SECS)
+        .and_then(|value| value.as_i64())
+        .unwrap_or(DEFAULT_GHOST_BRANCH_MAX_AGE_SECS)
+}
+
+pub fn backup_interval_hours(db: &Database) -> u64 {
+    stored(db, BACKUP_INTERVAL_HOURS)
+        .and_then(|value| value.as_u64())
+        .unwrap_or(DEFAULT_BACKUP_INTERVAL_HOURS)
+}
+
+pub fn backup_keep(db: &Database) -> usize {
+    stored(db, BACKUP_KEEP)
+        .and_then(|value| value.as_u64())
+        .unwrap_or(DEFAULT_BACKUP_KEEP) as usize
+}
+
+/// The stored value when there is one and it still validates
+fn stored(db: &Database, key: &str) -> Option<Value> {
+    let value = db.get_preference(key).ok()??;
+    validate(key, &value).is_ok().then_some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::testing::*;
+    use std::path::Path;
+
+    #[test]
+    fn a_replacing_empty_list_shows_everything_for_any_spelling_of_the_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("project");
+        std::fs::create_dir(&root).unwrap();
+        let root = files::canonical_root(&root.to_string_lossy());
+
+        let db = memory_db();
+        let fixture = project_with_column(&db, &root);
+        let spelled = format!("{}/", root);
+        assert!(file_tree_ignore(&db, &spelled).is_ignored(Path::new(".env"), false));
+        assert!(file_tree_ignore(&db, &spelled).is_ignored(Path::new("node_modules"), true));
+
+        let mut settings = db.get_project_settings(&fixture.project_id).unwrap();
+        settings.replace_ignore_patterns = true;
+        settings.ignore_patterns = Vec::new();
+        db.update_project_settings(&fixture.project_id, &settings)
+            .unwrap();
+
+        let ignored = file_tree_ignore(&db, &spelled);
+        assert_eq!(ignored, TreeIgnore::only(Vec::new()));
+        assert!(!ignored.is_ignored(Path::new(".env"), false));
+        assert!(!ignored.is_ignored(Path::new("node_modules"), true));
+    }
+}
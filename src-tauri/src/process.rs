@@ -0,0 +1,248 @@
+// Process management module
+// Spawns child processes with line-by-line output streaming and keeps a handle
+// on each running child so it can be killed later
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+/// Lines buffered between the pipe readers and the consumer. Readers wait when
+/// it's full, which pushes back on the child instead of dropping output.
+const LINE_BUFFER: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+pub struct RunningProcess {
+    pub pid: u32,
+    /// Resolves to the exit code once the process has exited and all of its
+    /// output has been delivered. `None` when it was killed or died by signal.
+    pub handle: JoinHandle<Option<i32>>,
+}
+
+pub struct ProcessManager {
+    running: Mutex<Running>,
+}
+
+#[derive(Default)]
+struct Running {
+    kill_switches: HashMap<u32, oneshot::Sender<()>>,
+    /// Caller-chosen keys (e.g. an execution id) for looking up a pid
+    tags: HashMap<String, u32>,
+}
+
+impl ProcessManager {
+    pub fn new() -> Self {
+        Self {
+            running: Mutex::new(Running::default()),
+        }
+    }
+
+    /// Spawn `program` and call `on_line` for every line it prints, in the order
+    /// lines arrive across both streams.
+    pub fn spawn<F>(
+        self: &Arc<Self>,
+        cwd: &str,
+        program: &str,
+        args: &[String],
+        env: &HashMap<String, String>,
+        mut on_line: F,
+    ) -> Result<RunningProcess, String>
+    where
+        F: FnMut(OutputStream, String) + Send + 'static,
+    {
+        let mut command = tokio::process::Command::new(program);
+        command
+            .args(args)
+            .current_dir(cwd)
+            .envs(env)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        // A group of its own, so a kill also reaches whatever it started
+        #[cfg(unix)]
+        command.process_group(0);
+        let mut child = command
+            .spawn()
+            .map_err(|e| format!("Failed to start {}: {}", program, e))?;
+
+        let pid = child
+            .id()
+            .ok_or_else(|| format!("{} exited before it could be tracked", program))?;
+
+        let (line_tx, mut line_rx) = mpsc::channel(LINE_BUFFER);
+        if let Some(stdout) = child.stdout.take() {
+            tokio::spawn(forward_lines(stdout, OutputStream::Stdout, line_tx.clone()));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            tokio::spawn(forward_lines(stderr, OutputStream::Stderr, line_tx));
+        }
+
+        // Finishes once both pipes are closed and every line was handed over
+        let consumer = tokio::spawn(async move {
+            while let Some((stream, line)) = line_rx.recv().await {
+                on_line(stream, line);
+            }
+        });
+
+        let (kill_tx, kill_rx) = oneshot::channel();
+        self.running.lock().kill_switches.insert(pid, kill_tx);
+
+        let manager = Arc::clone(self);
+        let handle = tokio::spawn(async move {
+            let status = tokio::select! {
+                status = child.wait() => status.ok(),
+                _ = kill_rx => {
+                    kill_group(pid);
+                    let _ = child.kill().await;
+                    None
+                }
+            };
+
+            let _ = consumer.await;
+            let mut running = manager.running.lock();
+            running.kill_switches.remove(&pid);
+            running.tags.retain(|_, tagged| *tagged != pid);
+            drop(running);
+
+            status.and_then(|s| s.code())
+        });
+
+        Ok(RunningProcess { pid, handle })
+    }
+
+    /// Kill a process started by `spawn`. Returns false if it isn't running.
+    pub fn kill(&self, pid: u32) -> bool {
+        match self.running.lock().kill_switches.remove(&pid) {
+            Some(kill_tx) => kill_tx.send(()).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Associate a running process with `tag` so it can be killed by tag. The
+    /// tag is dropped when the process exits.
+    pub fn tag(&self, pid: u32, tag: &str) {
+        let mut running = self.running.lock();
+        if running.kill_switches.contains_key(&pid) {
+            running.tags.insert(tag.to_string(), pid);
+        }
+    }
+
+    pub fn kill_tagged(&self, tag: &str) -> bool {
+        let pid = self.running.lock().tags.get(tag).copied();
+        pid.is_some_and(|pid| self.kill(pid))
+    }
+}
+
+impl Default for ProcessManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Kill every process in the group led by `pid`. Children of a shell would
+/// otherwise outlive it and keep its output pipes open.
+#[cfg(unix)]
+fn kill_group(pid: u32) {
+    if let Ok(pgid) = i32::try_from(pid) {
+        // SAFETY: kill(2) takes no pointers; a stale group just returns ESRCH
+        unsafe {
+            libc::kill(-pgid, libc::SIGKILL);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_group(_pid: u32) {}
+
+async fn forward_lines<R>(reader: R, stream: OutputStream, tx: mpsc::Sender<(OutputStream, String)>)
+where
+    R: AsyncRead + Unpin,
+{
+    // Byte lines, so output that isn't valid UTF-8 is shown mangled instead of
+    // ending the stream
+    let mut reader = BufReader::new(reader);
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+
+        let line = buf.strip_suffix(b"\n").unwrap_or(&buf);
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let line = String::from_utf8_lossy(line).into_owned();
+        if tx.send((stream, line)).await.is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn lines_arrive_in_order_as_they_are_printed() {
+        let script = "echo one; sleep 0.2; echo two >&2; sleep 0.2; echo three";
+        let args = ["-c".to_string(), script.to_string()];
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let recorder = Arc::clone(&seen);
+
+        let started = std::time::Instant::now();
+        let process = Arc::new(ProcessManager::new())
+            .spawn(".", "sh", &args, &HashMap::new(), move |stream, line| {
+                recorder.lock().push((stream, line, started.elapsed()));
+            })
+            .unwrap();
+        assert_eq!(process.handle.await.unwrap(), Some(0));
+
+        let seen = seen.lock();
+        let lines: Vec<_> = seen
+            .iter()
+            .map(|(stream, line, _)| (*stream, line.as_str()))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                (OutputStream::Stdout, "one"),
+                (OutputStream::Stderr, "two"),
+                (OutputStream::Stdout, "three"),
+            ]
+        );
+        // Streamed while the script sleeps, not all at once when it exits
+        for pair in seen.windows(2) {
+            let gap = pair[1].2 - pair[0].2;
+            assert!(gap >= std::time::Duration::from_millis(100), "{:?}", gap);
+        }
+    }
+
+    #[tokio::test]
+    async fn forward_lines_keeps_going_past_invalid_utf8() {
+        let output: &[u8] = b"first\r\nbad \xff\xfe byte\nlast without newline";
+        let (tx, mut rx) = mpsc::channel(8);
+
+        forward_lines(output, OutputStream::Stdout, tx).await;
+
+        let mut lines = Vec::new();
+        while let Some((_, line)) = rx.recv().await {
+            lines.push(line);
+        }
+        assert_eq!(
+            lines,
+            ["first", "bad \u{FFFD}\u{FFFD} byte", "last without newline"]
+        );
+    }
+}
@@ -0,0 +1,237 @@
+// Execution queue module
+// Gates card executions behind a semaphore so only `max_concurrent` run at once;
+// the rest wait in order, FIFO unless reordered, with their card in the `queued`
+// status
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Executions allowed to run at the same time unless configured otherwise
+pub const DEFAULT_MAX_CONCURRENT: usize = 2;
+
+/// Payload of the `queue-updated` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueSnapshot {
+    pub waiting: Vec<String>,
+    pub running: Vec<String>,
+    pub max_concurrent: usize,
+}
+
+pub struct ExecutionQueue {
+    semaphore: Arc<Semaphore>,
+    state: Mutex<QueueState>,
+}
+
+struct QueueState {
+    waiting: VecDeque<String>,
+    running: Vec<String>,
+    max_concurrent: usize,
+}
+
+impl ExecutionQueue {
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_concurrent)),
+            state: Mutex::new(QueueState {
+                waiting: VecDeque::new(),
+                running: Vec::new(),
+                max_concurrent,
+            }),
+        }
+    }
+
+    /// Add a card to the back of the queue. Returns false if it's already
+    /// waiting or running.
+    pub fn push(&self, card_id: &str) -> bool {
+        let mut state = self.state.lock();
+        if state
+            .waiting
+            .iter()
+            .chain(&state.running)
+            .any(|id| id == card_id)
+        {
+            return false;
+        }
+
+        state.waiting.push_back(card_id.to_string());
+        true
+    }
+
+    /// Drop a waiting card. Running cards are left alone.
+    pub fn remove(&self, card_id: &str) -> bool {
+        let mut state = self.state.lock();
+        let before = state.waiting.len();
+        state.waiting.retain(|id| id != card_id);
+        state.waiting.len() != before
+    }
+
+    /// Wait for a free slot. The slot is released when the permit is dropped.
+    pub async fn acquire(&self) -> OwnedSemaphorePermit {
+        Arc::clone(&self.semaphore)
+            .acquire_owned()
+            .await
+            .expect("execution queue semaphore is never closed")
+    }
+
+    /// Move the card at the front from waiting to running once a slot is
+    /// held. `None` if nothing is waiting, e.g. the card was cancelled.
+    pub fn start_next(&self) -> Option<String> {
+        let mut state = self.state.lock();
+        let card_id = state.waiting.pop_front()?;
+        state.running.push(card_id.clone());
+        Some(card_id)
+    }
+
+    /// Move a waiting card to the front. Returns false if it isn't waiting.
+    pub fn prioritize(&self, card_id: &str) -> bool {
+        let mut state = self.state.lock();
+        let Some(index) = state.waiting.iter().position(|id| id == card_id) else {
+            return false;
+        };
+
+        if let Some(card_id) = state.waiting.remove(index) {
+            state.waiting.push_front(card_id);
+        }
+        true
+    }
+
+    /// Put the waiting cards in `ordered_ids` first, in that order, followed
+    /// by any not listed in their current order. Ids that aren't waiting,
+    /// e.g. ones that started in the meantime, are skipped.
+    pub fn reorder(&self, ordered_ids: &[String]) {
+        let mut state = self.state.lock();
+        let mut rest = std::mem::take(&mut state.waiting);
+
+        for id in ordered_ids {
+            if let Some(index) = rest.iter().position(|waiting| waiting == id) {
+                if let Some(card_id) = rest.remove(index) {
+                    state.waiting.push_back(card_id);
+                }
+            }
+        }
+        state.waiting.extend(rest);
+    }
+
+    pub fn finish(&self, card_id: &str) {
+        self.state.lock().running.retain(|id| id != card_id);
+    }
+
+    /// Change the concurrency limit. Lowering it takes effect as running
+    /// executions finish; nothing already running is interrupted.
+    pub fn set_max_concurrent(&self, max_concurrent: usize) {
+        let mut state = self.state.lock();
+        let current = state.max_concurrent;
+        state.max_concurrent = max_concurrent;
+
+        if max_concurrent > current {
+            self.semaphore.add_permits(max_concurrent - current);
+        } else if max_concurrent < current {
+            let semaphore = Arc::clone(&self.semaphore);
+            let excess = (current - max_concurrent) as u32;
+            tokio::spawn(async move {
+                if let Ok(permits) = semaphore.acquire_many_owned(excess).await {
+                    permits.forget();
+                }
+            });
+        }
+    }
+
+    pub fn snapshot(&self) -> QueueSnapshot {
+        let state = self.state.lock();
+        QueueSnapshot {
+            waiting: state.waiting.iter().cloned().collect(),
+            running: state.running.clone(),
+            max_concurrent: state.max_concurrent,
+        }
+    }
+}
+
+impl Default for ExecutionQueue {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CONCURRENT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue_of(ids: &[&str]) -> ExecutionQueue {
+        let queue = ExecutionQueue::default();
+        for id in ids {
+            assert!(queue.push(id));
+        }
+        queue
+    }
+
+    #[test]
+    fn prioritized_card_starts_next() {
+        let queue = queue_of(&["a", "b", "c"]);
+
+        assert!(queue.prioritize("c"));
+        assert!(!queue.prioritize("missing"));
+        assert_eq!(queue.snapshot().waiting, ["c", "a", "b"]);
+
+        assert_eq!(queue.start_next().as_deref(), Some("c"));
+        assert_eq!(queue.snapshot().running, ["c"]);
+        assert!(!queue.prioritize("c"));
+    }
+
+    #[test]
+    fn reorder_puts_listed_cards_first() {
+        let queue = queue_of(&["a", "b", "c", "d"]);
+
+        queue.reorder(&["d".to_string(), "gone".to_string(), "b".to_string()]);
+        assert_eq!(queue.snapshot().waiting, ["d", "b", "a", "c"]);
+    }
+
+    #[tokio::test]
+    async fn no_more_than_max_concurrent_run_at_once() {
+        let ids = ["a", "b", "c", "d", "e"];
+        let queue = Arc::new(queue_of(&ids));
+        let busiest = Arc::new(Mutex::new(0));
+
+        // Each task stands in for enqueue_card's: hold a slot, start whichever
+        // card is next, run for a bit, then finish
+        let tasks: Vec<_> = ids
+            .iter()
+            .map(|_| {
+                let (queue, busiest) = (Arc::clone(&queue), Arc::clone(&busiest));
+                tokio::spawn(async move {
+                    let _permit = queue.acquire().await;
+                    let card_id = queue.start_next().unwrap();
+                    {
+                        let running = queue.snapshot().running.len();
+                        let mut busiest = busiest.lock();
+                        *busiest = (*busiest).max(running);
+                    }
+                    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                    queue.finish(&card_id);
+                })
+            })
+            .collect();
+
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        let snapshot = queue.snapshot();
+        assert_eq!(snapshot.running, ["a", "b"]);
+        assert_eq!(snapshot.waiting, ["c", "d", "e"]);
+
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(*busiest.lock(), DEFAULT_MAX_CONCURRENT);
+        let snapshot = queue.snapshot();
+        assert!(snapshot.running.is_empty() && snapshot.waiting.is_empty());
+    }
+
+    #[test]
+    fn cancelled_card_leaves_its_slot_unused() {
+        let queue = queue_of(&["a"]);
+
+        assert!(queue.remove("a"));
+        assert_eq!(queue.start_next(), None);
+    }
+}
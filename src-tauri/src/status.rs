@@ -0,0 +1,122 @@
+// Card status module
+// The allowed card status transitions. Anything not listed here is rejected
+// unless the caller explicitly forces it.
+
+/// Every value `cards.status` can hold
+pub const CARD_STATUSES: &[&str] = &["idle", "queued", "executing", "review", "done", "error"];
+
+/// Whether a card may go from `from` to `to`. Staying put is always allowed,
+/// any status can be reset to `idle`, and anything can fail into `error`.
+pub fn can_transition(from: &str, to: &str) -> bool {
+    if from == to || to == "idle" || to == "error" {
+        return true;
+    }
+
+    matches!(
+        (from, to),
+        ("idle", "queued")
+            // execute_card runs a card straight away, without the queue
+            | ("idle", "executing")
+            | ("queued", "executing")
+            | ("executing", "review")
+            | ("executing", "done")
+            | ("review", "queued")
+            | ("review", "done")
+            | ("error", "queued")
+    )
+}
+
+pub fn is_valid_status(status: &str) -> bool {
+    CARD_STATUSES.contains(&status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::testing::*;
+    use crate::error::AppError;
+
+    /// Every pair of statuses: exactly these are allowed
+    #[test]
+    fn transition_matrix() {
+        let allowed = [
+            ("idle", "idle"),
+            ("idle", "queued"),
+            ("idle", "executing"),
+            ("idle", "error"),
+            ("queued", "idle"),
+            ("queued", "queued"),
+            ("queued", "executing"),
+            ("queued", "error"),
+            ("executing", "idle"),
+            ("executing", "executing"),
+            ("executing", "review"),
+            ("executing", "done"),
+            ("executing", "error"),
+            ("review", "idle"),
+            ("review", "queued"),
+            ("review", "review"),
+            ("review", "done"),
+            ("review", "error"),
+            ("done", "idle"),
+            ("done", "done"),
+            ("done", "error"),
+            ("error", "idle"),
+            ("error", "queued"),
+            ("error", "error"),
+        ];
+
+        for from in CARD_STATUSES {
+            for to in CARD_STATUSES {
+                let expected = allowed.contains(&(from, to));
+                assert_eq!(can_transition(from, to), expected, "{} -> {}", from, to);
+            }
+        }
+    }
+
+    #[test]
+    fn allowed_transitions() {
+        for (from, to) in [
+            ("idle", "queued"),
+            ("queued", "executing"),
+            ("executing", "review"),
+            ("review", "done"),
+            ("done", "error"),
+            ("review", "error"),
+            ("error", "queued"),
+            ("done", "idle"),
+        ] {
+            assert!(can_transition(from, to), "{} -> {}", from, to);
+        }
+    }
+
+    #[test]
+    fn rejected_transitions() {
+        for (from, to) in [
+            ("done", "executing"),
+            ("done", "queued"),
+            ("idle", "done"),
+            ("idle", "review"),
+            ("queued", "done"),
+            ("error", "done"),
+        ] {
+            assert!(!can_transition(from, to), "{} -> {}", from, to);
+        }
+    }
+
+    #[test]
+    fn invalid_transition_names_both_states() {
+        let db = memory_db();
+        let fixture = project_with_column(&db, "/status");
+        let card_id = add_card(&db, &fixture.column_id, "Card");
+
+        let err = AppError::from(db.update_card_status(&card_id, "done", false).unwrap_err());
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["code"], "INVALID_TRANSITION");
+        assert_eq!(json["from"], "idle");
+        assert_eq!(json["to"], "done");
+
+        db.update_card_status(&card_id, "done", true).unwrap();
+        assert_eq!(db.get_card(&card_id).unwrap().unwrap().status, "done");
+    }
+}
@@ -0,0 +1,251 @@
+// Integrated terminal module
+// PTY-backed shell sessions. Output is read on a dedicated thread per session
+// and handed to a callback; input and resizes go through the manager by id.
+
+use crate::error::AppError;
+use parking_lot::Mutex;
+use portable_pty::{native_pty_system, Child, CommandBuilder, MasterPty, PtySize};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::sync::Arc;
+
+/// Bytes read from the PTY per chunk
+const READ_CHUNK: usize = 8192;
+
+struct Session {
+    master: Box<dyn MasterPty + Send>,
+    /// Locked on its own, so a write blocked on a full PTY holds up only this
+    /// session
+    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+    child: Box<dyn Child + Send + Sync>,
+}
+
+pub struct TerminalManager {
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl TerminalManager {
+    pub fn new() -> Self {
+        Self {
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Start the user's default shell in `cwd` and return the session id.
+    /// `on_output` gets the session id and the PTY's output as it arrives;
+    /// `on_exit` runs once the shell is gone.
+    pub fn create<F, E>(
+        self: &Arc<Self>,
+        cwd: &str,
+        rows: u16,
+        cols: u16,
+        mut on_output: F,
+        on_exit: E,
+    ) -> Result<String, String>
+    where
+        F: FnMut(&str, &str) + Send + 'static,
+        E: FnOnce(&str) + Send + 'static,
+    {
+        let pair = native_pty_system()
+            .openpty(pty_size(rows, cols))
+            .map_err(|e| format!("Failed to open PTY: {}", e))?;
+
+        let mut cmd = CommandBuilder::new_default_prog();
+        cmd.cwd(cwd);
+        cmd.env("TERM", "xterm-256color");
+
+        let child = pair
+            .slave
+            .spawn_command(cmd)
+            .map_err(|e| format!("Failed to start shell: {}", e))?;
+        // Only the child should hold the slave end, so reads see EOF when it exits
+        drop(pair.slave);
+
+        let mut reader = pair.master.try_clone_reader().map_err(|e| e.to_string())?;
+        let writer = pair.master.take_writer().map_err(|e| e.to_string())?;
+
+        let id = uuid::Uuid::new_v4().to_string();
+        self.sessions.lock().insert(
+            id.clone(),
+            Session {
+                master: pair.master,
+                writer: Arc::new(Mutex::new(writer)),
+                child,
+            },
+        );
+
+        let manager = Arc::clone(self);
+        let session_id = id.clone();
+        std::thread::spawn(move || {
+            let mut buf = [0u8; READ_CHUNK];
+            // Bytes of a UTF-8 sequence split across reads
+            let mut pending = Vec::new();
+
+            loop {
+                match reader.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => {
+                        pending.extend_from_slice(&buf[..n]);
+                        let text = take_utf8(&mut pending);
+                        if !text.is_empty() {
+                            on_output(&session_id, &text);
+                        }
+                    }
+                }
+            }
+
+            if !pending.is_empty() {
+                on_output(&session_id, &String::from_utf8_lossy(&pending));
+            }
+
+            if let Some(mut session) = manager.sessions.lock().remove(&session_id) {
+                let _ = session.child.wait();
+            }
+            on_exit(&session_id);
+        });
+
+        Ok(id)
+    }
+
+    pub fn write(&self, id: &str, data: &str) -> Result<(), AppError> {
+        let writer = self
+            .sessions
+            .lock()
+            .get(id)
+            .map(|session| Arc::clone(&session.writer))
+            .ok_or_else(|| not_found(id))?;
+
+        let mut writer = writer.lock();
+        writer
+            .write_all(data.as_bytes())
+            .and_then(|_| writer.flush())
+            .map_err(|e| AppError::io("Failed to write to terminal", e))
+    }
+
+    pub fn resize(&self, id: &str, rows: u16, cols: u16) -> Result<(), AppError> {
+        let sessions = self.sessions.lock();
+        let session = sessions.get(id).ok_or_else(|| not_found(id))?;
+
+        session
+            .master
+            .resize(pty_size(rows, cols))
+            .map_err(|e| AppError::Io(format!("Failed to resize terminal: {}", e)))
+    }
+
+    /// Kill the session's shell. Returns false if there was no such session.
+    pub fn close(&self, id: &str) -> bool {
+        match self.sessions.lock().remove(id) {
+            Some(mut session) => {
+                let _ = session.child.kill();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Default for TerminalManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn not_found(id: &str) -> AppError {
+    AppError::NotFound(format!("Terminal not found: {}", id))
+}
+
+fn pty_size(rows: u16, cols: u16) -> PtySize {
+    PtySize {
+        rows,
+        cols,
+        pixel_width: 0,
+        pixel_height: 0,
+    }
+}
+
+/// Take the longest valid UTF-8 prefix out of `pending`, leaving an incomplete
+/// trailing sequence behind. Invalid bytes are replaced rather than kept.
+fn take_utf8(pending: &mut Vec<u8>) -> String {
+    match std::str::from_utf8(pending) {
+        Ok(text) => {
+            let text = text.to_string();
+            pending.clear();
+            text
+        }
+        Err(e) if e.error_len().is_none() => {
+            let rest = pending.split_off(e.valid_up_to());
+            let text = String::from_utf8_lossy(pending).into_owned();
+            *pending = rest;
+            text
+        }
+        Err(_) => String::from_utf8_lossy(&std::mem::take(pending)).into_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn shell_output_comes_back_through_the_callback() {
+        let manager = Arc::new(TerminalManager::new());
+        let (output_tx, output_rx) = mpsc::channel();
+        let (exit_tx, exit_rx) = mpsc::channel();
+        let cwd = std::env::temp_dir();
+        let id = manager
+            .create(
+                &cwd.to_string_lossy(),
+                24,
+                80,
+                move |_, text| {
+                    let _ = output_tx.send(text.to_string());
+                },
+                move |id| {
+                    let _ = exit_tx.send(id.to_string());
+                },
+            )
+            .unwrap();
+
+        manager.write(&id, "echo hi\n").unwrap();
+        // The typed line is echoed back too; wait for the command's own
+        // output, which may follow terminal escapes and a carriage return
+        let printed_hi = |output: &str| {
+            output
+                .lines()
+                .any(|line| line.trim_end_matches('\r').rsplit('\r').next() == Some("hi"))
+        };
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut output = String::new();
+        while !printed_hi(&output) {
+            let left = deadline.saturating_duration_since(Instant::now());
+            match output_rx.recv_timeout(left) {
+                Ok(text) => output.push_str(&text),
+                Err(_) => panic!("no `hi` in {:?}", output),
+            }
+        }
+
+        assert!(manager.close(&id));
+        assert_eq!(exit_rx.recv_timeout(Duration::from_secs(10)).unwrap(), id);
+        assert!(!manager.close(&id));
+        let err = manager.write(&id, "echo gone\n").unwrap_err();
+        assert_eq!(err.code(), "NOT_FOUND");
+        assert_eq!(manager.resize(&id, 24, 80).unwrap_err().code(), "NOT_FOUND");
+    }
+
+    #[test]
+    fn utf8_split_across_reads_is_held_back() {
+        let mut pending = "hé".as_bytes()[..2].to_vec();
+        assert_eq!(take_utf8(&mut pending), "h");
+        assert_eq!(pending, [0xC3]);
+
+        pending.push(0xA9);
+        assert_eq!(take_utf8(&mut pending), "é");
+        assert!(pending.is_empty());
+
+        let mut invalid = vec![b'a', 0xFF, b'b'];
+        assert_eq!(take_utf8(&mut invalid), "a\u{FFFD}b");
+        assert!(invalid.is_empty());
+    }
+}
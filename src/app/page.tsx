@@ -13,6 +13,7 @@ import { ResizeHandle } from '@/components/layout/ResizeHandle';
 import { useBoardStore } from '@/store/boardStore';
 import { useTerminalStore } from '@/store/terminalStore';
 import { useResizablePanel } from '@/hooks/useResizablePanel';
+import { linkApi } from '@/lib/tauri';
 import type { Board as BoardType, Column } from '@/types';
 
 type MainView = 'board' | 'editor';
@@ -61,13 +62,21 @@ const DEMO_COLUMNS: Column[] = [
   },
 ];
 
+// Board windows (see windowApi.openBoard) load the app with ?board=<id>
+function boardWindowId(): string | null {
+  return new URLSearchParams(window.location.search).get('board');
+}
+
 export default function Home() {
-  const { setBoards, setColumns, setCurrentProject, boards, columns } = useBoardStore();
+  const { setBoards, setColumns, setCurrentProject, setActiveCard, boards, columns } =
+    useBoardStore();
   const [showSignalFeed, setShowSignalFeed] = useState(true);
   const [showChatPanel, setShowChatPanel] = useState(false);
   const [showTimeline, setShowTimeline] = useState(false);
   const [mainView, setMainView] = useState<MainView>('board');
   const [openFilePath, setOpenFilePath] = useState<string | null>(null);
+  const [activeBoardId, setActiveBoardId] = useState(DEMO_BOARD.id);
+  const [isBoardWindow, setIsBoardWindow] = useState(false);
 
   // Resizable sidebar
   const sidebarResize = useResizablePanel({
@@ -108,6 +117,54 @@ export default function Home() {
     }
   }, [setBoards, setColumns, setCurrentProject, boards, columns]);
 
+  // A board window shows just its board
+  useEffect(() => {
+    const boardId = boardWindowId();
+    if (boardId) {
+      setActiveBoardId(boardId);
+      setIsBoardWindow(true);
+    }
+  }, []);
+
+  // Card links (viraith://project/.../card/...) from the OS, including the one
+  // the app was launched with, replayed only once both listeners exist. Only
+  // the main window follows them.
+  useEffect(() => {
+    if (boardWindowId()) return;
+
+    let unlisteners: (() => void)[] = [];
+    let cancelled = false;
+
+    const setupLinks = async () => {
+      try {
+        const navigate = await linkApi.onNavigate((location) => {
+          setCurrentProject(location.project_id);
+          setActiveBoardId(location.board_id);
+          setMainView('board');
+          setActiveCard(location.card_id);
+        });
+        const navigateError = await linkApi.onNavigateError((error) => {
+          console.error(`Failed to open ${error.url}:`, error.reason);
+        });
+        unlisteners = [navigate, navigateError];
+        if (cancelled) {
+          unlisteners.forEach((unlisten) => unlisten());
+          return;
+        }
+        await linkApi.openLaunchLinks();
+      } catch (error) {
+        console.error('Failed to setup card link listeners:', error);
+      }
+    };
+
+    setupLinks();
+
+    return () => {
+      cancelled = true;
+      unlisteners.forEach((unlisten) => unlisten());
+    };
+  }, [setCurrentProject, setActiveCard]);
+
   // Handle file click from sidebar
   const handleFileClick = (path: string, name: string) => {
     setMainView('editor');
@@ -122,6 +179,24 @@ export default function Home() {
     setMainView('board');
   };
 
+  if (isBoardWindow) {
+    return (
+      <div className="h-screen flex flex-col overflow-hidden bg-background">
+        <Header onToggleSignalFeed={() => setShowSignalFeed(!showSignalFeed)} />
+        <main className="flex-1 flex overflow-hidden">
+          <div className="flex-1 flex flex-col overflow-hidden min-h-0">
+            <Board boardId={activeBoardId} />
+          </div>
+          {showSignalFeed && (
+            <aside className="w-72 border-l border-border-subtle">
+              <SignalFeed />
+            </aside>
+          )}
+        </main>
+      </div>
+    );
+  }
+
   return (
     <div className="h-screen flex flex-col overflow-hidden bg-background">
       {/* Header */}
@@ -152,7 +227,7 @@ export default function Home() {
           {/* Content area */}
           <div className="flex-1 flex flex-col overflow-hidden min-h-0">
             {mainView === 'board' ? (
-              <Board boardId="board-1" />
+              <Board boardId={activeBoardId} />
             ) : (
               <div className="h-full flex flex-col">
                 {/* Editor header with back button */}
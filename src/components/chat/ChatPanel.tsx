@@ -26,7 +26,7 @@ export function ChatPanel({ isOpen, onClose, width = 288 }: ChatPanelProps) {
   const [mounted, setMounted] = useState(false);
 
   const { cards, addCard, deleteCard, columns } = useBoardStore();
-  const { projectPath, fileTree } = useFileStore();
+  const { projectPath, projectId, fileTree } = useFileStore();
 
   const messagesEndRef = useRef<HTMLDivElement>(null);
 
@@ -176,7 +176,7 @@ export function ChatPanel({ isOpen, onClose, width = 288 }: ChatPanelProps) {
         }
 
         try {
-          const content = await fileApi.read(filePath);
+          const content = await fileApi.read(filePath, projectId ?? undefined);
           const maxLength = 3000;
           const truncated = content.length > maxLength
             ? content.slice(0, maxLength) + '\n... (truncated)'
@@ -212,7 +212,7 @@ export function ChatPanel({ isOpen, onClose, width = 288 }: ChatPanelProps) {
 
         for (const filePath of docFiles.slice(0, 3)) {
           try {
-            const content = await fileApi.read(filePath);
+            const content = await fileApi.read(filePath, projectId ?? undefined);
             const fileName = filePath.split('/').pop() || filePath;
             const maxLength = 1000;
             const truncated = content.length > maxLength
@@ -286,7 +286,7 @@ export function ChatPanel({ isOpen, onClose, width = 288 }: ChatPanelProps) {
       localAgent.unregisterTool('delete_card');
       localAgent.unregisterTool('delete_all_cards_in_column');
     };
-  }, [columns, cards, addCard, deleteCard, projectPath, fileTree]);
+  }, [columns, cards, addCard, deleteCard, projectPath, projectId, fileTree]);
 
   const handleSend = async () => {
     if (!input.trim() || isLoading) return;
@@ -5,6 +5,7 @@ import Editor from '@monaco-editor/react';
 import { X, Save, FileText } from 'lucide-react';
 import { cn } from '@/lib/utils';
 import { fileApi, isTauri } from '@/lib/tauri';
+import { useFileStore } from '@/store/fileStore';
 
 interface OpenFile {
   path: string;
@@ -89,6 +90,7 @@ export function CodeEditor({ className }: CodeEditorProps) {
   const [openFiles, setOpenFiles] = useState<OpenFile[]>([]);
   const [activeFile, setActiveFile] = useState<OpenFile | null>(null);
   const [loading, setLoading] = useState(false);
+  const { projectId } = useFileStore();
   const editorRef = useRef<any>(null);
 
   // Open a file
@@ -106,7 +108,7 @@ export function CodeEditor({ className }: CodeEditorProps) {
       // Read file content
       let content = '';
       if (isTauri) {
-        content = await fileApi.read(path);
+        content = await fileApi.read(path, projectId ?? undefined, true);
       } else {
         // Mock content for browser
         content = `// ${name}\n// File content would appear here in Tauri\n`;
@@ -126,7 +128,7 @@ export function CodeEditor({ className }: CodeEditorProps) {
     } finally {
       setLoading(false);
     }
-  }, [openFiles]);
+  }, [openFiles, projectId]);
 
   // Close a file
   const closeFile = useCallback((path: string) => {
@@ -50,7 +50,7 @@ export function Card({ card, isDragging }: CardProps) {
     findDoneColumn,
   } = useBoardStore();
   const { skills } = useSkillsStore();
-  const { projectPath } = useFileStore();
+  const { projectPath, projectId } = useFileStore();
   const { startExecution, completeExecution } = useTimelineStore();
   const [isDraggingFolder, setIsDraggingFolder] = useState(false);
   const [showAgentDialog, setShowAgentDialog] = useState(false);
@@ -259,6 +259,7 @@ export function Card({ card, isDragging }: CardProps) {
       // Call GLM-4 API with file operations support
       const response = await glmAgent.executeTaskWithFiles(autoPrompt, {
         folderPath: finalWorkingPath,
+        projectId: projectId ?? undefined,
       });
 
       if (response.success) {
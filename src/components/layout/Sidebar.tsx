@@ -23,7 +23,7 @@ import {
   MoreVertical,
 } from 'lucide-react';
 import { cn } from '@/lib/utils';
-import { isTauri, fileApi, shellApi } from '@/lib/tauri';
+import { isAppError, isTauri, fileApi, shellApi } from '@/lib/tauri';
 import { open } from '@tauri-apps/plugin-dialog';
 import { getCurrentWebviewWindow } from '@tauri-apps/api/webviewWindow';
 import { SkillsPanel } from '@/components/skills/SkillsPanel';
@@ -73,6 +73,7 @@ export function Sidebar({ onFileClick, width = 256 }: SidebarProps) {
   // Use file store
   const {
     projectPath,
+    projectId,
     fileTree,
     expandedFolders,
     isLoading,
@@ -231,14 +232,21 @@ export function Sidebar({ onFileClick, width = 256 }: SidebarProps) {
 
   // Delete file/folder
   const handleDelete = async (node: FileNode) => {
-    if (!confirm(`Are you sure you want to delete "${node.name}"?`)) return;
+    if (!confirm(`Move "${node.name}" to the trash?`)) return;
 
     try {
-      await fileApi.delete(node.path);
+      try {
+        await fileApi.trash(node.path);
+      } catch (error) {
+        // Some filesystems have no recycle bin; offer a permanent delete instead
+        if (!isAppError(error) || error.code !== 'UNSUPPORTED') throw error;
+        if (!confirm(`"${node.name}" can't be moved to the trash. Delete it permanently?`)) return;
+        await fileApi.delete(node.path, projectId ?? undefined);
+      }
       await handleRefresh();
     } catch (error) {
       console.error('Failed to delete:', error);
-      alert(`Failed to delete: ${error instanceof Error ? error.message : 'Unknown error'}`);
+      alert(`Failed to delete: ${isAppError(error) || error instanceof Error ? error.message : 'Unknown error'}`);
     }
   };
 
@@ -267,10 +275,10 @@ export function Sidebar({ onFileClick, width = 256 }: SidebarProps) {
     try {
       if (dialog.type === 'create-file') {
         const newPath = `${dialog.parentPath}/${inputValue}`;
-        await fileApi.create(newPath, '');
+        await fileApi.create(newPath, '', projectId ?? undefined);
       } else if (dialog.type === 'create-folder') {
         const newPath = `${dialog.parentPath}/${inputValue}`;
-        await fileApi.createDirectory(newPath);
+        await fileApi.createDirectory(newPath, projectId ?? undefined);
       } else if (dialog.type === 'rename') {
         const parts = dialog.parentPath.split('/');
         parts.pop();
@@ -279,16 +287,16 @@ export function Sidebar({ onFileClick, width = 256 }: SidebarProps) {
         // For now, we'll create a new file/folder and delete the old one
         if (isTauri) {
           // Read old content if it's a file
-          const content = await fileApi.read(dialog.parentPath);
-          await fileApi.create(newPath, content);
-          await fileApi.delete(dialog.parentPath);
+          const content = await fileApi.read(dialog.parentPath, projectId ?? undefined);
+          await fileApi.create(newPath, content, projectId ?? undefined);
+          await fileApi.delete(dialog.parentPath, projectId ?? undefined);
         }
       }
       await handleRefresh();
       setDialog({ type: null, parentPath: '', currentName: '', visible: false });
     } catch (error) {
       console.error('Failed to complete action:', error);
-      alert(`Failed: ${error instanceof Error ? error.message : 'Unknown error'}`);
+      alert(`Failed: ${isAppError(error) || error instanceof Error ? error.message : 'Unknown error'}`);
     }
   };
 
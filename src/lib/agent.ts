@@ -450,10 +450,10 @@ class GLMAgent {
   }
 
   // Read file contents from the project
-  async readProjectFile(filePath: string): Promise<string | null> {
+  async readProjectFile(filePath: string, projectId?: string): Promise<string | null> {
     if (!isTauri) return null;
     try {
-      return await fileApi.read(filePath);
+      return await fileApi.read(filePath, projectId);
     } catch (error) {
       console.error(`Failed to read file ${filePath}:`, error);
       return null;
@@ -478,7 +478,11 @@ class GLMAgent {
   }
 
   // Read multiple files and format them for context
-  async readFilesForContext(filePaths: string[], maxFiles: number = 10): Promise<string> {
+  async readFilesForContext(
+    filePaths: string[],
+    maxFiles: number = 10,
+    projectId?: string
+  ): Promise<string> {
     if (!isTauri) return '';
 
     const relevantExtensions = ['.ts', '.tsx', '.js', '.jsx', '.html', '.css', '.json', '.md'];
@@ -490,7 +494,7 @@ class GLMAgent {
 
     for (const filePath of relevantFiles) {
       try {
-        const content = await fileApi.read(filePath);
+        const content = await fileApi.read(filePath, projectId);
         if (content && content.length < 5000) { // Skip very large files
           const fileName = filePath.split('/').pop() || filePath;
           fileContents.push(`### ${fileName}\n\`\`\`\n${content}\n\`\`\``);
@@ -777,7 +781,8 @@ Provide your implementation:`;
   }
 
   // Execute file operations
-  async executeFileOperations(operations: FileOperation[]): Promise<{
+  // With projectId, operations outside the project folder fail instead of running
+  async executeFileOperations(operations: FileOperation[], projectId?: string): Promise<{
     success: boolean;
     created: string[];
     modified: string[];
@@ -806,14 +811,14 @@ Provide your implementation:`;
           const parentDir = op.path.substring(0, op.path.lastIndexOf('/'));
           if (parentDir) {
             try {
-              await fileApi.createDirectory(parentDir);
+              await fileApi.createDirectory(parentDir, projectId);
             } catch {
               // Directory might already exist
             }
           }
 
           // Write the file
-          await fileApi.write(op.path, op.content || '');
+          await fileApi.write(op.path, op.content || '', undefined, projectId);
 
           if (exists) {
             modified.push(op.path);
@@ -821,7 +826,7 @@ Provide your implementation:`;
             created.push(op.path);
           }
         } else if (op.type === 'delete') {
-          await fileApi.delete(op.path);
+          await fileApi.delete(op.path, projectId);
         }
       } catch (error) {
         errors.push(`Failed to ${op.type} ${op.path}: ${error}`);
@@ -851,6 +856,7 @@ Provide your implementation:`;
       files?: string[];
       additionalContext?: string;
       projectFileContent?: string;
+      projectId?: string;
     }
   ): Promise<AgentResponse> {
     // Build enhanced context with existing files
@@ -863,7 +869,7 @@ Provide your implementation:`;
         const fileTree = useFileStore.getState().fileTree;
         if (fileTree) {
           const filePaths = this.getFilePathsFromTree(fileTree);
-          const filesContext = await this.readFilesForContext(filePaths, 5);
+          const filesContext = await this.readFilesForContext(filePaths, 5, context.projectId);
           if (filesContext) {
             enhancedContext = filesContext + '\n\n' + enhancedContext;
           }
@@ -901,7 +907,7 @@ Provide your implementation:`;
 
     if (operations.length > 0) {
       // Execute file operations
-      const result = await this.executeFileOperations(operations);
+      const result = await this.executeFileOperations(operations, context?.projectId);
 
       console.log('[GLM Agent] File operations result:', {
         created: result.created.length,
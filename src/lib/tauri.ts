@@ -2,11 +2,65 @@
 // Provides type-safe wrappers for Tauri commands
 
 import { invoke } from '@tauri-apps/api/core';
-import type { Board, Card, Column, FileNode, Project } from '@/types';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import type {
+  AppError,
+  AppliedOperation,
+  BackupInfo,
+  BlockedCard,
+  Board,
+  BoardCard,
+  Card,
+  CardAuditEntry,
+  CardChanged,
+  CardDependencies,
+  CardExecution,
+  CardLocation,
+  CardMarkdown,
+  CardPriority,
+  CardSort,
+  CardStatus,
+  Column,
+  CommitSummary,
+  DiffStats,
+  DirStats,
+  DirtyTreeStrategy,
+  Draft,
+  DraftInfo,
+  FileDiff,
+  FileFormat,
+  FileMatch,
+  FileNode,
+  FileReadResult,
+  GitBranch,
+  GitCommit,
+  GitStatus,
+  MaintenanceReport,
+  MergeMode,
+  NavigateError,
+  Project,
+  ProjectReportExport,
+  ProjectStats,
+  RecentItem,
+  RecentKind,
+  RecentProject,
+  StartupHealth,
+  StartupState,
+  StashEntry,
+  ThemePreference,
+  ThemeState,
+  TodoScanResult,
+  TreeUpdate,
+  WindowState,
+} from '@/types';
 
 // Check if running in Tauri context
 export const isTauri = typeof window !== 'undefined' && '__TAURI__' in window;
 
+// Commands reject with an AppError; narrow an unknown catch value to one
+export const isAppError = (error: unknown): error is AppError =>
+  typeof error === 'object' && error !== null && 'code' in error && 'message' in error;
+
 // Project commands
 export const projectApi = {
   create: async (name: string, rootPath: string): Promise<Project> => {
@@ -19,10 +73,36 @@ export const projectApi = {
     return invoke('get_projects');
   },
 
+  getRecent: async (limit?: number): Promise<RecentProject[]> => {
+    if (!isTauri) return [];
+    return invoke('get_recent_projects', { limit });
+  },
+
+  touch: async (id: string): Promise<void> => {
+    if (!isTauri) return;
+    return invoke('touch_project', { id });
+  },
+
+  // While locked, changes to the project's boards fail with PROJECT_LOCKED
+  setLocked: async (id: string, locked: boolean): Promise<void> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('set_project_locked', { id, locked });
+  },
+
   delete: async (id: string): Promise<void> => {
     if (!isTauri) throw new Error('Not in Tauri context');
     return invoke('delete_project', { id });
   },
+
+  exportReport: async (projectId: string, destPath: string): Promise<ProjectReportExport> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('export_project_report', { projectId, destPath });
+  },
+
+  getStats: async (projectId: string): Promise<ProjectStats> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('get_project_stats', { projectId });
+  },
 };
 
 // Board commands
@@ -36,6 +116,11 @@ export const boardApi = {
     if (!isTauri) throw new Error('Not in Tauri context');
     return invoke('create_board', { projectId, name, position });
   },
+
+  getCards: async (boardId: string): Promise<BoardCard[]> => {
+    if (!isTauri) return [];
+    return invoke('get_cards_for_board', { boardId });
+  },
 };
 
 // Column commands
@@ -50,11 +135,28 @@ export const columnApi = {
     return invoke('create_column', { boardId, name, position });
   },
 
+  // All-or-nothing; columns are positioned in the order given
+  createMany: async (boardId: string, names: string[]): Promise<Column[]> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('create_columns', { boardId, names });
+  },
+
   update: async (id: string, name: string, position: number): Promise<void> => {
     if (!isTauri) throw new Error('Not in Tauri context');
     return invoke('update_column', { id, name, position });
   },
 
+  // orderedIds must hold every column of the board exactly once
+  reorder: async (boardId: string, orderedIds: string[]): Promise<Column[]> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('reorder_columns', { boardId, orderedIds });
+  },
+
+  setWipLimit: async (columnId: string, limit: number | null): Promise<void> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('set_wip_limit', { columnId, limit });
+  },
+
   delete: async (id: string): Promise<void> => {
     if (!isTauri) throw new Error('Not in Tauri context');
     return invoke('delete_column', { id });
@@ -63,34 +165,99 @@ export const columnApi = {
 
 // Card commands
 export const cardApi = {
-  getAll: async (columnId: string): Promise<Card[]> => {
+  getAll: async (columnId: string, sortBy?: CardSort): Promise<Card[]> => {
+    if (!isTauri) return [];
+    return invoke('get_cards', { columnId, sortBy });
+  },
+
+  getByPriority: async (projectId: string, priority: CardPriority): Promise<Card[]> => {
     if (!isTauri) return [];
-    return invoke('get_cards', { columnId });
+    return invoke('get_cards_by_priority', { projectId, priority });
   },
 
   create: async (
     columnId: string,
     title: string,
     description: string | null,
-    position: number
+    position: number,
+    priority?: CardPriority,
+    overrideLimit?: boolean
   ): Promise<Card> => {
     if (!isTauri) throw new Error('Not in Tauri context');
-    return invoke('create_card', { columnId, title, description, position });
+    return invoke('create_card', {
+      columnId,
+      title,
+      description,
+      position,
+      priority,
+      overrideLimit,
+    });
   },
 
   update: async (
     id: string,
     title: string,
     description: string | null,
-    status: string
+    status: string,
+    expectedVersion?: number
   ): Promise<void> => {
     if (!isTauri) throw new Error('Not in Tauri context');
-    return invoke('update_card', { id, title, description, status });
+    return invoke('update_card', { id, title, description, status, expectedVersion });
+  },
+
+  // Rejects a change the status rules don't allow with INVALID_TRANSITION
+  transitionStatus: async (id: string, newStatus: CardStatus): Promise<void> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('transition_card_status', { id, newStatus });
   },
 
-  move: async (id: string, columnId: string, position: number): Promise<void> => {
+  move: async (
+    id: string,
+    columnId: string,
+    position: number,
+    overrideLimit?: boolean
+  ): Promise<void> => {
     if (!isTauri) throw new Error('Not in Tauri context');
-    return invoke('move_card', { id, columnId, position });
+    return invoke('move_card', { id, columnId, position, overrideLimit });
+  },
+
+  // Stays within the project; lands at the end of targetColumnId, or of the
+  // board's first column
+  moveToBoard: async (
+    id: string,
+    boardId: string,
+    targetColumnId?: string,
+    keepLabels?: boolean
+  ): Promise<Card> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('move_card_to_board', { id, boardId, targetColumnId, keepLabels });
+  },
+
+  // Fires in every window after a card is created, edited, moved or deleted
+  onChanged: async (handler: (change: CardChanged) => void): Promise<UnlistenFn> => {
+    if (!isTauri) return () => {};
+    return listen<CardChanged>('card-changed', (event) => handler(event.payload));
+  },
+
+  // Bulk operations are all or nothing and resolve to the number of cards changed
+  bulkMove: async (ids: string[], targetColumnId: string): Promise<number> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('bulk_move_cards', { ids, targetColumnId });
+  },
+
+  bulkDelete: async (ids: string[]): Promise<number> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('bulk_delete_cards', { ids });
+  },
+
+  bulkUpdateStatus: async (ids: string[], status: string): Promise<number> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('bulk_update_status', { ids, status });
+  },
+
+  bulkAssignLabel: async (ids: string[], labelId: string): Promise<number> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('bulk_assign_label', { ids, labelId });
   },
 
   attachFolder: async (id: string, folderPath: string): Promise<void> => {
@@ -103,9 +270,54 @@ export const cardApi = {
     return invoke('delete_card', { id });
   },
 
-  execute: async (cardId: string): Promise<string> => {
-    if (!isTauri) return 'Not in Tauri context';
-    return invoke('execute_card_placeholder', { cardId });
+  // Resolves once the card's command exits
+  execute: async (cardId: string): Promise<CardExecution> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('execute_card', { cardId });
+  },
+
+  // Without destPath only the markdown comes back, e.g. for the clipboard
+  exportMarkdown: async (cardId: string, destPath?: string): Promise<CardMarkdown> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('export_card_markdown', { cardId, destPath });
+  },
+
+  getHistory: async (cardId: string): Promise<CardAuditEntry[]> => {
+    if (!isTauri) return [];
+    return invoke('get_card_history', { cardId });
+  },
+
+  undo: async (projectId: string): Promise<AppliedOperation | null> => {
+    if (!isTauri) return null;
+    return invoke('undo_last', { projectId });
+  },
+
+  redo: async (projectId: string): Promise<AppliedOperation | null> => {
+    if (!isTauri) return null;
+    return invoke('redo_last', { projectId });
+  },
+};
+
+// Card dependency commands
+export const dependencyApi = {
+  add: async (cardId: string, dependsOnCardId: string): Promise<void> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('add_dependency', { cardId, dependsOnCardId });
+  },
+
+  remove: async (cardId: string, dependsOnCardId: string): Promise<void> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('remove_dependency', { cardId, dependsOnCardId });
+  },
+
+  get: async (cardId: string): Promise<CardDependencies> => {
+    if (!isTauri) return { blocked_by: [], blocks: [] };
+    return invoke('get_dependencies', { cardId });
+  },
+
+  getBlocked: async (projectId: string): Promise<BlockedCard[]> => {
+    if (!isTauri) return [];
+    return invoke('get_blocked_cards', { projectId });
   },
 };
 
@@ -134,29 +346,69 @@ export const fileApi = {
     return invoke('get_file_tree', { projectPath });
   },
 
-  read: async (path: string): Promise<string> => {
+  // Changes to the tree last returned by getTree, as they happen on disk
+  onTreeUpdated: async (handler: (update: TreeUpdate) => void): Promise<UnlistenFn> => {
+    if (!isTauri) return () => {};
+    return listen<TreeUpdate>('tree-updated', (event) => handler(event.payload));
+  },
+
+  getDirectoryStats: async (path: string): Promise<DirStats> => {
+    if (!isTauri) return { total_bytes: 0, file_count: 0, dir_count: 0 };
+    return invoke('get_directory_stats', { path });
+  },
+
+  fuzzyFind: async (projectPath: string, query: string, limit?: number): Promise<FileMatch[]> => {
+    if (!isTauri) return [];
+    return invoke('fuzzy_find_files', { projectPath, query, limit });
+  },
+
+  // With projectId, paths outside the project folder reject with
+  // PATH_ESCAPES_PROJECT; the same goes for write, create, createDirectory and delete.
+  // Pass opened when the user opened the file, to add it to the recent files.
+  read: async (path: string, projectId?: string, opened?: boolean): Promise<string> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    const result = await invoke<FileReadResult>('read_file', { path, projectId, opened });
+    return result.content;
+  },
+
+  readWithFormat: async (
+    path: string,
+    projectId?: string,
+    opened?: boolean
+  ): Promise<FileReadResult> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('read_file', { path, projectId, opened });
+  },
+
+  // With projectId, the file's draft is also discarded once the write succeeds
+  write: async (
+    path: string,
+    content: string,
+    format?: FileFormat,
+    projectId?: string
+  ): Promise<void> => {
     if (!isTauri) throw new Error('Not in Tauri context');
-    return invoke('read_file', { path });
+    return invoke('write_file', { path, content, format, projectId });
   },
 
-  write: async (path: string, content: string): Promise<void> => {
+  create: async (path: string, content: string = '', projectId?: string): Promise<void> => {
     if (!isTauri) throw new Error('Not in Tauri context');
-    return invoke('write_file', { path, content });
+    return invoke('create_file', { path, content, projectId });
   },
 
-  create: async (path: string, content: string = ''): Promise<void> => {
+  createDirectory: async (path: string, projectId?: string): Promise<void> => {
     if (!isTauri) throw new Error('Not in Tauri context');
-    return invoke('create_file', { path, content });
+    return invoke('create_directory', { path, projectId });
   },
 
-  createDirectory: async (path: string): Promise<void> => {
+  delete: async (path: string, projectId?: string): Promise<void> => {
     if (!isTauri) throw new Error('Not in Tauri context');
-    return invoke('create_directory', { path });
+    return invoke('delete_file', { path, projectId });
   },
 
-  delete: async (path: string): Promise<void> => {
+  trash: async (path: string): Promise<void> => {
     if (!isTauri) throw new Error('Not in Tauri context');
-    return invoke('delete_file', { path });
+    return invoke('trash_path', { path });
   },
 
   exists: async (path: string): Promise<boolean> => {
@@ -172,14 +424,64 @@ export const fileApi = {
 
 // Git commands
 export const gitApi = {
-  getBranches: async (repoPath: string) => {
+  getBranches: async (repoPath: string): Promise<GitBranch[]> => {
     if (!isTauri) return [];
     return invoke('get_branches', { repoPath });
   },
 
-  createGhostBranch: async (repoPath: string, cardId: string): Promise<string> => {
+  // Rejects with UNSUPPORTED for a bare repository, DETACHED_HEAD without a branch
+  getStatus: async (repoPath: string): Promise<GitStatus> => {
     if (!isTauri) throw new Error('Not in Tauri context');
-    return invoke('create_ghost_branch', { repoPath, cardId });
+    return invoke('get_git_status', { repoPath });
+  },
+
+  // Newest first from branch, or HEAD; skip and limit (default 50) page through it
+  getCommitHistory: async (
+    repoPath: string,
+    branch?: string,
+    limit?: number,
+    skip?: number
+  ): Promise<GitCommit[]> => {
+    if (!isTauri) return [];
+    return invoke('get_commit_history', { repoPath, branch, limit, skip });
+  },
+
+  // Commits on the ghost branch that main (or master) doesn't have yet
+  getGhostBranchCommits: async (repoPath: string, ghostBranch: string): Promise<GitCommit[]> => {
+    if (!isTauri) return [];
+    return invoke('get_ghost_branch_commits', { repoPath, ghostBranch });
+  },
+
+  // createInitialCommit lets a repository without commits be branched from
+  // DIRTY_WORKING_TREE carries the files that block the switch
+  checkoutBranch: async (
+    repoPath: string,
+    branchName: string,
+    strategy?: DirtyTreeStrategy
+  ): Promise<void> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('checkout_branch', { repoPath, branchName, strategy });
+  },
+
+  createGhostBranch: async (
+    repoPath: string,
+    cardId: string,
+    createInitialCommit?: boolean,
+    strategy?: DirtyTreeStrategy
+  ): Promise<string> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('create_ghost_branch', { repoPath, cardId, createInitialCommit, strategy });
+  },
+
+  // Refuses to commit off a ghost branch unless allowNonGhost is set
+  commitChanges: async (
+    repoPath: string,
+    message: string,
+    paths?: string[],
+    allowNonGhost?: boolean
+  ): Promise<CommitSummary> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('commit_changes', { repoPath, message, paths, allowNonGhost });
   },
 
   getDiff: async (repoPath: string, branch1: string, branch2: string): Promise<string> => {
@@ -187,9 +489,90 @@ export const gitApi = {
     return invoke('get_branch_diff', { repoPath, branch1, branch2 });
   },
 
-  mergeGhostBranch: async (repoPath: string, ghostBranch: string): Promise<void> => {
+  getDiffStructured: async (
+    repoPath: string,
+    branch1: string,
+    branch2: string
+  ): Promise<FileDiff[]> => {
+    if (!isTauri) return [];
+    return invoke('get_branch_diff_structured', { repoPath, branch1, branch2 });
+  },
+
+  // Uncommitted changes, staged or not, against base or HEAD; untracked files count as added
+  getWorkdirDiff: async (repoPath: string, base?: string): Promise<FileDiff[]> => {
+    if (!isTauri) return [];
+    return invoke('get_workdir_diff', { repoPath, base });
+  },
+
+  // Accepts any revspec: branch names, SHAs or HEAD
+  getDiffStats: async (
+    repoPath: string,
+    branch1: string,
+    branch2: string
+  ): Promise<DiffStats> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('get_diff_stats', { repoPath, branch1, branch2 });
+  },
+
+  // mode defaults to 'merge'; a squash message lists the squashed commits
+  mergeGhostBranch: async (
+    repoPath: string,
+    ghostBranch: string,
+    mode?: MergeMode,
+    message?: string,
+    cardTitle?: string,
+    strategy?: DirtyTreeStrategy
+  ): Promise<void> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('merge_ghost_branch', {
+      repoPath,
+      ghostBranch,
+      mode,
+      message,
+      cardTitle,
+      strategy,
+    });
+  },
+
+  // Resolves to the SHA the branch pointed at, to recreate it from
+  discardGhostBranch: async (
+    repoPath: string,
+    ghostBranch: string,
+    strategy?: DirtyTreeStrategy
+  ): Promise<string> => {
     if (!isTauri) throw new Error('Not in Tauri context');
-    return invoke('merge_ghost_branch', { repoPath, ghostBranch });
+    return invoke('discard_ghost_branch', { repoPath, ghostBranch, strategy });
+  },
+
+  // Restores the changes stashed for a card by a 'stash' branch switch
+  applyCardStash: async (repoPath: string, cardId: string): Promise<void> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('apply_card_stash', { repoPath, cardId });
+  },
+
+  stashChanges: async (
+    repoPath: string,
+    message?: string,
+    includeUntracked?: boolean
+  ): Promise<StashEntry> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('stash_changes', { repoPath, message, includeUntracked });
+  },
+
+  listStashes: async (repoPath: string): Promise<StashEntry[]> => {
+    if (!isTauri) return [];
+    return invoke('list_stashes', { repoPath });
+  },
+
+  // Keeps the stash when it applies with conflicts (MERGE_CONFLICT)
+  applyStash: async (repoPath: string, index: number, drop: boolean): Promise<void> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('apply_stash', { repoPath, index, drop });
+  },
+
+  dropStash: async (repoPath: string, index: number): Promise<void> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('drop_stash', { repoPath, index });
   },
 };
 
@@ -206,20 +589,219 @@ export const shellApi = {
   },
 };
 
-// Window commands
+// Database maintenance commands
+export const maintenanceApi = {
+  getStartupHealth: async (): Promise<StartupHealth> => {
+    if (!isTauri) return { ok: true, problems: [] };
+    return invoke('get_startup_health');
+  },
+
+  run: async (vacuum = false): Promise<MaintenanceReport> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('run_db_maintenance', { vacuum });
+  },
+
+  createBackup: async (): Promise<BackupInfo> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('create_backup');
+  },
+
+  listBackups: async (): Promise<BackupInfo[]> => {
+    if (!isTauri) return [];
+    return invoke('list_backups');
+  },
+
+  restoreBackup: async (path: string): Promise<void> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('restore_backup', { path });
+  },
+
+  // Fires after every snapshot, automatic or not
+  onBackupCompleted: async (handler: (backup: BackupInfo) => void): Promise<UnlistenFn> => {
+    if (!isTauri) return () => {};
+    return listen<BackupInfo>('backup-completed', (event) => handler(event.payload));
+  },
+
+  isEncrypted: async (): Promise<boolean> => {
+    if (!isTauri) return false;
+    return invoke('is_database_encrypted');
+  },
+
+  // Only supported in builds with the encrypted-db feature
+  setPassword: async (newPassword: string): Promise<void> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('set_database_password', { newPassword });
+  },
+};
+
+// viraith://project/<id>/card/<id> links
+export const linkApi = {
+  getCardLink: async (cardId: string): Promise<string> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('get_card_link', { cardId });
+  },
+
+  // Call once the navigate listeners are registered
+  openLaunchLinks: async (): Promise<void> => {
+    if (!isTauri) return;
+    return invoke('open_launch_links');
+  },
+
+  onNavigate: async (handler: (location: CardLocation) => void): Promise<UnlistenFn> => {
+    if (!isTauri) return () => {};
+    return listen<CardLocation>('navigate', (event) => handler(event.payload));
+  },
+
+  onNavigateError: async (handler: (error: NavigateError) => void): Promise<UnlistenFn> => {
+    if (!isTauri) return () => {};
+    return listen<NavigateError>('navigate-error', (event) => handler(event.payload));
+  },
+};
+
+// Recently opened projects and files
+export const recentApi = {
+  record: async (kind: RecentKind, pathOrId: string): Promise<void> => {
+    if (!isTauri) return;
+    return invoke('record_recent', { kind, pathOrId });
+  },
+
+  get: async (kind: RecentKind, limit?: number): Promise<RecentItem[]> => {
+    if (!isTauri) return [];
+    return invoke('get_recent', { kind, limit });
+  },
+};
+
+// Draft commands: unsaved editor buffers kept for crash recovery
+export const draftApi = {
+  save: async (projectId: string, filePath: string, content: string): Promise<DraftInfo> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('save_draft', { projectId, filePath, content });
+  },
+
+  get: async (projectId: string, filePath: string): Promise<Draft | null> => {
+    if (!isTauri) return null;
+    return invoke('get_draft', { projectId, filePath });
+  },
+
+  list: async (projectId: string): Promise<DraftInfo[]> => {
+    if (!isTauri) return [];
+    return invoke('list_drafts', { projectId });
+  },
+
+  discard: async (projectId: string, filePath: string): Promise<boolean> => {
+    if (!isTauri) return false;
+    return invoke('discard_draft', { projectId, filePath });
+  },
+};
+
+// Preference commands
+// App-wide settings kept in the database, so they survive webview data clears.
+// Keys the backend reads itself (e.g. file_tree.ignore_patterns) are validated.
+export const preferenceApi = {
+  get: async <T = unknown>(key: string): Promise<T | null> => {
+    if (!isTauri) return null;
+    return invoke('get_preference', { key });
+  },
+
+  set: async (key: string, value: unknown): Promise<void> => {
+    if (!isTauri) return;
+    return invoke('set_preference', { key, value });
+  },
+
+  getAll: async (): Promise<Record<string, unknown>> => {
+    if (!isTauri) return {};
+    return invoke('get_all_preferences');
+  },
+
+  reset: async (): Promise<void> => {
+    if (!isTauri) return;
+    return invoke('reset_preferences');
+  },
+};
+
+// Todo commands
+export const todoApi = {
+  // Lists TODO / FIXME / HACK comments; with createCards each new one becomes
+  // a card in targetColumnId
+  scan: async (
+    projectId: string,
+    createCards: boolean,
+    targetColumnId?: string,
+    limits?: { maxFileSize?: number; maxFiles?: number }
+  ): Promise<TodoScanResult> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('scan_todos', {
+      projectId,
+      createCards,
+      targetColumnId,
+      maxFileSize: limits?.maxFileSize,
+      maxFiles: limits?.maxFiles,
+    });
+  },
+};
+
+// Window commands; `label` defaults to the main window
 export const windowApi = {
-  minimize: async (): Promise<void> => {
+  minimize: async (label?: string): Promise<void> => {
     if (!isTauri) return;
-    return invoke('minimize_window');
+    return invoke('minimize_window', { label });
   },
 
-  maximize: async (): Promise<void> => {
+  maximize: async (label?: string): Promise<void> => {
     if (!isTauri) return;
-    return invoke('maximize_window');
+    return invoke('maximize_window', { label });
   },
 
-  close: async (): Promise<void> => {
+  close: async (label?: string): Promise<void> => {
     if (!isTauri) return;
-    return invoke('close_window');
+    return invoke('close_window', { label });
+  },
+
+  // Resolves to whether the window is fullscreen after the toggle
+  toggleFullscreen: async (label?: string): Promise<boolean> => {
+    if (!isTauri) return false;
+    return invoke('toggle_fullscreen', { label });
+  },
+
+  setAlwaysOnTop: async (enabled: boolean, label?: string): Promise<boolean> => {
+    if (!isTauri) return false;
+    return invoke('set_always_on_top', { label, enabled });
+  },
+
+  saveState: async (label?: string): Promise<WindowState | null> => {
+    if (!isTauri) return null;
+    return invoke('save_window_state', { label });
+  },
+
+  getState: async (label?: string): Promise<WindowState | null> => {
+    if (!isTauri) return null;
+    return invoke('get_window_state', { label });
+  },
+
+  // Opens the board in its own window (or focuses it); resolves to the label
+  openBoard: async (boardId: string): Promise<string> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('open_board_window', { boardId });
+  },
+
+  getTheme: async (label?: string): Promise<ThemeState> => {
+    if (!isTauri) return { preference: 'system', resolved: 'light' };
+    return invoke('get_theme', { label });
+  },
+
+  setTheme: async (theme: ThemePreference): Promise<ThemeState> => {
+    if (!isTauri) throw new Error('Not in Tauri context');
+    return invoke('set_theme', { theme });
+  },
+
+  // Fired when the preference changes or, while following the system, the OS flips
+  onThemeChanged: async (handler: (theme: ThemeState) => void): Promise<UnlistenFn> => {
+    if (!isTauri) return () => {};
+    return listen<ThemeState>('theme-changed', (event) => handler(event.payload));
+  },
+
+  getStartupState: async (label?: string): Promise<StartupState> => {
+    if (!isTauri) return { theme: { preference: 'system', resolved: 'light' } };
+    return invoke('get_startup_state', { label });
   },
 };
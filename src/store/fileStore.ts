@@ -1,5 +1,5 @@
 import { create } from 'zustand';
-import { fileApi, isTauri } from '@/lib/tauri';
+import { fileApi, isAppError, isTauri, projectApi } from '@/lib/tauri';
 
 export interface FileNode {
   name: string;
@@ -11,6 +11,8 @@ export interface FileNode {
 interface FileState {
   // Data
   projectPath: string | null;
+  // Backend project for projectPath; file commands pass it to stay inside the folder
+  projectId: string | null;
   fileTree: FileNode | null;
   expandedFolders: Set<string>;
   isLoading: boolean;
@@ -37,13 +39,32 @@ const convertNode = (node: any): FileNode => ({
   children: node.children?.map(convertNode),
 });
 
+// The project registered for folderPath, registering one on first open.
+// Either way it is marked as opened so it tops the recent projects list.
+const projectFor = async (folderPath: string): Promise<string | null> => {
+  if (!isTauri) return null;
+  const name = folderPath.split(/[\\/]/).filter(Boolean).pop() || folderPath;
+  try {
+    return (await projectApi.create(name, folderPath)).id;
+  } catch (error) {
+    if (isAppError(error) && error.existing_project_id) {
+      const id = error.existing_project_id;
+      projectApi.touch(id).catch((e) => console.error('Failed to mark project opened:', e));
+      return id;
+    }
+    console.error('Failed to register project:', error);
+    return null;
+  }
+};
+
 export const useFileStore = create<FileState>((set, get) => ({
   projectPath: null,
+  projectId: null,
   fileTree: null,
   expandedFolders: new Set(),
   isLoading: false,
 
-  setProjectPath: (path) => set({ projectPath: path }),
+  setProjectPath: (path) => set({ projectPath: path, projectId: null }),
 
   setFileTree: (tree) => set({ fileTree: tree }),
 
@@ -72,10 +93,12 @@ export const useFileStore = create<FileState>((set, get) => ({
     try {
       const tree = await fileApi.getTree(folderPath);
       const converted = convertNode(tree);
+      const projectId = await projectFor(folderPath);
 
       set({
         fileTree: converted,
         projectPath: folderPath,
+        projectId,
         expandedFolders: new Set([converted.path]),
         isLoading: false,
       });
@@ -107,6 +130,7 @@ export const useFileStore = create<FileState>((set, get) => ({
     set({
       fileTree: null,
       projectPath: null,
+      projectId: null,
       expandedFolders: new Set(),
     });
     localStorage.removeItem(STORAGE_KEY);
@@ -7,6 +7,39 @@ export interface Project {
   createdAt: number;
   updatedAt: number;
   settings: ProjectSettings;
+  // Read-only: boards, columns and cards can't be changed
+  locked: boolean;
+}
+
+// Project as listed by get_recent_projects
+export interface RecentProject extends Project {
+  last_opened_at: number | null;
+  path_exists: boolean;
+}
+
+// Result of export_project_report; `by_status` omits statuses with no cards
+export interface ProjectReportExport {
+  path: string;
+  totals: {
+    boards: number;
+    columns: number;
+    cards: number;
+    by_status: Partial<Record<CardStatus, number>>;
+    overdue: number;
+    recently_updated: number;
+  };
+}
+
+// Dashboard figures from get_project_stats; `weekly` is the last 12 weeks,
+// oldest first, each starting Monday 00:00 UTC
+export interface ProjectStats {
+  boards: number;
+  columns: number;
+  cards: number;
+  cards_by_status: Record<CardStatus, number>;
+  weekly: { week_start: number; created: number; completed: number }[];
+  avg_seconds_to_done: number | null;
+  executions: { success: number; failed: number; cancelled: number; running: number };
 }
 
 export interface ProjectSettings {
@@ -29,12 +62,22 @@ export interface Column {
   position: number;
   automationRules: AutomationRule[];
   createdAt: number;
+  // Most live cards the column accepts; null is unlimited
+  wipLimit: number | null;
+  cardCount: number;
 }
 
+// Runs `action` when a card enters the column, if `condition` holds for it
 export interface AutomationRule {
-  trigger: 'on_enter' | 'on_exit';
-  action: 'execute' | 'notify' | 'move';
-  config: Record<string, unknown>;
+  trigger: 'on_enter';
+  condition?:
+    | { type: 'status_in'; statuses: CardStatus[] }
+    | { type: 'priority_in'; priorities: CardPriority[] };
+  action:
+    | { type: 'set_status'; status: CardStatus }
+    | { type: 'create_ghost_branch' }
+    | { type: 'attach_label'; label_id: string }
+    | { type: 'run_command'; command: string };
 }
 
 export interface Card {
@@ -47,9 +90,65 @@ export interface Card {
   agentConfig: AgentConfig;
   position: number;
   status: CardStatus;
+  priority: CardPriority;
   createdAt: number;
   updatedAt: number;
   metadata: CardMetadata;
+  // Bumped on every change; pass it to cardApi.update to catch concurrent edits
+  version?: number;
+}
+
+export type CardPriority = 'low' | 'normal' | 'high' | 'urgent';
+
+// Order for get_cards: column position, urgent first, or most recently updated
+export type CardSort = 'position' | 'priority' | 'updated';
+
+// Result of export_card_markdown; `path` is set when it was written to disk
+export interface CardMarkdown {
+  markdown: string;
+  path: string | null;
+}
+
+// Everything needed to open a card; payload of the `navigate` event
+export interface CardLocation {
+  project_id: string;
+  board_id: string;
+  column_id: string;
+  card_id: string;
+}
+
+export type CardChangeKind = 'created' | 'updated' | 'status_changed' | 'moved' | 'deleted';
+
+// Payload of the `card-changed` event; from_column_id is only set for moves
+export interface CardChanged {
+  card_id: string;
+  project_id: string;
+  board_id: string;
+  column_id: string;
+  from_column_id: string | null;
+  kind: CardChangeKind;
+}
+
+// A viraith:// link that couldn't be followed
+export interface NavigateError {
+  url: string;
+  reason: string;
+}
+
+// Both directions of a card's dependencies
+export interface CardDependencies {
+  blocked_by: Card[];
+  blocks: Card[];
+}
+
+// A card that can't start yet, with the ids of dependencies not done
+export interface BlockedCard extends Card {
+  blocked_by: string[];
+}
+
+// A card from get_cards_for_board, with its column's name
+export interface BoardCard extends Card {
+  column_name: string;
 }
 
 export type CardStatus =
@@ -123,6 +222,177 @@ export interface TimelineExecution extends CardExecution {
   duration?: number; // in milliseconds
 }
 
+// On-disk format of a text file, as detected by read_file
+export interface FileFormat {
+  encoding: 'utf-8' | 'utf-16le' | 'utf-16be';
+  bom: boolean;
+  // 'mixed' files are written back with their line endings untouched
+  line_ending: 'lf' | 'crlf' | 'mixed';
+}
+
+export interface FileReadResult extends FileFormat {
+  content: string;
+}
+
+// Unsaved editor buffer kept by save_draft; DraftInfo leaves out the content
+export interface DraftInfo {
+  id: string;
+  project_id: string;
+  file_path: string;
+  saved_at: number;
+  size: number;
+}
+
+export interface Draft extends Omit<DraftInfo, 'size'> {
+  content: string;
+}
+
+// Entry in the recent projects / files list; `item` is a project id or a path
+export type RecentKind = 'project' | 'file';
+
+export interface RecentItem {
+  kind: RecentKind;
+  item: string;
+  opened_at: number;
+}
+
+// Directory totals from get_directory_stats
+export interface DirStats {
+  total_bytes: number;
+  file_count: number;
+  dir_count: number;
+}
+
+// Quick-open hit from fuzzy_find_files; `indices` are matched character positions
+export interface FileMatch {
+  path: string;
+  score: number;
+  indices: number[];
+}
+
+// Source comment found by scan_todos; `path` is relative to the project root
+export interface TodoHit {
+  path: string;
+  line: number;
+  tag: 'TODO' | 'FIXME' | 'HACK';
+  text: string;
+}
+
+export interface TodoScanResult {
+  todos: TodoHit[];
+  files_scanned: number;
+  truncated: boolean;
+  created_card_ids: string[];
+}
+
+// Field change recorded in a card's audit log
+export interface CardAuditEntry {
+  id: string;
+  card_id: string;
+  field: string;
+  old_value: string | null;
+  new_value: string | null;
+  changed_at: number;
+}
+
+// Result of undo_last / redo_last
+export interface AppliedOperation {
+  kind: 'update' | 'move' | 'delete';
+  card_id: string;
+}
+
+// Saved main window geometry, in physical pixels
+export interface WindowState {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+  maximized: boolean;
+}
+
+export type ThemePreference = 'light' | 'dark' | 'system';
+
+export interface ThemeState {
+  preference: ThemePreference;
+  resolved: 'light' | 'dark';
+}
+
+// Fetched once before the first paint
+export interface StartupState {
+  theme: ThemeState;
+}
+
+// Database maintenance
+export interface StartupHealth {
+  ok: boolean;
+  problems: string[];
+}
+
+export interface MaintenanceReport {
+  integrity_ok: boolean;
+  integrity_problems: string[];
+  orphaned_rows: { table: string; rowid: number | null; parent: string }[];
+  vacuumed: boolean;
+  size_before: number;
+  size_after: number;
+  row_counts: { table: string; rows: number }[];
+}
+
+// A database snapshot in the app's backup folder
+export interface BackupInfo {
+  path: string;
+  name: string;
+  size: number;
+  created_at: number;
+  automatic: boolean;
+}
+
+// Error rejected by every Tauri command
+export type AppErrorCode =
+  | 'NOT_FOUND'
+  | 'CONFLICT'
+  | 'VALIDATION'
+  | 'IO'
+  | 'GIT'
+  | 'DATABASE'
+  | 'UNSUPPORTED'
+  | 'WIP_LIMIT_EXCEEDED'
+  | 'INVALID_TRANSITION'
+  | 'PROJECT_PATH_EXISTS'
+  | 'REPOSITORY_HAS_NO_COMMITS'
+  | 'DIRTY_WORKING_TREE'
+  | 'MERGE_CONFLICT'
+  | 'NOTHING_TO_COMMIT'
+  | 'DETACHED_HEAD'
+  | 'PROJECT_LOCKED'
+  | 'PATH_ESCAPES_PROJECT'
+  | 'TIMEOUT'
+  | 'INTERNAL';
+
+export interface AppError {
+  code: AppErrorCode;
+  // The backend variant, e.g. 'NotFound'
+  kind: string;
+  message: string;
+  // The variant's own fields, also spread below; null when it has none
+  detail: Record<string, unknown> | null;
+  field?: string;
+  existing_project_id?: string;
+  existing_project_name?: string;
+  // Set on a CONFLICT from an edit based on an outdated card
+  current_card?: Card;
+  // Set on WIP_LIMIT_EXCEEDED
+  limit?: number;
+  current?: number;
+  // Set on INVALID_TRANSITION
+  from?: CardStatus;
+  to?: CardStatus;
+  // Set on DIRTY_WORKING_TREE and MERGE_CONFLICT
+  files?: string[];
+  // Set on TIMEOUT
+  seconds?: number;
+}
+
 // File change tracking for diff viewer
 export interface FileChange {
   path: string;
@@ -147,18 +417,109 @@ export interface FileNode {
   modified?: number;
 }
 
+// Payload of the `tree-updated` event; nodes arrive as the backend serializes
+// them and root is the (canonical) project path the change belongs to
+export type TreeUpdate = { root: string } & (
+  | { kind: 'added'; parent: string; node: FileNode }
+  | { kind: 'removed'; path: string }
+  | { kind: 'renamed'; from: string; parent: string; node: FileNode }
+  | { kind: 'changed'; node: FileNode }
+);
+
+// ahead_behind is against main (or master) and null for that branch itself
 export interface GitBranch {
   name: string;
-  isHead: boolean;
-  isGhost: boolean;
-  lastCommit?: string;
+  is_head: boolean;
+  is_ghost: boolean;
+  last_commit: GitCommit | null;
+  ahead_behind: AheadBehind | null;
 }
 
+export interface AheadBehind {
+  ahead: number;
+  behind: number;
+}
+
+// message is the first line only; parent_count is above 1 for merges
 export interface GitCommit {
   sha: string;
   message: string;
   timestamp: number;
   author: string;
+  parent_count: number;
+}
+
+// index 0 is the newest stash
+export interface StashEntry {
+  index: number;
+  message: string;
+  timestamp: number;
+}
+
+// How merge_ghost_branch lands a ghost branch on the default branch
+export type MergeMode = 'merge' | 'squash';
+
+// What to do with uncommitted changes that are in the way of a branch switch
+export type DirtyTreeStrategy = 'refuse' | 'stash' | 'force';
+
+export type GitFileState =
+  | 'modified'
+  | 'added'
+  | 'deleted'
+  | 'renamed'
+  | 'untracked'
+  | 'conflicted';
+
+// A file changed in both the index and the working tree appears twice
+export interface GitFileStatus {
+  path: string;
+  status: GitFileState;
+  staged: boolean;
+}
+
+// Returned by get_git_status; ignored files are left out
+export interface GitStatus {
+  branch: string;
+  files: GitFileStatus[];
+  counts: Record<GitFileState, number> & { staged: number };
+}
+
+// Per-file result of get_branch_diff_structured and get_workdir_diff; binary
+// files have no hunks
+export interface FileDiff {
+  old_path: string | null;
+  new_path: string | null;
+  status: 'added' | 'deleted' | 'modified' | 'renamed' | 'copied' | 'typechange';
+  is_binary: boolean;
+  hunks: DiffHunk[];
+}
+
+export interface DiffHunk {
+  old_start: number;
+  old_lines: number;
+  new_start: number;
+  new_lines: number;
+  // origin is '+', '-' or ' '; '=', '>' and '<' mark a missing final newline
+  lines: { origin: string; content: string }[];
+}
+
+// Returned by get_diff_stats; binary files count no lines
+export interface FileDiffStat extends Pick<FileDiff, 'old_path' | 'new_path' | 'status' | 'is_binary'> {
+  insertions: number;
+  deletions: number;
+}
+
+export interface DiffStats {
+  files: FileDiffStat[];
+  files_changed: number;
+  insertions: number;
+  deletions: number;
+}
+
+// Returned by commit_changes
+export interface CommitSummary {
+  sha: string;
+  files_changed: number;
 }
 
 // Skills types for agent capabilities